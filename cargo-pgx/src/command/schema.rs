@@ -300,12 +300,16 @@ pub(crate) fn generate_schema(
     let mut seen_schemas = Vec::new();
     let mut num_funcs = 0_usize;
     let mut num_triggers = 0_usize;
+    let mut num_event_triggers = 0_usize;
     let mut num_types = 0_usize;
     let mut num_enums = 0_usize;
     let mut num_sqls = 0_usize;
     let mut num_ords = 0_usize;
     let mut num_hashes = 0_usize;
     let mut num_aggregates = 0_usize;
+    let mut num_fdws = 0_usize;
+    let mut num_index_ams = 0_usize;
+    let mut num_table_ams = 0_usize;
     for func in &fns_to_call {
         if func.starts_with("__pgx_internals_schema_") {
             let schema = func
@@ -318,6 +322,8 @@ pub(crate) fn generate_schema(
             num_funcs += 1;
         } else if func.starts_with("__pgx_internals_trigger_") {
             num_triggers += 1;
+        } else if func.starts_with("__pgx_internals_event_trigger_") {
+            num_event_triggers += 1;
         } else if func.starts_with("__pgx_internals_type_") {
             num_types += 1;
         } else if func.starts_with("__pgx_internals_enum_") {
@@ -330,11 +336,17 @@ pub(crate) fn generate_schema(
             num_hashes += 1;
         } else if func.starts_with("__pgx_internals_aggregate_") {
             num_aggregates += 1;
+        } else if func.starts_with("__pgx_internals_fdw_") {
+            num_fdws += 1;
+        } else if func.starts_with("__pgx_internals_index_am_") {
+            num_index_ams += 1;
+        } else if func.starts_with("__pgx_internals_table_am_") {
+            num_table_ams += 1;
         }
     }
 
     eprintln!(
-        "{} {} SQL entities: {} schemas ({} unique), {} functions, {} types, {} enums, {} sqls, {} ords, {} hashes, {} aggregates, {} triggers",
+        "{} {} SQL entities: {} schemas ({} unique), {} functions, {} types, {} enums, {} sqls, {} ords, {} hashes, {} aggregates, {} triggers, {} event triggers, {} foreign data wrappers, {} index access methods, {} table access methods",
         "  Discovered".bold().green(),
         fns_to_call.len().to_string().bold().cyan(),
         seen_schemas.iter().count().to_string().bold().cyan(),
@@ -347,6 +359,10 @@ pub(crate) fn generate_schema(
         num_hashes.to_string().bold().cyan(),
         num_aggregates.to_string().bold().cyan(),
         num_triggers.to_string().bold().cyan(),
+        num_event_triggers.to_string().bold().cyan(),
+        num_fdws.to_string().bold().cyan(),
+        num_index_ams.to_string().bold().cyan(),
+        num_table_ams.to_string().bold().cyan(),
     );
 
     tracing::debug!("Collecting {} SQL entities", fns_to_call.len());
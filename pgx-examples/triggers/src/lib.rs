@@ -23,7 +23,26 @@ enum TriggerError {
     TryFromInt(#[from] std::num::TryFromIntError),
 }
 
-#[pg_trigger]
+extension_sql!(
+    r#"
+CREATE TABLE test (
+    id serial8 NOT NULL PRIMARY KEY,
+    title varchar(50),
+    description text,
+    payload jsonb
+);
+"#,
+    name = "create_test_table"
+);
+
+#[pg_trigger(
+    name = "test_trigger",
+    table = "test",
+    events = "insert",
+    timing = "before",
+    level = "row",
+    requires = ["create_test_table"]
+)]
 fn trigger_example(
     trigger: &pgx::PgTrigger,
 ) -> Result<PgHeapTuple<'_, impl WhoAllocated<pgx::pg_sys::HeapTupleData>>, TriggerError> {
@@ -41,17 +60,9 @@ fn trigger_example(
 
 extension_sql!(
     r#"
-CREATE TABLE test (
-    id serial8 NOT NULL PRIMARY KEY,
-    title varchar(50),
-    description text,
-    payload jsonb
-);
-
-CREATE TRIGGER test_trigger BEFORE INSERT ON test FOR EACH ROW EXECUTE PROCEDURE trigger_example();
 INSERT INTO test (title, description, payload) VALUES ('Fox', 'a description', '{"key": "value"}');
 "#,
-    name = "create_trigger",
+    name = "insert_test_rows",
     requires = [trigger_example]
 );
 
@@ -0,0 +1,126 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Fields, Lit, Meta, NestedMeta, Type};
+
+pub(crate) fn impl_from_spi_row(ast: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let fields = match &ast.data {
+        Data::Struct(s) => match &s.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new(
+                    ast.span(),
+                    "#[derive(FromSpiRow)] can only be applied to structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                ast.span(),
+                "#[derive(FromSpiRow)] can only be applied to structs",
+            ))
+        }
+    };
+
+    let mut field_values = proc_macro2::TokenStream::new();
+    for field in fields {
+        let ident = field.ident.as_ref().unwrap();
+        let column = column_name(field)?.unwrap_or_else(|| ident.to_string());
+
+        let value = match option_inner_type(&field.ty) {
+            // nullable column
+            Some(inner) => quote! {
+                row.get_column::<#inner>(#column)?
+            },
+
+            // not-nullable column
+            None => {
+                let ty = &field.ty;
+                quote! {
+                    row.get_column::<#ty>(#column)?.ok_or_else(|| {
+                        ::pgx::spi::FromSpiRowError::UnexpectedNull(#column.to_string())
+                    })?
+                }
+            }
+        };
+
+        field_values.extend(quote! { #ident: #value, });
+    }
+
+    let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::pgx::spi::FromSpiRow for #name #ty_generics #where_clause {
+            fn from_spi_row(
+                row: &::pgx::spi::SpiHeapTupleData,
+            ) -> ::std::result::Result<Self, ::pgx::spi::FromSpiRowError> {
+                Ok(Self { #field_values })
+            }
+        }
+    })
+}
+
+/// Find the column name from a `#[pgx(rename = "...")]` attribute, if there is one
+fn column_name(field: &syn::Field) -> syn::Result<Option<String>> {
+    for attr in field.attrs.iter().filter(|attr| attr.path.is_ident("pgx")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            other => {
+                return Err(syn::Error::new(other.span(), "expected #[pgx(rename = \"...\")]"))
+            }
+        };
+
+        for nested in list.nested.iter() {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("rename") => {
+                    match &nv.lit {
+                        Lit::Str(s) => return Ok(Some(s.value())),
+                        other => {
+                            return Err(syn::Error::new(
+                                other.span(),
+                                "`rename` must be a string literal",
+                            ))
+                        }
+                    }
+                }
+                other => {
+                    return Err(syn::Error::new(
+                        other.span(),
+                        "unrecognized #[pgx] attribute for #[derive(FromSpiRow)]",
+                    ))
+                }
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// If `ty` is an `Option<T>`, return `T`
+fn option_inner_type(ty: &Type) -> Option<&Type> {
+    let path = match ty {
+        Type::Path(path) if path.qself.is_none() => &path.path,
+        _ => return None,
+    };
+    let segment = path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => {
+            match args.args.first() {
+                Some(syn::GenericArgument::Type(inner)) => Some(inner),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
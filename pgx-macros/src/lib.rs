@@ -9,7 +9,9 @@ Use of this source code is governed by the MIT license that can be found in the
 
 extern crate proc_macro;
 
+mod from_spi_row;
 mod operators;
+use from_spi_row::impl_from_spi_row;
 use operators::{impl_postgres_eq, impl_postgres_hash, impl_postgres_ord};
 
 use pgx_utils::rewriter::*;
@@ -576,6 +578,56 @@ pub fn pg_extern(attr: TokenStream, item: TokenStream) -> TokenStream {
     }
 }
 
+/**
+Declare a function as a Postgres procedure, created with `CREATE PROCEDURE` and run with `CALL`.
+
+Procedures accept arguments like `#[pg_extern]` functions, but cannot return a value.  They also
+don't accept the function-only attributes such as `immutable`, `strict`, `parallel_safe`, or `cost`.
+
+Unlike functions, a procedure that is `CALL`ed outside of an explicit transaction block may commit
+or roll back its work part way through, by way of [`Spi::execute_nonatomic`](pgx::Spi::execute_nonatomic):
+
+```rust,ignore
+use pgx::*;
+
+#[pg_procedure]
+fn purge_old_events(fcinfo: pg_sys::FunctionCallInfo, batch_size: i64) {
+    Spi::execute_nonatomic(fcinfo, |mut client| loop {
+        let deleted = client
+            .update(
+                "DELETE FROM events WHERE ctid IN (SELECT ctid FROM events WHERE created < now() - '30 days'::interval LIMIT $1)",
+                None,
+                Some(vec![(PgBuiltInOids::INT8OID.oid(), batch_size.into_datum())]),
+            )
+            .len();
+        client.commit();
+        if deleted == 0 {
+            break;
+        }
+    });
+}
+```
+
+Procedures require Postgres 11 or later.
+*/
+#[proc_macro_attribute]
+pub fn pg_procedure(attr: TokenStream, item: TokenStream) -> TokenStream {
+    fn wrapped(attr: TokenStream, item: TokenStream) -> Result<TokenStream, syn::Error> {
+        let pg_procedure_item = PgExtern::new_procedure(attr.into(), item.into())?;
+        Ok(pg_procedure_item.to_token_stream().into())
+    }
+
+    match wrapped(attr, item) {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            let msg = e.to_string();
+            TokenStream::from(quote! {
+              compile_error!(#msg);
+            })
+        }
+    }
+}
+
 /**
 Generate necessary bindings for using the enum with PostgreSQL.
 
@@ -985,6 +1037,33 @@ pub fn postgres_hash(input: TokenStream) -> TokenStream {
     impl_postgres_hash(ast).unwrap_or_else(syn::Error::into_compile_error).into()
 }
 
+/**
+Generate a `pgx::spi::FromSpiRow` implementation, so the struct can be read from SPI results with
+`SpiTupleTable::rows()`.
+
+Each field is read from the result column of the same name.  Fields of type `Option<T>` accept
+`NULL` values, while any other field type will produce an error for a `NULL` value.
+
+```rust,ignore
+use pgx::*;
+
+#[derive(FromSpiRow)]
+struct Dog {
+    name: String,
+    #[pgx(rename = "age_in_years")]
+    age: Option<i32>,
+}
+```
+Optionally accepts the following attributes on fields:
+
+* `rename`: Read the field from a differently-named column, such as `#[pgx(rename = "age_in_years")]`.
+*/
+#[proc_macro_derive(FromSpiRow, attributes(pgx))]
+pub fn from_spi_row(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as syn::DeriveInput);
+    impl_from_spi_row(ast).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
 /**
 Declare a `pgx::Aggregate` implentation on a type as able to used by Postgres as an aggregate.
 
@@ -1039,6 +1118,25 @@ pub fn pgx(_attr: TokenStream, item: TokenStream) -> TokenStream {
 /**
 Create a [PostgreSQL trigger function](https://www.postgresql.org/docs/current/plpgsql-trigger.html)
 
+Given a `table`, the trigger itself is created too:
+
+```rust,ignore
+#[pg_trigger(
+    table = "orders",
+    events = "insert|update of status",
+    timing = "before",
+    level = "row",
+    when = "NEW.x > 0",
+    requires = [ "create_orders" ]
+)]
+fn orders_trigger(trigger: &PgTrigger) -> Result<PgHeapTuple<'_, AllocatedByPostgres>, PgHeapTupleError> {
+    Ok(trigger.current().expect("No current HeapTuple"))
+}
+```
+
+Constraint triggers are created with `constraint = true`, optionally with `deferrable = true` and
+`initially = "deferred"`.
+
 Review the `pgx::trigger_support::PgTrigger` documentation for use.
 
  */
@@ -1069,3 +1167,167 @@ pub fn pg_trigger(attrs: TokenStream, input: TokenStream) -> TokenStream {
         }
     }
 }
+
+/**
+Declare a [PostgreSQL foreign data wrapper](https://www.postgresql.org/docs/current/fdwhandler.html)
+from an `impl pgx::fdw::ForeignDataWrapper for ...` block.
+
+This generates the wrapper's handler and validator functions, named after the type in snake case
+(`my_fdw_handler` and `my_fdw_validator` for `MyFdw`), and the `CREATE FOREIGN DATA WRAPPER`
+statement that uses them.
+
+Review the `pgx::fdw` documentation for use.
+ */
+#[proc_macro_attribute]
+pub fn pg_fdw(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    fn wrapped(item_impl: ItemImpl) -> Result<TokenStream, syn::Error> {
+        use pgx_utils::sql_entity_graph::PgFdw;
+
+        let fdw_item = PgFdw::new(item_impl)?;
+        Ok(fdw_item.to_token_stream().into())
+    }
+
+    let parsed_base = parse_macro_input!(item as syn::ItemImpl);
+    match wrapped(parsed_base) {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            let msg = e.to_string();
+            TokenStream::from(quote! {
+              compile_error!(#msg);
+            })
+        }
+    }
+}
+
+/**
+Declare a [PostgreSQL index access method](https://www.postgresql.org/docs/current/indexam.html)
+from an `impl pgx::index_am::IndexAmRoutine for ...` block.
+
+This generates the access method's handler function, named after the type in snake case
+(`my_index_handler` for `MyIndex`), the `CREATE ACCESS METHOD ... TYPE INDEX` statement that uses
+it, and a `CREATE OPERATOR CLASS ... DEFAULT FOR TYPE` statement for each of its
+`OPERATOR_CLASSES`.
+
+Review the `pgx::index_am` documentation for use.
+ */
+#[proc_macro_attribute]
+pub fn pg_index_am(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    fn wrapped(item_impl: ItemImpl) -> Result<TokenStream, syn::Error> {
+        use pgx_utils::sql_entity_graph::PgIndexAm;
+
+        let index_am_item = PgIndexAm::new(item_impl)?;
+        Ok(index_am_item.to_token_stream().into())
+    }
+
+    let parsed_base = parse_macro_input!(item as syn::ItemImpl);
+    match wrapped(parsed_base) {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            let msg = e.to_string();
+            TokenStream::from(quote! {
+              compile_error!(#msg);
+            })
+        }
+    }
+}
+
+/**
+Declare a [PostgreSQL table access method](https://www.postgresql.org/docs/current/tableam.html)
+from an `impl pgx::table_am::TableAmRoutine for ...` block.
+
+This generates the access method's handler function, named after the type in snake case
+(`my_storage_handler` for `MyStorage`), and the `CREATE ACCESS METHOD ... TYPE TABLE` statement
+that uses it.  Table access methods exist as of Postgres 12.
+
+Review the `pgx::table_am` documentation for use.
+ */
+#[proc_macro_attribute]
+pub fn pg_table_am(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    fn wrapped(item_impl: ItemImpl) -> Result<TokenStream, syn::Error> {
+        use pgx_utils::sql_entity_graph::PgTableAm;
+
+        let table_am_item = PgTableAm::new(item_impl)?;
+        Ok(table_am_item.to_token_stream().into())
+    }
+
+    let parsed_base = parse_macro_input!(item as syn::ItemImpl);
+    match wrapped(parsed_base) {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            let msg = e.to_string();
+            TokenStream::from(quote! {
+              compile_error!(#msg);
+            })
+        }
+    }
+}
+
+/**
+Declare a [PostgreSQL logical decoding output plugin](https://www.postgresql.org/docs/current/logicaldecoding-output-plugin.html)
+from an `impl pgx::logical::OutputPlugin for ...` block.
+
+This generates the `_PG_output_plugin_init` function Postgres calls when a replication slot names
+the extension's library as its plugin, so an extension can only have one.
+
+Review the `pgx::logical` documentation for use.
+ */
+#[proc_macro_attribute]
+pub fn pg_output_plugin(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let item_impl = parse_macro_input!(item as syn::ItemImpl);
+    let self_ty = &item_impl.self_ty;
+
+    TokenStream::from(quote! {
+        #item_impl
+
+        #[no_mangle]
+        #[doc(hidden)]
+        pub unsafe extern "C" fn _PG_output_plugin_init(cb: *mut ::pgx::pg_sys::OutputPluginCallbacks) {
+            ::pgx::logical::output_plugin_init::<#self_ty>(cb)
+        }
+    })
+}
+
+/**
+Create a [PostgreSQL event trigger function](https://www.postgresql.org/docs/current/event-triggers.html)
+
+Given an `event`, optionally limited to a comma separated list of command `tags`, the event trigger
+itself is created too:
+
+```rust,ignore
+#[pg_event_trigger(event = "ddl_command_end", tags = "CREATE TABLE, ALTER TABLE")]
+fn log_ddl(trigger: &PgEventTrigger) -> Result<(), PgTriggerError> {
+    info!("{} ran", trigger.tag()?);
+    Ok(())
+}
+```
+
+Review the `pgx::trigger_support::PgEventTrigger` documentation for use.
+
+ */
+#[proc_macro_attribute]
+pub fn pg_event_trigger(attrs: TokenStream, input: TokenStream) -> TokenStream {
+    fn wrapped(attrs: TokenStream, input: TokenStream) -> Result<TokenStream, syn::Error> {
+        use pgx_utils::sql_entity_graph::{PgEventTrigger, PgEventTriggerAttribute};
+        use syn::parse::Parser;
+        use syn::punctuated::Punctuated;
+        use syn::Token;
+
+        let attributes =
+            Punctuated::<PgEventTriggerAttribute, Token![,]>::parse_terminated.parse(attrs)?;
+        let item_fn: syn::ItemFn = syn::parse(input)?;
+        let event_trigger_item = PgEventTrigger::new(item_fn, attributes)?;
+        let event_trigger_tokens = event_trigger_item.to_token_stream();
+
+        Ok(event_trigger_tokens.into())
+    }
+
+    match wrapped(attrs, input) {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            let msg = e.to_string();
+            TokenStream::from(quote! {
+              compile_error!(#msg);
+            })
+        }
+    }
+}
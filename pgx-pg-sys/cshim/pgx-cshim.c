@@ -15,7 +15,19 @@ Use of this source code is governed by the MIT license that can be found in the
 
 #include "access/htup.h"
 #include "access/htup_details.h"
+#if IS_PG_10 || IS_PG_11
+#include "catalog/index.h"
+#else
+#include "access/tableam.h"
+#endif
 #include "catalog/pg_type.h"
+#if !(IS_PG_10 || IS_PG_11)
+#include "catalog/storage.h"
+#include "catalog/storage_xlog.h"
+#endif
+#include "executor/tuptable.h"
+#include "libpq/hba.h"
+#include "libpq/libpq-be.h"
 #if IS_PG_10 || IS_PG_11
 #include "nodes/relation.h"
 #else
@@ -23,10 +35,12 @@ Use of this source code is governed by the MIT license that can be found in the
 #endif
 #include "nodes/pg_list.h"
 #include "parser/parsetree.h"
+#include "replication/logical.h"
 #include "utils/memutils.h"
 #include "utils/builtins.h"
 #include "utils/array.h"
 #include "storage/spin.h"
+#include "storage/smgr.h"
 
 
 PGDLLEXPORT MemoryContext pgx_GetMemoryContextChunk(void *ptr);
@@ -162,3 +176,137 @@ PGDLLEXPORT bool pgx_SpinLockFree(slock_t *lock);
 bool pgx_SpinLockFree(slock_t *lock) {
     return SpinLockFree(lock);
 }
+
+PGDLLEXPORT char *pgx_Port_database_name(Port *port);
+char *pgx_Port_database_name(Port *port) {
+    return port->database_name;
+}
+
+PGDLLEXPORT char *pgx_Port_user_name(Port *port);
+char *pgx_Port_user_name(Port *port) {
+    return port->user_name;
+}
+
+PGDLLEXPORT char *pgx_Port_remote_host(Port *port);
+char *pgx_Port_remote_host(Port *port) {
+    return port->remote_host;
+}
+
+PGDLLEXPORT char *pgx_Port_remote_hostname(Port *port);
+char *pgx_Port_remote_hostname(Port *port) {
+    return port->remote_hostname;
+}
+
+PGDLLEXPORT char *pgx_Port_remote_port(Port *port);
+char *pgx_Port_remote_port(Port *port) {
+    return port->remote_port;
+}
+
+PGDLLEXPORT const char *pgx_Port_auth_method(Port *port);
+const char *pgx_Port_auth_method(Port *port) {
+    if (port->hba == NULL)
+        return NULL;
+
+    /* these are the names used in pg_hba.conf */
+    switch (port->hba->auth_method) {
+        case uaReject:
+        case uaImplicitReject:
+            return "reject";
+        case uaTrust:
+            return "trust";
+        case uaIdent:
+            return "ident";
+        case uaPassword:
+            return "password";
+        case uaMD5:
+            return "md5";
+        case uaSCRAM:
+            return "scram-sha-256";
+        case uaGSS:
+            return "gss";
+        case uaSSPI:
+            return "sspi";
+        case uaPAM:
+            return "pam";
+        case uaBSD:
+            return "bsd";
+        case uaLDAP:
+            return "ldap";
+        case uaCert:
+            return "cert";
+        case uaRADIUS:
+            return "radius";
+        case uaPeer:
+            return "peer";
+    }
+    return NULL;
+}
+
+PGDLLEXPORT TupleTableSlot *pgx_ExecClearTuple(TupleTableSlot *slot);
+TupleTableSlot *pgx_ExecClearTuple(TupleTableSlot *slot) {
+    return ExecClearTuple(slot);
+}
+
+PGDLLEXPORT Datum pgx_slot_getattr(TupleTableSlot *slot, int attnum, bool *isnull);
+Datum pgx_slot_getattr(TupleTableSlot *slot, int attnum, bool *isnull) {
+    return slot_getattr(slot, attnum, isnull);
+}
+
+PGDLLEXPORT double pgx_index_build_heap_scan(Relation heapRelation, Relation indexRelation, IndexInfo *indexInfo, IndexBuildCallback callback, void *callback_state);
+double pgx_index_build_heap_scan(Relation heapRelation, Relation indexRelation, IndexInfo *indexInfo, IndexBuildCallback callback, void *callback_state) {
+#if IS_PG_10 || IS_PG_11
+    return IndexBuildHeapScan(heapRelation, indexRelation, indexInfo, true, callback, callback_state);
+#else
+    return table_index_build_scan(heapRelation, indexRelation, indexInfo, true, true, callback, callback_state, NULL);
+#endif
+}
+
+#if !(IS_PG_10 || IS_PG_11)
+PGDLLEXPORT void pgx_table_storage_create(const RelFileNode *newrnode, char persistence);
+void pgx_table_storage_create(const RelFileNode *newrnode, char persistence) {
+    SMgrRelation srel = RelationCreateStorage(*newrnode, persistence);
+
+    /* unlogged tables need an init fork, which is empty like heap's */
+    if (persistence == RELPERSISTENCE_UNLOGGED) {
+        smgrcreate(srel, INIT_FORKNUM, false);
+        log_smgrcreate(newrnode, INIT_FORKNUM);
+        smgrimmedsync(srel, INIT_FORKNUM);
+    }
+    smgrclose(srel);
+}
+
+PGDLLEXPORT void pgx_table_storage_truncate(Relation rel);
+void pgx_table_storage_truncate(Relation rel) {
+    RelationTruncate(rel, 0);
+}
+
+PGDLLEXPORT uint64 pgx_table_storage_size(Relation rel, ForkNumber forkNumber);
+uint64 pgx_table_storage_size(Relation rel, ForkNumber forkNumber) {
+    uint64 nblocks = 0;
+
+    RelationOpenSmgr(rel);
+    if (forkNumber == InvalidForkNumber) {
+        for (int i = 0; i <= MAX_FORKNUM; i++)
+            if (smgrexists(rel->rd_smgr, i))
+                nblocks += smgrnblocks(rel->rd_smgr, i);
+    } else if (smgrexists(rel->rd_smgr, forkNumber)) {
+        nblocks = smgrnblocks(rel->rd_smgr, forkNumber);
+    }
+    return nblocks * BLCKSZ;
+}
+#endif
+
+PGDLLEXPORT StringInfo pgx_LogicalDecodingContext_out(LogicalDecodingContext *ctx);
+StringInfo pgx_LogicalDecodingContext_out(LogicalDecodingContext *ctx) {
+    return ctx->out;
+}
+
+PGDLLEXPORT List *pgx_LogicalDecodingContext_output_plugin_options(LogicalDecodingContext *ctx);
+List *pgx_LogicalDecodingContext_output_plugin_options(LogicalDecodingContext *ctx) {
+    return ctx->output_plugin_options;
+}
+
+PGDLLEXPORT void **pgx_LogicalDecodingContext_output_plugin_private(LogicalDecodingContext *ctx);
+void **pgx_LogicalDecodingContext_output_plugin_private(LogicalDecodingContext *ctx) {
+    return &ctx->output_plugin_private;
+}
@@ -134,6 +134,11 @@ pub use pg14_oids::*;
 
 // expose things we want available for all versions
 pub use all_versions::*;
+// bindgen also generates these from headers we include, so say which ones win
+pub use all_versions::{
+    ForeignDataWrapperRelationId, ForeignServerRelationId, ForeignTableRelationId,
+    UserMappingRelationId,
+};
 
 // and things that are version-specific
 #[cfg(feature = "pg10")]
@@ -242,6 +247,129 @@ mod all_versions {
     pub const FrozenTransactionId: super::TransactionId = 2 as super::TransactionId;
     pub const FirstNormalTransactionId: super::TransactionId = 3 as super::TransactionId;
     pub const MaxTransactionId: super::TransactionId = 0xFFFF_FFFF as super::TransactionId;
+    pub const InvalidMultiXactId: super::MultiXactId = 0 as super::MultiXactId;
+
+    /// the catalogs with an `options` column for foreign data wrappers to validate.  These come
+    /// from the `catalog/pg_foreign_*.h` and `catalog/pg_user_mapping.h` headers, whose OIDs are
+    /// fixed across all supported versions
+    pub const ForeignDataWrapperRelationId: super::Oid = 2328;
+    pub const ForeignServerRelationId: super::Oid = 1417;
+    pub const ForeignTableRelationId: super::Oid = 3118;
+    pub const UserMappingRelationId: super::Oid = 1418;
+
+    /// the heap table access method, from `catalog/pg_am.dat`, which TOAST tables use
+    #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14"))]
+    pub const HEAP_TABLE_AM_OID: super::Oid = 2;
+
+    /// the `post_parse_analyze_hook`, from `parser/analyze.h`.  Postgres 14 also hands it the
+    /// query's `JumbleState`, from `utils/queryjumble.h`
+    #[cfg(any(feature = "pg10", feature = "pg11", feature = "pg12", feature = "pg13"))]
+    pub type post_parse_analyze_hook_type =
+        Option<unsafe extern "C" fn(pstate: *mut super::ParseState, query: *mut super::Query)>;
+    #[cfg(feature = "pg14")]
+    pub type post_parse_analyze_hook_type = Option<
+        unsafe extern "C" fn(
+            pstate: *mut super::ParseState,
+            query: *mut super::Query,
+            jstate: *mut JumbleState,
+        ),
+    >;
+
+    #[cfg(feature = "pg14")]
+    #[repr(C)]
+    #[derive(Debug, Default, Copy, Clone)]
+    pub struct LocationLen {
+        pub location: std::os::raw::c_int,
+        pub length: std::os::raw::c_int,
+    }
+
+    #[cfg(feature = "pg14")]
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone)]
+    pub struct JumbleState {
+        pub jumble: *mut std::os::raw::c_uchar,
+        pub jumble_len: super::Size,
+        pub clocations: *mut LocationLen,
+        pub clocations_buf_size: std::os::raw::c_int,
+        pub clocations_count: std::os::raw::c_int,
+        pub highest_extern_param_id: std::os::raw::c_int,
+    }
+
+    extern "C" {
+        pub static mut post_parse_analyze_hook: post_parse_analyze_hook_type;
+    }
+
+    /// the `ClientAuthentication_hook`, from `libpq/auth.h`
+    pub type ClientAuthentication_hook_type =
+        Option<unsafe extern "C" fn(port: *mut super::Port, status: std::os::raw::c_int)>;
+
+    extern "C" {
+        pub static mut ClientAuthentication_hook: ClientAuthentication_hook_type;
+    }
+
+    /// the `object_access_hook` and the argument structs it's given, from `catalog/objectaccess.h`
+    pub type ObjectAccessType = std::os::raw::c_uint;
+    pub const ObjectAccessType_OAT_POST_CREATE: ObjectAccessType = 0;
+    pub const ObjectAccessType_OAT_DROP: ObjectAccessType = 1;
+    pub const ObjectAccessType_OAT_POST_ALTER: ObjectAccessType = 2;
+    pub const ObjectAccessType_OAT_NAMESPACE_SEARCH: ObjectAccessType = 3;
+    pub const ObjectAccessType_OAT_FUNCTION_EXECUTE: ObjectAccessType = 4;
+    #[cfg(any(feature = "pg13", feature = "pg14"))]
+    pub const ObjectAccessType_OAT_TRUNCATE: ObjectAccessType = 5;
+
+    #[repr(C)]
+    #[derive(Debug, Default, Copy, Clone)]
+    pub struct ObjectAccessPostCreate {
+        pub is_internal: bool,
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Default, Copy, Clone)]
+    pub struct ObjectAccessDrop {
+        pub dropflags: std::os::raw::c_int,
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Default, Copy, Clone)]
+    pub struct ObjectAccessPostAlter {
+        pub auxiliary_id: super::Oid,
+        pub is_internal: bool,
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Default, Copy, Clone)]
+    pub struct ObjectAccessNamespaceSearch {
+        pub ereport_on_violation: bool,
+        pub result: bool,
+    }
+
+    pub type object_access_hook_type = Option<
+        unsafe extern "C" fn(
+            access: ObjectAccessType,
+            classId: super::Oid,
+            objectId: super::Oid,
+            subId: std::os::raw::c_int,
+            arg: *mut std::os::raw::c_void,
+        ),
+    >;
+
+    extern "C" {
+        pub static mut object_access_hook: object_access_hook_type;
+    }
+
+    /// the `get_relation_info_hook`, from `optimizer/plancat.h`
+    pub type get_relation_info_hook_type = Option<
+        unsafe extern "C" fn(
+            root: *mut super::PlannerInfo,
+            relationObjectId: super::Oid,
+            inhparent: bool,
+            rel: *mut super::RelOptInfo,
+        ),
+    >;
+
+    extern "C" {
+        pub static mut get_relation_info_hook: get_relation_info_hook_type;
+    }
 
     #[pgx_macros::pg_guard]
     extern "C" {
@@ -456,6 +584,16 @@ mod all_versions {
         ) -> bool;
     }
 
+    #[pg_guard]
+    extern "C" {
+        pub fn LogLogicalMessage(
+            prefix: *const ::std::os::raw::c_char,
+            message: *const ::std::os::raw::c_char,
+            size: super::Size,
+            transactional: bool,
+        ) -> super::XLogRecPtr;
+    }
+
     #[pgx_macros::pg_guard]
     extern "C" {
         fn pgx_SpinLockInit(lock: *mut pg_sys::slock_t);
@@ -468,6 +606,92 @@ mod all_versions {
         pgx_SpinLockAcquire as SpinLockAcquire, pgx_SpinLockFree as SpinLockFree,
         pgx_SpinLockInit as SpinLockInit, pgx_SpinLockRelease as SpinLockRelease,
     };
+
+    #[pgx_macros::pg_guard]
+    extern "C" {
+        pub fn pgx_Port_database_name(port: *mut super::Port) -> *mut std::os::raw::c_char;
+        pub fn pgx_Port_user_name(port: *mut super::Port) -> *mut std::os::raw::c_char;
+        pub fn pgx_Port_remote_host(port: *mut super::Port) -> *mut std::os::raw::c_char;
+        pub fn pgx_Port_remote_hostname(port: *mut super::Port) -> *mut std::os::raw::c_char;
+        pub fn pgx_Port_remote_port(port: *mut super::Port) -> *mut std::os::raw::c_char;
+        pub fn pgx_Port_auth_method(port: *mut super::Port) -> *const std::os::raw::c_char;
+    }
+
+    #[pgx_macros::pg_guard]
+    extern "C" {
+        pub fn pgx_ExecClearTuple(slot: *mut super::TupleTableSlot) -> *mut super::TupleTableSlot;
+        pub fn pgx_slot_getattr(
+            slot: *mut super::TupleTableSlot,
+            attnum: ::std::os::raw::c_int,
+            isnull: *mut bool,
+        ) -> super::Datum;
+        pub fn pgx_index_build_heap_scan(
+            heap_relation: super::Relation,
+            index_relation: super::Relation,
+            index_info: *mut super::IndexInfo,
+            callback: super::IndexBuildCallback,
+            callback_state: *mut ::std::os::raw::c_void,
+        ) -> f64;
+    }
+
+    #[pgx_macros::pg_guard]
+    extern "C" {
+        pub fn pgx_LogicalDecodingContext_out(
+            ctx: *mut super::LogicalDecodingContext,
+        ) -> super::StringInfo;
+        pub fn pgx_LogicalDecodingContext_output_plugin_options(
+            ctx: *mut super::LogicalDecodingContext,
+        ) -> *mut super::List;
+        pub fn pgx_LogicalDecodingContext_output_plugin_private(
+            ctx: *mut super::LogicalDecodingContext,
+        ) -> *mut *mut ::std::os::raw::c_void;
+    }
+
+    #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14"))]
+    #[pgx_macros::pg_guard]
+    extern "C" {
+        pub fn pgx_table_storage_create(
+            newrnode: *const super::RelFileNode,
+            persistence: ::std::os::raw::c_char,
+        );
+        pub fn pgx_table_storage_truncate(rel: super::Relation);
+        pub fn pgx_table_storage_size(rel: super::Relation, fork_number: super::ForkNumber) -> u64;
+    }
+
+    // from `access/heaptoast.h`, which is new in Postgres 13
+    #[cfg(any(feature = "pg13", feature = "pg14"))]
+    #[pgx_macros::pg_guard]
+    extern "C" {
+        pub fn heap_fetch_toast_slice(
+            toastrel: super::Relation,
+            valueid: super::Oid,
+            attrsize: super::int32,
+            sliceoffset: super::int32,
+            slicelength: super::int32,
+            result: *mut super::varlena,
+        );
+    }
+
+    // from `utils/rls.h`, for checking row-level security the way `COPY FROM` does
+    pub type CheckEnableRlsResult = ::std::os::raw::c_uint;
+    pub const CheckEnableRlsResult_RLS_NONE: CheckEnableRlsResult = 0;
+    pub const CheckEnableRlsResult_RLS_NONE_ENV: CheckEnableRlsResult = 1;
+    pub const CheckEnableRlsResult_RLS_ENABLED: CheckEnableRlsResult = 2;
+
+    #[pgx_macros::pg_guard]
+    extern "C" {
+        pub fn check_enable_rls(
+            relid: super::Oid,
+            checkAsUser: super::Oid,
+            noError: bool,
+        ) -> CheckEnableRlsResult;
+    }
+
+    // from `utils/snapmgr.h`, which the generated bindings leave out
+    #[pgx_macros::pg_guard]
+    extern "C" {
+        pub fn GetLatestSnapshot() -> super::Snapshot;
+    }
 }
 
 mod internal {
@@ -572,6 +796,43 @@ mod internal {
                 build_callback_state as *mut std::os::raw::c_void,
             );
         }
+
+        // `COPY FROM` support for `pgx::copy`, from `commands/copy.h` and `parser/parse_relation.h`
+        #[repr(C)]
+        #[derive(Debug, Copy, Clone)]
+        pub struct CopyStateData {
+            _unused: [u8; 0],
+        }
+        pub type CopyState = *mut CopyStateData;
+        pub type copy_data_source_cb = Option<
+            unsafe extern "C" fn(
+                outbuf: *mut std::os::raw::c_void,
+                minread: std::os::raw::c_int,
+                maxread: std::os::raw::c_int,
+            ) -> std::os::raw::c_int,
+        >;
+
+        #[pgx_macros::pg_guard]
+        extern "C" {
+            pub fn addRangeTableEntryForRelation(
+                pstate: *mut crate::ParseState,
+                rel: crate::Relation,
+                alias: *mut crate::Alias,
+                inh: bool,
+                inFromCl: bool,
+            ) -> *mut crate::RangeTblEntry;
+            pub fn BeginCopyFrom(
+                pstate: *mut crate::ParseState,
+                rel: crate::Relation,
+                filename: *const std::os::raw::c_char,
+                is_program: bool,
+                data_source_cb: copy_data_source_cb,
+                attnamelist: *mut crate::List,
+                options: *mut crate::List,
+            ) -> CopyState;
+            pub fn EndCopyFrom(cstate: CopyState);
+            pub fn CopyFrom(cstate: CopyState) -> crate::uint64;
+        }
     }
 
     #[cfg(feature = "pg11")]
@@ -600,6 +861,43 @@ mod internal {
                 std::ptr::null_mut(),
             );
         }
+
+        // `COPY FROM` support for `pgx::copy`, from `commands/copy.h` and `parser/parse_relation.h`
+        #[repr(C)]
+        #[derive(Debug, Copy, Clone)]
+        pub struct CopyStateData {
+            _unused: [u8; 0],
+        }
+        pub type CopyState = *mut CopyStateData;
+        pub type copy_data_source_cb = Option<
+            unsafe extern "C" fn(
+                outbuf: *mut std::os::raw::c_void,
+                minread: std::os::raw::c_int,
+                maxread: std::os::raw::c_int,
+            ) -> std::os::raw::c_int,
+        >;
+
+        #[pgx_macros::pg_guard]
+        extern "C" {
+            pub fn addRangeTableEntryForRelation(
+                pstate: *mut crate::ParseState,
+                rel: crate::Relation,
+                alias: *mut crate::Alias,
+                inh: bool,
+                inFromCl: bool,
+            ) -> *mut crate::RangeTblEntry;
+            pub fn BeginCopyFrom(
+                pstate: *mut crate::ParseState,
+                rel: crate::Relation,
+                filename: *const std::os::raw::c_char,
+                is_program: bool,
+                data_source_cb: copy_data_source_cb,
+                attnamelist: *mut crate::List,
+                options: *mut crate::List,
+            ) -> CopyState;
+            pub fn EndCopyFrom(cstate: CopyState);
+            pub fn CopyFrom(cstate: CopyState) -> crate::uint64;
+        }
     }
 
     #[cfg(feature = "pg12")]
@@ -637,6 +935,44 @@ mod internal {
                 std::ptr::null_mut(),
             );
         }
+
+        // `COPY FROM` support for `pgx::copy`, from `commands/copy.h` and `parser/parse_relation.h`
+        #[repr(C)]
+        #[derive(Debug, Copy, Clone)]
+        pub struct CopyStateData {
+            _unused: [u8; 0],
+        }
+        pub type CopyState = *mut CopyStateData;
+        pub type copy_data_source_cb = Option<
+            unsafe extern "C" fn(
+                outbuf: *mut std::os::raw::c_void,
+                minread: std::os::raw::c_int,
+                maxread: std::os::raw::c_int,
+            ) -> std::os::raw::c_int,
+        >;
+
+        #[pgx_macros::pg_guard]
+        extern "C" {
+            pub fn addRangeTableEntryForRelation(
+                pstate: *mut crate::ParseState,
+                rel: crate::Relation,
+                lockmode: std::os::raw::c_int,
+                alias: *mut crate::Alias,
+                inh: bool,
+                inFromCl: bool,
+            ) -> *mut crate::RangeTblEntry;
+            pub fn BeginCopyFrom(
+                pstate: *mut crate::ParseState,
+                rel: crate::Relation,
+                filename: *const std::os::raw::c_char,
+                is_program: bool,
+                data_source_cb: copy_data_source_cb,
+                attnamelist: *mut crate::List,
+                options: *mut crate::List,
+            ) -> CopyState;
+            pub fn EndCopyFrom(cstate: CopyState);
+            pub fn CopyFrom(cstate: CopyState) -> crate::uint64;
+        }
     }
 
     #[cfg(feature = "pg13")]
@@ -673,6 +1009,44 @@ mod internal {
                 std::ptr::null_mut(),
             );
         }
+
+        // `COPY FROM` support for `pgx::copy`, from `commands/copy.h` and `parser/parse_relation.h`
+        #[repr(C)]
+        #[derive(Debug, Copy, Clone)]
+        pub struct CopyStateData {
+            _unused: [u8; 0],
+        }
+        pub type CopyState = *mut CopyStateData;
+        pub type copy_data_source_cb = Option<
+            unsafe extern "C" fn(
+                outbuf: *mut std::os::raw::c_void,
+                minread: std::os::raw::c_int,
+                maxread: std::os::raw::c_int,
+            ) -> std::os::raw::c_int,
+        >;
+
+        #[pgx_macros::pg_guard]
+        extern "C" {
+            pub fn addRangeTableEntryForRelation(
+                pstate: *mut crate::ParseState,
+                rel: crate::Relation,
+                lockmode: std::os::raw::c_int,
+                alias: *mut crate::Alias,
+                inh: bool,
+                inFromCl: bool,
+            ) -> *mut crate::ParseNamespaceItem;
+            pub fn BeginCopyFrom(
+                pstate: *mut crate::ParseState,
+                rel: crate::Relation,
+                filename: *const std::os::raw::c_char,
+                is_program: bool,
+                data_source_cb: copy_data_source_cb,
+                attnamelist: *mut crate::List,
+                options: *mut crate::List,
+            ) -> CopyState;
+            pub fn EndCopyFrom(cstate: CopyState);
+            pub fn CopyFrom(cstate: CopyState) -> crate::uint64;
+        }
     }
 
     #[cfg(feature = "pg14")]
@@ -709,6 +1083,45 @@ mod internal {
                 std::ptr::null_mut(),
             );
         }
+
+        // `COPY FROM` support for `pgx::copy`, from `commands/copy.h` and `parser/parse_relation.h`
+        #[repr(C)]
+        #[derive(Debug, Copy, Clone)]
+        pub struct CopyFromStateData {
+            _unused: [u8; 0],
+        }
+        pub type CopyFromState = *mut CopyFromStateData;
+        pub type copy_data_source_cb = Option<
+            unsafe extern "C" fn(
+                outbuf: *mut std::os::raw::c_void,
+                minread: std::os::raw::c_int,
+                maxread: std::os::raw::c_int,
+            ) -> std::os::raw::c_int,
+        >;
+
+        #[pgx_macros::pg_guard]
+        extern "C" {
+            pub fn addRangeTableEntryForRelation(
+                pstate: *mut crate::ParseState,
+                rel: crate::Relation,
+                lockmode: std::os::raw::c_int,
+                alias: *mut crate::Alias,
+                inh: bool,
+                inFromCl: bool,
+            ) -> *mut crate::ParseNamespaceItem;
+            pub fn BeginCopyFrom(
+                pstate: *mut crate::ParseState,
+                rel: crate::Relation,
+                whereClause: *mut crate::Node,
+                filename: *const std::os::raw::c_char,
+                is_program: bool,
+                data_source_cb: copy_data_source_cb,
+                attnamelist: *mut crate::List,
+                options: *mut crate::List,
+            ) -> CopyFromState;
+            pub fn EndCopyFrom(cstate: CopyFromState);
+            pub fn CopyFrom(cstate: CopyFromState) -> crate::uint64;
+        }
     }
 }
 
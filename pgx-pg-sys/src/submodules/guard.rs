@@ -137,6 +137,23 @@ impl<T> PgTryResult<T> {
         }
     }
 
+    /// Hand the caught error, if any, to the caller to deal with
+    ///
+    /// A Postgres ERROR is an `Err` holding a [`JumpContext`], and remains the current error in
+    /// Postgres, so the caller must either clean it up (with `FlushErrorState()`) or rethrow it
+    /// with `std::panic::resume_unwind()`.  Any other `Err` is a Rust panic, which should be
+    /// resumed.
+    pub fn into_result(self) -> std::thread::Result<T> {
+        if let Err(e) = &self.0 {
+            if e.is::<JumpContext>() {
+                // the panic that carried the ERROR here recorded its location, which no one will
+                // report, and which would otherwise be mistaken for that of the next panic
+                PANIC_LOCATION.with(|p| p.take());
+            }
+        }
+        self.0
+    }
+
     /// Perform some operation cleanup operation after the try block if an error was thrown.
     ///
     /// In the event an error was caught, it is rethrown.
@@ -209,7 +209,7 @@ fn format_loglines(session_id: &str, loglines: &LogLines) -> String {
     result
 }
 
-fn initialize_test_framework(
+pub(crate) fn initialize_test_framework(
     postgresql_conf: Vec<&'static str>,
 ) -> eyre::Result<(LogLines, String)> {
     let mut state = TEST_MUTEX.lock().unwrap_or_else(|_| {
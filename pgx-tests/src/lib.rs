@@ -23,6 +23,9 @@ pub mod pg_test {
     }
 
     pub fn postgresql_conf_options() -> Vec<&'static str> {
-        vec![]
+        // the output plugin in `tests::logical_tests` needs replication slots that decode the
+        // WAL, and `wal_level` can only be set when the server starts.  The other tests don't
+        // look at the WAL, so the extra it writes for them doesn't matter
+        vec!["wal_level = logical"]
     }
 }
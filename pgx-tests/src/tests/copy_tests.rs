@@ -0,0 +1,186 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+
+#[cfg(any(test, feature = "pg_test"))]
+#[pgx::pg_schema]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::copy::copy_into;
+    use pgx::prelude::*;
+    use pgx::subtransaction;
+
+    #[pg_test]
+    fn test_copy_into() {
+        Spi::run(
+            "CREATE TABLE tests.copy_dogs (id serial, name text, age int, good boolean DEFAULT true)",
+        );
+
+        let dogs = vec![
+            ("Brandy".to_string(), Some(7)),
+            ("tab\tnewline\nbackslash\\".to_string(), None),
+            ("Nami".to_string(), Some(3)),
+        ];
+        let inserted = copy_into("tests.copy_dogs", &["name", "age"], dogs);
+        assert_eq!(inserted, 3);
+
+        assert_eq!(
+            Spi::get_three::<String, i32, bool>(
+                "SELECT name, age, good FROM tests.copy_dogs WHERE id = 1"
+            ),
+            (Some("Brandy".to_string()), Some(7), Some(true))
+        );
+        assert_eq!(
+            Spi::get_two::<String, i32>("SELECT name, age FROM tests.copy_dogs WHERE id = 2"),
+            (Some("tab\tnewline\nbackslash\\".to_string()), None)
+        );
+    }
+
+    #[pg_test]
+    fn test_copy_into_all_columns() {
+        Spi::run("CREATE TABLE tests.copy_numbers (n bigint, label text)");
+        let inserted =
+            copy_into("tests.copy_numbers", &[], (1..=10_000i32).map(|n| (n, format!("#{}", n))));
+        assert_eq!(inserted, 10_000);
+        assert_eq!(
+            Spi::get_one::<i64>("SELECT sum(n)::bigint FROM tests.copy_numbers"),
+            Some(50_005_000)
+        );
+    }
+
+    #[pg_test]
+    fn test_copy_into_heap_tuples() {
+        Spi::run("CREATE TABLE tests.copy_tuples (name text, age int)");
+        let rows = ["Brandy", "Nami"].iter().enumerate().map(|(i, name)| {
+            let mut tuple = PgHeapTuple::new_composite_type("tests.copy_tuples").unwrap();
+            tuple.set_by_name("name", *name).unwrap();
+            tuple.set_by_name("age", i as i32).unwrap();
+            tuple
+        });
+        assert_eq!(copy_into("tests.copy_tuples", &[], rows), 2);
+        assert_eq!(
+            Spi::get_one::<String>("SELECT string_agg(name || age, ',') FROM tests.copy_tuples"),
+            Some("Brandy0,Nami1".to_string())
+        );
+    }
+
+    #[pg_test]
+    fn test_copy_into_heap_tuples_target_columns() {
+        Spi::run("CREATE TABLE tests.copy_narrow (age int, name text)");
+        Spi::run("CREATE TABLE tests.copy_wide (id int, name text, age int, extra text)");
+        let mut tuple = PgHeapTuple::new_composite_type("tests.copy_wide").unwrap();
+        tuple.set_by_name("id", 42).unwrap();
+        tuple.set_by_name("name", "Brandy").unwrap();
+        tuple.set_by_name("age", 7).unwrap();
+        tuple.set_by_name("extra", "ignored").unwrap();
+        assert_eq!(copy_into("tests.copy_narrow", &["name", "age"], vec![tuple]), 1);
+        assert_eq!(
+            Spi::get_two::<String, i32>("SELECT name, age FROM tests.copy_narrow"),
+            (Some("Brandy".to_string()), Some(7))
+        );
+    }
+
+    #[cfg(not(any(feature = "pg10", feature = "pg11")))]
+    #[pg_test]
+    fn test_copy_into_heap_tuples_skips_generated_columns() {
+        Spi::run(
+            "CREATE TABLE tests.copy_generated (
+                name text,
+                shout text GENERATED ALWAYS AS (upper(name)) STORED,
+                age int
+            )",
+        );
+        let mut tuple = PgHeapTuple::new_composite_type("tests.copy_generated").unwrap();
+        tuple.set_by_name("name", "Nami").unwrap();
+        tuple.set_by_name("age", 3).unwrap();
+        assert_eq!(copy_into("tests.copy_generated", &[], vec![tuple]), 1);
+        assert_eq!(
+            Spi::get_two::<String, i32>("SELECT shout, age FROM tests.copy_generated"),
+            (Some("NAMI".to_string()), Some(3))
+        );
+    }
+
+    #[pg_test]
+    fn test_copy_into_fires_triggers() {
+        Spi::run("CREATE TABLE tests.copy_triggered (name text)");
+        Spi::run(
+            "CREATE FUNCTION tests.copy_upper() RETURNS trigger LANGUAGE plpgsql AS $$
+             BEGIN NEW.name = upper(NEW.name); RETURN NEW; END; $$",
+        );
+        Spi::run(
+            "CREATE TRIGGER copy_upper BEFORE INSERT ON tests.copy_triggered
+             FOR EACH ROW EXECUTE PROCEDURE tests.copy_upper()",
+        );
+        copy_into("tests.copy_triggered", &["name"], vec![("brandy",)]);
+        assert_eq!(
+            Spi::get_one::<String>("SELECT name FROM tests.copy_triggered"),
+            Some("BRANDY".to_string())
+        );
+    }
+
+    #[pg_test(
+        error = "new row for relation \"copy_checked\" violates check constraint \"copy_checked_age_check\""
+    )]
+    fn test_copy_into_checks_constraints() {
+        Spi::run("CREATE TABLE tests.copy_checked (age int CHECK (age > 0))");
+        copy_into("tests.copy_checked", &["age"], vec![(1,), (-1,)]);
+    }
+
+    #[pg_test]
+    fn test_copy_into_undefined_names() {
+        Spi::run("CREATE TABLE tests.copy_columns (n int)");
+        let unknown = subtransaction(|| copy_into("tests.copy_columns", &["m"], vec![(1,)]));
+        let unknown = unknown.unwrap_err();
+        assert_eq!(unknown.sqlstate, "42703");
+        assert_eq!(unknown.message, "column \"m\" of relation \"copy_columns\" does not exist");
+
+        let repeated =
+            subtransaction(|| copy_into("tests.copy_columns", &["n", "n"], vec![(1, 1)]));
+        let repeated = repeated.unwrap_err();
+        assert_eq!(repeated.sqlstate, "42701");
+        assert_eq!(repeated.message, "column \"n\" specified more than once");
+
+        let missing = subtransaction(|| copy_into("tests.copy_nowhere", &[], vec![(1,)]));
+        let missing = missing.unwrap_err();
+        assert_eq!(missing.sqlstate, "42P01");
+        assert_eq!(missing.message, "relation \"tests.copy_nowhere\" does not exist");
+    }
+
+    #[pg_test(error = "COPY FROM not supported with row-level security")]
+    fn test_copy_into_row_level_security() {
+        Spi::run("CREATE TABLE tests.copy_secured (owner text)");
+        Spi::run("ALTER TABLE tests.copy_secured ENABLE ROW LEVEL SECURITY");
+        Spi::run(
+            "CREATE POLICY copy_own_rows ON tests.copy_secured \
+             WITH CHECK (owner = current_user)",
+        );
+        Spi::run("CREATE ROLE copy_rls_user");
+        Spi::run("GRANT USAGE ON SCHEMA tests TO copy_rls_user");
+        Spi::run("GRANT INSERT ON tests.copy_secured TO copy_rls_user");
+
+        // the table's owner and superusers aren't subject to its policies
+        Spi::run("SET LOCAL ROLE copy_rls_user");
+        copy_into("tests.copy_secured", &["owner"], vec![("someone else",)]);
+    }
+
+    #[pg_test(error = "cannot execute COPY FROM in a read-only transaction")]
+    fn test_copy_into_read_only_transaction() {
+        Spi::run("CREATE TABLE tests.copy_read_only (n int)");
+        Spi::run("SET TRANSACTION READ ONLY");
+        copy_into("tests.copy_read_only", &["n"], vec![(1,)]);
+    }
+
+    #[pg_test]
+    fn test_copy_into_temp_table_in_read_only_transaction() {
+        Spi::run("CREATE TEMP TABLE copy_temp (n int)");
+        Spi::run("SET TRANSACTION READ ONLY");
+        assert_eq!(copy_into("copy_temp", &["n"], vec![(1,), (2,)]), 2);
+    }
+}
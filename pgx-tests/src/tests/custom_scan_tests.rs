@@ -0,0 +1,106 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+
+#[cfg(any(test, feature = "pg_test"))]
+#[pgx::pg_schema]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::custom_scan::*;
+    use pgx::prelude::*;
+    use pgx::{
+        ExplainStateRef, IntoDatum, PlannerInfoRef, RangeTblEntryRef, RelOptInfoRef,
+        TupleTableSlotRef,
+    };
+
+    const CACHE: &[(i32, &str)] = &[(1, "one"), (2, "two"), (3, "three")];
+
+    /// Scans rows from a cache instead of from the table
+    struct CacheScan {
+        position: usize,
+    }
+
+    impl CustomScan for CacheScan {
+        const NAME: &'static str = "pgx_tests_cache_scan";
+
+        fn add_paths(
+            _root: &PlannerInfoRef<'_>,
+            rel: &mut RelOptInfoRef<'_>,
+            _rti: pg_sys::Index,
+            rte: &RangeTblEntryRef<'_>,
+        ) {
+            if rte
+                .relation()
+                .map(|relation| relation.name() == "custom_scan_cache")
+                .unwrap_or(false)
+            {
+                let path = new_custom_path::<CacheScan>(rel, 0.0, 0.0);
+                unsafe { rel.add_path(path.into_pg() as *mut pg_sys::Path) }
+            }
+        }
+
+        fn create(_plan: &pg_sys::CustomScan) -> Self {
+            CacheScan { position: 0 }
+        }
+
+        fn exec(
+            &mut self,
+            _node: &mut CustomScanStateRef<'_>,
+            slot: &mut TupleTableSlotRef<'_>,
+        ) -> bool {
+            match CACHE.get(self.position) {
+                Some(&(id, name)) => {
+                    self.position += 1;
+                    slot.store_datums(vec![id.into_datum(), name.into_datum()]);
+                    true
+                }
+                None => false,
+            }
+        }
+
+        fn rescan(&mut self, _node: &mut CustomScanStateRef<'_>) {
+            self.position = 0;
+        }
+
+        fn explain(
+            &mut self,
+            _node: &mut CustomScanStateRef<'_>,
+            explain: &mut ExplainStateRef<'_>,
+        ) {
+            explain.property_integer("Cached Rows", CACHE.len() as i64);
+        }
+    }
+
+    #[pg_test]
+    fn test_custom_scan() {
+        register_custom_scan::<CacheScan>();
+        Spi::run("CREATE TABLE tests.custom_scan_cache (id int, name text)");
+
+        let explain =
+            Spi::explain_with("SELECT * FROM tests.custom_scan_cache", Default::default());
+        assert_eq!("Custom Scan", explain.plan.node_type);
+        assert_eq!(
+            Some("pgx_tests_cache_scan"),
+            explain.plan.properties.get("Custom Plan Provider").and_then(|value| value.as_str())
+        );
+        assert_eq!(
+            Some(3),
+            explain.plan.properties.get("Cached Rows").and_then(|value| value.as_i64())
+        );
+
+        assert_eq!(Some(3), Spi::get_one::<i64>("SELECT count(*) FROM tests.custom_scan_cache"));
+        assert_eq!(
+            Some("two,three".to_string()),
+            Spi::get_one::<String>(
+                "SELECT string_agg(name, ',' ORDER BY id) FROM tests.custom_scan_cache WHERE id > 1"
+            )
+        );
+    }
+}
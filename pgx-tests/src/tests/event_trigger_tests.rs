@@ -0,0 +1,130 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+
+#[cfg(any(test, feature = "pg_test"))]
+#[pgx::pg_schema]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::prelude::*;
+    use pgx::{PgEventTrigger, PgTriggerError};
+    use std::sync::Mutex;
+
+    /// What the event triggers below saw, a line at a time
+    static EVENTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    fn take_events() -> Vec<String> {
+        std::mem::take(&mut *EVENTS.lock().unwrap())
+    }
+
+    #[pg_event_trigger(event = "ddl_command_end", tags = "COMMENT")]
+    fn event_trigger_on_comment(trigger: &PgEventTrigger) -> Result<(), PgTriggerError> {
+        let comment =
+            unsafe { trigger.parse_tree_as::<pg_sys::CommentStmt>(pg_sys::NodeTag_T_CommentStmt) };
+        let mut events = EVENTS.lock().unwrap();
+        events.push(format!(
+            "{} {} {}",
+            trigger.event()?.to_string(),
+            trigger.tag()?,
+            comment.map_or(false, |comment| comment.objtype == pg_sys::ObjectType_OBJECT_TABLE)
+        ));
+        for command in trigger.ddl_commands()? {
+            events.push(format!(
+                "{} {} {}",
+                command.command_tag, command.object_type, command.object_identity
+            ));
+        }
+        Ok(())
+    }
+
+    #[pg_event_trigger(event = "sql_drop", tags = "DROP SEQUENCE")]
+    fn event_trigger_on_drop(trigger: &PgEventTrigger) -> Result<(), PgTriggerError> {
+        let mut events = EVENTS.lock().unwrap();
+        for object in trigger.dropped_objects()?.filter(|object| object.original) {
+            events.push(format!(
+                "{} {} {}",
+                trigger.event()?.to_string(),
+                object.object_type,
+                object.address_names.join(".")
+            ));
+        }
+        Ok(())
+    }
+
+    #[pg_event_trigger]
+    fn event_trigger_function_only(_trigger: &PgEventTrigger) -> Result<(), PgTriggerError> {
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_event_trigger_ddl_commands() {
+        Spi::run("CREATE TABLE tests.event_trigger_things (id int)");
+        take_events();
+        Spi::run("COMMENT ON TABLE tests.event_trigger_things IS 'things'");
+        assert_eq!(
+            vec![
+                "ddl_command_end COMMENT true".to_string(),
+                "COMMENT table tests.event_trigger_things".to_string(),
+            ],
+            take_events()
+        );
+    }
+
+    #[pg_test]
+    fn test_event_trigger_dropped_objects() {
+        Spi::run("CREATE SEQUENCE tests.event_trigger_sequence");
+        take_events();
+        Spi::run("DROP SEQUENCE tests.event_trigger_sequence");
+        assert_eq!(
+            vec!["sql_drop sequence tests.event_trigger_sequence".to_string()],
+            take_events()
+        );
+    }
+
+    #[pg_test]
+    fn test_event_trigger_tags() {
+        Spi::run("CREATE TABLE tests.event_trigger_untagged_table (id int)");
+        take_events();
+        Spi::run("CREATE SEQUENCE tests.event_trigger_untagged");
+        // `sql_drop` fires, but not for the `DROP SEQUENCE` trigger
+        Spi::run("DROP TABLE tests.event_trigger_untagged_table");
+        assert_eq!(
+            Some(0),
+            Spi::get_one::<i64>(
+                "SELECT count(*) FROM pg_class WHERE relname = 'event_trigger_untagged_table'"
+            )
+        );
+        assert!(take_events().is_empty());
+    }
+
+    #[pg_test]
+    fn test_event_trigger_function_only() {
+        assert_eq!(
+            Some("event_trigger".to_string()),
+            Spi::get_one::<String>(
+                "SELECT prorettype::regtype::text FROM pg_proc \
+                 WHERE proname = 'event_trigger_function_only'"
+            )
+        );
+        assert_eq!(
+            Some(0),
+            Spi::get_one::<i64>(
+                "SELECT count(*) FROM pg_event_trigger WHERE evtname = 'event_trigger_function_only'"
+            )
+        );
+        assert_eq!(
+            Some("ddl_command_end {COMMENT}".to_string()),
+            Spi::get_one::<String>(
+                "SELECT evtevent || ' ' || evttags::text FROM pg_event_trigger \
+                 WHERE evtname = 'event_trigger_on_comment'"
+            )
+        );
+    }
+}
@@ -0,0 +1,279 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+
+#[cfg(any(test, feature = "pg_test"))]
+#[pgx::pg_schema]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::fdw::*;
+    use pgx::prelude::*;
+    use pgx::{
+        ExplainStateRef, IntoDatum, PgRelation, PlannerInfoRef, RelOptInfoRef, TupleTableSlotRef,
+    };
+    use serde::Deserialize;
+    use std::sync::Mutex;
+
+    /// What each `INSERT`, `UPDATE`, and `DELETE` asked the wrapper to do
+    static MODIFICATIONS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    #[derive(Deserialize)]
+    struct SeriesOptions {
+        start: i32,
+        stop: i32,
+        #[serde(default = "default_step")]
+        step: i32,
+    }
+
+    fn default_step() -> i32 {
+        1
+    }
+
+    impl SeriesOptions {
+        fn len(&self) -> i32 {
+            ((self.stop - self.start) / self.step + 1).max(0)
+        }
+    }
+
+    /// Produces the numbers from `start` to `stop` as the column `n`, and reports what it's asked
+    /// to modify rather than changing anything
+    struct SeriesFdw {
+        options: SeriesOptions,
+        next: i32,
+    }
+
+    #[pg_fdw]
+    impl ForeignDataWrapper for SeriesFdw {
+        const NAME: &'static str = "pgx_tests_series_fdw";
+        const MODIFY_OPERATIONS: ModifyOperations = ModifyOperations::ALL;
+        const ROWID_COLUMN: Option<&'static str> = Some("n");
+        type Options = SeriesOptions;
+
+        fn get_rel_size(
+            _root: &PlannerInfoRef<'_>,
+            rel: &mut RelOptInfoRef<'_>,
+            _foreigntableid: pg_sys::Oid,
+            plan_state: &mut FdwPlanState<SeriesOptions>,
+        ) {
+            let rows = plan_state.options().len();
+            rel.set_rows(rows as f64);
+            plan_state.set_state(rows);
+        }
+
+        fn get_paths(
+            root: &PlannerInfoRef<'_>,
+            rel: &mut RelOptInfoRef<'_>,
+            _foreigntableid: pg_sys::Oid,
+            plan_state: &mut FdwPlanState<SeriesOptions>,
+        ) {
+            // cost the path by the row count `get_rel_size()` left, rather than by the estimate
+            let rows = *plan_state.state::<i32>().expect("no state from get_rel_size()") as f64;
+            unsafe {
+                let path = pg_sys::create_foreignscan_path(
+                    root.as_ptr(),
+                    rel.as_ptr(),
+                    std::ptr::null_mut(),
+                    rows,
+                    0.0,
+                    rows,
+                    std::ptr::null_mut(),
+                    std::ptr::null_mut(),
+                    std::ptr::null_mut(),
+                    std::ptr::null_mut(),
+                );
+                rel.add_path(path as *mut pg_sys::Path)
+            }
+        }
+
+        fn begin_scan(
+            _node: &mut ForeignScanStateRef<'_>,
+            options: SeriesOptions,
+            _eflags: i32,
+        ) -> Self {
+            let next = options.start;
+            SeriesFdw { options, next }
+        }
+
+        fn iterate_scan(
+            &mut self,
+            _node: &mut ForeignScanStateRef<'_>,
+            slot: &mut TupleTableSlotRef<'_>,
+        ) -> bool {
+            if self.next > self.options.stop {
+                return false;
+            }
+            slot.store_datums(vec![self.next.into_datum()]);
+            self.next += self.options.step;
+            true
+        }
+
+        fn rescan(&mut self, _node: &mut ForeignScanStateRef<'_>) {
+            self.next = self.options.start;
+        }
+
+        fn explain_scan(
+            &mut self,
+            _node: &mut ForeignScanStateRef<'_>,
+            explain: &mut ExplainStateRef<'_>,
+        ) {
+            let options = &self.options;
+            explain.property_text(
+                "Series",
+                &format!("{} to {} by {}", options.start, options.stop, options.step),
+            );
+        }
+
+        fn begin_modify(_relation: &PgRelation, options: SeriesOptions) -> Self {
+            let next = options.start;
+            SeriesFdw { options, next }
+        }
+
+        fn insert(&mut self, slot: &mut TupleTableSlotRef<'_>) -> bool {
+            let n = slot.get_by_name::<i32>("n").unwrap().expect("n is NULL");
+            MODIFICATIONS.lock().unwrap().push(format!("insert {}", n));
+            true
+        }
+
+        fn update(&mut self, rowid: RowId, slot: &mut TupleTableSlotRef<'_>) -> bool {
+            let n = rowid.value::<i32>().unwrap().expect("rowid is NULL");
+            let new_n = slot.get_by_name::<i32>("n").unwrap().expect("n is NULL");
+            MODIFICATIONS.lock().unwrap().push(format!("update {} to {}", n, new_n));
+            true
+        }
+
+        fn delete(&mut self, rowid: RowId) -> bool {
+            assert_eq!(rowid.type_oid(), pg_sys::INT4OID);
+            let n = rowid.value::<i32>().unwrap().expect("rowid is NULL");
+            MODIFICATIONS.lock().unwrap().push(format!("delete {}", n));
+            true
+        }
+
+        fn import_foreign_schema(stmt: &ImportForeignSchema) -> Vec<String> {
+            [("one_to_ten", 10), ("one_to_twenty", 20)]
+                .iter()
+                .map(|(name, stop)| {
+                    format!(
+                        "CREATE FOREIGN TABLE {name} (n int) SERVER {server} OPTIONS (start '1', stop '{stop}', step '{step}')",
+                        name = name,
+                        server = stmt.server_name,
+                        stop = stop,
+                        step = stmt.remote_schema.len(),
+                    )
+                })
+                .collect()
+        }
+    }
+
+    #[pg_test]
+    fn test_fdw_parse_options() {
+        let options = vec![
+            ("start".to_string(), "1".to_string()),
+            ("stop".to_string(), "10".to_string()),
+            ("step".to_string(), "3".to_string()),
+        ];
+        let parsed = parse_options::<SeriesOptions>(&options).unwrap();
+        assert_eq!((1, 10, 3), (parsed.start, parsed.stop, parsed.step));
+        assert_eq!(4, parsed.len());
+
+        let options = vec![("start".to_string(), "1".to_string())];
+        assert!(parse_options::<SeriesOptions>(&options).is_err());
+        let options =
+            vec![("start".to_string(), "one".to_string()), ("stop".to_string(), "2".to_string())];
+        assert!(parse_options::<SeriesOptions>(&options).is_err());
+    }
+
+    #[pg_test]
+    fn test_fdw_table_options_override_server_options() {
+        Spi::run(
+            "CREATE SERVER fdw_series_server FOREIGN DATA WRAPPER pgx_tests_series_fdw \
+             OPTIONS (start '1', stop '100', step '10')",
+        );
+        Spi::run(
+            "CREATE FOREIGN TABLE tests.fdw_series (n int) \
+             SERVER fdw_series_server OPTIONS (stop '5', step '2')",
+        );
+        let relid = Spi::get_one::<pg_sys::Oid>("SELECT 'tests.fdw_series'::regclass::oid");
+        assert_eq!(
+            vec![
+                ("start".to_string(), "1".to_string()),
+                ("stop".to_string(), "5".to_string()),
+                ("step".to_string(), "2".to_string()),
+            ],
+            foreign_table_options(relid.unwrap())
+        );
+        assert_eq!(
+            Some("1,3,5".to_string()),
+            Spi::get_one::<String>("SELECT string_agg(n::text, ',') FROM tests.fdw_series")
+        );
+    }
+
+    #[pg_test]
+    fn test_fdw_plan_and_explain() {
+        Spi::run("CREATE SERVER fdw_explain_server FOREIGN DATA WRAPPER pgx_tests_series_fdw");
+        Spi::run(
+            "CREATE FOREIGN TABLE tests.fdw_explain (n int) \
+             SERVER fdw_explain_server OPTIONS (start '0', stop '99', step '3')",
+        );
+        let explain = Spi::explain_with("SELECT * FROM tests.fdw_explain", Default::default());
+        assert_eq!("Foreign Scan", explain.plan.node_type);
+        // the estimate made by `get_rel_size()` from the options is the path's, and so the plan's
+        assert_eq!(Some(34.0), explain.plan.plan_rows);
+        // as is the cost, from the row count `get_rel_size()` kept for `get_paths()`
+        assert_eq!(Some(34.0), explain.plan.total_cost);
+        assert_eq!(Some(&serde_json::json!("0 to 99 by 3")), explain.plan.properties.get("Series"));
+    }
+
+    #[pg_test]
+    fn test_fdw_modify_rowids() {
+        Spi::run("CREATE SERVER fdw_modify_server FOREIGN DATA WRAPPER pgx_tests_series_fdw");
+        Spi::run(
+            "CREATE FOREIGN TABLE tests.fdw_modify (n int) \
+             SERVER fdw_modify_server OPTIONS (start '1', stop '3')",
+        );
+        MODIFICATIONS.lock().unwrap().clear();
+
+        Spi::run("INSERT INTO tests.fdw_modify VALUES (10), (11)");
+        Spi::run("UPDATE tests.fdw_modify SET n = n * 100 WHERE n >= 2");
+        Spi::run("DELETE FROM tests.fdw_modify WHERE n = 1");
+        assert_eq!(
+            vec!["insert 10", "insert 11", "update 2 to 200", "update 3 to 300", "delete 1"],
+            *MODIFICATIONS.lock().unwrap()
+        );
+    }
+
+    #[pg_test(error = "invalid option \"bogus\" (valid options are: start, stop, step)")]
+    fn test_fdw_invalid_option() {
+        Spi::run(
+            "CREATE SERVER fdw_invalid_server FOREIGN DATA WRAPPER pgx_tests_series_fdw \
+             OPTIONS (bogus 'true')",
+        );
+    }
+
+    #[pg_test]
+    fn test_fdw_import_foreign_schema() {
+        Spi::run("CREATE SERVER fdw_import_server FOREIGN DATA WRAPPER pgx_tests_series_fdw");
+        Spi::run(
+            "IMPORT FOREIGN SCHEMA by_two EXCEPT (one_to_twenty) \
+             FROM SERVER fdw_import_server INTO tests",
+        );
+        assert_eq!(
+            Some(1),
+            Spi::get_one::<i64>(
+                "SELECT count(*) FROM pg_foreign_table JOIN pg_class ON ftrelid = pg_class.oid \
+                 WHERE relname IN ('one_to_ten', 'one_to_twenty')"
+            )
+        );
+        // the wrapper made the step the length of the remote schema's name
+        assert_eq!(
+            Some("1,7".to_string()),
+            Spi::get_one::<String>("SELECT string_agg(n::text, ',') FROM tests.one_to_ten")
+        );
+    }
+}
@@ -15,7 +15,10 @@ mod tests {
 
     use pgx::hooks::*;
     use pgx::prelude::*;
-    use pgx::PgList;
+    use pgx::{
+        warning, ErrorDataRef, PgList, PgSqlErrorCode, PlannerInfoRef, RangeTblEntryRef,
+        RelOptInfoRef,
+    };
 
     #[pg_test]
     unsafe fn test_callbacks() {
@@ -25,6 +28,18 @@ mod tests {
             events: u32,
         }
         impl PgHooks for TestHook {
+            fn hook_points(&self) -> HookPoints {
+                HookPoints {
+                    executor_start: true,
+                    executor_run: true,
+                    executor_finish: true,
+                    executor_end: true,
+                    executor_check_perms: true,
+                    planner: true,
+                    ..HookPoints::NONE
+                }
+            }
+
             fn executor_start(
                 &mut self,
                 query_desc: PgBox<QueryDesc>,
@@ -101,4 +116,343 @@ mod tests {
         // TODO:  it'd be nice to also test that .commit() and .abort() also get called
         //    but I don't see how to do that since we're running *inside* a transaction here
     }
+
+    #[pg_test]
+    unsafe fn test_only_requested_hook_points_installed() {
+        struct AccessOnlyHook;
+        impl PgHooks for AccessOnlyHook {
+            fn hook_points(&self) -> HookPoints {
+                HookPoints { object_access: true, ..HookPoints::NONE }
+            }
+        }
+
+        let executor_start = pg_sys::ExecutorStart_hook.map(|hook| hook as usize);
+        let emit_log = pg_sys::emit_log_hook.map(|hook| hook as usize);
+
+        static mut HOOK: AccessOnlyHook = AccessOnlyHook;
+        let id = pgx::hooks::register_hook(&mut HOOK);
+        assert!(pg_sys::object_access_hook.is_some());
+        assert_eq!(executor_start, pg_sys::ExecutorStart_hook.map(|hook| hook as usize));
+        assert_eq!(emit_log, pg_sys::emit_log_hook.map(|hook| hook as usize));
+        pgx::hooks::unregister_hook(id);
+    }
+
+    #[pg_test]
+    unsafe fn test_hook_chaining() {
+        use pgx::pg_sys::*;
+
+        static mut CALLS: Vec<&'static str> = Vec::new();
+
+        struct ChainedHook {
+            name: &'static str,
+        }
+        impl PgHooks for ChainedHook {
+            fn hook_points(&self) -> HookPoints {
+                HookPoints { executor_start: true, ..HookPoints::NONE }
+            }
+
+            fn executor_start(
+                &mut self,
+                query_desc: PgBox<QueryDesc>,
+                eflags: i32,
+                prev_hook: fn(PgBox<QueryDesc>, i32) -> HookResult<()>,
+            ) -> HookResult<()> {
+                unsafe { CALLS.push(self.name) };
+                let result = prev_hook(query_desc, eflags);
+                unsafe { CALLS.push(self.name) };
+                result
+            }
+        }
+
+        static mut FIRST: ChainedHook = ChainedHook { name: "first" };
+        static mut SECOND: ChainedHook = ChainedHook { name: "second" };
+        let first = pgx::hooks::register_hook(&mut FIRST);
+        let second = pgx::hooks::register_hook(&mut SECOND);
+
+        Spi::run("SELECT 1");
+        assert_eq!(vec!["first", "second", "second", "first"], CALLS);
+
+        assert!(pgx::hooks::unregister_hook(first).is_some());
+        assert!(pgx::hooks::unregister_hook(first).is_none());
+        CALLS.clear();
+        Spi::run("SELECT 1");
+        assert_eq!(vec!["second", "second"], CALLS);
+
+        assert!(pgx::hooks::unregister_hook(second).is_some());
+        CALLS.clear();
+        Spi::run("SELECT 1");
+        assert!(CALLS.is_empty());
+    }
+
+    #[pg_test]
+    unsafe fn test_unregister_after_error_in_hook() {
+        use pgx::pg_sys::*;
+
+        static mut CALLS: usize = 0;
+
+        struct ErroringHook;
+        impl PgHooks for ErroringHook {
+            fn hook_points(&self) -> HookPoints {
+                HookPoints { executor_run: true, ..HookPoints::NONE }
+            }
+
+            fn executor_run(
+                &mut self,
+                query_desc: PgBox<QueryDesc>,
+                direction: ScanDirection,
+                count: u64,
+                execute_once: bool,
+                prev_hook: fn(PgBox<QueryDesc>, ScanDirection, u64, bool) -> HookResult<()>,
+            ) -> HookResult<()> {
+                unsafe { CALLS += 1 };
+                prev_hook(query_desc, direction, count, execute_once)
+            }
+        }
+
+        static mut HOOK: ErroringHook = ErroringHook;
+        let id = pgx::hooks::register_hook(&mut HOOK);
+
+        // the ERROR is raised by the executor our hook chains to, and jumps right over it
+        let result = pgx::subtransaction(|| Spi::run("SELECT 1/0"));
+        assert!(result.is_err());
+        assert_eq!(CALLS, 1);
+
+        // hooks dispatch from the start of the chain again...
+        Spi::run("SELECT 1");
+        assert_eq!(CALLS, 2);
+
+        // ...and we're no longer considered to be inside a hook
+        assert!(pgx::hooks::unregister_hook(id).is_some());
+        Spi::run("SELECT 1");
+        assert_eq!(CALLS, 2);
+    }
+
+    #[pg_test]
+    unsafe fn test_post_parse_analyze() {
+        use pgx::pg_sys::*;
+
+        struct FingerprintHook {
+            fingerprints: Vec<u64>,
+        }
+        impl PgHooks for FingerprintHook {
+            fn hook_points(&self) -> HookPoints {
+                HookPoints { post_parse_analyze: true, ..HookPoints::NONE }
+            }
+
+            fn post_parse_analyze(
+                &mut self,
+                parse_state: PgBox<ParseState>,
+                query: PgBox<Query>,
+                jumble_state: Option<PgBox<JumbleState>>,
+                prev_hook: fn(
+                    PgBox<ParseState>,
+                    PgBox<Query>,
+                    Option<PgBox<JumbleState>>,
+                ) -> HookResult<()>,
+            ) -> HookResult<()> {
+                self.fingerprints.push(unsafe { pgx::query_fingerprint(query.as_ptr()) });
+                prev_hook(parse_state, query, jumble_state)
+            }
+        }
+
+        static mut HOOK: FingerprintHook = FingerprintHook { fingerprints: Vec::new() };
+        let id = pgx::hooks::register_hook(&mut HOOK);
+        Spi::run("SELECT 1 + 1 AS a");
+        Spi::run("SELECT   2 + 3 AS b");
+        Spi::run("SELECT NULL::int + 42");
+        Spi::run("SELECT 1.5 + 1");
+        Spi::run("SELECT relname FROM pg_class WHERE oid = 1259");
+        Spi::run("SELECT relname FROM pg_class c WHERE c.oid = 2619");
+        pgx::hooks::unregister_hook(id);
+
+        let fingerprints = &HOOK.fingerprints;
+        assert_eq!(6, fingerprints.len());
+        assert!(fingerprints.iter().all(|fingerprint| *fingerprint != 0));
+        assert_eq!(fingerprints[0], fingerprints[1]);
+        assert_eq!(fingerprints[0], fingerprints[2]);
+        assert_ne!(fingerprints[0], fingerprints[3]);
+        assert_eq!(fingerprints[4], fingerprints[5]);
+        assert_ne!(fingerprints[0], fingerprints[4]);
+    }
+
+    #[pg_test]
+    unsafe fn test_emit_log() {
+        struct LogHook {
+            messages: Vec<(Option<PgSqlErrorCode>, String)>,
+        }
+        impl PgHooks for LogHook {
+            fn hook_points(&self) -> HookPoints {
+                HookPoints { emit_log: true, ..HookPoints::NONE }
+            }
+
+            fn emit_log(
+                &mut self,
+                mut error_data: ErrorDataRef<'_>,
+                prev_hook: fn(ErrorDataRef<'_>) -> HookResult<()>,
+            ) -> HookResult<()> {
+                let message = error_data.message().unwrap_or_default().into_owned();
+                if message.starts_with("emit_log test") {
+                    self.messages.push((error_data.sqlstate(), message));
+                    error_data.set_output_to_server(false);
+
+                    // this must not find its way back here
+                    warning!("emit_log test from inside the hook");
+                }
+                prev_hook(error_data)
+            }
+        }
+
+        static mut HOOK: LogHook = LogHook { messages: Vec::new() };
+        let id = pgx::hooks::register_hook(&mut HOOK);
+        warning!("emit_log test");
+        pgx::hooks::unregister_hook(id);
+
+        assert_eq!(
+            vec![(Some(PgSqlErrorCode::ERRCODE_WARNING), "emit_log test".to_string())],
+            HOOK.messages
+        );
+    }
+
+    #[pg_test]
+    unsafe fn test_object_access() {
+        struct AuditHook {
+            events: Vec<(&'static str, pg_sys::Oid)>,
+            created: Vec<String>,
+        }
+        impl PgHooks for AuditHook {
+            fn hook_points(&self) -> HookPoints {
+                HookPoints { object_access: true, ..HookPoints::NONE }
+            }
+
+            fn object_access(
+                &mut self,
+                access: ObjectAccess<'_>,
+                prev_hook: fn(ObjectAccess<'_>) -> HookResult<()>,
+            ) -> HookResult<()> {
+                if access.is_relation() && access.sub_id == 0 {
+                    let event = match access.event {
+                        ObjectAccessEvent::PostCreate { .. } => {
+                            self.created.push(access.relation().unwrap().name().to_string());
+                            "create"
+                        }
+                        ObjectAccessEvent::PostAlter { .. } => "alter",
+                        ObjectAccessEvent::Drop { .. } => "drop",
+                        _ => "other",
+                    };
+                    self.events.push((event, access.object_id));
+                }
+                prev_hook(access)
+            }
+        }
+
+        static mut HOOK: AuditHook = AuditHook { events: Vec::new(), created: Vec::new() };
+        let id = pgx::hooks::register_hook(&mut HOOK);
+        Spi::run("CREATE TABLE tests.object_access_test (id int)");
+        let oid = Spi::get_one::<pg_sys::Oid>("SELECT 'tests.object_access_test'::regclass::oid")
+            .unwrap();
+        Spi::run("ALTER TABLE tests.object_access_test RENAME TO object_access_renamed");
+        Spi::run("DROP TABLE tests.object_access_renamed");
+        pgx::hooks::unregister_hook(id);
+
+        HOOK.events.dedup();
+        assert_eq!(vec![("create", oid), ("alter", oid), ("drop", oid)], HOOK.events);
+        assert_eq!(vec!["object_access_test".to_string()], HOOK.created);
+    }
+
+    #[pg_test]
+    unsafe fn test_planner_path_hooks() {
+        use pgx::pg_sys::*;
+
+        struct PathHook {
+            scanned: Vec<(u32, String)>,
+            joins: usize,
+        }
+        impl PgHooks for PathHook {
+            fn hook_points(&self) -> HookPoints {
+                HookPoints {
+                    get_relation_info: true,
+                    set_rel_pathlist: true,
+                    set_join_pathlist: true,
+                    ..HookPoints::NONE
+                }
+            }
+
+            fn get_relation_info(
+                &mut self,
+                root: PlannerInfoRef<'_>,
+                relation_oid: Oid,
+                inhparent: bool,
+                mut rel: RelOptInfoRef<'_>,
+                prev_hook: fn(PlannerInfoRef<'_>, Oid, bool, RelOptInfoRef<'_>) -> HookResult<()>,
+            ) -> HookResult<()> {
+                let rte = root.rt_fetch(rel.relid()).unwrap();
+                assert_eq!(Some(relation_oid), rte.relid());
+                if rte.relation().unwrap().name() == "planner_hook_a" {
+                    rel.set_tuples(12345.0);
+                }
+                prev_hook(root, relation_oid, inhparent, rel)
+            }
+
+            fn set_rel_pathlist(
+                &mut self,
+                root: PlannerInfoRef<'_>,
+                rel: RelOptInfoRef<'_>,
+                rti: Index,
+                rte: RangeTblEntryRef<'_>,
+                prev_hook: fn(
+                    PlannerInfoRef<'_>,
+                    RelOptInfoRef<'_>,
+                    Index,
+                    RangeTblEntryRef<'_>,
+                ) -> HookResult<()>,
+            ) -> HookResult<()> {
+                assert_eq!(rti, rel.relid());
+                assert!(!rel.pathlist().is_empty());
+                self.scanned.push((rti, rte.name().unwrap().to_string()));
+                prev_hook(root, rel, rti, rte)
+            }
+
+            fn set_join_pathlist(
+                &mut self,
+                root: PlannerInfoRef<'_>,
+                joinrel: RelOptInfoRef<'_>,
+                outerrel: RelOptInfoRef<'_>,
+                innerrel: RelOptInfoRef<'_>,
+                jointype: JoinType,
+                extra: PgBox<JoinPathExtraData>,
+                prev_hook: fn(
+                    PlannerInfoRef<'_>,
+                    RelOptInfoRef<'_>,
+                    RelOptInfoRef<'_>,
+                    RelOptInfoRef<'_>,
+                    JoinType,
+                    PgBox<JoinPathExtraData>,
+                ) -> HookResult<()>,
+            ) -> HookResult<()> {
+                assert_eq!(RelOptKind_RELOPT_JOINREL, joinrel.kind());
+                self.joins += 1;
+                prev_hook(root, joinrel, outerrel, innerrel, jointype, extra)
+            }
+        }
+
+        Spi::run("CREATE TABLE tests.planner_hook_a (id int)");
+        Spi::run("CREATE TABLE tests.planner_hook_b (id int)");
+
+        static mut HOOK: PathHook = PathHook { scanned: Vec::new(), joins: 0 };
+        let id = pgx::hooks::register_hook(&mut HOOK);
+        let explain = Spi::explain_with(
+            "SELECT * FROM tests.planner_hook_a a JOIN tests.planner_hook_b b ON a.id = b.id",
+            Default::default(),
+        );
+        let estimate = Spi::explain_with("SELECT * FROM tests.planner_hook_a", Default::default());
+        pgx::hooks::unregister_hook(id);
+
+        assert_eq!(
+            vec![(1, "a".to_string()), (2, "b".to_string()), (1, "planner_hook_a".to_string())],
+            HOOK.scanned
+        );
+        assert!(HOOK.joins > 0);
+        assert_eq!(2, explain.plan.plans.len());
+        assert_eq!(Some(12345.0), estimate.plan.plan_rows);
+    }
 }
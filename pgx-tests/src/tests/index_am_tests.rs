@@ -0,0 +1,290 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+
+#[cfg(any(test, feature = "pg_test"))]
+#[pgx::pg_schema]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::index_am::*;
+    use pgx::prelude::*;
+    use pgx::{FromDatum, IntoDatum, PgRelation, TryFromDatumError};
+    use std::collections::BTreeMap;
+    use std::os::raw::c_void;
+    use std::sync::Mutex;
+
+    /// The heap tids of each key of each index
+    static KEYS: Mutex<BTreeMap<(pg_sys::Oid, i32), Vec<pg_sys::ItemPointerData>>> =
+        Mutex::new(BTreeMap::new());
+
+    fn index_tuples(index: pg_sys::Oid) -> usize {
+        KEYS.lock()
+            .unwrap()
+            .iter()
+            .filter(|((oid, _), _)| *oid == index)
+            .map(|(_, t)| t.len())
+            .sum()
+    }
+
+    /// Indexes `int4` columns for `=` with `int4` and `int8` keys, and cleans up after `VACUUM`
+    struct KeyIndex {
+        matches: Vec<pg_sys::ItemPointerData>,
+    }
+
+    #[pg_index_am]
+    impl IndexAmRoutine for KeyIndex {
+        const NAME: &'static str = "pgx_tests_keys";
+        const CAPABILITIES: IndexAmCapabilities =
+            IndexAmCapabilities { strategies: 2, ..IndexAmCapabilities::DEFAULT };
+        const OPERATOR_CLASSES: &'static [OperatorClass] = &[OperatorClass {
+            name: "int4_pgx_tests_keys_ops",
+            for_type: "int4",
+            operators: &[(1, "="), (2, "=(int4, int8)")],
+            functions: &[],
+        }];
+
+        fn build(
+            heap: &PgRelation,
+            index: &PgRelation,
+            index_info: &mut pg_sys::IndexInfo,
+        ) -> pg_sys::IndexBuildResult {
+            let mut index_tuples = 0.0;
+            let heap_tuples = index_build_heap_scan(heap, index, index_info, |tid, values, _| {
+                if let Some(key) =
+                    values[0].and_then(|datum| unsafe { i32::from_datum(datum, false) })
+                {
+                    KEYS.lock().unwrap().entry((index.oid(), key)).or_default().push(tid);
+                    index_tuples += 1.0;
+                }
+            });
+            pg_sys::IndexBuildResult { heap_tuples, index_tuples }
+        }
+
+        fn insert(
+            index: &PgRelation,
+            values: &[Option<pg_sys::Datum>],
+            heap_tid: pg_sys::ItemPointerData,
+            _heap: &PgRelation,
+            _check_unique: pg_sys::IndexUniqueCheck,
+            _index_info: &mut pg_sys::IndexInfo,
+        ) -> bool {
+            if let Some(key) = values[0].and_then(|datum| unsafe { i32::from_datum(datum, false) })
+            {
+                KEYS.lock().unwrap().entry((index.oid(), key)).or_default().push(heap_tid);
+            }
+            false
+        }
+
+        fn bulk_delete(
+            info: &pg_sys::IndexVacuumInfo,
+            stats: &mut pg_sys::IndexBulkDeleteResult,
+            dead_tuples: &DeadTuples,
+        ) {
+            let index = unsafe { PgRelation::from_pg(info.index) }.oid();
+            for ((oid, _), tids) in KEYS.lock().unwrap().iter_mut() {
+                if *oid == index {
+                    let before = tids.len();
+                    tids.retain(|tid| !dead_tuples.is_dead(*tid));
+                    stats.tuples_removed += (before - tids.len()) as f64;
+                }
+            }
+            stats.num_index_tuples = index_tuples(index) as f64;
+        }
+
+        fn vacuum_cleanup(
+            info: &pg_sys::IndexVacuumInfo,
+            stats: Option<PgBox<pg_sys::IndexBulkDeleteResult>>,
+        ) -> Option<PgBox<pg_sys::IndexBulkDeleteResult>> {
+            if info.analyze_only {
+                return stats;
+            }
+            let mut stats = stats.unwrap_or_else(|| {
+                PgBox::<pg_sys::IndexBulkDeleteResult>::alloc0().into_pg_boxed()
+            });
+            stats.num_index_tuples =
+                index_tuples(unsafe { PgRelation::from_pg(info.index) }.oid()) as f64;
+            Some(stats)
+        }
+
+        fn begin_scan(_scan: &mut IndexScanDescRef<'_>) -> Self {
+            KeyIndex { matches: Vec::new() }
+        }
+
+        fn rescan(
+            &mut self,
+            scan: &mut IndexScanDescRef<'_>,
+            keys: &[ScanKey],
+            _orderbys: &[ScanKey],
+        ) {
+            let key = keys.first().and_then(|key| match key.subtype() {
+                pg_sys::INT8OID => {
+                    key.argument_as::<i64>().unwrap().and_then(|key| i32::try_from(key).ok())
+                }
+                _ => key.argument_as::<i32>().unwrap(),
+            });
+            let index = scan.index_relation().oid();
+            self.matches = key
+                .and_then(|key| KEYS.lock().unwrap().get(&(index, key)).cloned())
+                .unwrap_or_default();
+        }
+
+        fn get_tuple(
+            &mut self,
+            _scan: &mut IndexScanDescRef<'_>,
+            _direction: pg_sys::ScanDirection,
+        ) -> Option<pg_sys::ItemPointerData> {
+            self.matches.pop()
+        }
+    }
+
+    /// Says the heap tuples whose offset is at most `*state` are dead
+    unsafe extern "C" fn dead_up_to_offset(tid: pg_sys::ItemPointer, state: *mut c_void) -> bool {
+        (*tid).ip_posid <= *(state as *const u16)
+    }
+
+    fn vacuum_info(index: &PgRelation, analyze_only: bool) -> pg_sys::IndexVacuumInfo {
+        pg_sys::IndexVacuumInfo { index: index.as_ptr(), analyze_only, ..Default::default() }
+    }
+
+    fn create_indexed_table(name: &str) -> PgRelation {
+        Spi::run(&format!("CREATE TABLE tests.{} (id int)", name));
+        Spi::run(&format!("INSERT INTO tests.{} SELECT generate_series(1, 100)", name));
+        Spi::run(&format!("CREATE INDEX {0}_id ON tests.{0} USING pgx_tests_keys (id)", name));
+        PgRelation::open_with_name_and_share_lock(&format!("tests.{}_id", name)).unwrap()
+    }
+
+    #[pg_test]
+    fn test_index_am_operator_class() {
+        assert_eq!(
+            Some(2),
+            Spi::get_one::<i64>(
+                "SELECT count(*) FROM pg_amop JOIN pg_am ON amopmethod = pg_am.oid \
+                 WHERE amname = 'pgx_tests_keys'"
+            )
+        );
+    }
+
+    #[pg_test]
+    fn test_index_am_scans() {
+        create_indexed_table("index_am_scanned");
+        Spi::run("INSERT INTO tests.index_am_scanned VALUES (101), (42)");
+        Spi::run("SET LOCAL enable_seqscan = off");
+
+        Spi::run("SET LOCAL enable_bitmapscan = off");
+        let query = "SELECT * FROM tests.index_am_scanned WHERE id = 42";
+        assert_eq!("Index Scan", Spi::explain_with(query, Default::default()).plan.node_type);
+        assert_eq!(
+            Some(2),
+            Spi::get_one::<i64>(&format!("SELECT count(*) FROM ({}) matches", query))
+        );
+
+        Spi::run("SET LOCAL enable_bitmapscan = on");
+        Spi::run("SET LOCAL enable_indexscan = off");
+        let query = "SELECT * FROM tests.index_am_scanned WHERE id = 101";
+        assert_eq!("Bitmap Heap Scan", Spi::explain_with(query, Default::default()).plan.node_type);
+        assert_eq!(
+            Some(1),
+            Spi::get_one::<i64>(&format!("SELECT count(*) FROM ({}) matches", query))
+        );
+    }
+
+    #[pg_test]
+    fn test_index_am_cross_type_scan() {
+        create_indexed_table("index_am_cross_type");
+        Spi::run("SET LOCAL enable_seqscan = off");
+        Spi::run("SET LOCAL enable_bitmapscan = off");
+        let query = "SELECT * FROM tests.index_am_cross_type WHERE id = 42::int8";
+        assert_eq!("Index Scan", Spi::explain_with(query, Default::default()).plan.node_type);
+        assert_eq!(
+            Some(1),
+            Spi::get_one::<i64>(&format!("SELECT count(*) FROM ({}) matches", query))
+        );
+        assert_eq!(
+            Some(0),
+            Spi::get_one::<i64>(
+                "SELECT count(*) FROM tests.index_am_cross_type WHERE id = 4294967338::int8"
+            )
+        );
+    }
+
+    #[pg_test]
+    fn test_scan_key_argument_subtype() {
+        let data = pg_sys::ScanKeyData {
+            sk_subtype: pg_sys::INT8OID,
+            sk_argument: 42i64.into_datum().unwrap(),
+            ..Default::default()
+        };
+        let key = unsafe { &*(&data as *const pg_sys::ScanKeyData as *const ScanKey) };
+        assert_eq!(Ok(Some(42)), key.argument_as::<i64>());
+        assert!(matches!(key.argument_as::<i32>(), Err(TryFromDatumError::IncompatibleTypes)));
+    }
+
+    #[pg_test]
+    fn test_index_am_bulk_delete() {
+        let index = create_indexed_table("index_am_bulk_deleted");
+        let mut info = vacuum_info(&index, false);
+        let mut max_dead_offset = 10u16;
+        let stats = unsafe {
+            PgBox::from_pg(pg_sys::index_bulk_delete(
+                &mut info,
+                std::ptr::null_mut(),
+                Some(dead_up_to_offset),
+                &mut max_dead_offset as *mut u16 as *mut c_void,
+            ))
+        };
+        assert_eq!(10.0, stats.tuples_removed);
+        assert_eq!(90.0, stats.num_index_tuples);
+
+        // the heap tuples are still there, but the index no longer finds them
+        let id = Spi::get_one::<i32>(
+            "SELECT id FROM tests.index_am_bulk_deleted WHERE ctid = '(0,1)'::tid",
+        )
+        .unwrap();
+        Spi::run("SET LOCAL enable_seqscan = off");
+        assert_eq!(
+            Some(0),
+            Spi::get_one::<i64>(&format!(
+                "SELECT count(*) FROM tests.index_am_bulk_deleted WHERE id = {}",
+                id
+            ))
+        );
+
+        let stats = unsafe { pg_sys::index_vacuum_cleanup(&mut info, stats.into_pg()) };
+        assert_eq!(90.0, unsafe { (*stats).num_index_tuples });
+    }
+
+    #[pg_test]
+    fn test_index_am_vacuum_cleanup() {
+        let index = create_indexed_table("index_am_vacuumed");
+        let stats = unsafe {
+            pg_sys::index_vacuum_cleanup(&mut vacuum_info(&index, false), std::ptr::null_mut())
+        };
+        assert!(!stats.is_null());
+        assert_eq!(100.0, unsafe { (*stats).num_index_tuples });
+    }
+
+    #[pg_test]
+    fn test_index_am_vacuum_cleanup_reports_nothing() {
+        let index = create_indexed_table("index_am_analyzed");
+        let stats = unsafe {
+            pg_sys::index_vacuum_cleanup(&mut vacuum_info(&index, true), std::ptr::null_mut())
+        };
+        assert!(stats.is_null());
+    }
+
+    #[pg_test(error = "access method \"pgx_tests_keys\" does not support options")]
+    fn test_index_am_rejects_options() {
+        Spi::run("CREATE TABLE tests.index_am_options (id int)");
+        Spi::run(
+            "CREATE INDEX index_am_options_id ON tests.index_am_options \
+             USING pgx_tests_keys (id) WITH (fillfactor = 50)",
+        );
+    }
+}
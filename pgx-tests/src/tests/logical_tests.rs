@@ -0,0 +1,221 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+
+use pgx::prelude::*;
+
+/// Changes rows of a table in a transaction of its own, for `test_output_plugin_changes` to
+/// decode, since a test's own transaction never commits
+#[pg_guard]
+#[no_mangle]
+pub extern "C" fn logical_decoding_changes(_arg: pg_sys::Datum) {
+    use pgx::bgworkers::*;
+    BackgroundWorker::connect_worker_to_spi(
+        Some(crate::framework::get_pg_dbname()),
+        Some(crate::framework::get_pg_user().as_str()),
+    );
+    BackgroundWorker::transaction(|| {
+        Spi::run("CREATE TABLE tests.logical_things (id int PRIMARY KEY, name text)");
+        Spi::run("INSERT INTO tests.logical_things VALUES (1, 'one'), (2, 'two')");
+        Spi::run("UPDATE tests.logical_things SET name = 'TWO' WHERE id = 2");
+        Spi::run("DELETE FROM tests.logical_things WHERE id = 1");
+    });
+    BackgroundWorker::transaction(|| Spi::run("DROP TABLE tests.logical_things"));
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+#[pgx::pg_schema]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::bgworkers::BackgroundWorkerBuilder;
+    use pgx::logical::*;
+    use pgx::prelude::*;
+    use pgx::{AllocatedByPostgres, IntoDatum, PgLsn, PgOid};
+
+    /// `id:name` of a row with those columns
+    fn row(tuple: Option<PgHeapTuple<'_, AllocatedByPostgres>>) -> String {
+        match tuple {
+            Some(tuple) => format!(
+                "{}:{}",
+                tuple.get_by_name::<i32>("id").unwrap().unwrap(),
+                tuple.get_by_name::<String>("name").unwrap().unwrap_or_default()
+            ),
+            None => "no row".to_string(),
+        }
+    }
+
+    /// Writes a line for each transaction, change, and message, leaving out the messages whose
+    /// prefix isn't the `only-prefix` option, when there is one
+    struct Lines {
+        only_prefix: Option<String>,
+    }
+
+    #[pg_output_plugin]
+    impl OutputPlugin for Lines {
+        fn startup(
+            ctx: &mut LogicalDecodingContextRef<'_>,
+            _options: &mut StartupOptions<'_>,
+            _is_init: bool,
+        ) -> Self {
+            let only_prefix = ctx
+                .options()
+                .into_iter()
+                .find(|(name, _)| name == "only-prefix")
+                .and_then(|(_, value)| value);
+            Lines { only_prefix }
+        }
+
+        fn begin(
+            &mut self,
+            ctx: &mut LogicalDecodingContextRef<'_>,
+            txn: &ReorderBufferTxnRef<'_>,
+        ) {
+            assert_ne!(pg_sys::InvalidTransactionId, txn.xid());
+            ctx.write_bytes(b"BEGIN");
+        }
+
+        fn change(
+            &mut self,
+            ctx: &mut LogicalDecodingContextRef<'_>,
+            _txn: &ReorderBufferTxnRef<'_>,
+            change: &ReorderBufferChange<'_>,
+        ) {
+            // a delete's old row only has the replica identity's columns, the primary key
+            let row = match change.action() {
+                ChangeAction::Insert | ChangeAction::Update => row(change.new_tuple()),
+                ChangeAction::Delete => row(change.old_tuple()),
+            };
+            let mut out = ctx.prepare_write(true);
+            out.push_str(&format!("{:?} {} {}", change.action(), change.relation().name(), row));
+            ctx.write(true);
+        }
+
+        fn message(
+            &mut self,
+            ctx: &mut LogicalDecodingContextRef<'_>,
+            _txn: Option<&ReorderBufferTxnRef<'_>>,
+            message: &LogicalMessage<'_>,
+        ) {
+            let prefix = message.prefix.to_string_lossy();
+            if self.only_prefix.as_deref().map_or(true, |only_prefix| only_prefix == prefix) {
+                let content = String::from_utf8_lossy(message.content);
+                ctx.write_bytes(format!("message {}: {}", prefix, content).as_bytes());
+            }
+        }
+
+        fn commit(
+            &mut self,
+            ctx: &mut LogicalDecodingContextRef<'_>,
+            txn: &ReorderBufferTxnRef<'_>,
+            commit_lsn: pg_sys::XLogRecPtr,
+        ) {
+            assert_eq!(commit_lsn, txn.final_lsn());
+            ctx.write_bytes(b"COMMIT");
+        }
+    }
+
+    /// Write messages that aren't transactional, so they're decoded before the test's transaction
+    /// ends, to a new slot, and peek at the slot's changes with `options`
+    fn decode_messages(slot: &str, options: &str) -> Option<String> {
+        Spi::run(&format!(
+            "SELECT pg_create_logical_replication_slot('{}', 'pgx_tests', true)",
+            slot
+        ));
+        emit_message("pgx", b"hello", false);
+        let lsn = emit_message("other", b"world", false);
+        // decoding stops at the WAL that's been flushed
+        unsafe { pg_sys::XLogFlush(lsn.into()) };
+        Spi::get_one::<String>(&format!(
+            "SELECT string_agg(data, ',') FROM pg_logical_slot_peek_changes('{}', NULL, NULL{})",
+            slot, options
+        ))
+    }
+
+    #[pg_test]
+    fn test_output_plugin_messages() {
+        assert_eq!(
+            Some("message pgx: hello,message other: world".to_string()),
+            decode_messages("pgx_tests_messages", "")
+        );
+    }
+
+    #[pg_test]
+    fn test_output_plugin_options() {
+        assert_eq!(
+            Some("message pgx: hello".to_string()),
+            decode_messages("pgx_tests_options", ", 'only-prefix', 'pgx'")
+        );
+    }
+
+    #[pg_test]
+    fn test_output_plugin_changes() {
+        Spi::run(
+            "SELECT pg_create_logical_replication_slot('pgx_tests_changes', 'pgx_tests', true)",
+        );
+        let worker = BackgroundWorkerBuilder::new("logical_decoding_changes")
+            .set_library("pgx_tests")
+            .set_function("logical_decoding_changes")
+            .enable_spi_access()
+            .set_notify_pid(unsafe { pg_sys::MyProcPid })
+            .load_dynamic();
+        worker.wait_for_shutdown().expect("the worker didn't finish");
+
+        // the `DROP TABLE` has no changes to decode
+        assert_eq!(
+            Some(
+                "BEGIN,\
+                 Insert logical_things 1:one,\
+                 Insert logical_things 2:two,\
+                 Update logical_things 2:TWO,\
+                 Delete logical_things 1:,\
+                 COMMIT,\
+                 BEGIN,\
+                 COMMIT"
+                    .to_string()
+            ),
+            Spi::get_one::<String>(
+                "SELECT string_agg(data, ',') \
+                 FROM pg_logical_slot_peek_changes('pgx_tests_changes', NULL, NULL)"
+            )
+        );
+    }
+
+    #[pg_test]
+    fn test_emit_message_lsn() {
+        let lsn = emit_message("pgx", b"", true);
+        assert!(lsn.is_valid());
+        assert_eq!(
+            Some(true),
+            Spi::get_one::<bool>(&format!(
+                "SELECT '{}'::pg_lsn <= pg_current_wal_insert_lsn()",
+                lsn
+            ))
+        );
+    }
+
+    #[pg_test(error = "logical decoding message prefix must not contain a NUL byte")]
+    fn test_emit_message_nul_prefix() {
+        emit_message("pg\0x", b"", true);
+    }
+
+    #[pg_test]
+    fn test_pg_lsn() {
+        let lsn = Spi::get_one::<PgLsn>("SELECT '16/B374D848'::pg_lsn");
+        assert_eq!(Some(PgLsn::from(0x16_B374_D848)), lsn);
+        assert_eq!("16/B374D848", lsn.unwrap().to_string());
+        assert_eq!(
+            Some("0/2A".to_string()),
+            Spi::get_one_with_args::<String>(
+                "SELECT $1::text",
+                vec![(PgOid::from(PgLsn::type_oid()), PgLsn::from(42).into_datum())]
+            )
+        );
+    }
+}
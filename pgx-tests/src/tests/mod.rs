@@ -14,30 +14,40 @@ mod attributes_tests;
 mod bgworker_tests;
 mod bytea_tests;
 mod cfg_tests;
+mod copy_tests;
+mod custom_scan_tests;
 mod datetime_tests;
 mod default_arg_value_tests;
 mod derive_pgtype_lifetimes;
 mod enum_type_tests;
+mod event_trigger_tests;
 mod fcinfo_tests;
+mod fdw_tests;
 mod guc_tests;
 mod heap_tuple;
 mod hooks_tests;
+mod index_am_tests;
 mod inet_tests;
 mod internal_tests;
 mod json_tests;
 mod lifetime_tests;
 mod log_tests;
+mod logical_tests;
 mod memcxt_tests;
 mod name_tests;
 mod numeric_tests;
 mod pg_extern_tests;
 mod pg_try_tests;
 mod pgbox_tests;
+mod port_tests;
 mod postgres_type_tests;
+mod procedure_tests;
 mod schema_tests;
 mod spi_tests;
 mod srf_tests;
 mod struct_type_tests;
+mod subxact_tests;
+mod table_am_tests;
 mod trigger_tests;
 mod uuid_tests;
 mod variadic_tests;
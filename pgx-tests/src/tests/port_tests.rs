@@ -0,0 +1,69 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+
+#[cfg(any(test, feature = "pg_test"))]
+#[pgx::pg_schema]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::prelude::*;
+    use pgx::PortRef;
+
+    #[pg_test]
+    fn test_my_port() {
+        let port = PortRef::my_port().expect("the test backend has no client Port");
+        assert_eq!(port.as_ptr(), unsafe { pg_sys::MyProcPort });
+        assert_eq!(
+            port.database_name().map(|name| name.into_owned()),
+            Spi::get_one::<String>("SELECT current_database()::text")
+        );
+        assert_eq!(
+            port.user_name().map(|name| name.into_owned()),
+            Spi::get_one::<String>("SELECT session_user::text")
+        );
+    }
+
+    #[pg_test]
+    fn test_port_remote_address() {
+        let port = PortRef::my_port().unwrap();
+        // `inet_client_addr()` and `inet_client_port()` are NULL for Unix-domain sockets
+        assert_eq!(
+            port.remote_host().map(|host| host.into_owned()),
+            Spi::get_one::<String>("SELECT coalesce(host(inet_client_addr()), '[local]')")
+        );
+        assert_eq!(
+            port.remote_port().map(|port| port.into_owned()),
+            Spi::get_one::<String>("SELECT coalesce(inet_client_port()::text, '')")
+        );
+    }
+
+    #[pg_test]
+    fn test_port_auth_method() {
+        let port = PortRef::my_port().unwrap();
+        // we're connected, so `pg_hba.conf` didn't reject us
+        let methods = [
+            "trust",
+            "ident",
+            "password",
+            "md5",
+            "scram-sha-256",
+            "gss",
+            "sspi",
+            "pam",
+            "bsd",
+            "ldap",
+            "cert",
+            "radius",
+            "peer",
+        ];
+        let method = port.auth_method().expect("the client has no authentication method");
+        assert!(methods.contains(&method), "unexpected authentication method {}", method);
+    }
+}
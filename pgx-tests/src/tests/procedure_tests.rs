@@ -0,0 +1,94 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+
+#[cfg(all(any(test, feature = "pg_test"), not(feature = "pg10")))]
+#[pgx::pg_schema]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::prelude::*;
+    use pgx::IntoDatum;
+
+    #[pg_procedure]
+    fn insert_procedure_value(value: i32) {
+        Spi::run(&format!("INSERT INTO tests.procedure_values VALUES ({})", value));
+    }
+
+    #[pg_procedure]
+    fn insert_and_commit(fcinfo: pg_sys::FunctionCallInfo, value: i32) {
+        Spi::execute_nonatomic(fcinfo, |mut client| {
+            client.update(
+                "INSERT INTO tests.procedure_values VALUES ($1)",
+                None,
+                Some(vec![(PgBuiltInOids::INT4OID.oid(), value.into_datum())]),
+            );
+            client.commit();
+        });
+    }
+
+    #[pg_procedure]
+    fn commit_then_fail(fcinfo: pg_sys::FunctionCallInfo, value: i32) {
+        Spi::execute_nonatomic(fcinfo, |mut client| {
+            client.update(
+                "INSERT INTO tests.committed_values VALUES ($1)",
+                None,
+                Some(vec![(PgBuiltInOids::INT4OID.oid(), value.into_datum())]),
+            );
+            client.commit();
+            client.update(
+                "INSERT INTO tests.committed_values VALUES ($1)",
+                None,
+                Some(vec![(PgBuiltInOids::INT4OID.oid(), (value + 1).into_datum())]),
+            );
+            panic!("failed after committing {}", value);
+        });
+    }
+
+    #[pg_test]
+    fn test_call_procedure() {
+        Spi::run("CREATE TABLE tests.procedure_values (value int)");
+        Spi::run("CALL tests.insert_procedure_value(42)");
+        assert_eq!(Spi::get_one::<i32>("SELECT value FROM tests.procedure_values"), Some(42));
+    }
+
+    // tests run inside a transaction block, so the `CALL` is atomic and can't commit
+    #[pg_test(error = "invalid transaction termination")]
+    fn test_commit_in_atomic_call() {
+        Spi::run("CREATE TABLE tests.procedure_values (value int)");
+        Spi::run("CALL tests.insert_and_commit(42)");
+    }
+
+    // `#[pg_test]`s run inside a transaction block, so this `CALL`s the procedure from its own
+    // connection, where it's nonatomic and its `COMMIT` is real
+    #[test]
+    fn test_commit_in_nonatomic_call_persists() {
+        crate::framework::initialize_test_framework(vec![]).expect("failed to start Postgres");
+        let (mut client, _) = pgx_tests::client().expect("failed to connect to Postgres");
+
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS tests.committed_values; \
+                 CREATE TABLE tests.committed_values (value int)",
+            )
+            .unwrap();
+        let error = client.batch_execute("CALL tests.commit_then_fail(42)").unwrap_err();
+        assert_eq!(error.as_db_error().unwrap().message(), "failed after committing 42");
+
+        // the row inserted before the `COMMIT` survived the error, and the one after didn't
+        let values = client
+            .query("SELECT value FROM tests.committed_values", &[])
+            .unwrap()
+            .iter()
+            .map(|row| row.get::<_, i32>(0))
+            .collect::<Vec<_>>();
+        client.batch_execute("DROP TABLE tests.committed_values").unwrap();
+        assert_eq!(values, vec![42]);
+    }
+}
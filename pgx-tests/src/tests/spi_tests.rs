@@ -14,6 +14,9 @@ mod tests {
     use crate as pgx_tests;
 
     use pgx::prelude::*;
+    use pgx::{
+        ExplainOptions, FromSpiRow, FromSpiRowError, IntoDatum, PreparedStatement, SpiError,
+    };
 
     #[pg_test(error = "syntax error at or near \"THIS\"")]
     fn test_spi_failure() {
@@ -145,4 +148,348 @@ mod tests {
         );
         assert_eq!(result, Some(1));
     }
+
+    #[pg_test]
+    fn test_prepared_statement() {
+        thread_local! {
+            static STATEMENT: once_cell::unsync::OnceCell<PreparedStatement> =
+                once_cell::unsync::OnceCell::new();
+        }
+
+        for i in 0..3 {
+            Spi::execute(|client| {
+                STATEMENT.with(|statement| {
+                    let statement = statement.get_or_init(|| {
+                        client
+                            .prepare("SELECT $1::int + 1", &[PgBuiltInOids::INT4OID.oid()])
+                            .expect("failed to prepare statement")
+                    });
+                    let result = client
+                        .select_prepared(statement, None, (i,))
+                        .expect("failed to run prepared statement")
+                        .first()
+                        .get_one::<i32>();
+                    assert_eq!(result, Some(i + 1));
+                });
+            });
+        }
+    }
+
+    #[pg_test]
+    fn test_prepared_statement_argument_mismatch() {
+        Spi::execute(|client| {
+            let statement = client
+                .prepare("SELECT $1::int", &[PgBuiltInOids::INT4OID.oid()])
+                .expect("failed to prepare statement");
+            assert_eq!(
+                client.select_prepared(&statement, None, None).unwrap_err(),
+                SpiError::PreparedStatementArgumentMismatch { expected: 1, got: 0 }
+            );
+            assert_eq!(
+                client.select_prepared(&statement, None, ("42",)).unwrap_err(),
+                SpiError::PreparedStatementArgumentTypeMismatch {
+                    ordinal: 1,
+                    expected: pg_sys::INT4OID,
+                    got: pg_sys::TEXTOID,
+                }
+            );
+        });
+    }
+
+    #[pg_test]
+    fn test_prepared_statement_returns_error() {
+        Spi::execute(|mut client| {
+            client.update("CREATE TABLE tests.prepared_errors (id int primary key)", None, None);
+            let statement = client
+                .prepare(
+                    "INSERT INTO tests.prepared_errors VALUES ($1::text::int)",
+                    &[PgBuiltInOids::TEXTOID.oid()],
+                )
+                .expect("failed to prepare statement");
+
+            assert_eq!(client.update_prepared(&statement, None, ("1",)).unwrap().len(), 1);
+            match client.update_prepared(&statement, None, ("1",)) {
+                Err(SpiError::Postgres { sqlstate, .. }) => assert_eq!(sqlstate, "23505"),
+                _ => panic!("expected a unique violation"),
+            }
+            match client.update_prepared(&statement, None, ("one",)) {
+                Err(SpiError::Postgres { sqlstate, .. }) => assert_eq!(sqlstate, "22P02"),
+                _ => panic!("expected an invalid integer"),
+            }
+
+            // the failed executions were rolled back, and the transaction carried on
+            let count = client.select("SELECT count(*) FROM tests.prepared_errors", None, None);
+            assert_eq!(count.first().get_one::<i64>(), Some(1));
+        });
+    }
+
+    #[pg_test]
+    fn test_prepare_returns_error() {
+        Spi::execute(|client| match client.prepare("SELECT FROM WHERE", &[]) {
+            Err(SpiError::Postgres { sqlstate, .. }) => assert_eq!(sqlstate, "42601"),
+            _ => panic!("expected a syntax error"),
+        });
+    }
+
+    #[pg_test]
+    fn test_cursor() {
+        Spi::execute(|client| {
+            let mut cursor = client.open_cursor("SELECT * FROM generate_series(1, 10)", None);
+            assert_eq!(
+                cursor.fetch(3).map(|row| row[1].value::<i32>()).collect::<Vec<_>>(),
+                vec![Some(1), Some(2), Some(3)]
+            );
+            cursor.move_forward(2);
+            let mut rest = Vec::new();
+            let mut rows = cursor.iter(2);
+            while let Some(row) = rows.next() {
+                rest.push(row[1].value::<i32>().unwrap());
+            }
+            drop(rows);
+            assert_eq!(rest, vec![6, 7, 8, 9, 10]);
+            assert!(cursor.fetch(1).is_empty());
+        });
+    }
+
+    #[pg_test]
+    fn test_cursor_iter_by_ref_values() {
+        Spi::execute(|client| {
+            let mut cursor =
+                client.open_cursor("SELECT 'row ' || i FROM generate_series(1, 10) AS i", None);
+            let mut values = Vec::new();
+            let mut rows = cursor.iter(3);
+            while let Some(row) = rows.next() {
+                values.push(row[1].value::<String>().unwrap());
+            }
+            assert_eq!(values, (1..=10).map(|i| format!("row {}", i)).collect::<Vec<_>>());
+        });
+    }
+
+    #[pg_test]
+    fn test_cursor_with_args() {
+        Spi::execute(|client| {
+            let mut cursor = client.open_cursor(
+                "SELECT * FROM generate_series(1, $1)",
+                Some(vec![(PgBuiltInOids::INT4OID.oid(), 100.into_datum())]),
+            );
+            let mut count = 0;
+            let mut rows = cursor.iter(7);
+            while rows.next().is_some() {
+                count += 1;
+            }
+            assert_eq!(count, 100);
+        });
+    }
+
+    #[pg_test]
+    fn test_cursor_by_name() {
+        let name = Spi::connect(|client| {
+            let mut cursor = client.open_cursor("SELECT * FROM generate_series(1, 10)", None);
+            assert_eq!(cursor.fetch(5).len(), 5);
+            Ok(Some(cursor.detach_into_name()))
+        })
+        .expect("no cursor name");
+
+        Spi::execute(|client| {
+            let mut cursor = client.find_cursor(&name).expect("cursor was not found");
+            let mut rest = Vec::new();
+            let mut rows = cursor.iter(10);
+            while let Some(row) = rows.next() {
+                rest.push(row[1].value::<i32>().unwrap());
+            }
+            assert_eq!(rest, vec![6, 7, 8, 9, 10]);
+        });
+    }
+
+    #[pg_test]
+    fn test_cursor_not_found() {
+        Spi::execute(|client| {
+            assert!(client.find_cursor("no such cursor").is_none());
+        });
+    }
+
+    #[pg_test]
+    fn test_try_select_returns_error() {
+        Spi::execute(|client| {
+            let result = client.try_select("SELECT 1/0", None, None);
+            match result {
+                Err(SpiError::Postgres { sqlstate, message, .. }) => {
+                    assert_eq!(sqlstate, "22012");
+                    assert_eq!(message, "division by zero");
+                }
+                _ => panic!("expected a division by zero error"),
+            }
+
+            // the transaction is still usable
+            assert_eq!(client.select("SELECT 42", None, None).first().get_one::<i32>(), Some(42));
+        });
+    }
+
+    #[pg_test]
+    fn test_try_update_unique_violation() {
+        Spi::execute(|mut client| {
+            client.update("CREATE TABLE tests.try_update (id int primary key)", None, None);
+            client.update("INSERT INTO tests.try_update VALUES (1)", None, None);
+
+            for id in [1, 2, 1, 3] {
+                let result = client.try_update(
+                    "INSERT INTO tests.try_update VALUES ($1)",
+                    None,
+                    Some(vec![(PgBuiltInOids::INT4OID.oid(), id.into_datum())]),
+                );
+                match result {
+                    Ok(table) => assert_eq!(table.len(), 1),
+                    Err(SpiError::Postgres { sqlstate, .. }) => assert_eq!(sqlstate, "23505"),
+                    Err(e) => panic!("unexpected error: {}", e),
+                }
+            }
+
+            let count = client.select("SELECT count(*) FROM tests.try_update", None, None);
+            assert_eq!(count.first().get_one::<i64>(), Some(3));
+        });
+    }
+
+    #[derive(FromSpiRow, Debug, PartialEq)]
+    struct Dog {
+        name: String,
+        #[pgx(rename = "age_in_years")]
+        age: Option<i32>,
+    }
+
+    #[pg_test]
+    fn test_spi_rows() {
+        Spi::execute(|client| {
+            let dogs = client
+                .select(
+                    "SELECT * FROM (VALUES ('Nami', 7), ('Brandy', NULL)) AS dogs(name, age_in_years)",
+                    None,
+                    None,
+                )
+                .rows::<Dog>()
+                .collect::<Result<Vec<_>, _>>()
+                .expect("failed to read dogs");
+
+            assert_eq!(
+                dogs,
+                vec![
+                    Dog { name: "Nami".into(), age: Some(7) },
+                    Dog { name: "Brandy".into(), age: None }
+                ]
+            );
+        });
+    }
+
+    #[pg_test]
+    fn test_spi_rows_errors() {
+        Spi::execute(|client| {
+            let mut rows = client.select("SELECT 'Nami' AS name", None, None).rows::<Dog>();
+            assert_eq!(
+                rows.next(),
+                Some(Err(FromSpiRowError::NoSuchColumn("age_in_years".to_string())))
+            );
+
+            let mut rows = client
+                .select("SELECT 'Nami' AS name, 'old' AS age_in_years", None, None)
+                .rows::<Dog>();
+            assert!(matches!(rows.next(), Some(Err(FromSpiRowError::IncompatibleTypes { .. }))));
+
+            let mut rows = client
+                .select("SELECT NULL::text AS name, 7 AS age_in_years", None, None)
+                .rows::<Dog>();
+            assert_eq!(rows.next(), Some(Err(FromSpiRowError::UnexpectedNull("name".to_string()))));
+        });
+    }
+
+    #[pg_test]
+    fn test_spi_tuple_args() {
+        Spi::execute(|client| {
+            let (a, b) = client
+                .select("SELECT $1 + 1, $2 || '!'", None, (41, "bob"))
+                .first()
+                .get_two::<i32, String>();
+            assert_eq!(a, Some(42));
+            assert_eq!(b, Some("bob!".to_string()));
+
+            let none = client.select("SELECT $1::int IS NULL", None, (Option::<i32>::None,));
+            assert_eq!(none.first().get_one::<bool>(), Some(true));
+
+            let unit = client.select("SELECT 42", None, ());
+            assert_eq!(unit.first().get_one::<i32>(), Some(42));
+        });
+    }
+
+    #[pg_test]
+    fn test_spi_slice_args() {
+        let ids = [1i64, 2, 3];
+        let result = Spi::get_one_with_args::<i64>("SELECT $1 + $2 + $3", &ids[..]);
+        assert_eq!(result, Some(6));
+    }
+
+    #[pg_test]
+    fn test_connect_owned() {
+        let dogs = Spi::connect_owned(|client| {
+            Ok(client
+                .select(
+                    "SELECT * FROM (VALUES ('Nami', 7), ('Brandy', NULL)) AS dogs(name, age_in_years)",
+                    None,
+                    None,
+                )
+                .rows::<Dog>()
+                .collect::<Result<Vec<_>, _>>()
+                .expect("failed to read dogs"))
+        });
+        assert_eq!(
+            dogs,
+            vec![
+                Dog { name: "Nami".into(), age: Some(7) },
+                Dog { name: "Brandy".into(), age: None }
+            ]
+        );
+
+        let (relname, relkind) = Spi::connect_owned(|client| {
+            let table = client.select("SELECT 'pg_class'::text, 'r'::text", None, None).first();
+            Ok((table.get_datum::<String>(1).unwrap(), table.get_datum::<String>(2).unwrap()))
+        });
+        assert_eq!(relname, "pg_class");
+        assert_eq!(relkind, "r");
+    }
+
+    #[pg_test]
+    fn test_explain_with() {
+        Spi::run("CREATE TABLE tests.explain_dogs (id int, name text)");
+        Spi::run(
+            "INSERT INTO tests.explain_dogs SELECT x, 'dog ' || x FROM generate_series(1, 10) x",
+        );
+
+        let explain = Spi::explain_with(
+            "SELECT * FROM tests.explain_dogs WHERE id < 4",
+            ExplainOptions { analyze: true, buffers: true, ..Default::default() },
+        );
+        let plan = &explain.plan;
+        assert_eq!(plan.node_type, "Seq Scan");
+        assert_eq!(plan.relation_name.as_deref(), Some("explain_dogs"));
+        assert_eq!(plan.filter.as_deref(), Some("(id < 4)"));
+        assert!(plan.total_cost.is_some());
+        assert_eq!(plan.actual_rows, Some(3.0));
+        assert_eq!(plan.actual_loops, Some(1.0));
+        assert!(plan.buffers.shared_hit_blocks + plan.buffers.shared_read_blocks > 0);
+        assert!(explain.execution_time.is_some());
+    }
+
+    #[pg_test]
+    fn test_explain_with_child_plans() {
+        let explain = Spi::explain_with(
+            "SELECT * FROM generate_series(1, 10) x ORDER BY x DESC",
+            ExplainOptions { costs: false, ..Default::default() },
+        );
+        let plan = &explain.plan;
+        assert_eq!(plan.node_type, "Sort");
+        assert!(plan.total_cost.is_none());
+        assert!(plan.actual_rows.is_none());
+        assert!(plan.properties.contains_key("Sort Key"));
+        assert_eq!(plan.plans.len(), 1);
+        assert_eq!(plan.plans[0].parent_relationship.as_deref(), Some("Outer"));
+        assert_eq!(plan.iter().count(), 2);
+        assert!(explain.execution_time.is_none());
+    }
 }
@@ -0,0 +1,87 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+
+#[cfg(any(test, feature = "pg_test"))]
+#[pgx::pg_schema]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::prelude::*;
+    use pgx::subtransaction;
+
+    #[pg_test]
+    fn test_subtransaction_commits() {
+        Spi::run("CREATE TABLE tests.subxact_commit (id int)");
+        let result = subtransaction(|| {
+            Spi::run("INSERT INTO tests.subxact_commit VALUES (1)");
+            42
+        });
+        assert_eq!(result, Ok(42));
+        assert_eq!(Spi::get_one::<i64>("SELECT count(*) FROM tests.subxact_commit"), Some(1));
+    }
+
+    #[pg_test]
+    fn test_subtransaction_rolls_back_on_error() {
+        Spi::run("CREATE TABLE tests.subxact_rollback (id int primary key)");
+        for id in [1, 2, 2, 3] {
+            let result = subtransaction(|| {
+                Spi::run("INSERT INTO tests.subxact_rollback VALUES (0)");
+                Spi::run(&format!("INSERT INTO tests.subxact_rollback VALUES ({})", id));
+            });
+
+            // the (0) row from the failed subtransaction was rolled back too
+            Spi::run("DELETE FROM tests.subxact_rollback WHERE id = 0");
+
+            if let Err(e) = result {
+                assert_eq!(id, 2);
+                assert_eq!(e.sqlstate, "23505");
+                assert!(e.detail.is_some());
+            }
+        }
+        assert_eq!(Spi::get_one::<i64>("SELECT count(*) FROM tests.subxact_rollback"), Some(3));
+    }
+
+    #[pg_test]
+    fn test_subtransaction_inside_spi() {
+        Spi::execute(|mut client| {
+            let result = subtransaction(|| client.select("SELECT 1/0", None, None).len());
+            assert_eq!(result.unwrap_err().message, "division by zero");
+
+            client.update("SELECT 1", None, None);
+        });
+    }
+
+    #[pg_test]
+    fn test_nested_subtransactions() {
+        let result = subtransaction(|| subtransaction(|| Spi::run("SELECT 1/0")));
+        assert_eq!(result.unwrap().unwrap_err().sqlstate, "22012");
+    }
+
+    #[pg_extern]
+    fn subxact_panic_location() -> i32 {
+        panic!("panicked on line {}", line!())
+    }
+
+    #[pg_test]
+    fn test_subtransaction_error_leaves_no_panic_location() {
+        assert!(subtransaction(|| Spi::run("SELECT 1/0")).is_err());
+
+        // a panic after the caught ERROR is reported from where it happened
+        let error = subtransaction(|| Spi::run("SELECT tests.subxact_panic_location()"))
+            .expect_err("subxact_panic_location() didn't panic");
+        assert_eq!(error.message, format!("panicked on line {}", error.lineno));
+        assert!(error.filename.unwrap().ends_with("subxact_tests.rs"));
+    }
+
+    #[pg_test(error = "panic in subtransaction")]
+    fn test_subtransaction_panic() {
+        let _ = subtransaction(|| panic!("panic in subtransaction"));
+    }
+}
@@ -0,0 +1,380 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+
+#[cfg(all(
+    any(test, feature = "pg_test"),
+    any(feature = "pg12", feature = "pg13", feature = "pg14")
+))]
+#[pgx::pg_schema]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::prelude::*;
+    use pgx::table_am::*;
+    use pgx::{item_pointer_to_u64, u64_to_item_pointer, IntoDatum, PgRelation, TupleTableSlotRef};
+    use std::num::NonZeroUsize;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Mutex;
+
+    /// A version of a row, which the commands between `inserted` and `deleted` of the transaction
+    /// that made it see
+    struct Version {
+        table: pg_sys::Oid,
+        id: Option<i64>,
+        note: Option<String>,
+        inserted: pg_sys::CommandId,
+        deleted: Option<pg_sys::CommandId>,
+    }
+
+    /// Every version of every row, with its position as its tid
+    static VERSIONS: Mutex<Vec<Version>> = Mutex::new(Vec::new());
+
+    /// The `xmax` of the snapshot of the last scan that had one
+    static LAST_SCAN_XMAX: AtomicU32 = AtomicU32::new(0);
+
+    #[pg_extern]
+    fn table_am_last_scan_xmax() -> i64 {
+        LAST_SCAN_XMAX.load(Ordering::SeqCst) as i64
+    }
+
+    fn tid_of(position: usize) -> pg_sys::ItemPointerData {
+        let mut tid = pg_sys::ItemPointerData::default();
+        u64_to_item_pointer(position as u64 + 1, &mut tid);
+        tid
+    }
+
+    impl Version {
+        /// The version `tid` of a row of `table`, if there is one
+        fn find(
+            versions: &mut [Version],
+            table: pg_sys::Oid,
+            tid: pg_sys::ItemPointerData,
+        ) -> Option<&mut Version> {
+            let position = (item_pointer_to_u64(tid) as usize).checked_sub(1)?;
+            versions.get_mut(position).filter(|version| version.table == table)
+        }
+
+        /// Whether `snapshot` sees this version, going only by command ids, which is all a test's
+        /// single transaction needs
+        fn is_visible(&self, snapshot: pg_sys::Snapshot) -> bool {
+            match unsafe { snapshot.as_ref() } {
+                Some(snapshot) if snapshot.snapshot_type == pg_sys::SnapshotType_SNAPSHOT_MVCC => {
+                    self.inserted < snapshot.curcid
+                        && self.deleted.map_or(true, |deleted| deleted >= snapshot.curcid)
+                }
+                _ => self.deleted.is_none(),
+            }
+        }
+
+        fn store(&self, slot: &mut TupleTableSlotRef<'_>) {
+            slot.store_datums([self.id.into_datum(), self.note.clone().into_datum()]);
+        }
+    }
+
+    /// Keeps versions of the rows of its tables of `(int8, text)`, which commands see by their
+    /// command id, and gives them TOAST tables
+    struct VersionedTable {
+        position: usize,
+    }
+
+    impl VersionedTable {
+        fn append(rel: &PgRelation, slot: &mut TupleTableSlotRef<'_>, cid: pg_sys::CommandId) {
+            let mut versions = VERSIONS.lock().unwrap();
+            versions.push(Version {
+                table: rel.oid(),
+                id: slot.get_by_index(NonZeroUsize::new(1).unwrap()).unwrap(),
+                note: slot.get_by_index(NonZeroUsize::new(2).unwrap()).unwrap(),
+                inserted: cid,
+                deleted: None,
+            });
+            slot.set_tid(tid_of(versions.len() - 1));
+        }
+
+        fn delete(rel: &PgRelation, tid: pg_sys::ItemPointerData, cid: pg_sys::CommandId) -> bool {
+            let mut versions = VERSIONS.lock().unwrap();
+            match Version::find(&mut versions, rel.oid(), tid) {
+                Some(version) if version.deleted.is_none() => {
+                    version.deleted = Some(cid);
+                    true
+                }
+                _ => false,
+            }
+        }
+    }
+
+    #[pg_table_am]
+    impl TableAmRoutine for VersionedTable {
+        const NAME: &'static str = "pgx_tests_versioned";
+
+        fn begin_scan(scan: &mut TableScanDescRef<'_>) -> Self {
+            if !scan.snapshot().is_null() {
+                LAST_SCAN_XMAX.store(unsafe { (*scan.snapshot()).xmax }, Ordering::SeqCst);
+            }
+            VersionedTable { position: 0 }
+        }
+
+        fn rescan(&mut self, _scan: &mut TableScanDescRef<'_>) {
+            self.position = 0;
+        }
+
+        fn scan_next(
+            &mut self,
+            scan: &mut TableScanDescRef<'_>,
+            _direction: pg_sys::ScanDirection,
+            slot: &mut TupleTableSlotRef<'_>,
+        ) -> bool {
+            let table = scan.relation().oid();
+            let versions = VERSIONS.lock().unwrap();
+            while let Some(version) = versions.get(self.position) {
+                self.position += 1;
+                if version.table == table && version.is_visible(scan.snapshot()) {
+                    version.store(slot);
+                    slot.set_tid(tid_of(self.position - 1));
+                    return true;
+                }
+            }
+            false
+        }
+
+        fn tuple_insert(
+            rel: &PgRelation,
+            slot: &mut TupleTableSlotRef<'_>,
+            cid: pg_sys::CommandId,
+        ) {
+            Self::append(rel, slot, cid)
+        }
+
+        fn tuple_delete(
+            rel: &PgRelation,
+            tid: pg_sys::ItemPointerData,
+            cid: pg_sys::CommandId,
+            _snapshot: pg_sys::Snapshot,
+        ) -> bool {
+            Self::delete(rel, tid, cid)
+        }
+
+        fn tuple_update(
+            rel: &PgRelation,
+            otid: pg_sys::ItemPointerData,
+            slot: &mut TupleTableSlotRef<'_>,
+            cid: pg_sys::CommandId,
+            _snapshot: pg_sys::Snapshot,
+        ) -> bool {
+            Self::delete(rel, otid, cid) && {
+                Self::append(rel, slot, cid);
+                true
+            }
+        }
+
+        fn tuple_fetch_row_version(
+            rel: &PgRelation,
+            tid: pg_sys::ItemPointerData,
+            snapshot: pg_sys::Snapshot,
+            slot: &mut TupleTableSlotRef<'_>,
+        ) -> bool {
+            let mut versions = VERSIONS.lock().unwrap();
+            match Version::find(&mut versions, rel.oid(), tid) {
+                Some(version) if version.is_visible(snapshot) => {
+                    version.store(slot);
+                    true
+                }
+                _ => false,
+            }
+        }
+
+        fn relation_needs_toast_table(_rel: &PgRelation) -> bool {
+            true
+        }
+    }
+
+    fn create_table(name: &str) -> PgRelation {
+        Spi::run(&format!(
+            "CREATE TABLE tests.{} (id int8, note text) USING pgx_tests_versioned",
+            name
+        ));
+        Spi::run(&format!(
+            "INSERT INTO tests.{} SELECT i, 'note ' || i FROM generate_series(1, 5) i",
+            name
+        ));
+        PgRelation::open_with_name_and_share_lock(&format!("tests.{}", name)).unwrap()
+    }
+
+    #[pg_test]
+    fn test_table_am_access_method() {
+        assert_eq!(
+            Some("t".to_string()),
+            Spi::get_one::<String>(
+                "SELECT amtype::text FROM pg_am WHERE amname = 'pgx_tests_versioned'"
+            )
+        );
+    }
+
+    #[pg_test]
+    fn test_table_am_insert_update_delete() {
+        create_table("table_am_changed");
+        Spi::run("UPDATE tests.table_am_changed SET note = 'changed' WHERE id = 2");
+        Spi::run("DELETE FROM tests.table_am_changed WHERE id > 3");
+
+        assert_eq!(
+            Some("1:note 1,2:changed,3:note 3".to_string()),
+            Spi::get_one::<String>(
+                "SELECT string_agg(id || ':' || note, ',' ORDER BY id) FROM tests.table_am_changed"
+            )
+        );
+    }
+
+    #[pg_test]
+    fn test_table_am_statement_sees_rows_as_of_its_start() {
+        create_table("table_am_snapshot");
+        // the outer query has the snapshot the `DELETE` and `INSERT` had
+        assert_eq!(
+            Some(5),
+            Spi::get_one::<i64>(
+                "WITH deleted AS (DELETE FROM tests.table_am_snapshot WHERE id > 2 RETURNING id), \
+                      inserted AS (INSERT INTO tests.table_am_snapshot VALUES (6, 'note 6')) \
+                 SELECT count(*) FROM tests.table_am_snapshot"
+            )
+        );
+        assert_eq!(Some(3), Spi::get_one::<i64>("SELECT count(*) FROM tests.table_am_snapshot"));
+    }
+
+    #[pg_test]
+    fn test_table_am_index_scan() {
+        create_table("table_am_indexed");
+        Spi::run("CREATE INDEX table_am_indexed_id ON tests.table_am_indexed (id)");
+        Spi::run("UPDATE tests.table_am_indexed SET id = 1000 WHERE id = 4");
+        Spi::run("SET LOCAL enable_seqscan = off");
+
+        let explain = Spi::explain_with(
+            "SELECT * FROM tests.table_am_indexed WHERE id = 1000",
+            Default::default(),
+        );
+        assert_eq!("Index Scan", explain.plan.node_type);
+        assert_eq!(
+            Some("note 4".to_string()),
+            Spi::get_one::<String>("SELECT note FROM tests.table_am_indexed WHERE id = 1000")
+        );
+        // the index entry of the old version of the row finds one that's deleted
+        assert_eq!(
+            Some(0),
+            Spi::get_one::<i64>("SELECT count(*) FROM tests.table_am_indexed WHERE id = 4")
+        );
+    }
+
+    // the rows of `pgx_tests_versioned` live in the memory of the backend that made them, so
+    // rather than rows another connection committed, this checks that the build's snapshot sees
+    // that connection's transaction, which the `REPEATABLE READ` transaction's snapshot doesn't
+    #[test]
+    fn test_table_am_index_build_sees_rows_committed_since_the_transaction_snapshot() {
+        crate::framework::initialize_test_framework(vec![]).expect("failed to start Postgres");
+        let (mut client, _) = pgx_tests::client().expect("failed to connect to Postgres");
+        let (mut other, _) = pgx_tests::client().expect("failed to connect to Postgres");
+
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS tests.table_am_repeatable_read; \
+                 CREATE TABLE tests.table_am_repeatable_read (id int8) USING pgx_tests_versioned; \
+                 BEGIN ISOLATION LEVEL REPEATABLE READ",
+            )
+            .unwrap();
+        let transaction_xmax: i64 = client
+            .query_one("SELECT txid_snapshot_xmax(txid_current_snapshot())", &[])
+            .unwrap()
+            .get(0);
+        let committed: i64 = other.query_one("SELECT txid_current()", &[]).unwrap().get(0);
+        client
+            .batch_execute(
+                "CREATE INDEX table_am_repeatable_read_id ON tests.table_am_repeatable_read (id)",
+            )
+            .unwrap();
+        let build_xmax: i64 =
+            client.query_one("SELECT tests.table_am_last_scan_xmax()", &[]).unwrap().get(0);
+        client.batch_execute("ROLLBACK; DROP TABLE tests.table_am_repeatable_read").unwrap();
+
+        assert!(committed >= transaction_xmax);
+        assert!(committed < build_xmax);
+    }
+
+    #[pg_test]
+    fn test_table_am_tid_scan() {
+        create_table("table_am_tids");
+        let tid = Spi::get_one::<String>("SELECT ctid::text FROM tests.table_am_tids WHERE id = 3")
+            .unwrap();
+        let query = format!("SELECT id FROM tests.table_am_tids WHERE ctid = '{}'", tid);
+        assert_eq!("Tid Scan", Spi::explain_with(&query, Default::default()).plan.node_type);
+        assert_eq!(Some(3), Spi::get_one::<i64>(&query));
+
+        // neither deleted rows nor tids that were never used are found
+        Spi::run("DELETE FROM tests.table_am_tids WHERE id = 3");
+        for tid in [tid.as_str(), "(4000,1)"] {
+            assert_eq!(
+                Some(0),
+                Spi::get_one::<i64>(&format!(
+                    "SELECT count(*) FROM tests.table_am_tids WHERE ctid = '{}'",
+                    tid
+                ))
+            );
+        }
+    }
+
+    #[pg_test]
+    fn test_table_am_tuple_satisfies_snapshot() {
+        let rel = create_table("table_am_visible");
+        let satisfies = |tid: pg_sys::ItemPointerData, snapshot: pg_sys::Snapshot| unsafe {
+            let slot = pg_sys::MakeSingleTupleTableSlot(rel.rd_att, &pg_sys::TTSOpsHeapTuple);
+            (*slot).tts_tid = tid;
+            let routine = &*rel.rd_tableam;
+            let satisfies = routine.tuple_satisfies_snapshot.unwrap()(rel.as_ptr(), slot, snapshot);
+            pg_sys::ExecDropSingleTupleTableSlot(slot);
+            satisfies
+        };
+
+        unsafe {
+            pg_sys::CommandCounterIncrement();
+            let before = pg_sys::RegisterSnapshot(pg_sys::GetTransactionSnapshot());
+            Spi::run("DELETE FROM tests.table_am_visible WHERE id = 1");
+            pg_sys::CommandCounterIncrement();
+            let after = pg_sys::RegisterSnapshot(pg_sys::GetTransactionSnapshot());
+
+            let first =
+                tid_of(VERSIONS.lock().unwrap().iter().position(|v| v.table == rel.oid()).unwrap());
+            assert!(satisfies(first, before));
+            assert!(!satisfies(first, after));
+            assert!(!satisfies(tid_of(VERSIONS.lock().unwrap().len()), after));
+
+            pg_sys::UnregisterSnapshot(after);
+            pg_sys::UnregisterSnapshot(before);
+        }
+    }
+
+    #[pg_test]
+    fn test_table_am_toast_table() {
+        create_table("table_am_toasted");
+        assert_eq!(
+            Some("heap".to_string()),
+            Spi::get_one::<String>(
+                "SELECT amname::text FROM pg_class rel \
+                 JOIN pg_class toast ON toast.oid = rel.reltoastrelid \
+                 JOIN pg_am ON pg_am.oid = toast.relam \
+                 WHERE rel.oid = 'tests.table_am_toasted'::regclass"
+            )
+        );
+    }
+
+    #[pg_test(
+        error = "access method \"pgx_tests_versioned\" does not support INSERT ... ON CONFLICT"
+    )]
+    fn test_table_am_rejects_on_conflict() {
+        Spi::run(
+            "CREATE TABLE tests.table_am_unique (id int8 PRIMARY KEY) USING pgx_tests_versioned",
+        );
+        Spi::run("INSERT INTO tests.table_am_unique VALUES (1) ON CONFLICT DO NOTHING");
+    }
+}
@@ -13,7 +13,7 @@ mod tests {
     #[allow(unused_imports)]
     use crate as pgx_tests;
     use pgx::prelude::*;
-    use pgx::{AllocatedByPostgres, AllocatedByRust, WhoAllocated};
+    use pgx::{AllocatedByPostgres, AllocatedByRust, PgTupleDesc, WhoAllocated};
 
     /// Test some various formats of trigger signature we expect to support
     ///
@@ -94,6 +94,8 @@ mod tests {
         TryFromInt(#[from] std::num::TryFromIntError),
         #[error("PgTrigger error: {0}")]
         PgTrigger(#[from] pgx::trigger_support::PgTriggerError),
+        #[error("FromSpiRowError: {0}")]
+        FromSpiRow(#[from] pgx::FromSpiRowError),
     }
 
     #[pg_trigger]
@@ -596,4 +598,259 @@ mod tests {
             .expect("SQL select failed");
         assert_eq!(retval, "Fox");
     }
+
+    extension_sql!(
+        r#"
+        CREATE TABLE tests.created_trigger_things (id serial, species TEXT, status TEXT);
+    "#,
+        name = "create_created_trigger_things",
+    );
+
+    #[pg_trigger(
+        table = "tests.created_trigger_things",
+        events = "insert|update of species",
+        timing = "before",
+        level = "row",
+        when = "NEW.species = 'Fox'",
+        requires = ["create_created_trigger_things"]
+    )]
+    fn created_fox_to_bear(
+        trigger: &pgx::PgTrigger,
+    ) -> Result<PgHeapTuple<'_, impl WhoAllocated<pgx::pg_sys::HeapTupleData>>, TriggerError> {
+        field_species_fox_to_bear(trigger)
+    }
+
+    static CONSTRAINT_TRIGGER_FIRED: std::sync::atomic::AtomicUsize =
+        std::sync::atomic::AtomicUsize::new(0);
+
+    #[pg_trigger(
+        name = "count_deletes",
+        table = "tests.created_trigger_things",
+        events = "delete",
+        timing = "after",
+        level = "row",
+        constraint = true,
+        initially = "deferred",
+        requires = ["create_created_trigger_things"]
+    )]
+    fn created_constraint_count(
+        trigger: &pgx::PgTrigger,
+    ) -> Result<PgHeapTuple<'_, AllocatedByPostgres>, TriggerError> {
+        CONSTRAINT_TRIGGER_FIRED.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        trigger.current().ok_or(TriggerError::NullCurrent)
+    }
+
+    #[pg_test]
+    fn created_trigger_insert_and_update_of() {
+        Spi::run("INSERT INTO tests.created_trigger_things (species) VALUES ('Fox'), ('Cat')");
+        Spi::run("UPDATE tests.created_trigger_things SET species = 'Fox' WHERE species = 'Cat'");
+        Spi::run("UPDATE tests.created_trigger_things SET status = 'ok'");
+
+        assert_eq!(
+            Some(2),
+            Spi::get_one::<i64>(
+                "SELECT count(*) FROM tests.created_trigger_things WHERE species = 'Bear'"
+            )
+        );
+    }
+
+    #[pg_test]
+    fn created_trigger_when() {
+        Spi::run("INSERT INTO tests.created_trigger_things (species) VALUES ('Cat')");
+        assert_eq!(
+            Some("Cat"),
+            Spi::get_one::<&str>("SELECT species FROM tests.created_trigger_things")
+        );
+    }
+
+    #[pg_test]
+    fn created_constraint_trigger_is_deferred() {
+        assert_eq!(
+            Some(true),
+            Spi::get_one::<bool>(
+                "SELECT tgdeferrable AND tginitdeferred FROM pg_trigger WHERE tgname = 'count_deletes'"
+            )
+        );
+
+        Spi::run("INSERT INTO tests.created_trigger_things (species) VALUES ('Cat'), ('Dog')");
+        let before = CONSTRAINT_TRIGGER_FIRED.load(std::sync::atomic::Ordering::SeqCst);
+        Spi::run("DELETE FROM tests.created_trigger_things");
+        assert_eq!(before, CONSTRAINT_TRIGGER_FIRED.load(std::sync::atomic::Ordering::SeqCst));
+
+        Spi::run("SET CONSTRAINTS tests.count_deletes IMMEDIATE");
+        assert_eq!(before + 2, CONSTRAINT_TRIGGER_FIRED.load(std::sync::atomic::Ordering::SeqCst));
+    }
+
+    #[derive(pgx::FromSpiRow)]
+    struct TransitionThing {
+        id: i32,
+        species: Option<String>,
+    }
+
+    /// The row an `AFTER ... FOR EACH STATEMENT` trigger returns, which is ignored
+    fn ignored_row(
+        trigger: &pgx::PgTrigger,
+    ) -> Result<PgHeapTuple<'_, AllocatedByRust>, TriggerError> {
+        let tupdesc = unsafe { PgTupleDesc::from_pg_copy(trigger.relation_data().rd_att) };
+        let natts = tupdesc.len();
+        Ok(PgHeapTuple::from_datums(tupdesc, vec![None; natts])?)
+    }
+
+    /// Adds `line` to the log table named by the trigger's argument
+    fn log(trigger: &pgx::PgTrigger, line: &str) -> Result<(), TriggerError> {
+        let table = &trigger.extra_args()?[0];
+        Spi::run(&format!("INSERT INTO tests.{} (line) VALUES ('{}')", table, line));
+        Ok(())
+    }
+
+    /// Logs the rows of its transition tables into the table named by its argument
+    #[pg_trigger]
+    fn transition_tables_rows(
+        trigger: &pgx::PgTrigger,
+    ) -> Result<PgHeapTuple<'_, AllocatedByRust>, TriggerError> {
+        if let Some(old) = trigger.old_transition_table() {
+            for tuple in old {
+                let id = tuple.get_by_name::<i32>("id")?.ok_or(TriggerError::NullCurrent)?;
+                let species = tuple.get_by_name::<String>("species")?;
+                log(trigger, &format!("old {}:{}", id, species.unwrap_or_default()))?;
+            }
+        }
+        if let Some(new) = trigger.new_transition_table() {
+            for thing in new.rows::<TransitionThing>() {
+                let thing = thing?;
+                log(trigger, &format!("new {}:{}", thing.id, thing.species.unwrap_or_default()))?;
+            }
+        }
+        ignored_row(trigger)
+    }
+
+    fn ids(table: pgx::TransitionTable<'_>) -> Vec<i32> {
+        table.map(|tuple| tuple.get_by_name::<i32>("id").unwrap().unwrap()).collect()
+    }
+
+    /// Logs what its new transition table reads when read again, and by two readers at once
+    #[pg_trigger]
+    fn transition_table_rereads(
+        trigger: &pgx::PgTrigger,
+    ) -> Result<PgHeapTuple<'_, AllocatedByRust>, TriggerError> {
+        let new = || trigger.new_transition_table().expect("a NEW TABLE");
+
+        let first = new().next().map(|tuple| tuple.get_by_name::<i32>("id"));
+        log(trigger, &format!("first {:?}", first.transpose()?.flatten()))?;
+        log(trigger, &format!("again {:?}", ids(new())))?;
+
+        let mut partly = new();
+        partly.next();
+        log(trigger, &format!("meanwhile {:?}", ids(new())))?;
+        log(trigger, &format!("rest {:?}", ids(partly)))?;
+
+        for _ in 0..1000 {
+            new().next();
+        }
+        log(trigger, &format!("finally {:?}", ids(new())))?;
+        ignored_row(trigger)
+    }
+
+    #[derive(pgx::FromSpiRow)]
+    struct NamedThing {
+        #[allow(dead_code)]
+        species: String,
+    }
+
+    /// Reads its new transition table as rows that require a `species`
+    #[pg_trigger]
+    fn transition_rows_named(
+        trigger: &pgx::PgTrigger,
+    ) -> Result<PgHeapTuple<'_, AllocatedByRust>, TriggerError> {
+        for thing in trigger.new_transition_table().expect("a NEW TABLE").rows::<NamedThing>() {
+            thing?;
+        }
+        ignored_row(trigger)
+    }
+
+    fn create_transition_things(name: &str) {
+        Spi::run(&format!("CREATE TABLE tests.{} (id int, species TEXT)", name));
+        Spi::run(&format!("CREATE TABLE tests.{}_log (n serial, line TEXT)", name));
+        Spi::run(&format!("INSERT INTO tests.{} VALUES (1, 'Fox'), (2, 'Cat'), (3, 'Fox')", name));
+    }
+
+    fn logged(name: &str) -> Option<String> {
+        Spi::get_one::<String>(&format!(
+            "SELECT string_agg(line, ', ' ORDER BY n) FROM tests.{}_log",
+            name
+        ))
+    }
+
+    #[pg_test]
+    fn after_statement_transition_tables() {
+        create_transition_things("transition_things");
+        Spi::run(
+            r#"
+            CREATE TRIGGER transition_tables_rows
+                AFTER UPDATE ON tests.transition_things
+                REFERENCING OLD TABLE AS old_things NEW TABLE AS new_things
+                FOR EACH STATEMENT
+                EXECUTE PROCEDURE tests.transition_tables_rows('transition_things_log')
+        "#,
+        );
+        Spi::run("UPDATE tests.transition_things SET species = 'Bear' WHERE species = 'Fox'");
+
+        assert_eq!(
+            logged("transition_things").as_deref(),
+            Some("old 1:Fox, old 3:Fox, new 1:Bear, new 3:Bear")
+        );
+    }
+
+    #[pg_test]
+    fn after_statement_without_transition_tables() {
+        create_transition_things("no_transition_things");
+        Spi::run(
+            r#"
+            CREATE TRIGGER transition_tables_rows
+                AFTER INSERT ON tests.no_transition_things
+                FOR EACH STATEMENT
+                EXECUTE PROCEDURE tests.transition_tables_rows('no_transition_things_log')
+        "#,
+        );
+        Spi::run("INSERT INTO tests.no_transition_things VALUES (4, 'Fox')");
+        assert_eq!(logged("no_transition_things"), None);
+    }
+
+    #[pg_test]
+    fn after_statement_transition_table_rereads() {
+        create_transition_things("reread_things");
+        Spi::run(
+            r#"
+            CREATE TRIGGER transition_table_rereads
+                AFTER UPDATE ON tests.reread_things
+                REFERENCING NEW TABLE AS new_things
+                FOR EACH STATEMENT
+                EXECUTE PROCEDURE tests.transition_table_rereads('reread_things_log')
+        "#,
+        );
+        Spi::run("UPDATE tests.reread_things SET species = 'Bear'");
+
+        assert_eq!(
+            logged("reread_things").as_deref(),
+            Some(
+                "first Some(1), again [1, 2, 3], meanwhile [1, 2, 3], rest [2, 3], \
+                 finally [1, 2, 3]"
+            )
+        );
+    }
+
+    #[pg_test(error = "Trigger function panic: FromSpiRow(UnexpectedNull(\"species\"))")]
+    fn after_statement_transition_rows_error() {
+        create_transition_things("unnamed_things");
+        Spi::run(
+            r#"
+            CREATE TRIGGER transition_rows_named
+                AFTER UPDATE ON tests.unnamed_things
+                REFERENCING NEW TABLE AS new_things
+                FOR EACH STATEMENT
+                EXECUTE PROCEDURE tests.transition_rows_named()
+        "#,
+        );
+        Spi::run("UPDATE tests.unnamed_things SET species = NULL WHERE id = 2");
+    }
 }
@@ -19,7 +19,11 @@ pub(crate) mod control_file;
 pub(crate) mod extension_sql;
 pub(crate) mod mapping;
 pub mod metadata;
+pub(crate) mod pg_event_trigger;
 pub(crate) mod pg_extern;
+pub(crate) mod pg_fdw;
+pub(crate) mod pg_index_am;
+pub(crate) mod pg_table_am;
 pub(crate) mod pg_trigger;
 pub(crate) mod pgx_attribute;
 pub(crate) mod pgx_sql;
@@ -40,14 +44,23 @@ pub use control_file::ControlFile;
 pub use extension_sql::entity::{ExtensionSqlEntity, SqlDeclaredEntity};
 pub use extension_sql::{ExtensionSql, ExtensionSqlFile, SqlDeclared};
 pub use mapping::{RustSourceOnlySqlMapping, RustSqlMapping};
+pub use pg_event_trigger::attribute::PgEventTriggerAttribute;
+pub use pg_event_trigger::entity::PgEventTriggerEntity;
+pub use pg_event_trigger::PgEventTrigger;
 pub use pg_extern::entity::{
     PgExternArgumentEntity, PgExternEntity, PgExternReturnEntity, PgExternReturnEntityIteratedItem,
     PgOperatorEntity,
 };
 pub use pg_extern::{NameMacro, PgExtern, PgExternArgument, PgOperator};
+pub use pg_fdw::entity::PgFdwEntity;
+pub use pg_fdw::PgFdw;
+pub use pg_index_am::entity::{OperatorClassEntity, PgIndexAmEntity};
+pub use pg_index_am::PgIndexAm;
+pub use pg_table_am::entity::PgTableAmEntity;
+pub use pg_table_am::PgTableAm;
 pub use pg_trigger::attribute::PgTriggerAttribute;
-pub use pg_trigger::entity::PgTriggerEntity;
-pub use pg_trigger::PgTrigger;
+pub use pg_trigger::entity::{CreateTriggerEntity, PgTriggerEntity};
+pub use pg_trigger::{CreateTrigger, PgTrigger};
 pub use pgx_sql::{PgxSql, RustToSqlMapping};
 pub use positioning_ref::PositioningRef;
 pub use postgres_enum::entity::PostgresEnumEntity;
@@ -100,6 +113,10 @@ pub enum SqlGraphEntity {
     Hash(PostgresHashEntity),
     Aggregate(PgAggregateEntity),
     Trigger(PgTriggerEntity),
+    EventTrigger(PgEventTriggerEntity),
+    ForeignDataWrapper(PgFdwEntity),
+    IndexAccessMethod(PgIndexAmEntity),
+    TableAccessMethod(PgTableAmEntity),
 }
 
 impl SqlGraphEntity {
@@ -133,6 +150,10 @@ impl SqlGraphIdentifier for SqlGraphEntity {
             SqlGraphEntity::Hash(item) => item.dot_identifier(),
             SqlGraphEntity::Aggregate(item) => item.dot_identifier(),
             SqlGraphEntity::Trigger(item) => item.dot_identifier(),
+            SqlGraphEntity::EventTrigger(item) => item.dot_identifier(),
+            SqlGraphEntity::ForeignDataWrapper(item) => item.dot_identifier(),
+            SqlGraphEntity::IndexAccessMethod(item) => item.dot_identifier(),
+            SqlGraphEntity::TableAccessMethod(item) => item.dot_identifier(),
             SqlGraphEntity::ExtensionRoot(item) => item.dot_identifier(),
         }
     }
@@ -149,6 +170,10 @@ impl SqlGraphIdentifier for SqlGraphEntity {
             SqlGraphEntity::Hash(item) => item.rust_identifier(),
             SqlGraphEntity::Aggregate(item) => item.rust_identifier(),
             SqlGraphEntity::Trigger(item) => item.rust_identifier(),
+            SqlGraphEntity::EventTrigger(item) => item.rust_identifier(),
+            SqlGraphEntity::ForeignDataWrapper(item) => item.rust_identifier(),
+            SqlGraphEntity::IndexAccessMethod(item) => item.rust_identifier(),
+            SqlGraphEntity::TableAccessMethod(item) => item.rust_identifier(),
             SqlGraphEntity::ExtensionRoot(item) => item.rust_identifier(),
         }
     }
@@ -165,6 +190,10 @@ impl SqlGraphIdentifier for SqlGraphEntity {
             SqlGraphEntity::Hash(item) => item.file(),
             SqlGraphEntity::Aggregate(item) => item.file(),
             SqlGraphEntity::Trigger(item) => item.file(),
+            SqlGraphEntity::EventTrigger(item) => item.file(),
+            SqlGraphEntity::ForeignDataWrapper(item) => item.file(),
+            SqlGraphEntity::IndexAccessMethod(item) => item.file(),
+            SqlGraphEntity::TableAccessMethod(item) => item.file(),
             SqlGraphEntity::ExtensionRoot(item) => item.file(),
         }
     }
@@ -181,6 +210,10 @@ impl SqlGraphIdentifier for SqlGraphEntity {
             SqlGraphEntity::Hash(item) => item.line(),
             SqlGraphEntity::Aggregate(item) => item.line(),
             SqlGraphEntity::Trigger(item) => item.line(),
+            SqlGraphEntity::EventTrigger(item) => item.line(),
+            SqlGraphEntity::ForeignDataWrapper(item) => item.line(),
+            SqlGraphEntity::IndexAccessMethod(item) => item.line(),
+            SqlGraphEntity::TableAccessMethod(item) => item.line(),
             SqlGraphEntity::ExtensionRoot(item) => item.line(),
         }
     }
@@ -243,6 +276,18 @@ impl ToSql for SqlGraphEntity {
             SqlGraphEntity::Trigger(item) => {
                 item.to_sql_config.to_sql(self, context).unwrap_or_else(|| item.to_sql(context))
             }
+            SqlGraphEntity::EventTrigger(item) => {
+                item.to_sql_config.to_sql(self, context).unwrap_or_else(|| item.to_sql(context))
+            }
+            SqlGraphEntity::ForeignDataWrapper(item) => {
+                item.to_sql_config.to_sql(self, context).unwrap_or_else(|| item.to_sql(context))
+            }
+            SqlGraphEntity::IndexAccessMethod(item) => {
+                item.to_sql_config.to_sql(self, context).unwrap_or_else(|| item.to_sql(context))
+            }
+            SqlGraphEntity::TableAccessMethod(item) => {
+                item.to_sql_config.to_sql(self, context).unwrap_or_else(|| item.to_sql(context))
+            }
             SqlGraphEntity::ExtensionRoot(item) => item.to_sql(context),
         }
     }
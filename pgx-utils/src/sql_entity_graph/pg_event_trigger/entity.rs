@@ -0,0 +1,125 @@
+/*!
+
+`#[pg_event_trigger]` related entities for Rust to SQL translation
+
+> Like all of the [`sql_entity_graph`][crate::sql_entity_graph] APIs, this is considered **internal**
+to the `pgx` framework and very subject to change between versions. While you may use this, please do it with caution.
+
+*/
+use crate::sql_entity_graph::{
+    PgxSql, SqlGraphEntity, SqlGraphIdentifier, ToSql, ToSqlConfigEntity,
+};
+use core::cmp::{Eq, Ord, Ordering, PartialEq, PartialOrd};
+use core::fmt::Debug;
+use core::hash::Hash;
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct PgEventTriggerEntity {
+    pub function_name: &'static str,
+    /// The event to create an event trigger on, if the function gets one
+    pub event: Option<&'static str>,
+    /// The command tags the event trigger is limited to, if any
+    pub tags: &'static [&'static str],
+    pub to_sql_config: ToSqlConfigEntity,
+    pub file: &'static str,
+    pub line: u32,
+    pub module_path: &'static str,
+    pub full_path: &'static str,
+}
+
+impl PgEventTriggerEntity {
+    fn wrapper_function_name(&self) -> String {
+        self.function_name.to_string() + "_wrapper"
+    }
+}
+
+impl Ord for PgEventTriggerEntity {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.full_path.cmp(other.full_path)
+    }
+}
+
+impl PartialOrd for PgEventTriggerEntity {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<PgEventTriggerEntity> for SqlGraphEntity {
+    fn from(val: PgEventTriggerEntity) -> Self {
+        SqlGraphEntity::EventTrigger(val)
+    }
+}
+
+impl ToSql for PgEventTriggerEntity {
+    #[tracing::instrument(
+        level = "error",
+        skip(self, context),
+        fields(identifier = %self.rust_identifier()),
+    )]
+    fn to_sql(&self, context: &PgxSql) -> eyre::Result<String> {
+        let self_index = context.event_triggers[self];
+        let schema = context.schema_prefix_for(&self_index);
+
+        let mut sql = format!(
+            "\n\
+            -- {file}:{line}\n\
+            -- {full_path}\n\
+            CREATE FUNCTION {schema}\"{function_name}\"()\n\
+                \tRETURNS event_trigger\n\
+                \tLANGUAGE c\n\
+                \tAS 'MODULE_PATHNAME', '{wrapper_function_name}';\
+        ",
+            schema = schema,
+            file = self.file,
+            line = self.line,
+            full_path = self.full_path,
+            function_name = self.function_name,
+            wrapper_function_name = self.wrapper_function_name(),
+        );
+
+        if let Some(event) = self.event {
+            let when = if self.tags.is_empty() {
+                String::new()
+            } else {
+                let tags = self
+                    .tags
+                    .iter()
+                    .map(|tag| format!("'{}'", tag.replace('\'', "''")))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("\tWHEN TAG IN ({})\n", tags)
+            };
+            sql.push_str(&format!(
+                "\n\
+                CREATE EVENT TRIGGER \"{function_name}\"\n\
+                    \tON {event}\n\
+                    {when}\
+                    \tEXECUTE PROCEDURE {schema}\"{function_name}\"();\
+                ",
+                schema = schema,
+                function_name = self.function_name,
+                event = event,
+                when = when,
+            ));
+        }
+        Ok(sql)
+    }
+}
+
+impl SqlGraphIdentifier for PgEventTriggerEntity {
+    fn dot_identifier(&self) -> String {
+        format!("event trigger fn {}", self.full_path)
+    }
+    fn rust_identifier(&self) -> String {
+        self.full_path.to_string()
+    }
+
+    fn file(&self) -> Option<&'static str> {
+        Some(self.file)
+    }
+
+    fn line(&self) -> Option<u32> {
+        Some(self.line)
+    }
+}
@@ -0,0 +1,238 @@
+/*!
+
+`#[pg_event_trigger]` related macro expansion for Rust to SQL translation
+
+> Like all of the [`sql_entity_graph`][crate::sql_entity_graph] APIs, this is considered **internal**
+to the `pgx` framework and very subject to change between versions. While you may use this, please do it with caution.
+
+*/
+pub mod attribute;
+pub mod entity;
+
+use crate::sql_entity_graph::ToSqlConfig;
+use attribute::PgEventTriggerAttribute;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{quote, ToTokens, TokenStreamExt};
+use syn::{ItemFn, Token};
+
+#[derive(Debug, Clone)]
+pub struct PgEventTrigger {
+    func: syn::ItemFn,
+    event: Option<String>,
+    tags: Vec<String>,
+    to_sql_config: ToSqlConfig,
+}
+
+impl PgEventTrigger {
+    pub fn new(
+        func: ItemFn,
+        attributes: syn::punctuated::Punctuated<PgEventTriggerAttribute, Token![,]>,
+    ) -> Result<Self, syn::Error> {
+        let mut to_sql_config = None;
+        let mut event = None;
+        let mut tags = None;
+        for attribute in attributes {
+            match attribute {
+                PgEventTriggerAttribute::Sql(mut config) => {
+                    if to_sql_config.is_some() {
+                        return Err(syn::Error::new(
+                            Span::call_site(),
+                            "Multiple `sql` arguments found, it must be unique",
+                        ));
+                    }
+                    if let Some(ref mut content) = config.content {
+                        let value = content.value();
+                        let updated_value = value.replace(
+                            "@FUNCTION_NAME@",
+                            &*(func.sig.ident.to_string() + "_wrapper"),
+                        ) + "\n";
+                        *content = syn::LitStr::new(&updated_value, Span::call_site());
+                    };
+                    to_sql_config = Some(config);
+                }
+                PgEventTriggerAttribute::Event(value) => {
+                    if event.replace(value.value()).is_some() {
+                        return Err(syn::Error::new(
+                            value.span(),
+                            "Multiple `event` arguments found, it must be unique",
+                        ));
+                    }
+                }
+                PgEventTriggerAttribute::Tags(value) => {
+                    let parsed = value
+                        .value()
+                        .split(',')
+                        .map(|tag| tag.trim().to_string())
+                        .collect::<Vec<_>>();
+                    if parsed.iter().any(String::is_empty) {
+                        return Err(syn::Error::new(
+                            value.span(),
+                            "`tags` must be a comma separated list of command tags, like \"CREATE TABLE, ALTER TABLE\"",
+                        ));
+                    }
+                    if tags.replace(parsed).is_some() {
+                        return Err(syn::Error::new(
+                            value.span(),
+                            "Multiple `tags` arguments found, it must be unique",
+                        ));
+                    }
+                }
+            }
+        }
+        if event.is_none() && tags.is_some() {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "`tags` requires an `event` to create the event trigger on",
+            ));
+        }
+        let to_sql_config = to_sql_config.unwrap_or_default();
+
+        if !to_sql_config.overrides_default() {
+            crate::ident_is_acceptable_to_postgres(&func.sig.ident)?;
+        }
+
+        Ok(Self { func, event, tags: tags.unwrap_or_default(), to_sql_config })
+    }
+
+    pub fn entity_tokens(&self) -> Result<ItemFn, syn::Error> {
+        let sql_graph_entity_fn_name = syn::Ident::new(
+            &format!("__pgx_internals_event_trigger_{}", self.func.sig.ident.to_string()),
+            self.func.sig.ident.span(),
+        );
+        let func_sig_ident = &self.func.sig.ident;
+        let function_name = func_sig_ident.to_string();
+        let event = match &self.event {
+            Some(event) => quote! { Some(#event) },
+            None => quote! { None },
+        };
+        let tags = &self.tags;
+        let to_sql_config = &self.to_sql_config;
+
+        let tokens = quote! {
+            #[no_mangle]
+            #[doc(hidden)]
+            pub extern "Rust" fn #sql_graph_entity_fn_name() -> ::pgx::utils::sql_entity_graph::SqlGraphEntity {
+                use core::any::TypeId;
+                extern crate alloc;
+                use alloc::vec::Vec;
+                use alloc::vec;
+                let submission = ::pgx::utils::sql_entity_graph::PgEventTriggerEntity {
+                    function_name: #function_name,
+                    event: #event,
+                    tags: &[#(#tags),*],
+                    file: file!(),
+                    line: line!(),
+                    full_path: concat!(module_path!(), "::", stringify!(#func_sig_ident)),
+                    module_path: module_path!(),
+                    to_sql_config: #to_sql_config,
+                };
+                ::pgx::utils::sql_entity_graph::SqlGraphEntity::EventTrigger(submission)
+            }
+        };
+        syn::parse2(tokens)
+    }
+
+    pub fn wrapper_tokens(&self) -> Result<ItemFn, syn::Error> {
+        let function_ident = &self.func.sig.ident;
+        let extern_func_ident = syn::Ident::new(
+            &format!("{}_wrapper", self.func.sig.ident.to_string()),
+            self.func.sig.ident.span(),
+        );
+        let tokens = quote! {
+            #[no_mangle]
+            #[pgx::pg_guard]
+            extern "C" fn #extern_func_ident(fcinfo: ::pgx::pg_sys::FunctionCallInfo) -> ::pgx::pg_sys::Datum {
+                let maybe_pg_event_trigger = unsafe { ::pgx::trigger_support::PgEventTrigger::from_fcinfo(fcinfo) };
+                let pg_event_trigger = maybe_pg_event_trigger.expect("PgEventTrigger::from_fcinfo failed");
+                let event_trigger_fn_result: Result<(), _> = #function_ident(&pg_event_trigger);
+
+                event_trigger_fn_result.expect("Event trigger function panic");
+                ::pgx::pg_return_null(fcinfo)
+            }
+
+        };
+        syn::parse2(tokens)
+    }
+
+    pub fn finfo_tokens(&self) -> Result<ItemFn, syn::Error> {
+        let finfo_name = syn::Ident::new(
+            &format!("pg_finfo_{}_wrapper", self.func.sig.ident),
+            proc_macro2::Span::call_site(),
+        );
+        let tokens = quote! {
+            #[no_mangle]
+            #[doc(hidden)]
+            pub extern "C" fn #finfo_name() -> &'static ::pgx::pg_sys::Pg_finfo_record {
+                const V1_API: ::pgx::pg_sys::Pg_finfo_record = ::pgx::pg_sys::Pg_finfo_record { api_version: 1 };
+                &V1_API
+            }
+        };
+        syn::parse2(tokens)
+    }
+}
+
+impl ToTokens for PgEventTrigger {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let entity_func =
+            self.entity_tokens().expect("Generating entity function for event trigger");
+        let wrapper_func =
+            self.wrapper_tokens().expect("Generating wrappper function for event trigger");
+        let finfo_func = self.finfo_tokens().expect("Generating finfo function for event trigger");
+        let func = &self.func;
+
+        let items = quote! {
+            #func
+
+            #wrapper_func
+
+            #finfo_func
+
+            #entity_func
+        };
+        tokens.append_all(items);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::attribute::PgEventTriggerAttribute;
+    use super::PgEventTrigger;
+    use eyre::Result;
+    use proc_macro2::TokenStream;
+    use quote::quote;
+    use syn::parse::Parser;
+    use syn::punctuated::Punctuated;
+    use syn::{parse_quote, ItemFn, Token};
+
+    fn parse_event_trigger(attributes: TokenStream) -> Result<PgEventTrigger, syn::Error> {
+        let func: ItemFn = parse_quote! {
+            fn ddl_trigger(trigger: &PgEventTrigger) -> Result<(), PgTriggerError> {
+                todo!()
+            }
+        };
+        let attributes = Punctuated::<PgEventTriggerAttribute, Token![,]>::parse_terminated
+            .parse2(attributes)?;
+        PgEventTrigger::new(func, attributes)
+    }
+
+    #[test]
+    fn event_trigger_create_event_trigger() -> Result<()> {
+        let trigger = parse_event_trigger(quote! {
+            event = "sql_drop", tags = "DROP TABLE,  DROP SEQUENCE"
+        })?;
+        assert_eq!(trigger.event.as_deref(), Some("sql_drop"));
+        assert_eq!(trigger.tags, vec!["DROP TABLE", "DROP SEQUENCE"]);
+        // It should be possible to generate entity tokens.
+        let _ = trigger.entity_tokens()?;
+        Ok(())
+    }
+
+    #[test]
+    fn event_trigger_invalid() {
+        // `CREATE EVENT TRIGGER` would only reject these when the extension is created
+        assert!(parse_event_trigger(quote! { event = "ddl_command_ends" }).is_err());
+        assert!(parse_event_trigger(quote! { event = "DDL_COMMAND_END" }).is_err());
+        assert!(parse_event_trigger(quote! { event = "sql_drop", tags = "DROP TABLE," }).is_err());
+        assert!(parse_event_trigger(quote! { tags = "DROP TABLE" }).is_err());
+    }
+}
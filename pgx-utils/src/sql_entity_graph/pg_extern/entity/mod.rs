@@ -49,6 +49,8 @@ pub struct PgExternEntity {
     pub search_path: Option<Vec<&'static str>>,
     pub operator: Option<PgOperatorEntity>,
     pub to_sql_config: ToSqlConfigEntity,
+    /// Created with `CREATE PROCEDURE` instead of `CREATE FUNCTION`
+    pub procedure: bool,
 }
 
 impl std::hash::Hash for PgExternEntity {
@@ -111,7 +113,9 @@ impl ToSql for PgExternEntity {
         let mut extern_attrs = self.extern_attrs.clone();
         // if we already have a STRICT marker we do not need to add it
         // presume we can upgrade, then disprove it
-        let mut strict_upgrade = !extern_attrs.iter().any(|i| i == &ExternArgs::Strict);
+        // procedures can't be `STRICT`
+        let mut strict_upgrade =
+            !self.procedure && !extern_attrs.iter().any(|i| i == &ExternArgs::Strict);
         if strict_upgrade {
             // It may be possible to infer a `STRICT` marker though.
             // But we can only do that if the user hasn't used `Option<T>` or `pgx::Internal`
@@ -132,13 +136,14 @@ impl ToSql for PgExternEntity {
 
         let fn_sql = format!(
             "\
-                                CREATE {or_replace} FUNCTION {schema}\"{name}\"({arguments}) {returns}\n\
+                                CREATE {or_replace} {kind} {schema}\"{name}\"({arguments}) {returns}\n\
                                 {extern_attrs}\
                                 {search_path}\
                                 LANGUAGE c /* Rust */\n\
                                 AS '{module_pathname}', '{unaliased_name}_wrapper';\
                             ",
             or_replace = if extern_attrs.contains(&ExternArgs::CreateOrReplace) { "OR REPLACE" } else { "" },
+            kind = if self.procedure { "PROCEDURE" } else { "FUNCTION" },
             schema = self
                 .schema
                 .map(|schema| format!("{}.", schema))
@@ -275,6 +280,7 @@ impl ToSql for PgExternEntity {
                 Default::default()
             },
             returns = match &self.fn_return {
+                PgExternReturnEntity::None if self.procedure => String::new(),
                 PgExternReturnEntity::None => String::from("RETURNS void"),
                 PgExternReturnEntity::Type { ty } => {
                     let graph_index = context
@@ -70,6 +70,7 @@ pub struct PgExtern {
     attrs: Vec<Attribute>,
     func: syn::ItemFn,
     to_sql_config: ToSqlConfig,
+    procedure: bool,
 }
 
 impl PgExtern {
@@ -106,7 +107,44 @@ impl PgExtern {
             crate::ident_is_acceptable_to_postgres(&func.sig.ident)?;
         }
 
-        Ok(Self { attrs, func, to_sql_config })
+        Ok(Self { attrs, func, to_sql_config, procedure: false })
+    }
+
+    /// Parse a `#[pg_procedure]`, which is a `#[pg_extern]` that is created with `CREATE PROCEDURE`.
+    ///
+    /// Procedures can't return anything and don't accept the attributes that only apply to functions.
+    pub fn new_procedure(attr: TokenStream2, item: TokenStream2) -> Result<Self, syn::Error> {
+        let span = attr.span();
+        let mut procedure = Self::new(attr, item)?;
+        procedure.procedure = true;
+
+        for extern_attr in &procedure.attrs {
+            match extern_attr {
+                Attribute::Immutable
+                | Attribute::Strict
+                | Attribute::Stable
+                | Attribute::Volatile
+                | Attribute::ParallelSafe
+                | Attribute::ParallelUnsafe
+                | Attribute::ParallelRestricted
+                | Attribute::Cost(_) => {
+                    return Err(syn::Error::new(
+                        span,
+                        "#[pg_procedure] only supports `create_or_replace`, `raw`, `no_guard`, `error`, `schema`, `name`, `requires`, and `sql` attributes",
+                    ))
+                }
+                _ => (),
+            }
+        }
+
+        if let syn::ReturnType::Type(_, ty) = &procedure.func.sig.output {
+            return Err(syn::Error::new(
+                ty.span(),
+                "#[pg_procedure] functions cannot return a value",
+            ));
+        }
+
+        Ok(procedure)
     }
 
     fn name(&self) -> String {
@@ -278,6 +316,7 @@ impl PgExtern {
         };
 
         let operator = self.operator().into_iter();
+        let procedure = self.procedure;
         let to_sql_config = match self.overridden() {
             None => self.to_sql_config.clone(),
             Some(content) => {
@@ -313,6 +352,7 @@ impl PgExtern {
                     search_path: None #( .unwrap_or(Some(vec![#search_path])) )*,
                     operator: None #( .unwrap_or(Some(#operator)) )*,
                     to_sql_config: #to_sql_config,
+                    procedure: #procedure,
                 };
                 ::pgx::utils::sql_entity_graph::SqlGraphEntity::Function(submission)
             }
@@ -708,6 +748,6 @@ impl Parse for PgExtern {
             crate::ident_is_acceptable_to_postgres(&func.sig.ident)?;
         }
 
-        Ok(Self { attrs, func, to_sql_config })
+        Ok(Self { attrs, func, to_sql_config, procedure: false })
     }
 }
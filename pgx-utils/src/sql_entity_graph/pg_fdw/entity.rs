@@ -0,0 +1,115 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+/*!
+
+`#[pg_fdw]` related entities for Rust to SQL translation
+
+> Like all of the [`sql_entity_graph`][crate::sql_entity_graph] APIs, this is considered **internal**
+to the `pgx` framework and very subject to change between versions. While you may use this, please do it with caution.
+
+*/
+use crate::sql_entity_graph::{
+    PgxSql, SqlGraphEntity, SqlGraphIdentifier, ToSql, ToSqlConfigEntity,
+};
+use core::cmp::{Eq, Ord, Ordering, PartialEq, PartialOrd};
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// The output of a [`PgFdw`](crate::sql_entity_graph::pg_fdw::PgFdw) from `quote::ToTokens::to_tokens`.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct PgFdwEntity {
+    /// The name of the foreign data wrapper
+    pub name: &'static str,
+    /// The symbol of its handler function
+    pub handler: &'static str,
+    /// The symbol of its validator function
+    pub validator: &'static str,
+    pub to_sql_config: ToSqlConfigEntity,
+    pub file: &'static str,
+    pub line: u32,
+    pub module_path: &'static str,
+    pub full_path: &'static str,
+}
+
+impl Ord for PgFdwEntity {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.full_path.cmp(other.full_path)
+    }
+}
+
+impl PartialOrd for PgFdwEntity {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<PgFdwEntity> for SqlGraphEntity {
+    fn from(val: PgFdwEntity) -> Self {
+        SqlGraphEntity::ForeignDataWrapper(val)
+    }
+}
+
+impl ToSql for PgFdwEntity {
+    #[tracing::instrument(
+        level = "error",
+        skip(self, context),
+        fields(identifier = %self.rust_identifier()),
+    )]
+    fn to_sql(&self, context: &PgxSql) -> eyre::Result<String> {
+        let self_index = context.fdws[self];
+        let schema = context.schema_prefix_for(&self_index);
+        let module_pathname = context.get_module_pathname();
+
+        let sql = format!(
+            "\n\
+            -- {file}:{line}\n\
+            -- {full_path}\n\
+            CREATE FUNCTION {schema}\"{handler}\"()\n\
+                \tRETURNS fdw_handler\n\
+                \tSTRICT\n\
+                \tLANGUAGE c\n\
+                \tAS '{module_pathname}', '{handler}';\n\
+            CREATE FUNCTION {schema}\"{validator}\"(text[], oid)\n\
+                \tRETURNS void\n\
+                \tSTRICT\n\
+                \tLANGUAGE c\n\
+                \tAS '{module_pathname}', '{validator}';\n\
+            CREATE FOREIGN DATA WRAPPER \"{name}\"\n\
+                \tHANDLER {schema}\"{handler}\"\n\
+                \tVALIDATOR {schema}\"{validator}\";\
+        ",
+            schema = schema,
+            file = self.file,
+            line = self.line,
+            full_path = self.full_path,
+            module_pathname = module_pathname,
+            handler = self.handler,
+            validator = self.validator,
+            name = self.name,
+        );
+        Ok(sql)
+    }
+}
+
+impl SqlGraphIdentifier for PgFdwEntity {
+    fn dot_identifier(&self) -> String {
+        format!("foreign data wrapper {}", self.full_path)
+    }
+    fn rust_identifier(&self) -> String {
+        self.full_path.to_string()
+    }
+
+    fn file(&self) -> Option<&'static str> {
+        Some(self.file)
+    }
+
+    fn line(&self) -> Option<u32> {
+        Some(self.line)
+    }
+}
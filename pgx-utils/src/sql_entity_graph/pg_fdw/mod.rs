@@ -0,0 +1,187 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+/*!
+
+`#[pg_fdw]` related macro expansion for Rust to SQL translation
+
+> Like all of the [`sql_entity_graph`][crate::sql_entity_graph] APIs, this is considered **internal**
+to the `pgx` framework and very subject to change between versions. While you may use this, please do it with caution.
+
+*/
+pub(crate) mod entity;
+
+use crate::sql_entity_graph::ToSqlConfig;
+use convert_case::{Case, Casing};
+use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
+use quote::{quote, ToTokens, TokenStreamExt};
+use syn::spanned::Spanned;
+use syn::{ItemFn, ItemImpl};
+
+/// A parsed `#[pg_fdw]` item, which is an `impl ForeignDataWrapper for ...` block
+#[derive(Debug, Clone)]
+pub struct PgFdw {
+    item_impl: ItemImpl,
+    target_ident: Ident,
+    snake_case_target_ident: Ident,
+    to_sql_config: ToSqlConfig,
+}
+
+impl PgFdw {
+    pub fn new(item_impl: ItemImpl) -> Result<Self, syn::Error> {
+        let to_sql_config =
+            ToSqlConfig::from_attributes(item_impl.attrs.as_slice())?.unwrap_or_default();
+
+        match item_impl.trait_ {
+            Some((_, ref path, _))
+                if path
+                    .segments
+                    .last()
+                    .map(|last| last.ident == "ForeignDataWrapper")
+                    .unwrap_or(false) => {}
+            _ => {
+                return Err(syn::Error::new(
+                    item_impl.span(),
+                    "`#[pg_fdw]` only works with the `ForeignDataWrapper` trait.",
+                ))
+            }
+        }
+
+        let target_ident = match &*item_impl.self_ty {
+            syn::Type::Path(type_path) => {
+                type_path.path.segments.last().map(|last| last.ident.clone()).ok_or_else(|| {
+                    syn::Error::new(
+                        type_path.span(),
+                        "`#[pg_fdw]` only works with types whose path have a final segment.",
+                    )
+                })?
+            }
+            other => {
+                return Err(syn::Error::new(
+                    other.span(),
+                    "`#[pg_fdw]` only works with types whose path have a final segment.",
+                ))
+            }
+        };
+        let snake_case_target_ident =
+            Ident::new(&target_ident.to_string().to_case(Case::Snake), target_ident.span());
+        crate::ident_is_acceptable_to_postgres(&Ident::new(
+            &format!("{}_validator", snake_case_target_ident),
+            target_ident.span(),
+        ))?;
+
+        Ok(Self { item_impl, target_ident, snake_case_target_ident, to_sql_config })
+    }
+
+    fn handler_ident(&self) -> Ident {
+        Ident::new(&format!("{}_handler", self.snake_case_target_ident), Span::call_site())
+    }
+
+    fn validator_ident(&self) -> Ident {
+        Ident::new(&format!("{}_validator", self.snake_case_target_ident), Span::call_site())
+    }
+
+    pub fn entity_tokens(&self) -> Result<ItemFn, syn::Error> {
+        let sql_graph_entity_fn_name = syn::Ident::new(
+            &format!("__pgx_internals_fdw_{}", self.snake_case_target_ident),
+            self.target_ident.span(),
+        );
+        let self_ty = &self.item_impl.self_ty;
+        let target_ident = &self.target_ident;
+        let handler = self.handler_ident().to_string();
+        let validator = self.validator_ident().to_string();
+        let to_sql_config = &self.to_sql_config;
+
+        let tokens = quote! {
+            #[no_mangle]
+            #[doc(hidden)]
+            pub extern "Rust" fn #sql_graph_entity_fn_name() -> ::pgx::utils::sql_entity_graph::SqlGraphEntity {
+                let submission = ::pgx::utils::sql_entity_graph::PgFdwEntity {
+                    name: <#self_ty as ::pgx::fdw::ForeignDataWrapper>::NAME,
+                    handler: #handler,
+                    validator: #validator,
+                    file: file!(),
+                    line: line!(),
+                    full_path: concat!(module_path!(), "::", stringify!(#target_ident)),
+                    module_path: module_path!(),
+                    to_sql_config: #to_sql_config,
+                };
+                ::pgx::utils::sql_entity_graph::SqlGraphEntity::ForeignDataWrapper(submission)
+            }
+        };
+        syn::parse2(tokens)
+    }
+
+    pub fn handler_tokens(&self) -> Result<ItemFn, syn::Error> {
+        let self_ty = &self.item_impl.self_ty;
+        let handler = self.handler_ident();
+        let tokens = quote! {
+            #[no_mangle]
+            #[pgx::pg_guard]
+            extern "C" fn #handler(_fcinfo: ::pgx::pg_sys::FunctionCallInfo) -> ::pgx::pg_sys::Datum {
+                ::pgx::fdw::fdw_handler::<#self_ty>()
+            }
+        };
+        syn::parse2(tokens)
+    }
+
+    pub fn validator_tokens(&self) -> Result<ItemFn, syn::Error> {
+        let self_ty = &self.item_impl.self_ty;
+        let validator = self.validator_ident();
+        let tokens = quote! {
+            #[no_mangle]
+            #[pgx::pg_guard]
+            extern "C" fn #validator(fcinfo: ::pgx::pg_sys::FunctionCallInfo) -> ::pgx::pg_sys::Datum {
+                ::pgx::fdw::fdw_validator::<#self_ty>(fcinfo)
+            }
+        };
+        syn::parse2(tokens)
+    }
+
+    pub fn finfo_tokens(&self, function: &Ident) -> Result<ItemFn, syn::Error> {
+        let finfo_name = syn::Ident::new(&format!("pg_finfo_{}", function), Span::call_site());
+        let tokens = quote! {
+            #[no_mangle]
+            #[doc(hidden)]
+            pub extern "C" fn #finfo_name() -> &'static ::pgx::pg_sys::Pg_finfo_record {
+                const V1_API: ::pgx::pg_sys::Pg_finfo_record = ::pgx::pg_sys::Pg_finfo_record { api_version: 1 };
+                &V1_API
+            }
+        };
+        syn::parse2(tokens)
+    }
+}
+
+impl ToTokens for PgFdw {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let entity_func = self.entity_tokens().expect("Generating entity function for fdw");
+        let handler_func = self.handler_tokens().expect("Generating handler function for fdw");
+        let validator_func =
+            self.validator_tokens().expect("Generating validator function for fdw");
+        let handler_finfo_func =
+            self.finfo_tokens(&self.handler_ident()).expect("Generating finfo function for fdw");
+        let validator_finfo_func =
+            self.finfo_tokens(&self.validator_ident()).expect("Generating finfo function for fdw");
+        let item_impl = &self.item_impl;
+
+        let items = quote! {
+            #item_impl
+
+            #handler_func
+
+            #handler_finfo_func
+
+            #validator_func
+
+            #validator_finfo_func
+
+            #entity_func
+        };
+        tokens.append_all(items);
+    }
+}
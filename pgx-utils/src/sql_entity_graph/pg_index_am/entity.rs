@@ -0,0 +1,169 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+/*!
+
+`#[pg_index_am]` related entities for Rust to SQL translation
+
+> Like all of the [`sql_entity_graph`][crate::sql_entity_graph] APIs, this is considered **internal**
+to the `pgx` framework and very subject to change between versions. While you may use this, please do it with caution.
+
+*/
+use crate::sql_entity_graph::{
+    PgxSql, SqlGraphEntity, SqlGraphIdentifier, ToSql, ToSqlConfigEntity,
+};
+use core::cmp::{Eq, Ord, Ordering, PartialEq, PartialOrd};
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// A default operator class of an index access method
+///
+/// Each becomes a `CREATE OPERATOR CLASS name DEFAULT FOR TYPE for_type USING access_method`
+/// statement, whose items are the `operators` and `functions`, numbered by their strategy and
+/// support function numbers.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct OperatorClassEntity {
+    /// The name of the operator class
+    pub name: &'static str,
+    /// The SQL type it indexes, such as `"int4"`
+    pub for_type: &'static str,
+    /// The operators, by strategy number, such as `(1, "=")` or `(3, "&&(int4[], int4[])")`
+    pub operators: &'static [(u16, &'static str)],
+    /// The support functions, by support function number, such as `(1, "btint4cmp(int4, int4)")`
+    pub functions: &'static [(u16, &'static str)],
+}
+
+impl OperatorClassEntity {
+    /// The names of the operators and functions, without their argument types
+    pub(crate) fn item_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.operators
+            .iter()
+            .chain(self.functions.iter())
+            .map(|(_, item)| item.split('(').next().unwrap_or(item).trim().trim_matches('"'))
+    }
+}
+
+/// The output of a [`PgIndexAm`](crate::sql_entity_graph::pg_index_am::PgIndexAm) from `quote::ToTokens::to_tokens`.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct PgIndexAmEntity {
+    /// The name of the access method
+    pub name: &'static str,
+    /// The symbol of its handler function
+    pub handler: &'static str,
+    /// Its default operator classes
+    pub operator_classes: &'static [OperatorClassEntity],
+    pub to_sql_config: ToSqlConfigEntity,
+    pub file: &'static str,
+    pub line: u32,
+    pub module_path: &'static str,
+    pub full_path: &'static str,
+}
+
+impl Ord for PgIndexAmEntity {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.full_path.cmp(other.full_path)
+    }
+}
+
+impl PartialOrd for PgIndexAmEntity {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<PgIndexAmEntity> for SqlGraphEntity {
+    fn from(val: PgIndexAmEntity) -> Self {
+        SqlGraphEntity::IndexAccessMethod(val)
+    }
+}
+
+impl ToSql for PgIndexAmEntity {
+    #[tracing::instrument(
+        level = "error",
+        skip(self, context),
+        fields(identifier = %self.rust_identifier()),
+    )]
+    fn to_sql(&self, context: &PgxSql) -> eyre::Result<String> {
+        let self_index = context.index_ams[self];
+        let schema = context.schema_prefix_for(&self_index);
+        let module_pathname = context.get_module_pathname();
+
+        let mut sql = format!(
+            "\n\
+            -- {file}:{line}\n\
+            -- {full_path}\n\
+            CREATE FUNCTION {schema}\"{handler}\"(internal)\n\
+                \tRETURNS index_am_handler\n\
+                \tSTRICT\n\
+                \tLANGUAGE c\n\
+                \tAS '{module_pathname}', '{handler}';\n\
+            CREATE ACCESS METHOD \"{name}\"\n\
+                \tTYPE INDEX\n\
+                \tHANDLER {schema}\"{handler}\";\
+        ",
+            schema = schema,
+            file = self.file,
+            line = self.line,
+            full_path = self.full_path,
+            module_pathname = module_pathname,
+            handler = self.handler,
+            name = self.name,
+        );
+
+        for operator_class in self.operator_classes {
+            let items = operator_class
+                .operators
+                .iter()
+                .map(|(strategy, operator)| format!("\tOPERATOR {} {}", strategy, operator))
+                .chain(
+                    operator_class
+                        .functions
+                        .iter()
+                        .map(|(support, function)| format!("\tFUNCTION {} {}", support, function)),
+                )
+                .collect::<Vec<_>>();
+            if items.is_empty() {
+                return Err(eyre::eyre!(
+                    "operator class `{}` of access method `{}` has no operators or functions",
+                    operator_class.name,
+                    self.name
+                ));
+            }
+            sql.push_str(&format!(
+                "\n\
+                CREATE OPERATOR CLASS {schema}\"{opclass}\"\n\
+                    \tDEFAULT FOR TYPE {for_type} USING \"{name}\" AS\n\
+                {items};\
+            ",
+                schema = schema,
+                opclass = operator_class.name,
+                for_type = operator_class.for_type,
+                name = self.name,
+                items = items.join(",\n"),
+            ));
+        }
+        Ok(sql)
+    }
+}
+
+impl SqlGraphIdentifier for PgIndexAmEntity {
+    fn dot_identifier(&self) -> String {
+        format!("index access method {}", self.full_path)
+    }
+    fn rust_identifier(&self) -> String {
+        self.full_path.to_string()
+    }
+
+    fn file(&self) -> Option<&'static str> {
+        Some(self.file)
+    }
+
+    fn line(&self) -> Option<u32> {
+        Some(self.line)
+    }
+}
@@ -6,14 +6,25 @@
 to the `pgx` framework and very subject to change between versions. While you may use this, please do it with caution.
 
 */
-use crate::sql_entity_graph::ToSqlConfig;
+use crate::sql_entity_graph::{PositioningRef, ToSqlConfig};
 use proc_macro2::Span;
 use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
 use syn::Token;
 
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub enum PgTriggerAttribute {
     Sql(ToSqlConfig),
+    Name(syn::LitStr),
+    Table(syn::LitStr),
+    Events(syn::LitStr),
+    Timing(syn::LitStr),
+    Level(syn::LitStr),
+    When(syn::LitStr),
+    Constraint(syn::LitBool),
+    Deferrable(syn::LitBool),
+    Initially(syn::LitStr),
+    Requires(Punctuated<PositioningRef, Token![,]>),
 }
 
 impl Parse for PgTriggerAttribute {
@@ -37,6 +48,33 @@ impl Parse for PgTriggerAttribute {
                     }
                 }
             }
+            "name" | "table" | "events" | "timing" | "level" | "when" | "initially" => {
+                let _eq: Token![=] = input.parse()?;
+                let value: syn::LitStr = input.parse()?;
+                match ident.to_string().as_str() {
+                    "name" => Self::Name(value),
+                    "table" => Self::Table(value),
+                    "events" => Self::Events(value),
+                    "timing" => Self::Timing(value),
+                    "level" => Self::Level(value),
+                    "when" => Self::When(value),
+                    _ => Self::Initially(value),
+                }
+            }
+            "constraint" => {
+                let _eq: Token![=] = input.parse()?;
+                Self::Constraint(input.parse()?)
+            }
+            "deferrable" => {
+                let _eq: Token![=] = input.parse()?;
+                Self::Deferrable(input.parse()?)
+            }
+            "requires" => {
+                let _eq: Token![=] = input.parse()?;
+                let content;
+                let _bracket = syn::bracketed!(content in input);
+                Self::Requires(content.parse_terminated(PositioningRef::parse)?)
+            }
             e => {
                 return Err(syn::Error::new(
                     Span::call_site(),
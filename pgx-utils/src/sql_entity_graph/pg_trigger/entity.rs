@@ -7,7 +7,7 @@ to the `pgx` framework and very subject to change between versions. While you ma
 
 */
 use crate::sql_entity_graph::{
-    PgxSql, SqlGraphEntity, SqlGraphIdentifier, ToSql, ToSqlConfigEntity,
+    PgxSql, PositioningRef, SqlGraphEntity, SqlGraphIdentifier, ToSql, ToSqlConfigEntity,
 };
 use core::cmp::{Eq, Ord, Ordering, PartialEq, PartialOrd};
 use core::fmt::Debug;
@@ -16,6 +16,10 @@ use core::hash::Hash;
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct PgTriggerEntity {
     pub function_name: &'static str,
+    /// The `CREATE TRIGGER` statement to generate, if any
+    pub create_trigger: Option<CreateTriggerEntity>,
+    /// Entities the `CREATE TRIGGER` statement must come after, such as the `extension_sql!` creating its table
+    pub requires: Vec<PositioningRef>,
     pub to_sql_config: ToSqlConfigEntity,
     pub file: &'static str,
     pub line: u32,
@@ -23,6 +27,47 @@ pub struct PgTriggerEntity {
     pub full_path: &'static str,
 }
 
+/// The parts of a `CREATE TRIGGER` statement, already in SQL keywords like `UPDATE OF status`
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct CreateTriggerEntity {
+    pub name: &'static str,
+    pub table: &'static str,
+    pub events: &'static [&'static str],
+    pub timing: &'static str,
+    pub level: &'static str,
+    pub when: Option<&'static str>,
+    pub constraint: bool,
+    pub deferrable: bool,
+    pub initially_deferred: bool,
+}
+
+impl CreateTriggerEntity {
+    fn to_sql(&self, schema: &str, function_name: &str) -> String {
+        let mut sql = format!(
+            "CREATE {constraint}TRIGGER \"{name}\"\n\
+                \t{timing} {events} ON {table}\n",
+            constraint = if self.constraint { "CONSTRAINT " } else { "" },
+            name = self.name,
+            timing = self.timing,
+            events = self.events.join(" OR "),
+            table = self.table,
+        );
+        if self.deferrable {
+            sql.push_str("\tDEFERRABLE");
+            if self.initially_deferred {
+                sql.push_str(" INITIALLY DEFERRED");
+            }
+            sql.push('\n');
+        }
+        sql.push_str(&format!("\tFOR EACH {}\n", self.level));
+        if let Some(when) = self.when {
+            sql.push_str(&format!("\tWHEN ({})\n", when));
+        }
+        sql.push_str(&format!("\tEXECUTE PROCEDURE {}\"{}\"();", schema, function_name));
+        sql
+    }
+}
+
 impl PgTriggerEntity {
     fn wrapper_function_name(&self) -> String {
         self.function_name.to_string() + "_wrapper"
@@ -57,7 +102,7 @@ impl ToSql for PgTriggerEntity {
         let self_index = context.triggers[self];
         let schema = context.schema_prefix_for(&self_index);
 
-        let sql = format!(
+        let mut sql = format!(
             "\n\
             -- {file}:{line}\n\
             -- {full_path}\n\
@@ -73,6 +118,10 @@ impl ToSql for PgTriggerEntity {
             function_name = self.function_name,
             wrapper_function_name = self.wrapper_function_name(),
         );
+        if let Some(create_trigger) = &self.create_trigger {
+            sql.push_str("\n\n");
+            sql.push_str(&create_trigger.to_sql(&schema, self.function_name));
+        }
         Ok(sql)
     }
 }
@@ -9,7 +9,7 @@ to the `pgx` framework and very subject to change between versions. While you ma
 pub mod attribute;
 pub mod entity;
 
-use crate::sql_entity_graph::ToSqlConfig;
+use crate::sql_entity_graph::{PositioningRef, ToSqlConfig};
 use attribute::PgTriggerAttribute;
 use proc_macro2::{Span, TokenStream as TokenStream2};
 use quote::{quote, ToTokens, TokenStreamExt};
@@ -18,40 +18,145 @@ use syn::{ItemFn, Token};
 #[derive(Debug, Clone)]
 pub struct PgTrigger {
     func: syn::ItemFn,
+    create_trigger: Option<CreateTrigger>,
+    requires: Vec<PositioningRef>,
     to_sql_config: ToSqlConfig,
 }
 
+/// The `CREATE TRIGGER` statement to generate for a [`PgTrigger`], normalized to SQL keywords
+#[derive(Debug, Clone)]
+pub struct CreateTrigger {
+    pub name: String,
+    pub table: String,
+    pub events: Vec<String>,
+    pub timing: String,
+    pub level: String,
+    pub when: Option<String>,
+    pub constraint: bool,
+    pub deferrable: bool,
+    pub initially_deferred: bool,
+}
+
 impl PgTrigger {
     pub fn new(
         func: ItemFn,
         attributes: syn::punctuated::Punctuated<PgTriggerAttribute, Token![,]>,
     ) -> Result<Self, syn::Error> {
-        if attributes.len() > 1 {
-            return Err(syn::Error::new(
-                Span::call_site(),
-                "Multiple `sql` arguments found, it must be unique",
-            ));
-        };
-        let to_sql_config = attributes
-            .first()
-            .cloned()
-            .map(|PgTriggerAttribute::Sql(mut config)| {
-                if let Some(ref mut content) = config.content {
-                    let value = content.value();
-                    let updated_value = value
-                        .replace("@FUNCTION_NAME@", &*(func.sig.ident.to_string() + "_wrapper"))
-                        + "\n";
-                    *content = syn::LitStr::new(&updated_value, Span::call_site());
-                };
-                config
-            })
-            .unwrap_or_default();
+        let mut to_sql_config = None;
+        let mut name = None;
+        let mut table = None;
+        let mut events = None;
+        let mut timing = None;
+        let mut level = None;
+        let mut when = None;
+        let mut constraint = None;
+        let mut deferrable = None;
+        let mut initially = None;
+        let mut requires = Vec::new();
+        for attribute in attributes {
+            match attribute {
+                PgTriggerAttribute::Sql(mut config) => {
+                    if to_sql_config.is_some() {
+                        return Err(syn::Error::new(
+                            Span::call_site(),
+                            "Multiple `sql` arguments found, it must be unique",
+                        ));
+                    }
+                    if let Some(ref mut content) = config.content {
+                        let value = content.value();
+                        let updated_value = value.replace(
+                            "@FUNCTION_NAME@",
+                            &*(func.sig.ident.to_string() + "_wrapper"),
+                        ) + "\n";
+                        *content = syn::LitStr::new(&updated_value, Span::call_site());
+                    };
+                    to_sql_config = Some(config);
+                }
+                PgTriggerAttribute::Name(value) => set_once(&mut name, "name", value)?,
+                PgTriggerAttribute::Table(value) => set_once(&mut table, "table", value)?,
+                PgTriggerAttribute::Events(value) => set_once(&mut events, "events", value)?,
+                PgTriggerAttribute::Timing(value) => set_once(&mut timing, "timing", value)?,
+                PgTriggerAttribute::Level(value) => set_once(&mut level, "level", value)?,
+                PgTriggerAttribute::When(value) => set_once(&mut when, "when", value)?,
+                PgTriggerAttribute::Initially(value) => {
+                    set_once(&mut initially, "initially", value)?
+                }
+                PgTriggerAttribute::Constraint(value) => {
+                    let span = value.span();
+                    if constraint.replace(value.value).is_some() {
+                        return Err(syn::Error::new(
+                            span,
+                            "Multiple `constraint` arguments found, it must be unique",
+                        ));
+                    }
+                }
+                PgTriggerAttribute::Deferrable(value) => {
+                    let span = value.span();
+                    if deferrable.replace(value.value).is_some() {
+                        return Err(syn::Error::new(
+                            span,
+                            "Multiple `deferrable` arguments found, it must be unique",
+                        ));
+                    }
+                }
+                PgTriggerAttribute::Requires(items) => requires.extend(items),
+            }
+        }
+        let to_sql_config = to_sql_config.unwrap_or_default();
 
         if !to_sql_config.overrides_default() {
             crate::ident_is_acceptable_to_postgres(&func.sig.ident)?;
         }
 
-        Ok(Self { func, to_sql_config })
+        let create_trigger = match table {
+            None => {
+                let given = [
+                    ("name", name.is_some()),
+                    ("events", events.is_some()),
+                    ("timing", timing.is_some()),
+                    ("level", level.is_some()),
+                    ("when", when.is_some()),
+                    ("constraint", constraint.is_some()),
+                    ("deferrable", deferrable.is_some()),
+                    ("initially", initially.is_some()),
+                    ("requires", !requires.is_empty()),
+                ];
+                if let Some((attribute, _)) = given.iter().find(|(_, given)| *given) {
+                    return Err(syn::Error::new(
+                        Span::call_site(),
+                        format!("`{}` requires a `table` to create the trigger on", attribute),
+                    ));
+                }
+                None
+            }
+            Some(table) => {
+                let events = events.ok_or_else(|| {
+                    syn::Error::new(
+                        table.span(),
+                        "`table` requires `events`, like `events = \"insert|update of status\"`",
+                    )
+                })?;
+                let timing = timing.ok_or_else(|| {
+                    syn::Error::new(
+                        table.span(),
+                        "`table` requires a `timing` of \"before\", \"after\", or \"instead of\"",
+                    )
+                })?;
+                Some(CreateTrigger::new(
+                    name.map_or_else(|| func.sig.ident.to_string(), |name| name.value()),
+                    table,
+                    events,
+                    timing,
+                    level,
+                    when,
+                    constraint.unwrap_or(false),
+                    deferrable,
+                    initially,
+                )?)
+            }
+        };
+
+        Ok(Self { func, create_trigger, requires, to_sql_config })
     }
 
     pub fn entity_tokens(&self) -> Result<ItemFn, syn::Error> {
@@ -61,6 +166,11 @@ impl PgTrigger {
         );
         let func_sig_ident = &self.func.sig.ident;
         let function_name = func_sig_ident.to_string();
+        let create_trigger = match &self.create_trigger {
+            Some(create_trigger) => quote! { Some(#create_trigger) },
+            None => quote! { None },
+        };
+        let requires_iter = self.requires.iter();
         let to_sql_config = &self.to_sql_config;
 
         let tokens = quote! {
@@ -73,6 +183,8 @@ impl PgTrigger {
                 use alloc::vec;
                 let submission = ::pgx::utils::sql_entity_graph::PgTriggerEntity {
                     function_name: #function_name,
+                    create_trigger: #create_trigger,
+                    requires: vec![#(#requires_iter),*],
                     file: file!(),
                     line: line!(),
                     full_path: concat!(module_path!(), "::", stringify!(#func_sig_ident)),
@@ -149,3 +261,272 @@ impl ToTokens for PgTrigger {
         tokens.append_all(items);
     }
 }
+
+impl CreateTrigger {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        name: String,
+        table: syn::LitStr,
+        events: syn::LitStr,
+        timing: syn::LitStr,
+        level: Option<syn::LitStr>,
+        when: Option<syn::LitStr>,
+        constraint: bool,
+        deferrable: Option<bool>,
+        initially: Option<syn::LitStr>,
+    ) -> Result<Self, syn::Error> {
+        let events = events
+            .value()
+            .split('|')
+            .map(|event| {
+                parse_event(event).ok_or_else(|| {
+                    syn::Error::new(
+                        events.span(),
+                        format!(
+                            "Invalid trigger event `{}`, expected `insert`, `update`, `update of <columns>`, `delete`, or `truncate`",
+                            event.trim()
+                        ),
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let timing = match normalize(&timing.value()).as_str() {
+            "before" => "BEFORE",
+            "after" => "AFTER",
+            "instead of" => "INSTEAD OF",
+            _ => {
+                return Err(syn::Error::new(
+                    timing.span(),
+                    "`timing` must be \"before\", \"after\", or \"instead of\"",
+                ))
+            }
+        };
+        let level = match level {
+            None => "STATEMENT",
+            Some(level) => match normalize(&level.value()).as_str() {
+                "row" => "ROW",
+                "statement" => "STATEMENT",
+                _ => {
+                    return Err(syn::Error::new(
+                        level.span(),
+                        "`level` must be \"row\" or \"statement\"",
+                    ))
+                }
+            },
+        };
+        let initially_deferred = match initially {
+            None => false,
+            Some(initially) => match normalize(&initially.value()).as_str() {
+                "deferred" => true,
+                "immediate" => false,
+                _ => {
+                    return Err(syn::Error::new(
+                        initially.span(),
+                        "`initially` must be \"deferred\" or \"immediate\"",
+                    ))
+                }
+            },
+        };
+        if constraint {
+            // Postgres only accepts constraint triggers of this kind
+            if timing != "AFTER" || level != "ROW" {
+                return Err(syn::Error::new(
+                    table.span(),
+                    "`constraint` triggers must have a `timing` of \"after\" and a `level` of \"row\"",
+                ));
+            }
+            if deferrable == Some(false) && initially_deferred {
+                return Err(syn::Error::new(
+                    table.span(),
+                    "`initially = \"deferred\"` triggers must be `deferrable`",
+                ));
+            }
+        } else if deferrable.is_some() || initially_deferred {
+            return Err(syn::Error::new(
+                table.span(),
+                "`deferrable` and `initially` require `constraint = true`",
+            ));
+        }
+
+        Ok(Self {
+            name,
+            table: table.value(),
+            events,
+            timing: timing.to_string(),
+            level: level.to_string(),
+            when: when.map(|when| when.value()),
+            constraint,
+            deferrable: deferrable.unwrap_or(initially_deferred),
+            initially_deferred,
+        })
+    }
+}
+
+impl ToTokens for CreateTrigger {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let CreateTrigger {
+            name,
+            table,
+            events,
+            timing,
+            level,
+            when,
+            constraint,
+            deferrable,
+            initially_deferred,
+        } = self;
+        let when = match when {
+            Some(when) => quote! { Some(#when) },
+            None => quote! { None },
+        };
+        let quoted = quote! {
+            ::pgx::utils::sql_entity_graph::CreateTriggerEntity {
+                name: #name,
+                table: #table,
+                events: &[#(#events),*],
+                timing: #timing,
+                level: #level,
+                when: #when,
+                constraint: #constraint,
+                deferrable: #deferrable,
+                initially_deferred: #initially_deferred,
+            }
+        };
+        tokens.append_all(quoted);
+    }
+}
+
+fn set_once(
+    slot: &mut Option<syn::LitStr>,
+    attribute: &str,
+    value: syn::LitStr,
+) -> Result<(), syn::Error> {
+    let span = value.span();
+    match slot.replace(value) {
+        None => Ok(()),
+        Some(_) => Err(syn::Error::new(
+            span,
+            format!("Multiple `{}` arguments found, it must be unique", attribute),
+        )),
+    }
+}
+
+/// Lowercases `value` and collapses its whitespace, so `"Instead  Of"` is `"instead of"`
+fn normalize(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Turns an event like `update of a,b` into `UPDATE OF a, b`
+fn parse_event(event: &str) -> Option<String> {
+    let event = event.trim();
+    let (keyword, rest) = match event.find(char::is_whitespace) {
+        Some(index) => (&event[..index], event[index..].trim()),
+        None => (event, ""),
+    };
+    match (keyword.to_lowercase().as_str(), rest) {
+        ("insert", "") => Some("INSERT".to_string()),
+        ("delete", "") => Some("DELETE".to_string()),
+        ("truncate", "") => Some("TRUNCATE".to_string()),
+        ("update", "") => Some("UPDATE".to_string()),
+        ("update", rest) => {
+            let (of, columns) = rest.split_at(rest.find(char::is_whitespace)?);
+            if !of.eq_ignore_ascii_case("of") {
+                return None;
+            }
+            let columns = columns.split(',').map(str::trim).collect::<Vec<_>>();
+            if columns.iter().any(|column| column.is_empty()) {
+                return None;
+            }
+            Some(format!("UPDATE OF {}", columns.join(", ")))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::attribute::PgTriggerAttribute;
+    use super::PgTrigger;
+    use eyre::Result;
+    use proc_macro2::TokenStream;
+    use quote::quote;
+    use syn::parse::Parser;
+    use syn::punctuated::Punctuated;
+    use syn::{parse_quote, ItemFn, Token};
+
+    fn parse_trigger(attributes: TokenStream) -> Result<PgTrigger, syn::Error> {
+        let func: ItemFn = parse_quote! {
+            fn orders_trigger(trigger: &PgTrigger) -> Result<PgHeapTuple<'_, AllocatedByPostgres>, PgHeapTupleError> {
+                todo!()
+            }
+        };
+        let attributes =
+            Punctuated::<PgTriggerAttribute, Token![,]>::parse_terminated.parse2(attributes)?;
+        PgTrigger::new(func, attributes)
+    }
+
+    #[test]
+    fn trigger_create_trigger() -> Result<()> {
+        let trigger = parse_trigger(quote! {
+            table = "orders",
+            events = "insert| Update OF status,x",
+            timing = "Before",
+            level = "row",
+            when = "NEW.x > 0",
+            requires = ["create_orders"]
+        })?;
+        let create_trigger = trigger.create_trigger.as_ref().unwrap();
+        assert_eq!(create_trigger.name, "orders_trigger");
+        assert_eq!(create_trigger.events, vec!["INSERT", "UPDATE OF status, x"]);
+        assert_eq!(create_trigger.timing, "BEFORE");
+        assert_eq!(create_trigger.level, "ROW");
+        assert_eq!(create_trigger.when.as_deref(), Some("NEW.x > 0"));
+        assert!(!create_trigger.constraint);
+        assert_eq!(trigger.requires.len(), 1);
+        // It should be possible to generate entity tokens.
+        let _ = trigger.entity_tokens()?;
+        Ok(())
+    }
+
+    #[test]
+    fn trigger_constraint() -> Result<()> {
+        let trigger = parse_trigger(quote! {
+            table = "orders",
+            events = "delete",
+            timing = "after",
+            level = "row",
+            constraint = true,
+            initially = "deferred"
+        })?;
+        let create_trigger = trigger.create_trigger.as_ref().unwrap();
+        assert!(create_trigger.constraint);
+        assert!(create_trigger.deferrable);
+        assert!(create_trigger.initially_deferred);
+
+        // Postgres only has `AFTER ... FOR EACH ROW` constraint triggers
+        assert!(parse_trigger(quote! {
+            table = "orders", events = "delete", timing = "before", level = "row", constraint = true
+        })
+        .is_err());
+        assert!(parse_trigger(quote! {
+            table = "orders", events = "delete", timing = "after", deferrable = true
+        })
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn trigger_invalid() {
+        // Without a `table`, there's no trigger to create
+        assert!(parse_trigger(quote! { events = "insert" }).is_err());
+        assert!(parse_trigger(quote! { table = "orders", timing = "before" }).is_err());
+        assert!(parse_trigger(quote! { table = "orders", events = "upsert", timing = "before" })
+            .is_err());
+        assert!(parse_trigger(
+            quote! { table = "orders", events = "update of", timing = "before" }
+        )
+        .is_err());
+        assert!(parse_trigger(quote! { table = "orders", events = "insert", timing = "during" })
+            .is_err());
+    }
+}
@@ -30,7 +30,11 @@ use crate::sql_entity_graph::control_file::ControlFile;
 use crate::sql_entity_graph::extension_sql::entity::{ExtensionSqlEntity, SqlDeclaredEntity};
 use crate::sql_entity_graph::extension_sql::SqlDeclared;
 use crate::sql_entity_graph::mapping::RustSourceOnlySqlMapping;
+use crate::sql_entity_graph::pg_event_trigger::entity::PgEventTriggerEntity;
 use crate::sql_entity_graph::pg_extern::entity::PgExternEntity;
+use crate::sql_entity_graph::pg_fdw::entity::PgFdwEntity;
+use crate::sql_entity_graph::pg_index_am::entity::PgIndexAmEntity;
+use crate::sql_entity_graph::pg_table_am::entity::PgTableAmEntity;
 use crate::sql_entity_graph::pg_trigger::entity::PgTriggerEntity;
 use crate::sql_entity_graph::positioning_ref::PositioningRef;
 use crate::sql_entity_graph::postgres_enum::entity::PostgresEnumEntity;
@@ -84,6 +88,10 @@ pub struct PgxSql {
     pub hashes: HashMap<PostgresHashEntity, NodeIndex>,
     pub aggregates: HashMap<PgAggregateEntity, NodeIndex>,
     pub triggers: HashMap<PgTriggerEntity, NodeIndex>,
+    pub event_triggers: HashMap<PgEventTriggerEntity, NodeIndex>,
+    pub fdws: HashMap<PgFdwEntity, NodeIndex>,
+    pub index_ams: HashMap<PgIndexAmEntity, NodeIndex>,
+    pub table_ams: HashMap<PgTableAmEntity, NodeIndex>,
     pub extension_name: String,
     pub versioned_so: bool,
 }
@@ -113,6 +121,10 @@ impl PgxSql {
         let mut hashes: Vec<PostgresHashEntity> = Vec::default();
         let mut aggregates: Vec<PgAggregateEntity> = Vec::default();
         let mut triggers: Vec<PgTriggerEntity> = Vec::default();
+        let mut event_triggers: Vec<PgEventTriggerEntity> = Vec::default();
+        let mut fdws: Vec<PgFdwEntity> = Vec::default();
+        let mut index_ams: Vec<PgIndexAmEntity> = Vec::default();
+        let mut table_ams: Vec<PgTableAmEntity> = Vec::default();
         for entity in entities {
             match entity {
                 SqlGraphEntity::ExtensionRoot(input_control) => {
@@ -146,6 +158,18 @@ impl PgxSql {
                 SqlGraphEntity::Trigger(input_trigger) => {
                     triggers.push(input_trigger);
                 }
+                SqlGraphEntity::EventTrigger(input_event_trigger) => {
+                    event_triggers.push(input_event_trigger);
+                }
+                SqlGraphEntity::ForeignDataWrapper(input_fdw) => {
+                    fdws.push(input_fdw);
+                }
+                SqlGraphEntity::IndexAccessMethod(input_index_am) => {
+                    index_ams.push(input_index_am);
+                }
+                SqlGraphEntity::TableAccessMethod(input_table_am) => {
+                    table_ams.push(input_table_am);
+                }
             }
         }
 
@@ -187,6 +211,13 @@ impl PgxSql {
             &mapped_types,
         )?;
         let mapped_triggers = initialize_triggers(&mut graph, root, bootstrap, finalize, triggers)?;
+        let mapped_event_triggers =
+            initialize_event_triggers(&mut graph, root, bootstrap, finalize, event_triggers)?;
+        let mapped_fdws = initialize_fdws(&mut graph, root, bootstrap, finalize, fdws)?;
+        let mapped_index_ams =
+            initialize_index_ams(&mut graph, root, bootstrap, finalize, index_ams)?;
+        let mapped_table_ams =
+            initialize_table_ams(&mut graph, root, bootstrap, finalize, table_ams)?;
 
         // Now we can circle back and build up the edge sets.
         connect_schemas(&mut graph, &mapped_schemas, root);
@@ -237,7 +268,19 @@ impl PgxSql {
             &mapped_builtin_types,
             &mapped_externs,
         )?;
-        connect_triggers(&mut graph, &mapped_triggers, &mapped_schemas);
+        connect_triggers(
+            &mut graph,
+            &mapped_triggers,
+            &mapped_schemas,
+            &mapped_types,
+            &mapped_enums,
+            &mapped_externs,
+            &mapped_extension_sqls,
+        )?;
+        connect_event_triggers(&mut graph, &mapped_event_triggers, &mapped_schemas);
+        connect_fdws(&mut graph, &mapped_fdws, &mapped_schemas);
+        connect_index_ams(&mut graph, &mapped_index_ams, &mapped_schemas, &mapped_externs);
+        connect_table_ams(&mut graph, &mapped_table_ams, &mapped_schemas);
 
         let this = Self {
             source_mappings: source_mappings.into_iter().map(|x| (x.rust.clone(), x)).collect(),
@@ -252,6 +295,10 @@ impl PgxSql {
             hashes: mapped_hashes,
             aggregates: mapped_aggregates,
             triggers: mapped_triggers,
+            event_triggers: mapped_event_triggers,
+            fdws: mapped_fdws,
+            index_ams: mapped_index_ams,
+            table_ams: mapped_table_ams,
             graph: graph,
             graph_root: root,
             graph_bootstrap: bootstrap,
@@ -385,6 +432,22 @@ impl PgxSql {
                         "label = \"{}\", penwidth = 0, style = \"filled\", fillcolor = \"#FFE4E0\", weight = 5, shape = \"diamond\"",
                         node.dot_identifier()
                     ),
+                    SqlGraphEntity::EventTrigger(_item) => format!(
+                        "label = \"{}\", penwidth = 0, style = \"filled\", fillcolor = \"#FFE4E0\", weight = 5, shape = \"diamond\"",
+                        node.dot_identifier()
+                    ),
+                    SqlGraphEntity::ForeignDataWrapper(_item) => format!(
+                        "label = \"{}\", penwidth = 0, style = \"filled\", fillcolor = \"#FFE4E0\", weight = 5, shape = \"diamond\"",
+                        node.dot_identifier()
+                    ),
+                    SqlGraphEntity::IndexAccessMethod(_item) => format!(
+                        "label = \"{}\", penwidth = 0, style = \"filled\", fillcolor = \"#FFE4E0\", weight = 5, shape = \"diamond\"",
+                        node.dot_identifier()
+                    ),
+                    SqlGraphEntity::TableAccessMethod(_item) => format!(
+                        "label = \"{}\", penwidth = 0, style = \"filled\", fillcolor = \"#FFE4E0\", weight = 5, shape = \"diamond\"",
+                        node.dot_identifier()
+                    ),
                     SqlGraphEntity::CustomSql(_item) => format!(
                         "label = \"{}\", weight = 3, shape = \"signature\"",
                         node.dot_identifier()
@@ -1494,7 +1557,11 @@ fn connect_triggers(
     graph: &mut StableGraph<SqlGraphEntity, SqlGraphRelationship>,
     triggers: &HashMap<PgTriggerEntity, NodeIndex>,
     schemas: &HashMap<SchemaEntity, NodeIndex>,
-) {
+    types: &HashMap<PostgresTypeEntity, NodeIndex>,
+    enums: &HashMap<PostgresEnumEntity, NodeIndex>,
+    externs: &HashMap<PgExternEntity, NodeIndex>,
+    extension_sqls: &HashMap<ExtensionSqlEntity, NodeIndex>,
+) -> eyre::Result<()> {
     for (item, &index) in triggers {
         make_schema_connection(
             graph,
@@ -1504,6 +1571,198 @@ fn connect_triggers(
             item.module_path,
             schemas,
         );
+
+        for requires in &item.requires {
+            if let Some(target) = find_positioning_ref_target(
+                requires,
+                types,
+                enums,
+                externs,
+                schemas,
+                extension_sqls,
+                triggers,
+            ) {
+                tracing::debug!(from = %item.rust_identifier(), to = ?graph[*target].rust_identifier(), "Adding Trigger after positioning ref target");
+                graph.add_edge(*target, index, SqlGraphRelationship::RequiredBy);
+            } else {
+                return Err(eyre!(
+                    "Could not find `requires` target of `{}` ({}:{}): {}",
+                    item.rust_identifier(),
+                    item.file,
+                    item.line,
+                    match requires {
+                        PositioningRef::FullPath(path) => path.to_string(),
+                        PositioningRef::Name(name) => format!(r#""{}""#, name),
+                    },
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[tracing::instrument(level = "info", skip_all)]
+fn initialize_event_triggers(
+    graph: &mut StableGraph<SqlGraphEntity, SqlGraphRelationship>,
+    root: NodeIndex,
+    bootstrap: Option<NodeIndex>,
+    finalize: Option<NodeIndex>,
+    event_triggers: Vec<PgEventTriggerEntity>,
+) -> eyre::Result<HashMap<PgEventTriggerEntity, NodeIndex>> {
+    let mut mapped_event_triggers = HashMap::default();
+    for item in event_triggers {
+        let entity: SqlGraphEntity = item.clone().into();
+        let index = graph.add_node(entity);
+
+        mapped_event_triggers.insert(item, index);
+        build_base_edges(graph, index, root, bootstrap, finalize);
+    }
+    Ok(mapped_event_triggers)
+}
+
+#[tracing::instrument(level = "info", skip_all)]
+fn connect_event_triggers(
+    graph: &mut StableGraph<SqlGraphEntity, SqlGraphRelationship>,
+    event_triggers: &HashMap<PgEventTriggerEntity, NodeIndex>,
+    schemas: &HashMap<SchemaEntity, NodeIndex>,
+) {
+    for (item, &index) in event_triggers {
+        make_schema_connection(
+            graph,
+            "Event trigger",
+            index,
+            &item.rust_identifier(),
+            item.module_path,
+            schemas,
+        );
+    }
+}
+
+#[tracing::instrument(level = "info", skip_all)]
+fn initialize_fdws(
+    graph: &mut StableGraph<SqlGraphEntity, SqlGraphRelationship>,
+    root: NodeIndex,
+    bootstrap: Option<NodeIndex>,
+    finalize: Option<NodeIndex>,
+    fdws: Vec<PgFdwEntity>,
+) -> eyre::Result<HashMap<PgFdwEntity, NodeIndex>> {
+    let mut mapped_fdws = HashMap::default();
+    for item in fdws {
+        let entity: SqlGraphEntity = item.clone().into();
+        let index = graph.add_node(entity);
+
+        mapped_fdws.insert(item, index);
+        build_base_edges(graph, index, root, bootstrap, finalize);
+    }
+    Ok(mapped_fdws)
+}
+
+#[tracing::instrument(level = "info", skip_all)]
+fn connect_fdws(
+    graph: &mut StableGraph<SqlGraphEntity, SqlGraphRelationship>,
+    fdws: &HashMap<PgFdwEntity, NodeIndex>,
+    schemas: &HashMap<SchemaEntity, NodeIndex>,
+) {
+    for (item, &index) in fdws {
+        make_schema_connection(
+            graph,
+            "Foreign data wrapper",
+            index,
+            &item.rust_identifier(),
+            item.module_path,
+            schemas,
+        );
+    }
+}
+
+#[tracing::instrument(level = "info", skip_all)]
+fn initialize_index_ams(
+    graph: &mut StableGraph<SqlGraphEntity, SqlGraphRelationship>,
+    root: NodeIndex,
+    bootstrap: Option<NodeIndex>,
+    finalize: Option<NodeIndex>,
+    index_ams: Vec<PgIndexAmEntity>,
+) -> eyre::Result<HashMap<PgIndexAmEntity, NodeIndex>> {
+    let mut mapped_index_ams = HashMap::default();
+    for item in index_ams {
+        let entity: SqlGraphEntity = item.clone().into();
+        let index = graph.add_node(entity);
+
+        mapped_index_ams.insert(item, index);
+        build_base_edges(graph, index, root, bootstrap, finalize);
+    }
+    Ok(mapped_index_ams)
+}
+
+#[tracing::instrument(level = "info", skip_all)]
+fn connect_index_ams(
+    graph: &mut StableGraph<SqlGraphEntity, SqlGraphRelationship>,
+    index_ams: &HashMap<PgIndexAmEntity, NodeIndex>,
+    schemas: &HashMap<SchemaEntity, NodeIndex>,
+    externs: &HashMap<PgExternEntity, NodeIndex>,
+) {
+    for (item, &index) in index_ams {
+        make_schema_connection(
+            graph,
+            "Index access method",
+            index,
+            &item.rust_identifier(),
+            item.module_path,
+            schemas,
+        );
+
+        // The operator classes' operators and support functions may be ones the extension defines
+        for item_name in item.operator_classes.iter().flat_map(|opclass| opclass.item_names()) {
+            for (extern_item, &extern_index) in externs {
+                let is_operator = extern_item
+                    .operator
+                    .as_ref()
+                    .and_then(|operator| operator.opname)
+                    .map(|opname| opname == item_name)
+                    .unwrap_or(false);
+                if extern_item.name == item_name || is_operator {
+                    tracing::debug!(from = ?item.full_path, to = extern_item.full_path, "Adding Index access method after Extern edge");
+                    graph.add_edge(extern_index, index, SqlGraphRelationship::RequiredBy);
+                }
+            }
+        }
+    }
+}
+
+#[tracing::instrument(level = "info", skip_all)]
+fn initialize_table_ams(
+    graph: &mut StableGraph<SqlGraphEntity, SqlGraphRelationship>,
+    root: NodeIndex,
+    bootstrap: Option<NodeIndex>,
+    finalize: Option<NodeIndex>,
+    table_ams: Vec<PgTableAmEntity>,
+) -> eyre::Result<HashMap<PgTableAmEntity, NodeIndex>> {
+    let mut mapped_table_ams = HashMap::default();
+    for item in table_ams {
+        let entity: SqlGraphEntity = item.clone().into();
+        let index = graph.add_node(entity);
+
+        mapped_table_ams.insert(item, index);
+        build_base_edges(graph, index, root, bootstrap, finalize);
+    }
+    Ok(mapped_table_ams)
+}
+
+#[tracing::instrument(level = "info", skip_all)]
+fn connect_table_ams(
+    graph: &mut StableGraph<SqlGraphEntity, SqlGraphRelationship>,
+    table_ams: &HashMap<PgTableAmEntity, NodeIndex>,
+    schemas: &HashMap<SchemaEntity, NodeIndex>,
+) {
+    for (item, &index) in table_ams {
+        make_schema_connection(
+            graph,
+            "Table access method",
+            index,
+            &item.rust_identifier(),
+            item.module_path,
+            schemas,
+        );
     }
 }
 
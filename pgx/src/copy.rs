@@ -0,0 +1,401 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+
+//! Bulk loading of rows into a table through Postgres' `COPY FROM` machinery
+use crate::log::raise;
+use crate::{
+    direct_function_call, pg_guard, pg_sys, IntoDatum, PgList, PgMemoryContexts, PgOid, PgRelation,
+    PgSqlErrorCode, PgTupleDesc,
+};
+use std::collections::HashMap;
+use std::os::raw::{c_char, c_int, c_void};
+use std::panic::AssertUnwindSafe;
+
+/// A row that can be loaded into a table with [`copy_into`].
+///
+/// This is implemented for tuples (up to 16 elements) of [`IntoDatum`] values and for
+/// [`PgHeapTuple`][crate::PgHeapTuple]s.
+pub trait CopyRow {
+    /// Write each of this row's column values, in order, to `writer`
+    fn write_copy_row(self, writer: &mut CopyRowWriter);
+}
+
+seq_macro::seq!(I in 0..16 {
+    #(
+        seq_macro::seq!(N in 0..=I {
+            impl<#(Col~N: IntoDatum,)*> CopyRow for (#(Col~N,)*) {
+                fn write_copy_row(self, writer: &mut CopyRowWriter) {
+                    #(
+                        writer.push(self.N);
+                    )*
+                }
+            }
+        });
+    )*
+});
+
+/// Encodes the values of a [`CopyRow`] into `COPY`'s text format.
+///
+/// Each value is converted to text with its type's output function, and then converted back by
+/// the target column's input function, just as if it had been sent by a client with `COPY FROM
+/// STDIN`.  So a value doesn't need to be of the column's exact type, as long as its text form is
+/// acceptable to the column's type.
+pub struct CopyRowWriter {
+    buf: Vec<u8>,
+    columns: usize,
+    target_columns: Vec<String>,
+    output_functions: HashMap<pg_sys::Oid, pg_sys::Oid>,
+}
+
+impl CopyRowWriter {
+    fn new() -> Self {
+        CopyRowWriter {
+            buf: Vec::new(),
+            columns: 0,
+            target_columns: Vec::new(),
+            output_functions: HashMap::new(),
+        }
+    }
+
+    /// The names of the table columns that each row's values are loaded into, in order
+    ///
+    /// These are the `columns` given to [`copy_into`] or, if none were given, every column of the
+    /// table that isn't dropped or generated.
+    pub fn target_columns(&self) -> &[String] {
+        &self.target_columns
+    }
+
+    /// Write the next column's value
+    pub fn push<T: IntoDatum>(&mut self, value: T) {
+        unsafe { self.push_datum(PgOid::from(T::type_oid()), value.into_datum()) }
+    }
+
+    /// Write the next column's value as a raw datum of the specified type, where `None` is NULL
+    ///
+    /// ## Safety
+    ///
+    /// `datum` must be a valid datum of type `type_oid`
+    pub unsafe fn push_datum(&mut self, type_oid: PgOid, datum: Option<pg_sys::Datum>) {
+        if self.columns > 0 {
+            self.buf.push(b'\t');
+        }
+        self.columns += 1;
+
+        let datum = match datum {
+            Some(datum) => datum,
+            None => {
+                self.buf.extend_from_slice(b"\\N");
+                return;
+            }
+        };
+
+        let output_function = *self.output_functions.entry(type_oid.value()).or_insert_with(|| {
+            let mut output_function = pg_sys::InvalidOid;
+            let mut is_varlena = false;
+            pg_sys::getTypeOutputInfo(type_oid.value(), &mut output_function, &mut is_varlena);
+            output_function
+        });
+
+        let text = pg_sys::OidOutputFunctionCall(output_function, datum);
+        for byte in std::ffi::CStr::from_ptr(text).to_bytes() {
+            match byte {
+                b'\\' => self.buf.extend_from_slice(b"\\\\"),
+                b'\t' => self.buf.extend_from_slice(b"\\t"),
+                b'\n' => self.buf.extend_from_slice(b"\\n"),
+                b'\r' => self.buf.extend_from_slice(b"\\r"),
+                byte => self.buf.push(*byte),
+            }
+        }
+        pg_sys::pfree(text as *mut c_void);
+    }
+
+    fn end_row(&mut self) {
+        self.buf.push(b'\n');
+        self.columns = 0;
+    }
+}
+
+/// Something that produces `COPY` data on demand
+trait CopyDataSource {
+    /// Fill `outbuf` with as much data as is available, returning how many bytes were written.
+    /// Zero means there's no more data.
+    fn read(&mut self, outbuf: &mut [u8]) -> usize;
+}
+
+/// The rows of an in-progress [`copy_into`], encoded as `COPY` asks for more data
+struct CopySource<I> {
+    rows: I,
+    writer: CopyRowWriter,
+    position: usize,
+    memcxt: PgMemoryContexts,
+}
+
+impl<R: CopyRow, I: Iterator<Item = R>> CopyDataSource for CopySource<I> {
+    fn read(&mut self, outbuf: &mut [u8]) -> usize {
+        let mut written = 0;
+        while written < outbuf.len() {
+            if self.position == self.writer.buf.len() {
+                // encode the next row
+                let row = match self.rows.next() {
+                    Some(row) => row,
+                    None => break,
+                };
+
+                self.writer.buf.clear();
+                self.position = 0;
+
+                // the datums and their text forms are only needed until they're encoded
+                self.memcxt.reset();
+                let outer_memcxt = self.memcxt.set_as_current();
+                let writer = &mut self.writer;
+                let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                    row.write_copy_row(writer);
+                    writer.end_row();
+                }));
+                // make sure we're out of our context before it's deleted, even if we failed
+                outer_memcxt.set_as_current();
+                if let Err(e) = result {
+                    std::panic::resume_unwind(e)
+                }
+            }
+
+            let len = (self.writer.buf.len() - self.position).min(outbuf.len() - written);
+            outbuf[written..written + len]
+                .copy_from_slice(&self.writer.buf[self.position..self.position + len]);
+            self.position += len;
+            written += len;
+        }
+        written
+    }
+}
+
+/// The data source of the [`copy_into`] that is currently running, if any
+static mut CURRENT_SOURCE: Option<*mut dyn CopyDataSource> = None;
+
+#[pg_guard]
+unsafe extern "C" fn copy_data_source(
+    outbuf: *mut c_void,
+    _minread: c_int,
+    maxread: c_int,
+) -> c_int {
+    let source = CURRENT_SOURCE.expect("copy_into() data source is not set");
+    let outbuf = std::slice::from_raw_parts_mut(outbuf as *mut u8, maxread as usize);
+    (*source).read(outbuf) as c_int
+}
+
+/// Restores the previous [`CURRENT_SOURCE`], even if `COPY` raises an ERROR, so that a nested
+/// [`copy_into`] (from a trigger, for example) leaves the outer one intact
+struct CurrentSourceGuard(Option<*mut dyn CopyDataSource>);
+
+impl Drop for CurrentSourceGuard {
+    fn drop(&mut self) {
+        unsafe {
+            CURRENT_SOURCE = self.0;
+        }
+    }
+}
+
+/// Is `att` a generated column, which `COPY` can't load values into?
+#[cfg(any(feature = "pg10", feature = "pg11"))]
+fn is_generated(_att: &pg_sys::FormData_pg_attribute) -> bool {
+    false
+}
+
+/// Is `att` a generated column, which `COPY` can't load values into?
+#[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14"))]
+fn is_generated(att: &pg_sys::FormData_pg_attribute) -> bool {
+    att.attgenerated != 0
+}
+
+/// Load `rows` into the table named `relation`, returning the number of rows that were inserted.
+///
+/// This uses the same machinery as `COPY FROM`, so it's much faster than inserting the rows one at
+/// a time through SPI, while still firing triggers, checking constraints, and filling in column
+/// defaults, just like `COPY` does.
+///
+/// `relation` may be schema-qualified.  `columns` are the table columns that each row's values are
+/// loaded into, in order.  If `columns` is empty, each row must have a value for every column of
+/// the table.  The current user must have `INSERT` privilege on the table (or the columns).
+///
+/// As with `COPY FROM`, tables with row-level security enabled aren't supported, and only
+/// temporary tables may be loaded in a read-only transaction.
+///
+/// ## Examples
+///
+/// ```rust,no_run
+/// use pgx::*;
+///
+/// let inserted = pgx::copy::copy_into(
+///     "public.dogs",
+///     &["name", "age"],
+///     vec![("Brandy", 7), ("Nami", 3)],
+/// );
+/// assert_eq!(inserted, 2);
+/// ```
+pub fn copy_into<R: CopyRow, I: IntoIterator<Item = R>>(
+    relation: &str,
+    columns: &[&str],
+    rows: I,
+) -> u64 {
+    let mut source = CopySource {
+        rows: rows.into_iter(),
+        writer: CopyRowWriter::new(),
+        position: 0,
+        memcxt: PgMemoryContexts::new("copy_into"),
+    };
+
+    unsafe {
+        let relid =
+            direct_function_call::<pg_sys::Oid>(pg_sys::to_regclass, vec![relation.into_datum()])
+                .unwrap_or_else(|| {
+                    raise(
+                        PgSqlErrorCode::ERRCODE_UNDEFINED_TABLE,
+                        &format!("relation \"{}\" does not exist", relation),
+                    )
+                });
+
+        // like COPY, we hold the lock until the end of the transaction, so the relation is just
+        // closed when it's dropped
+        let lockmode = pg_sys::RowExclusiveLock as pg_sys::LOCKMODE;
+        let rel = PgRelation::from_pg_owned(pg_sys::relation_open(relid, lockmode));
+
+        // like COPY FROM, don't skip the row-level security policies that INSERT would check, and
+        // only write to temporary tables in a read-only transaction
+        if pg_sys::check_enable_rls(relid, pg_sys::InvalidOid, false)
+            == pg_sys::CheckEnableRlsResult_RLS_ENABLED
+        {
+            raise(
+                PgSqlErrorCode::ERRCODE_FEATURE_NOT_SUPPORTED,
+                "COPY FROM not supported with row-level security",
+            );
+        }
+        if !(*rel.as_ptr()).rd_islocaltemp {
+            pg_sys::PreventCommandIfReadOnly(b"COPY FROM\0".as_ptr() as *const c_char);
+        }
+
+        let pstate = pg_sys::make_parsestate(std::ptr::null_mut());
+
+        #[cfg(any(feature = "pg10", feature = "pg11"))]
+        let rte = pg_sys::addRangeTableEntryForRelation(
+            pstate,
+            rel.as_ptr(),
+            std::ptr::null_mut(),
+            false,
+            false,
+        );
+        #[cfg(feature = "pg12")]
+        let rte = pg_sys::addRangeTableEntryForRelation(
+            pstate,
+            rel.as_ptr(),
+            lockmode,
+            std::ptr::null_mut(),
+            false,
+            false,
+        );
+        #[cfg(any(feature = "pg13", feature = "pg14"))]
+        let rte = (*pg_sys::addRangeTableEntryForRelation(
+            pstate,
+            rel.as_ptr(),
+            lockmode,
+            std::ptr::null_mut(),
+            false,
+            false,
+        ))
+        .p_rte;
+
+        // reject unknown and repeated columns the same way COPY FROM does
+        let tupdesc = PgTupleDesc::from_relation(&rel);
+        for (i, column) in columns.iter().enumerate() {
+            if !tupdesc.iter().any(|att| !att.is_dropped() && att.name() == *column) {
+                raise(
+                    PgSqlErrorCode::ERRCODE_UNDEFINED_COLUMN,
+                    &format!("column \"{}\" of relation \"{}\" does not exist", column, rel.name()),
+                );
+            }
+            if columns[..i].contains(column) {
+                raise(
+                    PgSqlErrorCode::ERRCODE_DUPLICATE_COLUMN,
+                    &format!("column \"{}\" specified more than once", column),
+                );
+            }
+        }
+
+        // check INSERT privileges the same way COPY FROM does
+        let mut attnamelist = PgList::<pg_sys::Value>::new();
+        (*rte).requiredPerms = pg_sys::ACL_INSERT as pg_sys::AclMode;
+        for att in tupdesc.iter() {
+            let is_target = if columns.is_empty() {
+                !att.is_dropped() && !is_generated(att)
+            } else {
+                !att.is_dropped() && columns.contains(&att.name())
+            };
+            if !is_target {
+                continue;
+            }
+            (*rte).insertedCols = pg_sys::bms_add_member(
+                (*rte).insertedCols,
+                att.attnum as i32 - pg_sys::FirstLowInvalidHeapAttributeNumber,
+            );
+            if columns.is_empty() {
+                source.writer.target_columns.push(att.name().to_string());
+            }
+        }
+        source.writer.target_columns.extend(columns.iter().map(|column| column.to_string()));
+        for column in columns {
+            attnamelist
+                .push(pg_sys::makeString(PgMemoryContexts::CurrentMemoryContext.pstrdup(column)));
+        }
+        pg_sys::ExecCheckRTPerms((*pstate).p_rtable, true);
+
+        // our text is in the server's encoding, whatever the client's might be
+        let mut options = PgList::<pg_sys::DefElem>::new();
+        options.push(pg_sys::makeDefElem(
+            PgMemoryContexts::CurrentMemoryContext.pstrdup("encoding"),
+            pg_sys::makeString(pg_sys::GetDatabaseEncodingName() as *mut _) as *mut pg_sys::Node,
+            -1,
+        ));
+
+        #[cfg(not(feature = "pg14"))]
+        let cstate = pg_sys::BeginCopyFrom(
+            pstate,
+            rel.as_ptr(),
+            std::ptr::null(),
+            false,
+            Some(copy_data_source),
+            attnamelist.into_pg(),
+            options.into_pg(),
+        );
+        #[cfg(feature = "pg14")]
+        let cstate = pg_sys::BeginCopyFrom(
+            pstate,
+            rel.as_ptr(),
+            std::ptr::null_mut(),
+            std::ptr::null(),
+            false,
+            Some(copy_data_source),
+            attnamelist.into_pg(),
+            options.into_pg(),
+        );
+
+        let _guard = CurrentSourceGuard(CURRENT_SOURCE);
+        // SAFETY:  `source` outlives the `CopyFrom()` call, which is the only time it's used, and
+        // the guard above clears it afterwards
+        let source: &mut dyn CopyDataSource = &mut source;
+        CURRENT_SOURCE = Some(std::mem::transmute::<_, *mut (dyn CopyDataSource + 'static)>(
+            source as *mut dyn CopyDataSource,
+        ));
+
+        let processed = pg_sys::CopyFrom(cstate);
+
+        pg_sys::EndCopyFrom(cstate);
+        pg_sys::free_parsestate(pstate);
+
+        processed
+    }
+}
@@ -0,0 +1,465 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+
+//! Custom scan providers, which add scan nodes of their own to Postgres' planner and executor
+//!
+//! A provider implements [`CustomScan`] and is registered with [`register_custom_scan`], usually
+//! from `_PG_init()`.  From then on, the planner asks the provider (from the `set_rel_pathlist`
+//! hook) to add paths of its own, made with [`new_custom_path`], for each relation it plans to
+//! scan.  If one of those paths is the cheapest, the provider turns it into a `CustomScan` plan
+//! node, and each time that plan is executed, an instance of the provider is created to produce
+//! its rows.
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! struct CountingScan {
+//!     next: i32,
+//! }
+//!
+//! impl CustomScan for CountingScan {
+//!     const NAME: &'static str = "counting_scan";
+//!
+//!     fn add_paths(
+//!         _root: &PlannerInfoRef<'_>,
+//!         rel: &mut RelOptInfoRef<'_>,
+//!         _rti: pg_sys::Index,
+//!         rte: &RangeTblEntryRef<'_>,
+//!     ) {
+//!         if rte.relation().map(|relation| relation.name() == "counting").unwrap_or(false) {
+//!             let path = new_custom_path::<CountingScan>(rel, 0.0, 10.0);
+//!             unsafe { rel.add_path(path.into_pg() as *mut pg_sys::Path) }
+//!         }
+//!     }
+//!
+//!     fn create(_plan: &pg_sys::CustomScan) -> Self {
+//!         CountingScan { next: 0 }
+//!     }
+//!
+//!     fn exec(
+//!         &mut self,
+//!         _node: &mut CustomScanStateRef<'_>,
+//!         slot: &mut TupleTableSlotRef<'_>,
+//!     ) -> bool {
+//!         if self.next == 10 {
+//!             return false;
+//!         }
+//!         self.next += 1;
+//!         slot.store_datums(vec![self.next.into_datum()]);
+//!         true
+//!     }
+//!
+//!     fn rescan(&mut self, _node: &mut CustomScanStateRef<'_>) {
+//!         self.next = 0;
+//!     }
+//! }
+//!
+//! #[pg_guard]
+//! pub extern "C" fn _PG_init() {
+//!     register_custom_scan::<CountingScan>();
+//! }
+//! ```
+use crate::hooks::{register_hook, HookPoints, HookResult, PgHooks};
+use crate::{
+    pg_sys, ExplainStateRef, PgBox, PgList, PgMemoryContexts, PgRelation, PlannerInfoRef,
+    RangeTblEntryRef, RelOptInfoRef, TupleTableSlotRef,
+};
+use std::any::TypeId;
+use std::ffi::CString;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::os::raw::c_int;
+
+/// A custom scan provider
+///
+/// The associated functions are called by the planner, and the methods by the executor, on an
+/// instance of the provider made by [`CustomScan::create`] for each execution of the plan.
+pub trait CustomScan: Sized + 'static {
+    /// The provider's name, which `EXPLAIN` shows as the `Custom Plan Provider`.  It must be unique
+    /// among the providers registered in a backend.
+    const NAME: &'static str;
+
+    /// Whether the paths made by [`new_custom_path`] may run in a parallel worker.  Only set this
+    /// when every instance of the provider can produce its rows on its own, since no state is
+    /// shared with the workers:  pgx doesn't register the custom scan's DSM callbacks.
+    const PARALLEL_SAFE: bool = false;
+
+    /// Add paths for scanning `rel`, which is the relation with range table index `rti`, made with
+    /// [`new_custom_path`] and offered to the planner with [`RelOptInfoRef::add_path`]
+    ///
+    /// This is called after the planner has made its own paths for the relation, for every base
+    /// relation (and inheritance child, or partition) in the query, whether or not it's a table.
+    fn add_paths(
+        root: &PlannerInfoRef<'_>,
+        rel: &mut RelOptInfoRef<'_>,
+        rti: pg_sys::Index,
+        rte: &RangeTblEntryRef<'_>,
+    );
+
+    /// Make the plan node for `path`, one of this provider's paths that the planner chose
+    ///
+    /// `tlist` is the target list the plan must produce, `clauses` are the restriction clauses it
+    /// must enforce, and `custom_plans` are the plans made for the path's `custom_paths`, if any.
+    ///
+    /// The default makes a plain scan of `rel`, where the executor checks all of `clauses` against
+    /// each row the scan produces, and the path's `custom_private` list is carried over to the
+    /// plan.  Whatever the plan node's `methods` are set to is replaced with this provider's.
+    fn plan_custom_path(
+        root: &PlannerInfoRef<'_>,
+        rel: &RelOptInfoRef<'_>,
+        path: &pg_sys::CustomPath,
+        tlist: PgList<pg_sys::TargetEntry>,
+        clauses: PgList<pg_sys::RestrictInfo>,
+        custom_plans: PgList<pg_sys::Plan>,
+    ) -> PgBox<pg_sys::CustomScan> {
+        let _ = root;
+        let mut cscan = PgBox::<pg_sys::CustomScan>::alloc_node(pg_sys::NodeTag_T_CustomScan);
+        cscan.scan.plan.targetlist = tlist.into_pg();
+        cscan.scan.plan.qual = unsafe { pg_sys::extract_actual_clauses(clauses.into_pg(), false) };
+        cscan.scan.scanrelid = rel.relid();
+        cscan.flags = path.flags;
+        cscan.custom_plans = custom_plans.into_pg();
+        cscan.custom_private = path.custom_private;
+        cscan.into_pg_boxed()
+    }
+
+    /// Make an instance of the provider to execute `plan`
+    fn create(plan: &pg_sys::CustomScan) -> Self;
+
+    /// Get ready to execute the plan.  By now, the scan slot and the relation being scanned (if
+    /// any) have been set up in `node`.
+    ///
+    /// `eflags` are the `pg_sys::EXEC_FLAG_*` flags.  With `pg_sys::EXEC_FLAG_EXPLAIN_ONLY`, the
+    /// plan is only being set up for `EXPLAIN`, and won't be executed.
+    fn begin(&mut self, node: &mut CustomScanStateRef<'_>, eflags: i32) {
+        let _ = (node, eflags);
+    }
+
+    /// Produce the next row, by storing it in `slot` and returning `true`, or return `false` when
+    /// there are no more rows
+    ///
+    /// Rows that don't pass the plan's `qual` are skipped, and the rest are projected to the
+    /// plan's target list, by the executor.
+    fn exec(&mut self, node: &mut CustomScanStateRef<'_>, slot: &mut TupleTableSlotRef<'_>)
+        -> bool;
+
+    /// Start producing rows from the beginning again, perhaps with different parameters
+    fn rescan(&mut self, node: &mut CustomScanStateRef<'_>);
+
+    /// Finish executing the plan, releasing whatever resources aren't released when the
+    /// instance is dropped.  It's dropped when the executor's memory is freed, whether or not this
+    /// was called first.
+    fn end(&mut self, node: &mut CustomScanStateRef<'_>) {
+        let _ = node;
+    }
+
+    /// Add properties of the plan to the output of `EXPLAIN`
+    fn explain(&mut self, node: &mut CustomScanStateRef<'_>, explain: &mut ExplainStateRef<'_>) {
+        let _ = (node, explain);
+    }
+}
+
+/// The executor's state for a `CustomScan` plan node
+///
+/// This dereferences to the underlying `pg_sys::CustomScanState` for anything that doesn't have
+/// a method of its own.
+pub struct CustomScanStateRef<'a> {
+    ptr: *mut pg_sys::CustomScanState,
+    _marker: PhantomData<&'a mut pg_sys::CustomScanState>,
+}
+
+impl<'a> CustomScanStateRef<'a> {
+    /// ## Safety
+    ///
+    /// `ptr` must be a valid pointer that outlives the returned `CustomScanStateRef`
+    pub unsafe fn from_pg(ptr: *mut pg_sys::CustomScanState) -> Self {
+        assert!(!ptr.is_null(), "CustomScanState is NULL");
+        CustomScanStateRef { ptr, _marker: PhantomData }
+    }
+
+    pub fn as_ptr(&self) -> *mut pg_sys::CustomScanState {
+        self.ptr
+    }
+
+    /// The plan node being executed
+    pub fn plan(&self) -> &'a pg_sys::CustomScan {
+        unsafe { &*((*self.ptr).ss.ps.plan as *mut pg_sys::CustomScan) }
+    }
+
+    /// The relation being scanned, if the plan scans one.  The executor holds a lock on it.
+    pub fn relation(&self) -> Option<PgRelation> {
+        unsafe {
+            let relation = (*self.ptr).ss.ss_currentRelation;
+            if relation.is_null() {
+                None
+            } else {
+                Some(PgRelation::from_pg(relation))
+            }
+        }
+    }
+
+    /// The snapshot the plan is being executed with
+    pub fn snapshot(&self) -> pg_sys::Snapshot {
+        unsafe { (*(*self.ptr).ss.ps.state).es_snapshot }
+    }
+}
+
+impl Deref for CustomScanStateRef<'_> {
+    type Target = pg_sys::CustomScanState;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.ptr }
+    }
+}
+
+impl DerefMut for CustomScanStateRef<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.ptr }
+    }
+}
+
+/// Make a path for scanning `rel` with the provider `S`, which must have been registered with
+/// [`register_custom_scan`]
+///
+/// The path is unparameterized and unordered, and produces as many rows as the planner estimated
+/// for `rel`.  It's only parallel safe if the provider opted in with
+/// [`CustomScan::PARALLEL_SAFE`] and the planner is considering parallel plans for `rel`.  Any of
+/// that can be changed through the returned path before it's offered to the planner, as can its
+/// `flags`, `custom_paths`, and `custom_private`.
+pub fn new_custom_path<S: CustomScan>(
+    rel: &RelOptInfoRef<'_>,
+    startup_cost: f64,
+    total_cost: f64,
+) -> PgBox<pg_sys::CustomPath> {
+    let methods = methods::<S>();
+    let mut path = PgBox::<pg_sys::CustomPath>::alloc_node(pg_sys::NodeTag_T_CustomPath);
+    path.path.pathtype = pg_sys::NodeTag_T_CustomScan;
+    path.path.parent = rel.as_ptr();
+    path.path.pathtarget = rel.reltarget;
+    path.path.parallel_safe = S::PARALLEL_SAFE && rel.consider_parallel;
+    path.path.rows = rel.rows();
+    path.path.startup_cost = startup_cost;
+    path.path.total_cost = total_cost;
+    path.methods = &methods.path;
+    path.into_pg_boxed()
+}
+
+/// The callback tables, and path hook, of a registered provider
+struct Methods {
+    type_id: TypeId,
+    path: pg_sys::CustomPathMethods,
+    scan: pg_sys::CustomScanMethods,
+    exec: pg_sys::CustomExecMethods,
+    add_paths:
+        fn(&PlannerInfoRef<'_>, &mut RelOptInfoRef<'_>, pg_sys::Index, &RangeTblEntryRef<'_>),
+}
+
+static mut PROVIDERS: Vec<&'static Methods> = Vec::new();
+
+fn methods<S: CustomScan>() -> &'static Methods {
+    unsafe {
+        PROVIDERS
+            .iter()
+            .find(|methods| methods.type_id == TypeId::of::<S>())
+            .unwrap_or_else(|| panic!("custom scan provider \"{}\" is not registered", S::NAME))
+    }
+}
+
+/// Register the custom scan provider `S`, so that the planner asks it for paths from now on
+///
+/// This is usually called from `_PG_init()`.  Registering the same provider again does nothing.
+pub fn register_custom_scan<S: CustomScan>() {
+    unsafe {
+        if PROVIDERS.iter().any(|methods| methods.type_id == TypeId::of::<S>()) {
+            return;
+        }
+
+        let name = CString::new(S::NAME).expect("custom scan provider name contains a NUL byte");
+        let name = name.into_raw() as *const _;
+        let methods: &'static Methods = Box::leak(Box::new(Methods {
+            type_id: TypeId::of::<S>(),
+            path: pg_sys::CustomPathMethods {
+                CustomName: name,
+                PlanCustomPath: Some(plan_custom_path::<S>),
+                ..Default::default()
+            },
+            scan: pg_sys::CustomScanMethods {
+                CustomName: name,
+                CreateCustomScanState: Some(create_custom_scan_state::<S>),
+            },
+            exec: pg_sys::CustomExecMethods {
+                CustomName: name,
+                BeginCustomScan: Some(begin_custom_scan::<S>),
+                ExecCustomScan: Some(exec_custom_scan::<S>),
+                EndCustomScan: Some(end_custom_scan::<S>),
+                ReScanCustomScan: Some(rescan_custom_scan::<S>),
+                ExplainCustomScan: Some(explain_custom_scan::<S>),
+                ..Default::default()
+            },
+            add_paths: S::add_paths,
+        }));
+
+        // so a plan that's been copied to a parallel worker, for example, can find its methods
+        pg_sys::RegisterCustomScanMethods(&methods.scan);
+
+        if PROVIDERS.is_empty() {
+            register_hook(Box::leak(Box::new(CustomScanPaths)));
+        }
+        PROVIDERS.push(methods);
+    }
+}
+
+/// Asks each registered provider for paths from the `set_rel_pathlist` hook
+struct CustomScanPaths;
+
+impl PgHooks for CustomScanPaths {
+    fn hook_points(&self) -> HookPoints {
+        HookPoints { set_rel_pathlist: true, ..HookPoints::NONE }
+    }
+
+    fn set_rel_pathlist(
+        &mut self,
+        root: PlannerInfoRef<'_>,
+        rel: RelOptInfoRef<'_>,
+        rti: pg_sys::Index,
+        rte: RangeTblEntryRef<'_>,
+        prev_hook: fn(
+            root: PlannerInfoRef<'_>,
+            rel: RelOptInfoRef<'_>,
+            rti: pg_sys::Index,
+            rte: RangeTblEntryRef<'_>,
+        ) -> HookResult<()>,
+    ) -> HookResult<()> {
+        let (root_ptr, rel_ptr, rte_ptr) = (root.as_ptr(), rel.as_ptr(), rte.as_ptr());
+        let result = prev_hook(root, rel, rti, rte);
+
+        unsafe {
+            let root = PlannerInfoRef::from_pg(root_ptr);
+            let mut rel = RelOptInfoRef::from_pg(rel_ptr);
+            let rte = RangeTblEntryRef::from_pg(rte_ptr);
+            for methods in PROVIDERS.iter() {
+                (methods.add_paths)(&root, &mut rel, rti, &rte);
+            }
+        }
+        result
+    }
+}
+
+/// The `CustomScanState` node made for a provider, which is followed by its instance
+#[repr(C)]
+struct CustomScanNode<S> {
+    css: pg_sys::CustomScanState,
+    provider: *mut S,
+}
+
+unsafe fn provider<'a, S>(node: *mut pg_sys::CustomScanState) -> &'a mut S {
+    &mut *(*(node as *mut CustomScanNode<S>)).provider
+}
+
+unsafe extern "C" fn plan_custom_path<S: CustomScan>(
+    root: *mut pg_sys::PlannerInfo,
+    rel: *mut pg_sys::RelOptInfo,
+    best_path: *mut pg_sys::CustomPath,
+    tlist: *mut pg_sys::List,
+    clauses: *mut pg_sys::List,
+    custom_plans: *mut pg_sys::List,
+) -> *mut pg_sys::Plan {
+    crate::guard::guard(|| unsafe {
+        let mut cscan = S::plan_custom_path(
+            &PlannerInfoRef::from_pg(root),
+            &RelOptInfoRef::from_pg(rel),
+            &*best_path,
+            PgList::from_pg(tlist),
+            PgList::from_pg(clauses),
+            PgList::from_pg(custom_plans),
+        );
+        cscan.methods = &methods::<S>().scan;
+        cscan.into_pg() as *mut pg_sys::Plan
+    })
+}
+
+unsafe extern "C" fn create_custom_scan_state<S: CustomScan>(
+    cscan: *mut pg_sys::CustomScan,
+) -> *mut pg_sys::Node {
+    crate::guard::guard(|| unsafe {
+        let mut memcxt = PgMemoryContexts::CurrentMemoryContext;
+        let node = memcxt.palloc0_struct::<CustomScanNode<S>>();
+        (*node).css.ss.ps.type_ = pg_sys::NodeTag_T_CustomScanState;
+        (*node).css.methods = &methods::<S>().exec;
+        (*node).provider = memcxt.leak_and_drop_on_delete(S::create(&*cscan));
+        node as *mut pg_sys::Node
+    })
+}
+
+unsafe extern "C" fn begin_custom_scan<S: CustomScan>(
+    node: *mut pg_sys::CustomScanState,
+    _estate: *mut pg_sys::EState,
+    eflags: c_int,
+) {
+    crate::guard::guard(|| unsafe {
+        provider::<S>(node).begin(&mut CustomScanStateRef::from_pg(node), eflags)
+    })
+}
+
+unsafe extern "C" fn exec_custom_scan<S: CustomScan>(
+    node: *mut pg_sys::CustomScanState,
+) -> *mut pg_sys::TupleTableSlot {
+    crate::guard::guard(|| unsafe {
+        pg_sys::ExecScan(&mut (*node).ss, Some(next_custom_scan::<S>), Some(recheck_custom_scan))
+    })
+}
+
+/// The access method that `ExecScan()` calls for the next row, before checking the plan's `qual`
+/// and projecting
+unsafe extern "C" fn next_custom_scan<S: CustomScan>(
+    ss: *mut pg_sys::ScanState,
+) -> *mut pg_sys::TupleTableSlot {
+    crate::guard::guard(|| unsafe {
+        let node = ss as *mut pg_sys::CustomScanState;
+        let mut slot = TupleTableSlotRef::from_pg((*ss).ss_ScanTupleSlot);
+        slot.clear();
+        if !provider::<S>(node).exec(&mut CustomScanStateRef::from_pg(node), &mut slot) {
+            // an empty slot tells ExecScan() there are no more rows
+            slot.clear();
+        }
+        slot.as_ptr()
+    })
+}
+
+/// Rows are produced as they are, so there's nothing to recheck for `EvalPlanQual()`
+unsafe extern "C" fn recheck_custom_scan(
+    _ss: *mut pg_sys::ScanState,
+    _slot: *mut pg_sys::TupleTableSlot,
+) -> bool {
+    true
+}
+
+unsafe extern "C" fn end_custom_scan<S: CustomScan>(node: *mut pg_sys::CustomScanState) {
+    crate::guard::guard(|| unsafe {
+        provider::<S>(node).end(&mut CustomScanStateRef::from_pg(node))
+    })
+}
+
+unsafe extern "C" fn rescan_custom_scan<S: CustomScan>(node: *mut pg_sys::CustomScanState) {
+    crate::guard::guard(|| unsafe {
+        provider::<S>(node).rescan(&mut CustomScanStateRef::from_pg(node));
+        pg_sys::ExecScanReScan(&mut (*node).ss);
+    })
+}
+
+unsafe extern "C" fn explain_custom_scan<S: CustomScan>(
+    node: *mut pg_sys::CustomScanState,
+    _ancestors: *mut pg_sys::List,
+    es: *mut pg_sys::ExplainState,
+) {
+    crate::guard::guard(|| unsafe {
+        provider::<S>(node)
+            .explain(&mut CustomScanStateRef::from_pg(node), &mut ExplainStateRef::from_pg(es))
+    })
+}
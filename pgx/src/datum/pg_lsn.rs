@@ -0,0 +1,89 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+use crate::{pg_sys, FromDatum, IntoDatum};
+use pgx_utils::sql_entity_graph::metadata::{
+    ArgumentError, Returns, ReturnsError, SqlMapping, SqlTranslatable,
+};
+
+/// A location in the write-ahead log, as a `pg_lsn`
+///
+/// It displays as Postgres does, as the high and low 32 bits in hex, like `16/B374D848`.
+#[derive(Clone, Copy, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Debug)]
+#[repr(transparent)]
+pub struct PgLsn(pg_sys::XLogRecPtr);
+
+impl PgLsn {
+    /// The LSN of no location, `0/0`
+    pub const INVALID: PgLsn = PgLsn(0);
+
+    pub fn is_valid(&self) -> bool {
+        *self != PgLsn::INVALID
+    }
+}
+
+impl From<pg_sys::XLogRecPtr> for PgLsn {
+    fn from(lsn: pg_sys::XLogRecPtr) -> Self {
+        PgLsn(lsn)
+    }
+}
+
+impl From<PgLsn> for pg_sys::XLogRecPtr {
+    fn from(lsn: PgLsn) -> Self {
+        lsn.0
+    }
+}
+
+impl IntoDatum for PgLsn {
+    #[inline]
+    fn into_datum(self) -> Option<pg_sys::Datum> {
+        Some(pg_sys::Datum::from(self.0))
+    }
+
+    #[inline]
+    fn type_oid() -> u32 {
+        #[cfg(any(feature = "pg10", feature = "pg11", feature = "pg12", feature = "pg13"))]
+        {
+            pg_sys::LSNOID
+        }
+        #[cfg(feature = "pg14")]
+        {
+            pg_sys::PG_LSNOID
+        }
+    }
+}
+
+impl FromDatum for PgLsn {
+    #[inline]
+    unsafe fn from_polymorphic_datum(
+        datum: pg_sys::Datum,
+        is_null: bool,
+        _typoid: pg_sys::Oid,
+    ) -> Option<PgLsn> {
+        if is_null {
+            None
+        } else {
+            Some(PgLsn(datum.value() as _))
+        }
+    }
+}
+
+impl std::fmt::Display for PgLsn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:X}/{:X}", self.0 >> 32, self.0 as u32)
+    }
+}
+
+unsafe impl SqlTranslatable for crate::datum::PgLsn {
+    fn argument_sql() -> Result<SqlMapping, ArgumentError> {
+        Ok(SqlMapping::literal("pg_lsn"))
+    }
+    fn return_sql() -> Result<Returns, ReturnsError> {
+        Ok(Returns::One(SqlMapping::literal("pg_lsn")))
+    }
+}
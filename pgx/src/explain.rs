@@ -0,0 +1,236 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+
+//! Typed `EXPLAIN` output, as produced by [`Spi::explain_with`][crate::Spi::explain_with], and
+//! a view of `EXPLAIN`'s state for plan nodes that describe themselves
+
+use crate::{pg_sys, PgMemoryContexts};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+/// The options to run `EXPLAIN` with.
+///
+/// These mirror the options of the same name in the `EXPLAIN` command.  Note that `analyze`
+/// actually executes the query.  `buffers` requires `analyze` before Postgres 13, and `settings`
+/// requires Postgres 12 or later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExplainOptions {
+    pub analyze: bool,
+    pub buffers: bool,
+    pub verbose: bool,
+    pub costs: bool,
+    /// Only applies when `analyze` is also set
+    pub timing: bool,
+    pub settings: bool,
+}
+
+impl Default for ExplainOptions {
+    /// The same defaults Postgres uses: only `costs` and `timing` are on
+    fn default() -> Self {
+        ExplainOptions {
+            analyze: false,
+            buffers: false,
+            verbose: false,
+            costs: true,
+            timing: true,
+            settings: false,
+        }
+    }
+}
+
+impl ExplainOptions {
+    /// The option list for `EXPLAIN (...)`, always in JSON format
+    pub(crate) fn to_sql(&self) -> String {
+        let mut options = vec![String::from("FORMAT JSON")];
+        if self.analyze {
+            options.push(String::from("ANALYZE"));
+            options.push(format!("TIMING {}", self.timing));
+        }
+        if self.buffers {
+            options.push(String::from("BUFFERS"));
+        }
+        if self.verbose {
+            options.push(String::from("VERBOSE"));
+        }
+        options.push(format!("COSTS {}", self.costs));
+        if self.settings {
+            options.push(String::from("SETTINGS"));
+        }
+        options.join(", ")
+    }
+}
+
+/// The result of [`Spi::explain_with`][crate::Spi::explain_with]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Explain {
+    /// The root of the plan tree
+    #[serde(rename = "Plan")]
+    pub plan: ExplainPlan,
+    /// Milliseconds spent planning, with `analyze`
+    #[serde(rename = "Planning Time")]
+    pub planning_time: Option<f64>,
+    /// Milliseconds spent executing, with `analyze`
+    #[serde(rename = "Execution Time")]
+    pub execution_time: Option<f64>,
+    /// Any non-default planner settings, with `settings`
+    #[serde(rename = "Settings", default)]
+    pub settings: HashMap<String, String>,
+}
+
+/// A node in an `EXPLAIN` plan tree
+///
+/// The estimates are `None` when `costs` is off, and the actual values are `None` without
+/// `analyze` (or for nodes that were never executed).  Node-specific properties that don't have a
+/// field of their own, such as `Sort Key` or `Hash Cond`, can be found in `properties`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ExplainPlan {
+    #[serde(rename = "Node Type")]
+    pub node_type: String,
+    /// How this node relates to its parent, such as `Outer`, `Inner`, or `InitPlan`
+    #[serde(rename = "Parent Relationship")]
+    pub parent_relationship: Option<String>,
+    #[serde(rename = "Relation Name")]
+    pub relation_name: Option<String>,
+    /// The relation's schema, with `verbose`
+    #[serde(rename = "Schema")]
+    pub schema: Option<String>,
+    #[serde(rename = "Alias")]
+    pub alias: Option<String>,
+    #[serde(rename = "Index Name")]
+    pub index_name: Option<String>,
+    #[serde(rename = "Join Type")]
+    pub join_type: Option<String>,
+    #[serde(rename = "Startup Cost")]
+    pub startup_cost: Option<f64>,
+    #[serde(rename = "Total Cost")]
+    pub total_cost: Option<f64>,
+    #[serde(rename = "Plan Rows")]
+    pub plan_rows: Option<f64>,
+    #[serde(rename = "Plan Width")]
+    pub plan_width: Option<i32>,
+    /// Milliseconds until the first row, with `analyze` and `timing`
+    #[serde(rename = "Actual Startup Time")]
+    pub actual_startup_time: Option<f64>,
+    /// Milliseconds until the last row, with `analyze` and `timing`
+    #[serde(rename = "Actual Total Time")]
+    pub actual_total_time: Option<f64>,
+    /// The average number of rows per loop
+    #[serde(rename = "Actual Rows")]
+    pub actual_rows: Option<f64>,
+    #[serde(rename = "Actual Loops")]
+    pub actual_loops: Option<f64>,
+    /// The node's output columns, with `verbose`
+    #[serde(rename = "Output", default)]
+    pub output: Vec<String>,
+    #[serde(rename = "Filter")]
+    pub filter: Option<String>,
+    #[serde(flatten)]
+    pub buffers: ExplainBuffers,
+    #[serde(rename = "Plans", default)]
+    pub plans: Vec<ExplainPlan>,
+    /// Any other properties of this node, keyed by their `EXPLAIN` name
+    #[serde(flatten)]
+    pub properties: HashMap<String, serde_json::Value>,
+}
+
+/// Buffer usage of an [`ExplainPlan`] node, which is all zero without `buffers`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct ExplainBuffers {
+    #[serde(rename = "Shared Hit Blocks")]
+    pub shared_hit_blocks: i64,
+    #[serde(rename = "Shared Read Blocks")]
+    pub shared_read_blocks: i64,
+    #[serde(rename = "Shared Dirtied Blocks")]
+    pub shared_dirtied_blocks: i64,
+    #[serde(rename = "Shared Written Blocks")]
+    pub shared_written_blocks: i64,
+    #[serde(rename = "Local Hit Blocks")]
+    pub local_hit_blocks: i64,
+    #[serde(rename = "Local Read Blocks")]
+    pub local_read_blocks: i64,
+    #[serde(rename = "Local Dirtied Blocks")]
+    pub local_dirtied_blocks: i64,
+    #[serde(rename = "Local Written Blocks")]
+    pub local_written_blocks: i64,
+    #[serde(rename = "Temp Read Blocks")]
+    pub temp_read_blocks: i64,
+    #[serde(rename = "Temp Written Blocks")]
+    pub temp_written_blocks: i64,
+}
+
+impl ExplainPlan {
+    /// Iterate over this node and all of its descendants, depth first
+    pub fn iter(&self) -> impl Iterator<Item = &ExplainPlan> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.plans.iter().rev());
+            Some(node)
+        })
+    }
+}
+
+/// The state of an `EXPLAIN` in progress, as given to plan nodes that add properties of their
+/// own to its output, such as [`CustomScan::explain`][crate::custom_scan::CustomScan::explain]
+///
+/// Properties are written in whatever format the `EXPLAIN` asked for.
+pub struct ExplainStateRef<'a> {
+    ptr: *mut pg_sys::ExplainState,
+    _marker: PhantomData<&'a mut pg_sys::ExplainState>,
+}
+
+impl<'a> ExplainStateRef<'a> {
+    /// ## Safety
+    ///
+    /// `ptr` must be a valid pointer that outlives the returned `ExplainStateRef`
+    pub unsafe fn from_pg(ptr: *mut pg_sys::ExplainState) -> Self {
+        assert!(!ptr.is_null(), "ExplainState is NULL");
+        ExplainStateRef { ptr, _marker: PhantomData }
+    }
+
+    pub fn as_ptr(&self) -> *mut pg_sys::ExplainState {
+        self.ptr
+    }
+
+    /// Was `VERBOSE` given?
+    pub fn verbose(&self) -> bool {
+        unsafe { (*self.ptr).verbose }
+    }
+
+    /// Was `ANALYZE` given?  If so, the plan has been executed.
+    pub fn analyze(&self) -> bool {
+        unsafe { (*self.ptr).analyze }
+    }
+
+    /// Are costs being shown?
+    pub fn costs(&self) -> bool {
+        unsafe { (*self.ptr).costs }
+    }
+
+    /// Add a property with a text value
+    pub fn property_text(&mut self, label: &str, value: &str) {
+        let memcxt = PgMemoryContexts::CurrentMemoryContext;
+        unsafe {
+            pg_sys::ExplainPropertyText(memcxt.pstrdup(label), memcxt.pstrdup(value), self.ptr)
+        }
+    }
+
+    /// Add a property with an integer value
+    pub fn property_integer(&mut self, label: &str, value: i64) {
+        let label = PgMemoryContexts::CurrentMemoryContext.pstrdup(label);
+        unsafe {
+            #[cfg(feature = "pg10")]
+            pg_sys::ExplainPropertyLong(label, value as std::os::raw::c_long, self.ptr);
+            #[cfg(not(feature = "pg10"))]
+            pg_sys::ExplainPropertyInteger(label, std::ptr::null(), value, self.ptr);
+        }
+    }
+}
@@ -0,0 +1,1141 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+
+//! Foreign data wrappers, which make data from outside the database available as foreign tables
+//!
+//! A wrapper implements [`ForeignDataWrapper`] in an `impl` block marked `#[pg_fdw]`, which adds
+//! its handler and validator functions, and the `CREATE FOREIGN DATA WRAPPER` statement that uses
+//! them, to the extension's schema.  The options given to the wrapper, its servers, and their
+//! foreign tables are parsed into the wrapper's [`ForeignDataWrapper::Options`], which is any type
+//! that implements `serde::Deserialize`, with each option's value parsed from its string as the
+//! field's type requires.
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct SeriesOptions {
+//!     start: i64,
+//!     stop: i64,
+//! }
+//!
+//! struct SeriesFdw {
+//!     start: i64,
+//!     stop: i64,
+//!     next: i64,
+//! }
+//!
+//! #[pg_fdw]
+//! impl ForeignDataWrapper for SeriesFdw {
+//!     const NAME: &'static str = "series_fdw";
+//!     type Options = SeriesOptions;
+//!
+//!     fn begin_scan(
+//!         _node: &mut ForeignScanStateRef<'_>,
+//!         options: SeriesOptions,
+//!         _eflags: i32,
+//!     ) -> Self {
+//!         SeriesFdw { start: options.start, stop: options.stop, next: options.start }
+//!     }
+//!
+//!     fn iterate_scan(
+//!         &mut self,
+//!         _node: &mut ForeignScanStateRef<'_>,
+//!         slot: &mut TupleTableSlotRef<'_>,
+//!     ) -> bool {
+//!         if self.next > self.stop {
+//!             return false;
+//!         }
+//!         slot.store_datums(vec![self.next.into_datum()]);
+//!         self.next += 1;
+//!         true
+//!     }
+//!
+//!     fn rescan(&mut self, _node: &mut ForeignScanStateRef<'_>) {
+//!         self.next = self.start;
+//!     }
+//! }
+//! ```
+//!
+//! With the extension installed, the wrapper is used like any other:
+//!
+//! ```sql
+//! CREATE SERVER series FOREIGN DATA WRAPPER series_fdw;
+//! CREATE FOREIGN TABLE one_to_ten (n bigint) SERVER series OPTIONS (start '1', stop '10');
+//! ```
+use crate::log::raise;
+use crate::{
+    pg_getarg_datum_raw, pg_sys, ExplainStateRef, FromDatum, IntoDatum, PgBox, PgList,
+    PgMemoryContexts, PgRelation, PgSqlErrorCode, PlannerInfoRef, RelOptInfoRef, TryFromDatumError,
+    TupleTableSlotRef,
+};
+use serde::de::{self, DeserializeOwned, IntoDeserializer, Unexpected, Visitor};
+use std::any::Any;
+use std::ffi::{CStr, CString};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::os::raw::{c_char, c_int, c_void};
+
+/// A foreign data wrapper
+///
+/// The associated functions are called by the planner, and the methods by the executor, on an
+/// instance of the wrapper made by [`ForeignDataWrapper::begin_scan`] for each scan of a foreign
+/// table, or by [`ForeignDataWrapper::begin_modify`] for each `INSERT`, `UPDATE`, or `DELETE` of
+/// one.
+pub trait ForeignDataWrapper: Sized + 'static {
+    /// The wrapper's name, as given to `CREATE FOREIGN DATA WRAPPER`
+    const NAME: &'static str;
+
+    /// Which of `INSERT`, `UPDATE`, and `DELETE` the wrapper's foreign tables support
+    const MODIFY_OPERATIONS: ModifyOperations = ModifyOperations::NONE;
+
+    /// The column that identifies each row of a foreign table, which `UPDATE` and `DELETE` need
+    /// to tell the wrapper which rows to change.  Scans must produce its value for every row.
+    const ROWID_COLUMN: Option<&'static str> = None;
+
+    /// The options of a foreign table, merged with those of its server and of the wrapper, where
+    /// the table's override its server's, which override the wrapper's
+    type Options: DeserializeOwned;
+
+    /// Check the options given to `CREATE` (or `ALTER`) `FOREIGN DATA WRAPPER`, `SERVER`,
+    /// `USER MAPPING`, or `FOREIGN TABLE`, where `catalog` says which, returning the message of
+    /// the error to raise if any are invalid
+    ///
+    /// The default rejects wrapper, server, and table options that aren't fields of
+    /// [`ForeignDataWrapper::Options`] (if it's a struct), and accepts all user mapping and column
+    /// options.
+    fn validate_options(
+        options: &[(String, String)],
+        catalog: FdwOptionsCatalog,
+    ) -> Result<(), String> {
+        validate_option_names::<Self::Options>(options, catalog)
+    }
+
+    /// Estimate the size of a scan of `rel`, the foreign table `foreigntableid`, by setting its
+    /// `rows` (and perhaps its `width` and `tuples`)
+    ///
+    /// `plan_state` holds the table's options, and anything the wrapper wants to keep for
+    /// [`ForeignDataWrapper::get_paths`] and [`ForeignDataWrapper::get_plan`].  `rel`'s
+    /// `fdw_private` is where pgx keeps `plan_state`, so it must be left alone.  The default
+    /// leaves the planner's estimates as they are.
+    fn get_rel_size(
+        root: &PlannerInfoRef<'_>,
+        rel: &mut RelOptInfoRef<'_>,
+        foreigntableid: pg_sys::Oid,
+        plan_state: &mut FdwPlanState<Self::Options>,
+    ) {
+        let _ = (root, rel, foreigntableid, plan_state);
+    }
+
+    /// Add paths for scanning `rel`, the foreign table `foreigntableid`, made with
+    /// `pg_sys::create_foreignscan_path()` and offered to the planner with
+    /// [`RelOptInfoRef::add_path`]
+    ///
+    /// The default adds a single unordered path, that costs one for each of `rel`'s rows.
+    fn get_paths(
+        root: &PlannerInfoRef<'_>,
+        rel: &mut RelOptInfoRef<'_>,
+        foreigntableid: pg_sys::Oid,
+        plan_state: &mut FdwPlanState<Self::Options>,
+    ) {
+        let _ = (foreigntableid, plan_state);
+        unsafe {
+            let path = pg_sys::create_foreignscan_path(
+                root.as_ptr(),
+                rel.as_ptr(),
+                std::ptr::null_mut(),
+                rel.rows(),
+                10.0,
+                10.0 + rel.rows(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            );
+            rel.add_path(path as *mut pg_sys::Path)
+        }
+    }
+
+    /// Make the plan node for `path`, one of the paths for `rel` that the planner chose
+    ///
+    /// `tlist` is the target list the plan must produce and `clauses` are the restriction clauses
+    /// it must enforce.
+    ///
+    /// The default makes a plain scan of `rel`, where the executor checks all of `clauses` against
+    /// each row the scan produces, and the path's `fdw_private` list is carried over to the plan.
+    fn get_plan(
+        root: &PlannerInfoRef<'_>,
+        rel: &RelOptInfoRef<'_>,
+        foreigntableid: pg_sys::Oid,
+        plan_state: &mut FdwPlanState<Self::Options>,
+        path: &pg_sys::ForeignPath,
+        tlist: PgList<pg_sys::TargetEntry>,
+        clauses: PgList<pg_sys::RestrictInfo>,
+    ) -> PgBox<pg_sys::ForeignScan> {
+        let _ = (root, foreigntableid, plan_state);
+        unsafe {
+            PgBox::from_pg(pg_sys::make_foreignscan(
+                tlist.into_pg(),
+                pg_sys::extract_actual_clauses(clauses.into_pg(), false),
+                rel.relid(),
+                std::ptr::null_mut(),
+                path.fdw_private,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            ))
+        }
+    }
+
+    /// Make an instance of the wrapper to scan the foreign table of `node`
+    ///
+    /// `eflags` are the `pg_sys::EXEC_FLAG_*` flags.  With `pg_sys::EXEC_FLAG_EXPLAIN_ONLY`, the
+    /// plan is only being set up for `EXPLAIN`, and won't be executed.
+    fn begin_scan(node: &mut ForeignScanStateRef<'_>, options: Self::Options, eflags: i32) -> Self;
+
+    /// Produce the next row, by storing it in `slot` and returning `true`, or return `false` when
+    /// there are no more rows
+    fn iterate_scan(
+        &mut self,
+        node: &mut ForeignScanStateRef<'_>,
+        slot: &mut TupleTableSlotRef<'_>,
+    ) -> bool;
+
+    /// Start producing rows from the beginning again
+    fn rescan(&mut self, node: &mut ForeignScanStateRef<'_>);
+
+    /// Finish the scan, releasing whatever resources aren't released when the instance is
+    /// dropped.  It's dropped when the executor's memory is freed, whether or not this was called
+    /// first.
+    fn end_scan(&mut self, node: &mut ForeignScanStateRef<'_>) {
+        let _ = node;
+    }
+
+    /// Add properties of the scan to the output of `EXPLAIN`
+    fn explain_scan(
+        &mut self,
+        node: &mut ForeignScanStateRef<'_>,
+        explain: &mut ExplainStateRef<'_>,
+    ) {
+        let _ = (node, explain);
+    }
+
+    /// Make an instance of the wrapper to `INSERT` into, `UPDATE`, or `DELETE` from `relation`,
+    /// for the operations in [`ForeignDataWrapper::MODIFY_OPERATIONS`]
+    fn begin_modify(relation: &PgRelation, options: Self::Options) -> Self {
+        let _ = options;
+        not_supported::<Self>("INSERT, UPDATE, or DELETE", relation.name())
+    }
+
+    /// Insert the row in `slot`, returning `false` if it wasn't inserted after all
+    fn insert(&mut self, slot: &mut TupleTableSlotRef<'_>) -> bool {
+        let _ = slot;
+        not_supported::<Self>("INSERT", "this foreign table")
+    }
+
+    /// Replace the row identified by `rowid` with the row in `slot`, returning `false` if it
+    /// wasn't updated after all
+    fn update(&mut self, rowid: RowId, slot: &mut TupleTableSlotRef<'_>) -> bool {
+        let _ = (rowid, slot);
+        not_supported::<Self>("UPDATE", "this foreign table")
+    }
+
+    /// Delete the row identified by `rowid`, returning `false` if it wasn't deleted after all
+    fn delete(&mut self, rowid: RowId) -> bool {
+        let _ = rowid;
+        not_supported::<Self>("DELETE", "this foreign table")
+    }
+
+    /// Finish modifying the foreign table.  As with [`ForeignDataWrapper::end_scan`], the instance
+    /// is dropped later, whether or not this was called first.
+    fn end_modify(&mut self) {}
+
+    /// Make the `CREATE FOREIGN TABLE` statements for `IMPORT FOREIGN SCHEMA`
+    ///
+    /// The statements' tables are created in the statement's `local_schema`, whatever schema they
+    /// name, and those that `LIMIT TO` or `EXCEPT` exclude are skipped, so neither needs to be
+    /// handled here.
+    fn import_foreign_schema(stmt: &ImportForeignSchema) -> Vec<String> {
+        not_supported::<Self>("IMPORT FOREIGN SCHEMA", &stmt.remote_schema)
+    }
+}
+
+/// Which of `INSERT`, `UPDATE`, and `DELETE` a [`ForeignDataWrapper`] supports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ModifyOperations {
+    pub insert: bool,
+    pub update: bool,
+    pub delete: bool,
+}
+
+impl ModifyOperations {
+    /// The foreign tables are read-only
+    pub const NONE: ModifyOperations =
+        ModifyOperations { insert: false, update: false, delete: false };
+
+    /// The foreign tables support `INSERT`, `UPDATE`, and `DELETE`
+    pub const ALL: ModifyOperations = ModifyOperations { insert: true, update: true, delete: true };
+}
+
+/// What the planner callbacks of a [`ForeignDataWrapper`] share for one foreign table: its
+/// options, parsed once, and whatever state the wrapper keeps from one callback for the next
+///
+/// It lives as long as the planner's memory, so it's gone by the time the executor begins a scan.
+pub struct FdwPlanState<O> {
+    options: O,
+    state: Option<Box<dyn Any>>,
+}
+
+impl<O> FdwPlanState<O> {
+    /// The foreign table's options
+    pub fn options(&self) -> &O {
+        &self.options
+    }
+
+    /// The state the wrapper last set, if it's a `T`
+    pub fn state<T: 'static>(&self) -> Option<&T> {
+        self.state.as_ref()?.downcast_ref()
+    }
+
+    /// The state the wrapper last set, mutably, if it's a `T`
+    pub fn state_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.state.as_mut()?.downcast_mut()
+    }
+
+    /// Keep `state` for the planner callbacks that follow, replacing (and dropping) any set before
+    pub fn set_state<T: 'static>(&mut self, state: T) {
+        self.state = Some(Box::new(state));
+    }
+}
+
+/// Which kind of object options are given to, for [`ForeignDataWrapper::validate_options`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FdwOptionsCatalog {
+    Wrapper,
+    Server,
+    UserMapping,
+    Table,
+    Column,
+}
+
+impl FdwOptionsCatalog {
+    /// The kind of object that options are stored for in the catalog `oid`
+    pub fn from_oid(oid: pg_sys::Oid) -> Option<Self> {
+        match oid {
+            pg_sys::ForeignDataWrapperRelationId => Some(FdwOptionsCatalog::Wrapper),
+            pg_sys::ForeignServerRelationId => Some(FdwOptionsCatalog::Server),
+            pg_sys::UserMappingRelationId => Some(FdwOptionsCatalog::UserMapping),
+            pg_sys::ForeignTableRelationId => Some(FdwOptionsCatalog::Table),
+            pg_sys::AttributeRelationId => Some(FdwOptionsCatalog::Column),
+            _ => None,
+        }
+    }
+}
+
+/// The value of a foreign table's [`ForeignDataWrapper::ROWID_COLUMN`] for a row being updated
+/// or deleted
+pub struct RowId {
+    datum: Option<pg_sys::Datum>,
+    type_oid: pg_sys::Oid,
+}
+
+impl RowId {
+    /// The value, which is `None` if it's NULL
+    ///
+    /// ## Errors
+    /// - return [`TryFromDatumError::IncompatibleTypes`] if the Rust type of the value is not
+    /// compatible with the column's Postgres type
+    pub fn value<T: FromDatum + IntoDatum + 'static>(
+        &self,
+    ) -> Result<Option<T>, TryFromDatumError> {
+        match self.datum {
+            None => Ok(None),
+            Some(datum) => unsafe { T::try_from_datum(datum, false, self.type_oid) },
+        }
+    }
+
+    /// The value as a datum of the column's type, which is `None` if it's NULL
+    pub fn datum(&self) -> Option<pg_sys::Datum> {
+        self.datum
+    }
+
+    /// The oid of the column's type
+    pub fn type_oid(&self) -> pg_sys::Oid {
+        self.type_oid
+    }
+}
+
+/// The tables that `IMPORT FOREIGN SCHEMA` imports
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportTables {
+    All,
+    LimitTo(Vec<String>),
+    Except(Vec<String>),
+}
+
+/// An `IMPORT FOREIGN SCHEMA` statement
+#[derive(Debug, Clone)]
+pub struct ImportForeignSchema {
+    /// The server to import from
+    pub server_name: String,
+    pub server_oid: pg_sys::Oid,
+    /// The schema on the server to import
+    pub remote_schema: String,
+    /// The schema to create the foreign tables in
+    pub local_schema: String,
+    pub tables: ImportTables,
+    /// The statement's own options.  Those of the server can be had from
+    /// [`foreign_server_options`].
+    pub options: Vec<(String, String)>,
+}
+
+impl ImportForeignSchema {
+    unsafe fn from_pg(stmt: *mut pg_sys::ImportForeignSchemaStmt, server_oid: pg_sys::Oid) -> Self {
+        let stmt = &*stmt;
+        let table_names = || {
+            PgList::<pg_sys::RangeVar>::from_pg(stmt.table_list)
+                .iter_ptr()
+                .map(|rv| string((*rv).relname))
+                .collect()
+        };
+        ImportForeignSchema {
+            server_name: string(stmt.server_name),
+            server_oid,
+            remote_schema: string(stmt.remote_schema),
+            local_schema: string(stmt.local_schema),
+            tables: match stmt.list_type {
+                pg_sys::ImportForeignSchemaType_FDW_IMPORT_SCHEMA_LIMIT_TO => {
+                    ImportTables::LimitTo(table_names())
+                }
+                pg_sys::ImportForeignSchemaType_FDW_IMPORT_SCHEMA_EXCEPT => {
+                    ImportTables::Except(table_names())
+                }
+                _ => ImportTables::All,
+            },
+            options: options_from_list(stmt.options),
+        }
+    }
+}
+
+/// The executor's state for a `ForeignScan` plan node
+///
+/// This dereferences to the underlying `pg_sys::ForeignScanState` for anything that doesn't have
+/// a method of its own.
+pub struct ForeignScanStateRef<'a> {
+    ptr: *mut pg_sys::ForeignScanState,
+    _marker: PhantomData<&'a mut pg_sys::ForeignScanState>,
+}
+
+impl<'a> ForeignScanStateRef<'a> {
+    /// ## Safety
+    ///
+    /// `ptr` must be a valid pointer that outlives the returned `ForeignScanStateRef`
+    pub unsafe fn from_pg(ptr: *mut pg_sys::ForeignScanState) -> Self {
+        assert!(!ptr.is_null(), "ForeignScanState is NULL");
+        ForeignScanStateRef { ptr, _marker: PhantomData }
+    }
+
+    pub fn as_ptr(&self) -> *mut pg_sys::ForeignScanState {
+        self.ptr
+    }
+
+    /// The plan node being executed
+    pub fn plan(&self) -> &'a pg_sys::ForeignScan {
+        unsafe { &*((*self.ptr).ss.ps.plan as *mut pg_sys::ForeignScan) }
+    }
+
+    /// The foreign table being scanned.  The executor holds a lock on it.
+    pub fn relation(&self) -> PgRelation {
+        unsafe { PgRelation::from_pg((*self.ptr).ss.ss_currentRelation) }
+    }
+
+    /// The snapshot the plan is being executed with
+    pub fn snapshot(&self) -> pg_sys::Snapshot {
+        unsafe { (*(*self.ptr).ss.ps.state).es_snapshot }
+    }
+}
+
+impl Deref for ForeignScanStateRef<'_> {
+    type Target = pg_sys::ForeignScanState;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.ptr }
+    }
+}
+
+impl DerefMut for ForeignScanStateRef<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.ptr }
+    }
+}
+
+/// The options of the foreign table `relid`, merged with those of its server and of its wrapper,
+/// where the table's override its server's, which override the wrapper's
+pub fn foreign_table_options(relid: pg_sys::Oid) -> Vec<(String, String)> {
+    unsafe {
+        let table = pg_sys::GetForeignTable(relid);
+        let mut options = foreign_server_options((*table).serverid);
+        merge_options(&mut options, options_from_list((*table).options));
+        options
+    }
+}
+
+/// The options of the foreign server `serverid`, merged with those of its wrapper, where the
+/// server's override the wrapper's
+pub fn foreign_server_options(serverid: pg_sys::Oid) -> Vec<(String, String)> {
+    unsafe {
+        let server = pg_sys::GetForeignServer(serverid);
+        let wrapper = pg_sys::GetForeignDataWrapper((*server).fdwid);
+        let mut options = options_from_list((*wrapper).options);
+        merge_options(&mut options, options_from_list((*server).options));
+        options
+    }
+}
+
+fn merge_options(options: &mut Vec<(String, String)>, overrides: Vec<(String, String)>) {
+    for (name, value) in overrides {
+        match options.iter_mut().find(|(existing, _)| *existing == name) {
+            Some(option) => option.1 = value,
+            None => options.push((name, value)),
+        }
+    }
+}
+
+/// The `(name, value)` pairs of a `List` of option `DefElem`s
+unsafe fn options_from_list(list: *mut pg_sys::List) -> Vec<(String, String)> {
+    PgList::<pg_sys::DefElem>::from_pg(list)
+        .iter_ptr()
+        .map(|def| (string((*def).defname), string(pg_sys::defGetString(def))))
+        .collect()
+}
+
+unsafe fn string(string: *const c_char) -> String {
+    if string.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(string).to_string_lossy().into_owned()
+    }
+}
+
+/// Parse `(name, value)` pairs of options into `O`, where each option is a field, and its value
+/// is parsed as the field's type requires
+///
+/// Booleans are parsed as Postgres does, so `on`, `yes`, and `1` are all `true`, for example.
+/// Fields that are `Option`s may be left out, as may those with `#[serde(default)]`, and enums
+/// with unit variants are parsed from the variants' names.
+pub fn parse_options<O: DeserializeOwned>(
+    options: &[(String, String)],
+) -> Result<O, de::value::Error> {
+    O::deserialize(de::value::MapDeserializer::new(
+        options.iter().map(|(name, value)| (name.as_str(), OptionValue(value.as_str()))),
+    ))
+}
+
+/// Check that each option is a field of `O`, unless they're user mapping or column options, or
+/// `O` isn't a struct.  This is the default [`ForeignDataWrapper::validate_options`].
+pub fn validate_option_names<O: DeserializeOwned>(
+    options: &[(String, String)],
+    catalog: FdwOptionsCatalog,
+) -> Result<(), String> {
+    if let FdwOptionsCatalog::UserMapping | FdwOptionsCatalog::Column = catalog {
+        return Ok(());
+    }
+
+    if let Err(FieldNames(Some(fields))) = O::deserialize(FieldNames(None)) {
+        for (name, _) in options {
+            if !fields.contains(&name.as_str()) {
+                return Err(format!(
+                    "invalid option \"{}\" (valid options are: {})",
+                    name,
+                    fields.join(", ")
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The value of an option, which deserializes as whatever type is asked of it
+struct OptionValue<'a>(&'a str);
+
+impl OptionValue<'_> {
+    fn parse<T: std::str::FromStr>(&self, expected: &'static str) -> Result<T, de::value::Error> {
+        self.0
+            .trim()
+            .parse()
+            .map_err(|_| de::Error::invalid_value(Unexpected::Str(self.0), &expected))
+    }
+}
+
+impl<'de> IntoDeserializer<'de, de::value::Error> for OptionValue<'_> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident, $expected:literal;)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                visitor.$visit(self.parse($expected)?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for OptionValue<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_str(self.0)
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0.trim().to_lowercase().as_str() {
+            "true" | "t" | "yes" | "y" | "on" | "1" => visitor.visit_bool(true),
+            "false" | "f" | "no" | "n" | "off" | "0" => visitor.visit_bool(false),
+            _ => Err(de::Error::invalid_value(Unexpected::Str(self.0), &"a boolean")),
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_i8 => visit_i8, "an integer";
+        deserialize_i16 => visit_i16, "an integer";
+        deserialize_i32 => visit_i32, "an integer";
+        deserialize_i64 => visit_i64, "an integer";
+        deserialize_u8 => visit_u8, "an unsigned integer";
+        deserialize_u16 => visit_u16, "an unsigned integer";
+        deserialize_u32 => visit_u32, "an unsigned integer";
+        deserialize_u64 => visit_u64, "an unsigned integer";
+        deserialize_f32 => visit_f32, "a number";
+        deserialize_f64 => visit_f64, "a number";
+        deserialize_char => visit_char, "a single character";
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_enum(IntoDeserializer::<Self::Error>::into_deserializer(self.0.trim()))
+    }
+
+    serde::forward_to_deserialize_any! {
+        i128 u128 str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+/// A deserializer that fails with the names of the fields of the struct being deserialized, or
+/// with `None` if it's not a struct
+#[derive(Debug)]
+struct FieldNames(Option<&'static [&'static str]>);
+
+impl std::fmt::Display for FieldNames {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+impl std::error::Error for FieldNames {}
+
+impl de::Error for FieldNames {
+    fn custom<T: std::fmt::Display>(_msg: T) -> Self {
+        FieldNames(None)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for FieldNames {
+    type Error = FieldNames;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(FieldNames(None))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        Err(FieldNames(Some(fields)))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier ignored_any
+    }
+}
+
+#[track_caller]
+fn not_supported<W: ForeignDataWrapper>(operation: &str, object: &str) -> ! {
+    raise(
+        PgSqlErrorCode::ERRCODE_FEATURE_NOT_SUPPORTED,
+        &format!(
+            "foreign data wrapper \"{}\" does not support {} of {}",
+            W::NAME,
+            operation,
+            object
+        ),
+    )
+}
+
+/// The [`ForeignDataWrapper::Options`] of the foreign table `relid`
+fn table_options<W: ForeignDataWrapper>(relid: pg_sys::Oid) -> W::Options {
+    parse_options(&foreign_table_options(relid)).unwrap_or_else(|e| {
+        let name = unsafe { string(pg_sys::get_rel_name(relid)) };
+        raise(
+            PgSqlErrorCode::ERRCODE_FDW_INVALID_ATTRIBUTE_VALUE,
+            &format!("invalid options for foreign table \"{}\": {}", name, e),
+        )
+    })
+}
+
+/// The handler function of the wrapper `W`, which `#[pg_fdw]` makes, returns this
+pub fn fdw_handler<W: ForeignDataWrapper>() -> pg_sys::Datum {
+    let mut routine = PgBox::<pg_sys::FdwRoutine>::alloc_node(pg_sys::NodeTag_T_FdwRoutine);
+    routine.GetForeignRelSize = Some(get_foreign_rel_size::<W>);
+    routine.GetForeignPaths = Some(get_foreign_paths::<W>);
+    routine.GetForeignPlan = Some(get_foreign_plan::<W>);
+    routine.BeginForeignScan = Some(begin_foreign_scan::<W>);
+    routine.IterateForeignScan = Some(iterate_foreign_scan::<W>);
+    routine.ReScanForeignScan = Some(rescan_foreign_scan::<W>);
+    routine.EndForeignScan = Some(end_foreign_scan::<W>);
+    routine.ExplainForeignScan = Some(explain_foreign_scan::<W>);
+    routine.ImportForeignSchema = Some(import_foreign_schema::<W>);
+
+    let operations = W::MODIFY_OPERATIONS;
+    if operations != ModifyOperations::NONE {
+        routine.IsForeignRelUpdatable = Some(is_foreign_rel_updatable::<W>);
+        routine.BeginForeignModify = Some(begin_foreign_modify::<W>);
+        routine.EndForeignModify = Some(end_foreign_modify::<W>);
+    }
+    if operations.insert {
+        routine.ExecForeignInsert = Some(exec_foreign_insert::<W>);
+        #[cfg(not(feature = "pg10"))]
+        {
+            routine.BeginForeignInsert = Some(begin_foreign_insert::<W>);
+            routine.EndForeignInsert = Some(end_foreign_insert::<W>);
+        }
+    }
+    if operations.update || operations.delete {
+        routine.AddForeignUpdateTargets = Some(add_foreign_update_targets::<W>);
+    }
+    if operations.update {
+        routine.ExecForeignUpdate = Some(exec_foreign_update::<W>);
+    }
+    if operations.delete {
+        routine.ExecForeignDelete = Some(exec_foreign_delete::<W>);
+    }
+    routine.into_pg().into()
+}
+
+/// The validator function of the wrapper `W`, which `#[pg_fdw]` makes, calls this with its
+/// arguments, which are the options as a `text[]` and the oid of the catalog they're for
+pub fn fdw_validator<W: ForeignDataWrapper>(fcinfo: pg_sys::FunctionCallInfo) -> pg_sys::Datum {
+    let options =
+        unsafe { options_from_list(pg_sys::untransformRelOptions(pg_getarg_datum_raw(fcinfo, 0))) };
+    let catalog = pg_getarg_datum_raw(fcinfo, 1).value() as pg_sys::Oid;
+
+    if let Some(catalog) = FdwOptionsCatalog::from_oid(catalog) {
+        if let Err(message) = W::validate_options(&options, catalog) {
+            raise(PgSqlErrorCode::ERRCODE_FDW_INVALID_OPTION_NAME, &message);
+        }
+    }
+    pg_sys::Datum::from(0)
+}
+
+unsafe fn scan_state<'a, W>(node: *mut pg_sys::ForeignScanState) -> &'a mut W {
+    &mut *((*node).fdw_state as *mut W)
+}
+
+unsafe extern "C" fn get_foreign_rel_size<W: ForeignDataWrapper>(
+    root: *mut pg_sys::PlannerInfo,
+    baserel: *mut pg_sys::RelOptInfo,
+    foreigntableid: pg_sys::Oid,
+) {
+    crate::guard::guard(|| unsafe {
+        // parse the options once for all of the planner callbacks, and drop them, along with the
+        // wrapper's state, with its memory
+        let plan_state =
+            PgMemoryContexts::CurrentMemoryContext.leak_and_drop_on_delete(FdwPlanState {
+                options: table_options::<W>(foreigntableid),
+                state: None,
+            });
+        (*baserel).fdw_private = plan_state as *mut c_void;
+
+        W::get_rel_size(
+            &PlannerInfoRef::from_pg(root),
+            &mut RelOptInfoRef::from_pg(baserel),
+            foreigntableid,
+            &mut *plan_state,
+        )
+    })
+}
+
+/// The [`FdwPlanState`] that [`get_foreign_rel_size`] left in `baserel`
+unsafe fn plan_state<'a, W: ForeignDataWrapper>(
+    baserel: *mut pg_sys::RelOptInfo,
+) -> &'a mut FdwPlanState<W::Options> {
+    &mut *((*baserel).fdw_private as *mut FdwPlanState<W::Options>)
+}
+
+unsafe extern "C" fn get_foreign_paths<W: ForeignDataWrapper>(
+    root: *mut pg_sys::PlannerInfo,
+    baserel: *mut pg_sys::RelOptInfo,
+    foreigntableid: pg_sys::Oid,
+) {
+    crate::guard::guard(|| unsafe {
+        W::get_paths(
+            &PlannerInfoRef::from_pg(root),
+            &mut RelOptInfoRef::from_pg(baserel),
+            foreigntableid,
+            plan_state::<W>(baserel),
+        )
+    })
+}
+
+unsafe extern "C" fn get_foreign_plan<W: ForeignDataWrapper>(
+    root: *mut pg_sys::PlannerInfo,
+    baserel: *mut pg_sys::RelOptInfo,
+    foreigntableid: pg_sys::Oid,
+    best_path: *mut pg_sys::ForeignPath,
+    tlist: *mut pg_sys::List,
+    scan_clauses: *mut pg_sys::List,
+    _outer_plan: *mut pg_sys::Plan,
+) -> *mut pg_sys::ForeignScan {
+    crate::guard::guard(|| unsafe {
+        W::get_plan(
+            &PlannerInfoRef::from_pg(root),
+            &RelOptInfoRef::from_pg(baserel),
+            foreigntableid,
+            plan_state::<W>(baserel),
+            &*best_path,
+            PgList::from_pg(tlist),
+            PgList::from_pg(scan_clauses),
+        )
+        .into_pg()
+    })
+}
+
+unsafe extern "C" fn begin_foreign_scan<W: ForeignDataWrapper>(
+    node: *mut pg_sys::ForeignScanState,
+    eflags: c_int,
+) {
+    crate::guard::guard(|| unsafe {
+        let mut scan = ForeignScanStateRef::from_pg(node);
+        let options = table_options::<W>(scan.relation().oid());
+        let wrapper = W::begin_scan(&mut scan, options, eflags);
+        (*node).fdw_state =
+            PgMemoryContexts::CurrentMemoryContext.leak_and_drop_on_delete(wrapper) as *mut c_void;
+    })
+}
+
+unsafe extern "C" fn iterate_foreign_scan<W: ForeignDataWrapper>(
+    node: *mut pg_sys::ForeignScanState,
+) -> *mut pg_sys::TupleTableSlot {
+    crate::guard::guard(|| unsafe {
+        let mut slot = TupleTableSlotRef::from_pg((*node).ss.ss_ScanTupleSlot);
+        slot.clear();
+        if !scan_state::<W>(node).iterate_scan(&mut ForeignScanStateRef::from_pg(node), &mut slot) {
+            // an empty slot tells the executor there are no more rows
+            slot.clear();
+        }
+        slot.as_ptr()
+    })
+}
+
+unsafe extern "C" fn rescan_foreign_scan<W: ForeignDataWrapper>(
+    node: *mut pg_sys::ForeignScanState,
+) {
+    crate::guard::guard(|| unsafe {
+        scan_state::<W>(node).rescan(&mut ForeignScanStateRef::from_pg(node))
+    })
+}
+
+unsafe extern "C" fn end_foreign_scan<W: ForeignDataWrapper>(node: *mut pg_sys::ForeignScanState) {
+    crate::guard::guard(|| unsafe {
+        scan_state::<W>(node).end_scan(&mut ForeignScanStateRef::from_pg(node))
+    })
+}
+
+unsafe extern "C" fn explain_foreign_scan<W: ForeignDataWrapper>(
+    node: *mut pg_sys::ForeignScanState,
+    es: *mut pg_sys::ExplainState,
+) {
+    crate::guard::guard(|| unsafe {
+        scan_state::<W>(node).explain_scan(
+            &mut ForeignScanStateRef::from_pg(node),
+            &mut ExplainStateRef::from_pg(es),
+        )
+    })
+}
+
+unsafe extern "C" fn import_foreign_schema<W: ForeignDataWrapper>(
+    stmt: *mut pg_sys::ImportForeignSchemaStmt,
+    server_oid: pg_sys::Oid,
+) -> *mut pg_sys::List {
+    crate::guard::guard(|| unsafe {
+        let mut commands = PgList::<c_char>::new();
+        for command in W::import_foreign_schema(&ImportForeignSchema::from_pg(stmt, server_oid)) {
+            let command = CString::new(command).expect("command contains a NUL byte");
+            commands.push(pg_sys::pstrdup(command.as_ptr()));
+        }
+        commands.into_pg()
+    })
+}
+
+/// The name of the junk column that carries a row's [`RowId`] from the scan to the modification
+const ROWID_ATTNAME: &[u8] = b"pgx_rowid\0";
+
+/// The state of a modification, in its `ResultRelInfo`'s `ri_FdwState`
+struct ModifyState<W> {
+    wrapper: W,
+    rowid_attno: pg_sys::AttrNumber,
+}
+
+unsafe fn modify_state<'a, W>(rinfo: *mut pg_sys::ResultRelInfo) -> &'a mut ModifyState<W> {
+    &mut *((*rinfo).ri_FdwState as *mut ModifyState<W>)
+}
+
+unsafe extern "C" fn is_foreign_rel_updatable<W: ForeignDataWrapper>(
+    _rel: pg_sys::Relation,
+) -> c_int {
+    let operations = W::MODIFY_OPERATIONS;
+    let mut events: c_int = 0;
+    if operations.insert {
+        events |= 1 << pg_sys::CmdType_CMD_INSERT;
+    }
+    if operations.update {
+        events |= 1 << pg_sys::CmdType_CMD_UPDATE;
+    }
+    if operations.delete {
+        events |= 1 << pg_sys::CmdType_CMD_DELETE;
+    }
+    events
+}
+
+/// A `Var` for the [`ForeignDataWrapper::ROWID_COLUMN`] of `relation`, as range table entry `varno`
+unsafe fn rowid_var<W: ForeignDataWrapper>(
+    varno: pg_sys::Index,
+    relation: pg_sys::Relation,
+) -> *mut pg_sys::Var {
+    let relation = PgRelation::from_pg(relation);
+    let column = W::ROWID_COLUMN.unwrap_or_else(|| {
+        not_supported::<W>("UPDATE or DELETE (it has no ROWID_COLUMN)", relation.name())
+    });
+    let tupdesc = relation.tuple_desc();
+    let att =
+        tupdesc.iter().find(|att| !att.is_dropped() && att.name() == column).unwrap_or_else(|| {
+            raise(
+                PgSqlErrorCode::ERRCODE_UNDEFINED_COLUMN,
+                &format!(
+                    "column \"{}\" of foreign table \"{}\" does not exist",
+                    column,
+                    relation.name()
+                ),
+            )
+        });
+    pg_sys::makeVar(varno, att.attnum, att.atttypid, att.atttypmod, att.attcollation, 0)
+}
+
+#[cfg(any(feature = "pg10", feature = "pg11", feature = "pg12", feature = "pg13"))]
+unsafe extern "C" fn add_foreign_update_targets<W: ForeignDataWrapper>(
+    parsetree: *mut pg_sys::Query,
+    _target_rte: *mut pg_sys::RangeTblEntry,
+    target_relation: pg_sys::Relation,
+) {
+    crate::guard::guard(|| unsafe {
+        let var = rowid_var::<W>((*parsetree).resultRelation as pg_sys::Index, target_relation);
+        let mut tlist = PgList::<pg_sys::TargetEntry>::from_pg((*parsetree).targetList);
+        tlist.push(pg_sys::makeTargetEntry(
+            var as *mut pg_sys::Expr,
+            (tlist.len() + 1) as pg_sys::AttrNumber,
+            pg_sys::pstrdup(ROWID_ATTNAME.as_ptr() as *const c_char),
+            true,
+        ));
+        (*parsetree).targetList = tlist.into_pg();
+    })
+}
+
+#[cfg(feature = "pg14")]
+unsafe extern "C" fn add_foreign_update_targets<W: ForeignDataWrapper>(
+    root: *mut pg_sys::PlannerInfo,
+    rtindex: pg_sys::Index,
+    _target_rte: *mut pg_sys::RangeTblEntry,
+    target_relation: pg_sys::Relation,
+) {
+    crate::guard::guard(|| unsafe {
+        let var = rowid_var::<W>(rtindex, target_relation);
+        pg_sys::add_row_identity_var(root, var, rtindex, ROWID_ATTNAME.as_ptr() as *const c_char);
+    })
+}
+
+/// The attribute number of the [`RowId`] junk column in the rows of `mtstate`'s subplan
+unsafe fn rowid_attno(
+    mtstate: *mut pg_sys::ModifyTableState,
+    subplan_index: c_int,
+) -> pg_sys::AttrNumber {
+    #[cfg(any(feature = "pg10", feature = "pg11", feature = "pg12", feature = "pg13"))]
+    let subplan = (**(*mtstate).mt_plans.add(subplan_index as usize)).plan;
+    #[cfg(feature = "pg14")]
+    let subplan = {
+        let _ = subplan_index;
+        (*(*mtstate).ps.lefttree).plan
+    };
+
+    let attno = pg_sys::ExecFindJunkAttributeInTlist(
+        (*subplan).targetlist,
+        ROWID_ATTNAME.as_ptr() as *const c_char,
+    );
+    if attno == 0 {
+        raise(PgSqlErrorCode::ERRCODE_INTERNAL_ERROR, "could not find junk pgx_rowid column");
+    }
+    attno
+}
+
+unsafe fn begin_modify<W: ForeignDataWrapper>(
+    rinfo: *mut pg_sys::ResultRelInfo,
+    rowid_attno: pg_sys::AttrNumber,
+) {
+    let relation = PgRelation::from_pg((*rinfo).ri_RelationDesc);
+    let wrapper = W::begin_modify(&relation, table_options::<W>(relation.oid()));
+    (*rinfo).ri_FdwState = PgMemoryContexts::CurrentMemoryContext
+        .leak_and_drop_on_delete(ModifyState { wrapper, rowid_attno })
+        as *mut c_void;
+}
+
+unsafe extern "C" fn begin_foreign_modify<W: ForeignDataWrapper>(
+    mtstate: *mut pg_sys::ModifyTableState,
+    rinfo: *mut pg_sys::ResultRelInfo,
+    _fdw_private: *mut pg_sys::List,
+    subplan_index: c_int,
+    eflags: c_int,
+) {
+    crate::guard::guard(|| unsafe {
+        if eflags as u32 & pg_sys::EXEC_FLAG_EXPLAIN_ONLY != 0 {
+            return;
+        }
+
+        let rowid_attno = match (*mtstate).operation {
+            pg_sys::CmdType_CMD_UPDATE | pg_sys::CmdType_CMD_DELETE => {
+                rowid_attno(mtstate, subplan_index)
+            }
+            _ => 0,
+        };
+        begin_modify::<W>(rinfo, rowid_attno)
+    })
+}
+
+/// For `COPY` into a foreign table, and rows routed to a partition that's a foreign table
+#[cfg(not(feature = "pg10"))]
+unsafe extern "C" fn begin_foreign_insert<W: ForeignDataWrapper>(
+    _mtstate: *mut pg_sys::ModifyTableState,
+    rinfo: *mut pg_sys::ResultRelInfo,
+) {
+    crate::guard::guard(|| unsafe { begin_modify::<W>(rinfo, 0) })
+}
+
+unsafe extern "C" fn exec_foreign_insert<W: ForeignDataWrapper>(
+    _estate: *mut pg_sys::EState,
+    rinfo: *mut pg_sys::ResultRelInfo,
+    slot: *mut pg_sys::TupleTableSlot,
+    _plan_slot: *mut pg_sys::TupleTableSlot,
+) -> *mut pg_sys::TupleTableSlot {
+    crate::guard::guard(|| unsafe {
+        if modify_state::<W>(rinfo).wrapper.insert(&mut TupleTableSlotRef::from_pg(slot)) {
+            slot
+        } else {
+            std::ptr::null_mut()
+        }
+    })
+}
+
+/// The [`RowId`] of the row being updated or deleted, from the junk column of `plan_slot`
+unsafe fn rowid(plan_slot: *mut pg_sys::TupleTableSlot, rowid_attno: pg_sys::AttrNumber) -> RowId {
+    let plan_slot = TupleTableSlotRef::from_pg(plan_slot);
+    let type_oid = plan_slot
+        .tuple_desc()
+        .get(rowid_attno as usize - 1)
+        .expect("pgx_rowid is not in the plan slot")
+        .type_oid()
+        .value();
+    let mut is_null = false;
+    let datum = pg_sys::pgx_slot_getattr(plan_slot.as_ptr(), rowid_attno as c_int, &mut is_null);
+    RowId { datum: if is_null { None } else { Some(datum) }, type_oid }
+}
+
+unsafe extern "C" fn exec_foreign_update<W: ForeignDataWrapper>(
+    _estate: *mut pg_sys::EState,
+    rinfo: *mut pg_sys::ResultRelInfo,
+    slot: *mut pg_sys::TupleTableSlot,
+    plan_slot: *mut pg_sys::TupleTableSlot,
+) -> *mut pg_sys::TupleTableSlot {
+    crate::guard::guard(|| unsafe {
+        let state = modify_state::<W>(rinfo);
+        let rowid = rowid(plan_slot, state.rowid_attno);
+        if state.wrapper.update(rowid, &mut TupleTableSlotRef::from_pg(slot)) {
+            slot
+        } else {
+            std::ptr::null_mut()
+        }
+    })
+}
+
+unsafe extern "C" fn exec_foreign_delete<W: ForeignDataWrapper>(
+    _estate: *mut pg_sys::EState,
+    rinfo: *mut pg_sys::ResultRelInfo,
+    slot: *mut pg_sys::TupleTableSlot,
+    plan_slot: *mut pg_sys::TupleTableSlot,
+) -> *mut pg_sys::TupleTableSlot {
+    crate::guard::guard(|| unsafe {
+        let state = modify_state::<W>(rinfo);
+        if state.wrapper.delete(rowid(plan_slot, state.rowid_attno)) {
+            slot
+        } else {
+            std::ptr::null_mut()
+        }
+    })
+}
+
+unsafe extern "C" fn end_foreign_modify<W: ForeignDataWrapper>(
+    _estate: *mut pg_sys::EState,
+    rinfo: *mut pg_sys::ResultRelInfo,
+) {
+    crate::guard::guard(|| unsafe {
+        // there's no state when the plan was only set up for EXPLAIN
+        if !(*rinfo).ri_FdwState.is_null() {
+            modify_state::<W>(rinfo).wrapper.end_modify()
+        }
+    })
+}
+
+#[cfg(not(feature = "pg10"))]
+unsafe extern "C" fn end_foreign_insert<W: ForeignDataWrapper>(
+    estate: *mut pg_sys::EState,
+    rinfo: *mut pg_sys::ResultRelInfo,
+) {
+    end_foreign_modify::<W>(estate, rinfo)
+}
@@ -2,6 +2,7 @@
 //!
 //! [`PgHeapTuple`]s also describe composite types as defined by [`pgx::composite_type!()`][crate::composite_type].
 use crate::pg_sys::{Datum, Oid};
+use crate::copy::{CopyRow, CopyRowWriter};
 use crate::{
     heap_getattr_raw, pg_sys, AllocatedByPostgres, AllocatedByRust, FromDatum, IntoDatum, PgBox,
     PgMemoryContexts, PgTupleDesc, TriggerTuple, TryFromDatumError, WhoAllocated,
@@ -126,6 +127,22 @@ impl<'a> PgHeapTuple<'a, AllocatedByPostgres> {
 }
 
 impl<'a> PgHeapTuple<'a, AllocatedByRust> {
+    /// Creates a new [PgHeapTuple] from a [PgTupleDesc] and a [pg_sys::HeapTuple] pointer that
+    /// was `palloc()`'d for Rust to own, such as a copy, which is `pfree()`'d when dropped.
+    ///
+    /// ## Safety
+    ///
+    /// This function is unsafe as we cannot guarantee that the [pg_sys::HeapTuple] pointer is valid
+    /// and not otherwise owned, nor can we guaratee that the provided [PgTupleDesc] properly
+    /// describes the structure of the heap tuple.
+    pub(crate) unsafe fn from_owned_heap_tuple(
+        tupdesc: PgTupleDesc<'a>,
+        heap_tuple: pg_sys::HeapTuple,
+    ) -> Self {
+        let tuple = PgBox::<pg_sys::HeapTupleData, AllocatedByRust>::from_rust(heap_tuple);
+        Self { tuple, tupdesc }
+    }
+
     /** Create a new heap tuple in the shape of a defined composite type
 
     ```rust,no_run
@@ -322,6 +339,33 @@ impl<'a, AllocatedBy: WhoAllocated<pg_sys::HeapTupleData>> IntoDatum
     }
 }
 
+impl<'a, AllocatedBy: WhoAllocated<pg_sys::HeapTupleData>> CopyRow
+    for PgHeapTuple<'a, AllocatedBy>
+{
+    /// Writes the attributes named by the writer's [target columns][CopyRowWriter::target_columns],
+    /// in that order, so the tuple's descriptor doesn't need to match the table's column for column
+    fn write_copy_row(self, writer: &mut CopyRowWriter) {
+        let values = writer
+            .target_columns()
+            .iter()
+            .map(|name| {
+                let att = self
+                    .tupdesc
+                    .iter()
+                    .find(|att| !att.is_dropped() && att.name() == name)
+                    .unwrap_or_else(|| panic!("tuple has no attribute named \"{}\"", name));
+                let attno = NonZeroUsize::new(att.attnum as usize).unwrap();
+                let datum =
+                    unsafe { heap_getattr_raw(self.tuple.as_ptr(), attno, self.tupdesc.as_ptr()) };
+                (att.type_oid(), datum)
+            })
+            .collect::<Vec<_>>();
+        for (type_oid, datum) in values {
+            unsafe { writer.push_datum(type_oid, datum) }
+        }
+    }
+}
+
 impl<'a, AllocatedBy: WhoAllocated<pg_sys::HeapTupleData>> PgHeapTuple<'a, AllocatedBy> {
     /// Consume this [`PgHeapTuple`] and return a composite Datum representation, containing the tuple
     /// data and the corresponding tuple descriptor information.
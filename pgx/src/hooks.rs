@@ -9,8 +9,11 @@ Use of this source code is governed by the MIT license that can be found in the
 
 //! A trait and registration system for hooking Postgres internal operations such as its planner and executor
 use crate::prelude::*;
-use crate::{void_mut_ptr, PgBox, PgList};
-use std::ops::Deref;
+use crate::{
+    void_mut_ptr, ErrorDataRef, PgBox, PgList, PgRelation, PlannerInfoRef, PortRef,
+    RangeTblEntryRef, RelOptInfoRef,
+};
+use std::ops::{Deref, DerefMut};
 
 pub struct HookResult<T> {
     pub inner: T,
@@ -30,7 +33,131 @@ impl<T> Deref for HookResult<T> {
     }
 }
 
+/// Postgres' record of the constants in a query, as found while computing its query id
+#[cfg(feature = "pg14")]
+pub use pg_sys::JumbleState;
+
+/// Postgres' record of the constants in a query, which doesn't exist before Postgres 14
+#[cfg(not(feature = "pg14"))]
+pub enum JumbleState {}
+
+/// An access to a database object, as given to [`PgHooks::object_access`]
+pub struct ObjectAccess<'a> {
+    /// The oid of the catalog the object is in, such as `pg_sys::RelationRelationId`
+    pub class_id: pg_sys::Oid,
+    pub object_id: pg_sys::Oid,
+    /// The column number, for columns of a relation, or zero
+    pub sub_id: i32,
+    pub event: ObjectAccessEvent<'a>,
+    access: pg_sys::ObjectAccessType,
+    arg: void_mut_ptr,
+}
+
+/// What is happening to the object of an [`ObjectAccess`]
+#[derive(Debug)]
+pub enum ObjectAccessEvent<'a> {
+    /// The object was just created.  `is_internal` is true if it was created by the system
+    /// rather than directly by the user, such as the row type of a new table.
+    PostCreate { is_internal: bool },
+
+    /// The object is about to be dropped.  `dropflags` are the `pg_sys::PERFORM_DELETION_*`
+    /// flags.
+    Drop { dropflags: i32 },
+
+    /// The object was just altered.  `auxiliary_id` is set when the object is a sub-object of
+    /// another, such as an attribute default of a column.
+    PostAlter { auxiliary_id: pg_sys::Oid, is_internal: bool },
+
+    /// The namespace is about to be searched.  Setting `allowed` to false denies the search,
+    /// which raises an ERROR if `ereport_on_violation` is true.
+    NamespaceSearch { ereport_on_violation: bool, allowed: &'a mut bool },
+
+    /// The function is about to be executed
+    FunctionExecute,
+
+    /// The relation is about to be truncated.  Only on Postgres 13 and later.
+    Truncate,
+}
+
+impl<'a> ObjectAccess<'a> {
+    unsafe fn from_pg(
+        access: pg_sys::ObjectAccessType,
+        class_id: pg_sys::Oid,
+        object_id: pg_sys::Oid,
+        sub_id: i32,
+        arg: void_mut_ptr,
+    ) -> Self {
+        let event = match access {
+            pg_sys::ObjectAccessType_OAT_POST_CREATE => {
+                let arg = (arg as *mut pg_sys::ObjectAccessPostCreate).as_ref();
+                ObjectAccessEvent::PostCreate {
+                    is_internal: arg.map_or(false, |arg| arg.is_internal),
+                }
+            }
+            pg_sys::ObjectAccessType_OAT_DROP => {
+                let arg = (arg as *mut pg_sys::ObjectAccessDrop).as_ref();
+                ObjectAccessEvent::Drop { dropflags: arg.map_or(0, |arg| arg.dropflags) }
+            }
+            pg_sys::ObjectAccessType_OAT_POST_ALTER => {
+                let arg = (arg as *mut pg_sys::ObjectAccessPostAlter).as_ref();
+                ObjectAccessEvent::PostAlter {
+                    auxiliary_id: arg.map_or(pg_sys::InvalidOid, |arg| arg.auxiliary_id),
+                    is_internal: arg.map_or(false, |arg| arg.is_internal),
+                }
+            }
+            pg_sys::ObjectAccessType_OAT_NAMESPACE_SEARCH => {
+                let arg = (arg as *mut pg_sys::ObjectAccessNamespaceSearch)
+                    .as_mut()
+                    .expect("ObjectAccessNamespaceSearch is NULL");
+                ObjectAccessEvent::NamespaceSearch {
+                    ereport_on_violation: arg.ereport_on_violation,
+                    allowed: &mut arg.result,
+                }
+            }
+            pg_sys::ObjectAccessType_OAT_FUNCTION_EXECUTE => ObjectAccessEvent::FunctionExecute,
+            #[cfg(any(feature = "pg13", feature = "pg14"))]
+            pg_sys::ObjectAccessType_OAT_TRUNCATE => ObjectAccessEvent::Truncate,
+            _ => panic!("unrecognized ObjectAccessType: {}", access),
+        };
+
+        ObjectAccess { class_id, object_id, sub_id, event, access, arg }
+    }
+
+    /// Is the object a relation (a table, index, view, etc), or a column of one?
+    pub fn is_relation(&self) -> bool {
+        self.class_id == pg_sys::RelationRelationId
+    }
+
+    /// Open the object, if it's a relation (or the relation of a column), and if it can still be
+    /// found.
+    ///
+    /// This doesn't take a lock on the relation, and Postgres doesn't hold one for every kind of
+    /// access, so lock it first (with `pg_sys::LockRelationOid()`) if it mustn't change.
+    pub fn relation(&self) -> Option<PgRelation> {
+        if !self.is_relation() {
+            return None;
+        }
+
+        unsafe {
+            let rel = pg_sys::RelationIdGetRelation(self.object_id);
+            if rel.is_null() {
+                None
+            } else {
+                Some(PgRelation::from_pg_owned(rel))
+            }
+        }
+    }
+}
+
 pub trait PgHooks {
+    /// The hook points this instance overrides the methods of
+    ///
+    /// pgx only installs itself into a hook point once a registered instance asks for it, so that
+    /// queries don't pay for hooks nobody is using.  An instance may still be called at a hook
+    /// point it didn't ask for, if another instance did, in which case the default method just
+    /// calls `prev_hook`.
+    fn hook_points(&self) -> HookPoints;
+
     /// Hook for plugins to get control in ExecutorStart()
     fn executor_start(
         &mut self,
@@ -140,6 +267,126 @@ pub trait PgHooks {
         prev_hook(parse, query_string, cursor_options, bound_params)
     }
 
+    /// Hook for plugins to get control after the planner has gathered information about a base
+    /// relation (its size, indexes, and so on), such as to adjust it
+    fn get_relation_info(
+        &mut self,
+        root: PlannerInfoRef<'_>,
+        relation_oid: pg_sys::Oid,
+        inhparent: bool,
+        rel: RelOptInfoRef<'_>,
+        prev_hook: fn(
+            root: PlannerInfoRef<'_>,
+            relation_oid: pg_sys::Oid,
+            inhparent: bool,
+            rel: RelOptInfoRef<'_>,
+        ) -> HookResult<()>,
+    ) -> HookResult<()> {
+        prev_hook(root, relation_oid, inhparent, rel)
+    }
+
+    /// Hook for plugins to get control after the planner has generated the paths for scanning a
+    /// base relation, such as to add paths of their own with [`RelOptInfoRef::add_path`]
+    fn set_rel_pathlist(
+        &mut self,
+        root: PlannerInfoRef<'_>,
+        rel: RelOptInfoRef<'_>,
+        rti: pg_sys::Index,
+        rte: RangeTblEntryRef<'_>,
+        prev_hook: fn(
+            root: PlannerInfoRef<'_>,
+            rel: RelOptInfoRef<'_>,
+            rti: pg_sys::Index,
+            rte: RangeTblEntryRef<'_>,
+        ) -> HookResult<()>,
+    ) -> HookResult<()> {
+        prev_hook(root, rel, rti, rte)
+    }
+
+    /// Hook for plugins to get control after the planner has generated the paths for joining
+    /// two relations, such as to add paths of their own with [`RelOptInfoRef::add_path`]
+    fn set_join_pathlist(
+        &mut self,
+        root: PlannerInfoRef<'_>,
+        joinrel: RelOptInfoRef<'_>,
+        outerrel: RelOptInfoRef<'_>,
+        innerrel: RelOptInfoRef<'_>,
+        jointype: pg_sys::JoinType,
+        extra: PgBox<pg_sys::JoinPathExtraData>,
+        prev_hook: fn(
+            root: PlannerInfoRef<'_>,
+            joinrel: RelOptInfoRef<'_>,
+            outerrel: RelOptInfoRef<'_>,
+            innerrel: RelOptInfoRef<'_>,
+            jointype: pg_sys::JoinType,
+            extra: PgBox<pg_sys::JoinPathExtraData>,
+        ) -> HookResult<()>,
+    ) -> HookResult<()> {
+        prev_hook(root, joinrel, outerrel, innerrel, jointype, extra)
+    }
+
+    /// Hook for plugins to get control at the end of parse analysis, such as to compute a
+    /// [`query_fingerprint`][crate::query_fingerprint] of the query
+    ///
+    /// `jumble_state` is only ever provided on Postgres 14, when Postgres is computing query ids
+    /// itself (see the `compute_query_id` setting).
+    fn post_parse_analyze(
+        &mut self,
+        parse_state: PgBox<pg_sys::ParseState>,
+        query: PgBox<pg_sys::Query>,
+        jumble_state: Option<PgBox<JumbleState>>,
+        prev_hook: fn(
+            parse_state: PgBox<pg_sys::ParseState>,
+            query: PgBox<pg_sys::Query>,
+            jumble_state: Option<PgBox<JumbleState>>,
+        ) -> HookResult<()>,
+    ) -> HookResult<()> {
+        prev_hook(parse_state, query, jumble_state)
+    }
+
+    /// Hook for plugins to get control just before a message is written to the server log
+    ///
+    /// This is only called for messages that are going to the server log, although that can be
+    /// changed with [`ErrorDataRef::set_output_to_server`].  Any messages logged from inside this
+    /// hook are not passed to it again (or to any other `PgHooks` instance), so it's safe to log
+    /// from here.  This must not raise an ERROR (or panic) itself.
+    fn emit_log(
+        &mut self,
+        error_data: ErrorDataRef<'_>,
+        prev_hook: fn(error_data: ErrorDataRef<'_>) -> HookResult<()>,
+    ) -> HookResult<()> {
+        prev_hook(error_data)
+    }
+
+    /// Hook for plugins to get control after a client has been authenticated (or not), but
+    /// before it's told so
+    ///
+    /// `status` is `pg_sys::STATUS_OK` if Postgres accepted the client's credentials, in which
+    /// case the connection can still be refused with [`PortRef::reject`].  Otherwise, the
+    /// connection will be refused after this returns.  This is only ever called if the extension
+    /// is loaded through `shared_preload_libraries`.
+    fn client_authentication(
+        &mut self,
+        port: PortRef<'_>,
+        status: i32,
+        prev_hook: fn(port: PortRef<'_>, status: i32) -> HookResult<()>,
+    ) -> HookResult<()> {
+        prev_hook(port, status)
+    }
+
+    /// Hook for plugins to get control when a database object is created, altered, dropped, or
+    /// otherwise accessed
+    ///
+    /// An ERROR raised from here (by panicking, for example) aborts whatever was being done to
+    /// the object.
+    fn object_access(
+        &mut self,
+        access: ObjectAccess<'_>,
+        prev_hook: fn(access: ObjectAccess<'_>) -> HookResult<()>,
+    ) -> HookResult<()> {
+        prev_hook(access)
+    }
+
     /// Called when the transaction aborts
     fn abort(&mut self) {}
 
@@ -147,8 +394,68 @@ pub trait PgHooks {
     fn commit(&mut self) {}
 }
 
+/// Which hook points a [`PgHooks`] instance overrides the methods of, where each is named after
+/// its method
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HookPoints {
+    pub executor_start: bool,
+    pub executor_run: bool,
+    pub executor_finish: bool,
+    pub executor_end: bool,
+    pub executor_check_perms: bool,
+    pub process_utility: bool,
+    pub planner: bool,
+    pub post_parse_analyze: bool,
+    pub emit_log: bool,
+    pub client_authentication: bool,
+    pub object_access: bool,
+    pub get_relation_info: bool,
+    pub set_rel_pathlist: bool,
+    pub set_join_pathlist: bool,
+}
+
+impl HookPoints {
+    /// No hook points, for an instance that only wants [`PgHooks::abort`] and [`PgHooks::commit`]
+    pub const NONE: HookPoints = HookPoints {
+        executor_start: false,
+        executor_run: false,
+        executor_finish: false,
+        executor_end: false,
+        executor_check_perms: false,
+        process_utility: false,
+        planner: false,
+        post_parse_analyze: false,
+        emit_log: false,
+        client_authentication: false,
+        object_access: false,
+        get_relation_info: false,
+        set_rel_pathlist: false,
+        set_join_pathlist: false,
+    };
+
+    /// Every hook point
+    pub const ALL: HookPoints = HookPoints {
+        executor_start: true,
+        executor_run: true,
+        executor_finish: true,
+        executor_end: true,
+        executor_check_perms: true,
+        process_utility: true,
+        planner: true,
+        post_parse_analyze: true,
+        emit_log: true,
+        client_authentication: true,
+        object_access: true,
+        get_relation_info: true,
+        set_rel_pathlist: true,
+        set_join_pathlist: true,
+    };
+}
+
 struct Hooks {
-    current_hook: Box<&'static mut (dyn PgHooks)>,
+    registered: Vec<RegisteredHook>,
+    next_id: u64,
+    installed: HookPoints,
     prev_executor_start_hook: pg_sys::ExecutorStart_hook_type,
     prev_executor_run_hook: pg_sys::ExecutorRun_hook_type,
     prev_executor_finish_hook: pg_sys::ExecutorFinish_hook_type,
@@ -156,68 +463,333 @@ struct Hooks {
     prev_executor_check_perms_hook: pg_sys::ExecutorCheckPerms_hook_type,
     prev_process_utility_hook: pg_sys::ProcessUtility_hook_type,
     prev_planner_hook: pg_sys::planner_hook_type,
+    prev_post_parse_analyze_hook: pg_sys::post_parse_analyze_hook_type,
+    prev_emit_log_hook: pg_sys::emit_log_hook_type,
+    prev_client_authentication_hook: pg_sys::ClientAuthentication_hook_type,
+    prev_object_access_hook: pg_sys::object_access_hook_type,
+    prev_get_relation_info_hook: pg_sys::get_relation_info_hook_type,
+    prev_set_rel_pathlist_hook: pg_sys::set_rel_pathlist_hook_type,
+    prev_set_join_pathlist_hook: pg_sys::set_join_pathlist_hook_type,
+}
+
+struct RegisteredHook {
+    id: HookId,
+    hook: *mut dyn PgHooks,
 }
 
+/// Identifies a `PgHooks` instance registered with [`register_hook`], so that it can later be
+/// removed with [`unregister_hook`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HookId(u64);
+
 static mut HOOKS: Option<Hooks> = None;
 
+/// The position, in the chain of registered hooks, of the hook that is currently running.  This
+/// is how a `prev_hook` function knows which hook comes next.
+static mut CHAIN_POSITION: Option<usize> = None;
+
+/// The [`CHAIN_POSITION`] as of the start of each open subtransaction, innermost last.  An ERROR
+/// can longjmp out of the middle of the chain without running any of our `Drop` impls, so the
+/// position is put back from here when a subtransaction aborts, and cleared when the top-level
+/// transaction aborts.
+static mut SUBXACT_CHAIN_POSITIONS: Vec<(pg_sys::SubTransactionId, Option<usize>)> = Vec::new();
+
 /// Register a `PgHook` instance to respond to the various hook points
-pub unsafe fn register_hook(hook: &'static mut (dyn PgHooks)) {
-    if HOOKS.is_some() {
-        panic!("PgHook instance already registered");
-    }
-    HOOKS = Some(Hooks {
-        current_hook: Box::new(hook),
-        prev_executor_start_hook: pg_sys::ExecutorStart_hook
-            .replace(pgx_executor_start)
-            .or(Some(pgx_standard_executor_start_wrapper)),
-        prev_executor_run_hook: pg_sys::ExecutorRun_hook
-            .replace(pgx_executor_run)
-            .or(Some(pgx_standard_executor_run_wrapper)),
-        prev_executor_finish_hook: pg_sys::ExecutorFinish_hook
-            .replace(pgx_executor_finish)
-            .or(Some(pgx_standard_executor_finish_wrapper)),
-        prev_executor_end_hook: pg_sys::ExecutorEnd_hook
-            .replace(pgx_executor_end)
-            .or(Some(pgx_standard_executor_end_wrapper)),
-        prev_executor_check_perms_hook: pg_sys::ExecutorCheckPerms_hook
-            .replace(pgx_executor_check_perms)
-            .or(Some(pgx_standard_executor_check_perms_wrapper)),
-        prev_process_utility_hook: pg_sys::ProcessUtility_hook
-            .replace(pgx_process_utility)
-            .or(Some(pgx_standard_process_utility_wrapper)),
-        prev_planner_hook: pg_sys::planner_hook
-            .replace(pgx_planner)
-            .or(Some(pgx_standard_planner_wrapper)),
-    });
+///
+/// Any number of instances can be registered.  They're chained in the order they were registered:
+/// the first one registered is called first, and its `prev_hook` calls the second one, and so on.
+/// The last one's `prev_hook` calls whatever hook was installed before pgx's (by another
+/// extension, for example), or Postgres' standard implementation if there wasn't one.
+///
+/// Only the hook points the instance asks for with [`PgHooks::hook_points`] are installed, if
+/// they aren't already.
+///
+/// The returned [`HookId`] can be passed to [`unregister_hook`] to remove the instance from the
+/// chain.
+pub unsafe fn register_hook(hook: &'static mut (dyn PgHooks)) -> HookId {
+    let hooks = HOOKS.get_or_insert_with(|| register_xact_callbacks());
+    hooks.install(hook.hook_points());
+    hooks.next_id += 1;
+    let id = HookId(hooks.next_id);
+    hooks.registered.push(RegisteredHook { id, hook });
+    id
+}
+
+/// Remove a `PgHook` instance, previously registered with [`register_hook`], from the chain of
+/// hooks, returning it.  Returns `None` if it's not registered.
+///
+/// The instances registered before and after it are chained together in its place.  This cannot
+/// be called while any hook is running.
+pub unsafe fn unregister_hook(id: HookId) -> Option<&'static mut (dyn PgHooks)> {
+    if CHAIN_POSITION.is_some() {
+        panic!("cannot unregister a PgHook instance while a hook is running");
+    }
+    let registered = &mut HOOKS.as_mut()?.registered;
+    let position = registered.iter().position(|registered| registered.id == id)?;
+    Some(&mut *registered.remove(position).hook)
+}
 
+/// Start keeping track of the registered hooks, which happens when the first `PgHook` instance is
+/// registered, before any hook points are installed
+unsafe fn register_xact_callbacks() -> Hooks {
     unsafe extern "C" fn xact_callback(event: pg_sys::XactEvent, _: void_mut_ptr) {
         match event {
             pg_sys::XactEvent_XACT_EVENT_ABORT => {
-                crate::guard(|| HOOKS.as_mut().unwrap().current_hook.abort());
+                // no hook can still be running once the whole transaction has aborted
+                CHAIN_POSITION = None;
+                SUBXACT_CHAIN_POSITIONS.clear();
+                crate::guard(|| {
+                    for hook in registered_hooks() {
+                        (*hook).abort();
+                    }
+                });
             }
             pg_sys::XactEvent_XACT_EVENT_PRE_COMMIT => {
-                crate::guard(|| HOOKS.as_mut().unwrap().current_hook.commit());
+                crate::guard(|| {
+                    for hook in registered_hooks() {
+                        (*hook).commit();
+                    }
+                });
+            }
+            _ => { /* noop */ }
+        }
+    }
+
+    unsafe extern "C" fn subxact_callback(
+        event: pg_sys::SubXactEvent,
+        subid: pg_sys::SubTransactionId,
+        _: pg_sys::SubTransactionId,
+        _: void_mut_ptr,
+    ) {
+        match event {
+            pg_sys::SubXactEvent_SUBXACT_EVENT_START_SUB => {
+                SUBXACT_CHAIN_POSITIONS.push((subid, CHAIN_POSITION));
+            }
+            pg_sys::SubXactEvent_SUBXACT_EVENT_COMMIT_SUB
+            | pg_sys::SubXactEvent_SUBXACT_EVENT_ABORT_SUB => {
+                // subtransactions started before our hooks were installed aren't in the list
+                if let Some(index) =
+                    SUBXACT_CHAIN_POSITIONS.iter().rposition(|(started, _)| *started == subid)
+                {
+                    let (_, position) = SUBXACT_CHAIN_POSITIONS.remove(index);
+                    SUBXACT_CHAIN_POSITIONS.truncate(index);
+                    if event == pg_sys::SubXactEvent_SUBXACT_EVENT_ABORT_SUB {
+                        CHAIN_POSITION = position;
+                    }
+                }
             }
             _ => { /* noop */ }
         }
     }
 
     pg_sys::RegisterXactCallback(Some(xact_callback), std::ptr::null_mut());
+    pg_sys::RegisterSubXactCallback(Some(subxact_callback), std::ptr::null_mut());
+
+    Hooks {
+        registered: Vec::new(),
+        next_id: 0,
+        installed: HookPoints::NONE,
+        prev_executor_start_hook: None,
+        prev_executor_run_hook: None,
+        prev_executor_finish_hook: None,
+        prev_executor_end_hook: None,
+        prev_executor_check_perms_hook: None,
+        prev_process_utility_hook: None,
+        prev_planner_hook: None,
+        prev_post_parse_analyze_hook: None,
+        prev_emit_log_hook: None,
+        prev_client_authentication_hook: None,
+        prev_object_access_hook: None,
+        prev_get_relation_info_hook: None,
+        prev_set_rel_pathlist_hook: None,
+        prev_set_join_pathlist_hook: None,
+    }
+}
+
+impl Hooks {
+    /// Install our hooks in place of whatever hooks are currently installed at each of `points`
+    /// that isn't already ours.  Once installed, they stay installed even if every instance that
+    /// asked for them is later unregistered.
+    unsafe fn install(&mut self, points: HookPoints) {
+        macro_rules! install {
+            ($point:ident, $prev:ident, $hook:expr, $ours:expr $(, $standard:expr)?) => {
+                if points.$point && !self.installed.$point {
+                    self.installed.$point = true;
+                    self.$prev = $hook.replace($ours)$(.or(Some($standard)))?;
+                }
+            };
+        }
+
+        install!(
+            executor_start,
+            prev_executor_start_hook,
+            pg_sys::ExecutorStart_hook,
+            pgx_executor_start,
+            pgx_standard_executor_start_wrapper
+        );
+        install!(
+            executor_run,
+            prev_executor_run_hook,
+            pg_sys::ExecutorRun_hook,
+            pgx_executor_run,
+            pgx_standard_executor_run_wrapper
+        );
+        install!(
+            executor_finish,
+            prev_executor_finish_hook,
+            pg_sys::ExecutorFinish_hook,
+            pgx_executor_finish,
+            pgx_standard_executor_finish_wrapper
+        );
+        install!(
+            executor_end,
+            prev_executor_end_hook,
+            pg_sys::ExecutorEnd_hook,
+            pgx_executor_end,
+            pgx_standard_executor_end_wrapper
+        );
+        install!(
+            executor_check_perms,
+            prev_executor_check_perms_hook,
+            pg_sys::ExecutorCheckPerms_hook,
+            pgx_executor_check_perms,
+            pgx_standard_executor_check_perms_wrapper
+        );
+        install!(
+            process_utility,
+            prev_process_utility_hook,
+            pg_sys::ProcessUtility_hook,
+            pgx_process_utility,
+            pgx_standard_process_utility_wrapper
+        );
+        install!(
+            planner,
+            prev_planner_hook,
+            pg_sys::planner_hook,
+            pgx_planner,
+            pgx_standard_planner_wrapper
+        );
+        // there's no standard post-parse-analysis function to fall back to
+        install!(
+            post_parse_analyze,
+            prev_post_parse_analyze_hook,
+            pg_sys::post_parse_analyze_hook,
+            pgx_post_parse_analyze
+        );
+        install!(emit_log, prev_emit_log_hook, pg_sys::emit_log_hook, pgx_emit_log);
+        install!(
+            client_authentication,
+            prev_client_authentication_hook,
+            pg_sys::ClientAuthentication_hook,
+            pgx_client_authentication
+        );
+        install!(
+            object_access,
+            prev_object_access_hook,
+            pg_sys::object_access_hook,
+            pgx_object_access
+        );
+        // the planner does nothing more itself for any of these
+        install!(
+            get_relation_info,
+            prev_get_relation_info_hook,
+            pg_sys::get_relation_info_hook,
+            pgx_get_relation_info
+        );
+        install!(
+            set_rel_pathlist,
+            prev_set_rel_pathlist_hook,
+            pg_sys::set_rel_pathlist_hook,
+            pgx_set_rel_pathlist
+        );
+        install!(
+            set_join_pathlist,
+            prev_set_join_pathlist_hook,
+            pg_sys::set_join_pathlist_hook,
+            pgx_set_join_pathlist
+        );
+    }
+}
+
+/// A snapshot of the registered hooks, in order, which stays valid even if a hook registers
+/// another one while we're iterating
+unsafe fn registered_hooks() -> Vec<*mut dyn PgHooks> {
+    HOOKS.as_ref().unwrap().registered.iter().map(|registered| registered.hook).collect()
+}
+
+/// Starts a new trip down the chain of hooks when a hook point is entered, even if we're already
+/// somewhere in the chain for another hook point, and puts things back when it's dropped
+struct ChainStart(Option<usize>);
+
+impl ChainStart {
+    unsafe fn new() -> Self {
+        ChainStart(CHAIN_POSITION.take())
+    }
+}
+
+impl Drop for ChainStart {
+    fn drop(&mut self) {
+        unsafe {
+            CHAIN_POSITION = self.0;
+        }
+    }
+}
+
+/// The hook at some position in the chain, which is the currently running one for as long as
+/// this is alive
+struct ChainedHook {
+    hook: *mut dyn PgHooks,
+    prev_position: Option<usize>,
+}
+
+impl Deref for ChainedHook {
+    type Target = dyn PgHooks;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.hook }
+    }
+}
+
+impl DerefMut for ChainedHook {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.hook }
+    }
+}
+
+impl Drop for ChainedHook {
+    fn drop(&mut self) {
+        unsafe {
+            CHAIN_POSITION = self.prev_position;
+        }
+    }
+}
+
+/// The hook after the one that is currently running, or `None` if we've reached the end of the
+/// chain and should call the hook that was installed before ours
+unsafe fn next_hook() -> Option<ChainedHook> {
+    let position = CHAIN_POSITION.map_or(0, |position| position + 1);
+    let hook = HOOKS.as_ref().unwrap().registered.get(position)?.hook;
+    Some(ChainedHook { hook, prev_position: CHAIN_POSITION.replace(position) })
 }
 
 #[pg_guard]
 unsafe extern "C" fn pgx_executor_start(query_desc: *mut pg_sys::QueryDesc, eflags: i32) {
     fn prev(query_desc: PgBox<pg_sys::QueryDesc>, eflags: i32) -> HookResult<()> {
         unsafe {
-            (HOOKS.as_mut().unwrap().prev_executor_start_hook.as_ref().unwrap())(
-                query_desc.into_pg(),
-                eflags,
-            )
+            match next_hook() {
+                Some(mut hook) => hook.executor_start(query_desc, eflags, prev),
+                None => {
+                    (HOOKS.as_mut().unwrap().prev_executor_start_hook.as_ref().unwrap())(
+                        query_desc.into_pg(),
+                        eflags,
+                    );
+                    HookResult::new(())
+                }
+            }
         }
-        HookResult::new(())
     }
-    let hook = &mut HOOKS.as_mut().unwrap().current_hook;
-    hook.executor_start(PgBox::from_pg(query_desc), eflags, prev);
+    let _chain = ChainStart::new();
+    prev(PgBox::from_pg(query_desc), eflags);
 }
 
 #[pg_guard]
@@ -234,43 +806,62 @@ unsafe extern "C" fn pgx_executor_run(
         execute_once: bool,
     ) -> HookResult<()> {
         unsafe {
-            (HOOKS.as_mut().unwrap().prev_executor_run_hook.as_ref().unwrap())(
-                query_desc.into_pg(),
-                direction,
-                count,
-                execute_once,
-            )
+            match next_hook() {
+                Some(mut hook) => {
+                    hook.executor_run(query_desc, direction, count, execute_once, prev)
+                }
+                None => {
+                    (HOOKS.as_mut().unwrap().prev_executor_run_hook.as_ref().unwrap())(
+                        query_desc.into_pg(),
+                        direction,
+                        count,
+                        execute_once,
+                    );
+                    HookResult::new(())
+                }
+            }
         }
-        HookResult::new(())
     }
-    let hook = &mut HOOKS.as_mut().unwrap().current_hook;
-    hook.executor_run(PgBox::from_pg(query_desc), direction, count, execute_once, prev);
+    let _chain = ChainStart::new();
+    prev(PgBox::from_pg(query_desc), direction, count, execute_once);
 }
 
 #[pg_guard]
 unsafe extern "C" fn pgx_executor_finish(query_desc: *mut pg_sys::QueryDesc) {
     fn prev(query_desc: PgBox<pg_sys::QueryDesc>) -> HookResult<()> {
         unsafe {
-            (HOOKS.as_mut().unwrap().prev_executor_finish_hook.as_ref().unwrap())(
-                query_desc.into_pg(),
-            )
+            match next_hook() {
+                Some(mut hook) => hook.executor_finish(query_desc, prev),
+                None => {
+                    (HOOKS.as_mut().unwrap().prev_executor_finish_hook.as_ref().unwrap())(
+                        query_desc.into_pg(),
+                    );
+                    HookResult::new(())
+                }
+            }
         }
-        HookResult::new(())
     }
-    let hook = &mut HOOKS.as_mut().unwrap().current_hook;
-    hook.executor_finish(PgBox::from_pg(query_desc), prev);
+    let _chain = ChainStart::new();
+    prev(PgBox::from_pg(query_desc));
 }
 
 #[pg_guard]
 unsafe extern "C" fn pgx_executor_end(query_desc: *mut pg_sys::QueryDesc) {
     fn prev(query_desc: PgBox<pg_sys::QueryDesc>) -> HookResult<()> {
         unsafe {
-            (HOOKS.as_mut().unwrap().prev_executor_end_hook.as_ref().unwrap())(query_desc.into_pg())
+            match next_hook() {
+                Some(mut hook) => hook.executor_end(query_desc, prev),
+                None => {
+                    (HOOKS.as_mut().unwrap().prev_executor_end_hook.as_ref().unwrap())(
+                        query_desc.into_pg(),
+                    );
+                    HookResult::new(())
+                }
+            }
         }
-        HookResult::new(())
     }
-    let hook = &mut HOOKS.as_mut().unwrap().current_hook;
-    hook.executor_end(PgBox::from_pg(query_desc), prev);
+    let _chain = ChainStart::new();
+    prev(PgBox::from_pg(query_desc));
 }
 
 #[pg_guard]
@@ -282,15 +873,24 @@ unsafe extern "C" fn pgx_executor_check_perms(
         range_table: PgList<*mut pg_sys::RangeTblEntry>,
         ereport_on_violation: bool,
     ) -> HookResult<bool> {
-        HookResult::new(unsafe {
-            (HOOKS.as_mut().unwrap().prev_executor_check_perms_hook.as_ref().unwrap())(
-                range_table.into_pg(),
-                ereport_on_violation,
-            )
-        })
+        unsafe {
+            match next_hook() {
+                Some(mut hook) => {
+                    hook.executor_check_perms(range_table, ereport_on_violation, prev)
+                }
+                None => HookResult::new((HOOKS
+                    .as_mut()
+                    .unwrap()
+                    .prev_executor_check_perms_hook
+                    .as_ref()
+                    .unwrap())(
+                    range_table.into_pg(), ereport_on_violation
+                )),
+            }
+        }
     }
-    let hook = &mut HOOKS.as_mut().unwrap().current_hook;
-    hook.executor_check_perms(PgList::from_pg(range_table), ereport_on_violation, prev).inner
+    let _chain = ChainStart::new();
+    prev(PgList::from_pg(range_table), ereport_on_violation).inner
 }
 
 #[cfg(any(feature = "pg10", feature = "pg11", feature = "pg12", feature = "pg13"))]
@@ -314,21 +914,39 @@ unsafe extern "C" fn pgx_process_utility(
         dest: PgBox<pg_sys::DestReceiver>,
         completion_tag: *mut pg_sys::QueryCompletion,
     ) -> HookResult<()> {
-        HookResult::new(unsafe {
-            (HOOKS.as_mut().unwrap().prev_process_utility_hook.as_ref().unwrap())(
-                pstmt.into_pg(),
-                query_string.as_ptr(),
-                context,
-                params.into_pg(),
-                query_env.into_pg(),
-                dest.into_pg(),
-                completion_tag,
-            )
-        })
-    }
-
-    let hook = &mut HOOKS.as_mut().unwrap().current_hook;
-    hook.process_utility_hook(
+        unsafe {
+            match next_hook() {
+                Some(mut hook) => hook.process_utility_hook(
+                    pstmt,
+                    query_string,
+                    _read_only_tree,
+                    context,
+                    params,
+                    query_env,
+                    dest,
+                    completion_tag,
+                    prev,
+                ),
+                None => HookResult::new((HOOKS
+                    .as_mut()
+                    .unwrap()
+                    .prev_process_utility_hook
+                    .as_ref()
+                    .unwrap())(
+                    pstmt.into_pg(),
+                    query_string.as_ptr(),
+                    context,
+                    params.into_pg(),
+                    query_env.into_pg(),
+                    dest.into_pg(),
+                    completion_tag,
+                )),
+            }
+        }
+    }
+
+    let _chain = ChainStart::new();
+    prev(
         PgBox::from_pg(pstmt),
         std::ffi::CStr::from_ptr(query_string),
         None,
@@ -337,7 +955,6 @@ unsafe extern "C" fn pgx_process_utility(
         PgBox::from_pg(query_env),
         PgBox::from_pg(dest),
         completion_tag,
-        prev,
     )
     .inner
 }
@@ -363,22 +980,40 @@ unsafe extern "C" fn pgx_process_utility(
         dest: PgBox<pg_sys::DestReceiver>,
         completion_tag: *mut pg_sys::QueryCompletion,
     ) -> HookResult<()> {
-        HookResult::new(unsafe {
-            (HOOKS.as_mut().unwrap().prev_process_utility_hook.as_ref().unwrap())(
-                pstmt.into_pg(),
-                query_string.as_ptr(),
-                read_only_tree.unwrap(),
-                context,
-                params.into_pg(),
-                query_env.into_pg(),
-                dest.into_pg(),
-                completion_tag,
-            )
-        })
-    }
-
-    let hook = &mut HOOKS.as_mut().unwrap().current_hook;
-    hook.process_utility_hook(
+        unsafe {
+            match next_hook() {
+                Some(mut hook) => hook.process_utility_hook(
+                    pstmt,
+                    query_string,
+                    read_only_tree,
+                    context,
+                    params,
+                    query_env,
+                    dest,
+                    completion_tag,
+                    prev,
+                ),
+                None => HookResult::new((HOOKS
+                    .as_mut()
+                    .unwrap()
+                    .prev_process_utility_hook
+                    .as_ref()
+                    .unwrap())(
+                    pstmt.into_pg(),
+                    query_string.as_ptr(),
+                    read_only_tree.unwrap(),
+                    context,
+                    params.into_pg(),
+                    query_env.into_pg(),
+                    dest.into_pg(),
+                    completion_tag,
+                )),
+            }
+        }
+    }
+
+    let _chain = ChainStart::new();
+    prev(
         PgBox::from_pg(pstmt),
         std::ffi::CStr::from_ptr(query_string),
         Some(read_only_tree),
@@ -387,7 +1022,6 @@ unsafe extern "C" fn pgx_process_utility(
         PgBox::from_pg(query_env),
         PgBox::from_pg(dest),
         completion_tag,
-        prev,
     )
     .inner
 }
@@ -422,40 +1056,293 @@ unsafe extern "C" fn pgx_planner_impl(
 ) -> *mut pg_sys::PlannedStmt {
     fn prev(
         parse: PgBox<pg_sys::Query>,
-        #[allow(unused_variables)] query_string: *const ::std::os::raw::c_char,
+        query_string: *const ::std::os::raw::c_char,
         cursor_options: i32,
         bound_params: PgBox<pg_sys::ParamListInfoData>,
     ) -> HookResult<*mut pg_sys::PlannedStmt> {
-        HookResult::new(unsafe {
-            #[cfg(any(feature = "pg10", feature = "pg11", feature = "pg12"))]
-            {
-                (HOOKS.as_mut().unwrap().prev_planner_hook.as_ref().unwrap())(
-                    parse.into_pg(),
-                    cursor_options,
-                    bound_params.into_pg(),
-                )
+        unsafe {
+            if let Some(mut hook) = next_hook() {
+                return hook.planner(parse, query_string, cursor_options, bound_params, prev);
             }
 
-            #[cfg(any(feature = "pg13", feature = "pg14"))]
-            {
-                (HOOKS.as_mut().unwrap().prev_planner_hook.as_ref().unwrap())(
-                    parse.into_pg(),
-                    query_string,
-                    cursor_options,
-                    bound_params.into_pg(),
-                )
+            HookResult::new({
+                #[cfg(any(feature = "pg10", feature = "pg11", feature = "pg12"))]
+                {
+                    (HOOKS.as_mut().unwrap().prev_planner_hook.as_ref().unwrap())(
+                        parse.into_pg(),
+                        cursor_options,
+                        bound_params.into_pg(),
+                    )
+                }
+
+                #[cfg(any(feature = "pg13", feature = "pg14"))]
+                {
+                    (HOOKS.as_mut().unwrap().prev_planner_hook.as_ref().unwrap())(
+                        parse.into_pg(),
+                        query_string,
+                        cursor_options,
+                        bound_params.into_pg(),
+                    )
+                }
+            })
+        }
+    }
+    let _chain = ChainStart::new();
+    prev(PgBox::from_pg(parse), query_string, cursor_options, PgBox::from_pg(bound_params)).inner
+}
+
+#[pg_guard]
+unsafe extern "C" fn pgx_get_relation_info(
+    root: *mut pg_sys::PlannerInfo,
+    relation_oid: pg_sys::Oid,
+    inhparent: bool,
+    rel: *mut pg_sys::RelOptInfo,
+) {
+    fn prev(
+        root: PlannerInfoRef<'_>,
+        relation_oid: pg_sys::Oid,
+        inhparent: bool,
+        rel: RelOptInfoRef<'_>,
+    ) -> HookResult<()> {
+        unsafe {
+            if let Some(mut hook) = next_hook() {
+                return hook.get_relation_info(root, relation_oid, inhparent, rel, prev);
+            }
+
+            if let Some(prev_hook) = HOOKS.as_mut().unwrap().prev_get_relation_info_hook {
+                prev_hook(root.as_ptr(), relation_oid, inhparent, rel.as_ptr());
             }
-        })
+            HookResult::new(())
+        }
     }
-    let hook = &mut HOOKS.as_mut().unwrap().current_hook;
-    hook.planner(
-        PgBox::from_pg(parse),
-        query_string,
-        cursor_options,
-        PgBox::from_pg(bound_params),
-        prev,
-    )
-    .inner
+    let _chain = ChainStart::new();
+    prev(PlannerInfoRef::from_pg(root), relation_oid, inhparent, RelOptInfoRef::from_pg(rel));
+}
+
+#[pg_guard]
+unsafe extern "C" fn pgx_set_rel_pathlist(
+    root: *mut pg_sys::PlannerInfo,
+    rel: *mut pg_sys::RelOptInfo,
+    rti: pg_sys::Index,
+    rte: *mut pg_sys::RangeTblEntry,
+) {
+    fn prev(
+        root: PlannerInfoRef<'_>,
+        rel: RelOptInfoRef<'_>,
+        rti: pg_sys::Index,
+        rte: RangeTblEntryRef<'_>,
+    ) -> HookResult<()> {
+        unsafe {
+            if let Some(mut hook) = next_hook() {
+                return hook.set_rel_pathlist(root, rel, rti, rte, prev);
+            }
+
+            if let Some(prev_hook) = HOOKS.as_mut().unwrap().prev_set_rel_pathlist_hook {
+                prev_hook(root.as_ptr(), rel.as_ptr(), rti, rte.as_ptr());
+            }
+            HookResult::new(())
+        }
+    }
+    let _chain = ChainStart::new();
+    prev(
+        PlannerInfoRef::from_pg(root),
+        RelOptInfoRef::from_pg(rel),
+        rti,
+        RangeTblEntryRef::from_pg(rte),
+    );
+}
+
+#[pg_guard]
+unsafe extern "C" fn pgx_set_join_pathlist(
+    root: *mut pg_sys::PlannerInfo,
+    joinrel: *mut pg_sys::RelOptInfo,
+    outerrel: *mut pg_sys::RelOptInfo,
+    innerrel: *mut pg_sys::RelOptInfo,
+    jointype: pg_sys::JoinType,
+    extra: *mut pg_sys::JoinPathExtraData,
+) {
+    fn prev(
+        root: PlannerInfoRef<'_>,
+        joinrel: RelOptInfoRef<'_>,
+        outerrel: RelOptInfoRef<'_>,
+        innerrel: RelOptInfoRef<'_>,
+        jointype: pg_sys::JoinType,
+        extra: PgBox<pg_sys::JoinPathExtraData>,
+    ) -> HookResult<()> {
+        unsafe {
+            if let Some(mut hook) = next_hook() {
+                return hook
+                    .set_join_pathlist(root, joinrel, outerrel, innerrel, jointype, extra, prev);
+            }
+
+            if let Some(prev_hook) = HOOKS.as_mut().unwrap().prev_set_join_pathlist_hook {
+                prev_hook(
+                    root.as_ptr(),
+                    joinrel.as_ptr(),
+                    outerrel.as_ptr(),
+                    innerrel.as_ptr(),
+                    jointype,
+                    extra.into_pg(),
+                );
+            }
+            HookResult::new(())
+        }
+    }
+    let _chain = ChainStart::new();
+    prev(
+        PlannerInfoRef::from_pg(root),
+        RelOptInfoRef::from_pg(joinrel),
+        RelOptInfoRef::from_pg(outerrel),
+        RelOptInfoRef::from_pg(innerrel),
+        jointype,
+        PgBox::from_pg(extra),
+    );
+}
+
+#[cfg(any(feature = "pg10", feature = "pg11", feature = "pg12", feature = "pg13"))]
+#[pg_guard]
+unsafe extern "C" fn pgx_post_parse_analyze(
+    parse_state: *mut pg_sys::ParseState,
+    query: *mut pg_sys::Query,
+) {
+    pgx_post_parse_analyze_impl(parse_state, query, std::ptr::null_mut())
+}
+
+#[cfg(feature = "pg14")]
+#[pg_guard]
+unsafe extern "C" fn pgx_post_parse_analyze(
+    parse_state: *mut pg_sys::ParseState,
+    query: *mut pg_sys::Query,
+    jumble_state: *mut pg_sys::JumbleState,
+) {
+    pgx_post_parse_analyze_impl(parse_state, query, jumble_state)
+}
+
+unsafe fn pgx_post_parse_analyze_impl(
+    parse_state: *mut pg_sys::ParseState,
+    query: *mut pg_sys::Query,
+    jumble_state: *mut JumbleState,
+) {
+    fn prev(
+        parse_state: PgBox<pg_sys::ParseState>,
+        query: PgBox<pg_sys::Query>,
+        jumble_state: Option<PgBox<JumbleState>>,
+    ) -> HookResult<()> {
+        unsafe {
+            if let Some(mut hook) = next_hook() {
+                return hook.post_parse_analyze(parse_state, query, jumble_state, prev);
+            }
+
+            #[allow(unused_variables)]
+            let jumble_state = jumble_state.map_or(std::ptr::null_mut(), |state| state.into_pg());
+            if let Some(prev_hook) = HOOKS.as_mut().unwrap().prev_post_parse_analyze_hook {
+                #[cfg(any(
+                    feature = "pg10",
+                    feature = "pg11",
+                    feature = "pg12",
+                    feature = "pg13"
+                ))]
+                prev_hook(parse_state.into_pg(), query.into_pg());
+
+                #[cfg(feature = "pg14")]
+                prev_hook(parse_state.into_pg(), query.into_pg(), jumble_state);
+            }
+            HookResult::new(())
+        }
+    }
+    let _chain = ChainStart::new();
+    let jumble_state =
+        if jumble_state.is_null() { None } else { Some(PgBox::from_pg(jumble_state)) };
+    prev(PgBox::from_pg(parse_state), PgBox::from_pg(query), jumble_state);
+}
+
+/// Are we in the middle of [`pgx_emit_log`]?
+static mut IN_EMIT_LOG: bool = false;
+
+#[pg_guard]
+unsafe extern "C" fn pgx_emit_log(error_data: *mut pg_sys::ErrorData) {
+    fn prev(error_data: ErrorDataRef<'_>) -> HookResult<()> {
+        unsafe {
+            if let Some(mut hook) = next_hook() {
+                return hook.emit_log(error_data, prev);
+            }
+
+            if let Some(prev_hook) = HOOKS.as_mut().unwrap().prev_emit_log_hook {
+                prev_hook(error_data.as_ptr());
+            }
+            HookResult::new(())
+        }
+    }
+
+    // a message logged from inside a hook skips straight past them all
+    if IN_EMIT_LOG {
+        if let Some(prev_hook) = HOOKS.as_mut().unwrap().prev_emit_log_hook {
+            prev_hook(error_data);
+        }
+        return;
+    }
+
+    struct InEmitLog;
+    impl Drop for InEmitLog {
+        fn drop(&mut self) {
+            unsafe {
+                IN_EMIT_LOG = false;
+            }
+        }
+    }
+
+    IN_EMIT_LOG = true;
+    let _in_emit_log = InEmitLog;
+    let _chain = ChainStart::new();
+    prev(ErrorDataRef::from_pg(error_data));
+}
+
+#[pg_guard]
+unsafe extern "C" fn pgx_client_authentication(port: *mut pg_sys::Port, status: i32) {
+    fn prev(port: PortRef<'_>, status: i32) -> HookResult<()> {
+        unsafe {
+            if let Some(mut hook) = next_hook() {
+                return hook.client_authentication(port, status, prev);
+            }
+
+            if let Some(prev_hook) = HOOKS.as_mut().unwrap().prev_client_authentication_hook {
+                prev_hook(port.as_ptr(), status);
+            }
+            HookResult::new(())
+        }
+    }
+    let _chain = ChainStart::new();
+    prev(PortRef::from_pg(port), status);
+}
+
+#[pg_guard]
+unsafe extern "C" fn pgx_object_access(
+    access: pg_sys::ObjectAccessType,
+    class_id: pg_sys::Oid,
+    object_id: pg_sys::Oid,
+    sub_id: i32,
+    arg: void_mut_ptr,
+) {
+    fn prev(access: ObjectAccess<'_>) -> HookResult<()> {
+        unsafe {
+            if let Some(mut hook) = next_hook() {
+                return hook.object_access(access, prev);
+            }
+
+            if let Some(prev_hook) = HOOKS.as_mut().unwrap().prev_object_access_hook {
+                prev_hook(
+                    access.access,
+                    access.class_id,
+                    access.object_id,
+                    access.sub_id,
+                    access.arg,
+                );
+            }
+            HookResult::new(())
+        }
+    }
+    let _chain = ChainStart::new();
+    prev(ObjectAccess::from_pg(access, class_id, object_id, sub_id, arg));
 }
 
 #[pg_guard]
@@ -0,0 +1,928 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+
+//! Index access methods, which add new kinds of indexes to Postgres
+//!
+//! An access method implements [`IndexAmRoutine`] in an `impl` block marked `#[pg_index_am]`,
+//! which adds its handler function, the `CREATE ACCESS METHOD ... TYPE INDEX` statement that uses
+//! it, and a `CREATE OPERATOR CLASS` statement for each of its
+//! [`IndexAmRoutine::OPERATOR_CLASSES`], to the extension's schema.  The associated functions
+//! build and maintain indexes of the access method, and the methods scan one of them, on an
+//! instance of the access method made by [`IndexAmRoutine::begin_scan`] for each scan.
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//! use std::sync::Mutex;
+//!
+//! /// Every indexed `int4`, as `(index, key, heap tid)`
+//! static ENTRIES: Mutex<Vec<(pg_sys::Oid, i32, pg_sys::ItemPointerData)>> = Mutex::new(Vec::new());
+//!
+//! struct ListIndex {
+//!     matches: Vec<pg_sys::ItemPointerData>,
+//! }
+//!
+//! #[pg_index_am]
+//! impl IndexAmRoutine for ListIndex {
+//!     const NAME: &'static str = "list";
+//!     const CAPABILITIES: IndexAmCapabilities =
+//!         IndexAmCapabilities { strategies: 1, ..IndexAmCapabilities::DEFAULT };
+//!     const OPERATOR_CLASSES: &'static [OperatorClass] = &[OperatorClass {
+//!         name: "int4_list_ops",
+//!         for_type: "int4",
+//!         operators: &[(1, "=")],
+//!         functions: &[],
+//!     }];
+//!
+//!     fn build(
+//!         heap: &PgRelation,
+//!         index: &PgRelation,
+//!         index_info: &mut pg_sys::IndexInfo,
+//!     ) -> pg_sys::IndexBuildResult {
+//!         let mut index_tuples = 0.0;
+//!         let heap_tuples = index_build_heap_scan(heap, index, index_info, |tid, values, _| {
+//!             Self::insert_entry(index, values, tid);
+//!             index_tuples += 1.0;
+//!         });
+//!         pg_sys::IndexBuildResult { heap_tuples, index_tuples }
+//!     }
+//!
+//!     fn insert(
+//!         index: &PgRelation,
+//!         values: &[Option<pg_sys::Datum>],
+//!         heap_tid: pg_sys::ItemPointerData,
+//!         _heap: &PgRelation,
+//!         _check_unique: pg_sys::IndexUniqueCheck,
+//!         _index_info: &mut pg_sys::IndexInfo,
+//!     ) -> bool {
+//!         Self::insert_entry(index, values, heap_tid);
+//!         false
+//!     }
+//!
+//!     fn begin_scan(_scan: &mut IndexScanDescRef<'_>) -> Self {
+//!         ListIndex { matches: Vec::new() }
+//!     }
+//!
+//!     fn rescan(
+//!         &mut self,
+//!         scan: &mut IndexScanDescRef<'_>,
+//!         keys: &[ScanKey],
+//!         _orderbys: &[ScanKey],
+//!     ) {
+//!         let index = scan.index_relation().oid();
+//!         let key = keys.first().and_then(|key| key.argument_as::<i32>().ok().flatten());
+//!         self.matches = ENTRIES
+//!             .lock()
+//!             .unwrap()
+//!             .iter()
+//!             .filter(|entry| entry.0 == index && Some(entry.1) == key)
+//!             .map(|entry| entry.2)
+//!             .collect();
+//!     }
+//!
+//!     fn get_tuple(
+//!         &mut self,
+//!         _scan: &mut IndexScanDescRef<'_>,
+//!         _direction: pg_sys::ScanDirection,
+//!     ) -> Option<pg_sys::ItemPointerData> {
+//!         self.matches.pop()
+//!     }
+//! }
+//!
+//! impl ListIndex {
+//!     fn insert_entry(
+//!         index: &PgRelation,
+//!         values: &[Option<pg_sys::Datum>],
+//!         tid: pg_sys::ItemPointerData,
+//!     ) {
+//!         if let Some(key) = values[0].and_then(|datum| unsafe { i32::from_datum(datum, false) }) {
+//!             ENTRIES.lock().unwrap().push((index.oid(), key, tid));
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! With the extension installed, the access method is used like any other:
+//!
+//! ```sql
+//! CREATE INDEX things_id ON things USING list (id);
+//! ```
+use crate::log::raise;
+use crate::{
+    pg_sys, FromDatum, IntoDatum, PgBox, PgMemoryContexts, PgRelation, PgSqlErrorCode,
+    PlannerInfoRef, TryFromDatumError,
+};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::os::raw::{c_int, c_void};
+
+pub use crate::utils::sql_entity_graph::OperatorClassEntity as OperatorClass;
+
+/// An index access method
+///
+/// The associated functions build, maintain, and cost indexes of the access method, and the
+/// methods are called on an instance of it made by [`IndexAmRoutine::begin_scan`] for each scan
+/// of one of them.
+pub trait IndexAmRoutine: Sized + 'static {
+    /// The access method's name, as given to `CREATE ACCESS METHOD`
+    const NAME: &'static str;
+
+    /// What indexes of the access method can do, which the planner and executor consult
+    const CAPABILITIES: IndexAmCapabilities = IndexAmCapabilities::DEFAULT;
+
+    /// The operator classes to create as the default ones of the access method for their types
+    const OPERATOR_CLASSES: &'static [OperatorClass] = &[];
+
+    /// Build a new index of `heap`, returning how many heap tuples were scanned and how many
+    /// index tuples were made from them
+    ///
+    /// [`index_build_heap_scan`] scans the heap for the values to index.
+    fn build(
+        heap: &PgRelation,
+        index: &PgRelation,
+        index_info: &mut pg_sys::IndexInfo,
+    ) -> pg_sys::IndexBuildResult;
+
+    /// Write an empty index into the initialization fork of an unlogged index
+    ///
+    /// The default raises an error, so the access method can't make unlogged indexes.
+    fn build_empty(_index: &PgRelation) {
+        raise(
+            PgSqlErrorCode::ERRCODE_FEATURE_NOT_SUPPORTED,
+            &format!("access method \"{}\" does not support unlogged indexes", Self::NAME),
+        )
+    }
+
+    /// Add an entry for the heap tuple `heap_tid`, whose indexed columns are `values`, to the
+    /// index, returning whether it's known to be unique when `check_unique` is
+    /// `UNIQUE_CHECK_PARTIAL` (and otherwise, anything)
+    fn insert(
+        index: &PgRelation,
+        values: &[Option<pg_sys::Datum>],
+        heap_tid: pg_sys::ItemPointerData,
+        heap: &PgRelation,
+        check_unique: pg_sys::IndexUniqueCheck,
+        index_info: &mut pg_sys::IndexInfo,
+    ) -> bool;
+
+    /// Remove the entries of the heap tuples that `dead_tuples` says are dead from the index, and
+    /// count them in `stats`
+    ///
+    /// The default removes nothing, for access methods that don't store anything.
+    fn bulk_delete(
+        _info: &pg_sys::IndexVacuumInfo,
+        _stats: &mut pg_sys::IndexBulkDeleteResult,
+        _dead_tuples: &DeadTuples,
+    ) {
+    }
+
+    /// Clean up after a `VACUUM`, or an `ANALYZE` when `info.analyze_only`, returning the index's
+    /// statistics, which `VACUUM` records in `pg_class`
+    ///
+    /// `stats` are those [`IndexAmRoutine::bulk_delete`] filled in, or `None` if it wasn't called.
+    /// Returning `None` reports nothing, leaving the index's `pg_class` statistics as they were.
+    /// New statistics can be made with
+    /// `PgBox::<pg_sys::IndexBulkDeleteResult>::alloc0().into_pg_boxed()`, and the default
+    /// returns `stats` as they are.
+    fn vacuum_cleanup(
+        _info: &pg_sys::IndexVacuumInfo,
+        stats: Option<PgBox<pg_sys::IndexBulkDeleteResult>>,
+    ) -> Option<PgBox<pg_sys::IndexBulkDeleteResult>> {
+        stats
+    }
+
+    /// Estimate the costs of scanning the index as `path` describes
+    ///
+    /// The default is Postgres' [`generic_cost_estimate`].
+    fn cost_estimate(
+        root: &PlannerInfoRef<'_>,
+        path: &mut pg_sys::IndexPath,
+        loop_count: f64,
+    ) -> IndexCosts {
+        generic_cost_estimate(root, path, loop_count)
+    }
+
+    /// Parse and, if `validate`, check the `WITH (...)` options given to `CREATE INDEX`,
+    /// returning them as the `bytea` that is kept in the index's relcache entry (or null)
+    ///
+    /// The default rejects all options.
+    fn options(reloptions: pg_sys::Datum, validate: bool) -> *mut pg_sys::bytea {
+        if validate && !reloptions.is_null() {
+            raise(
+                PgSqlErrorCode::ERRCODE_INVALID_PARAMETER_VALUE,
+                &format!("access method \"{}\" does not support options", Self::NAME),
+            )
+        }
+        std::ptr::null_mut()
+    }
+
+    /// Check the operator class `opclassoid` of the access method, returning whether it's
+    /// valid, after reporting what isn't with `INFO` messages
+    fn validate(_opclassoid: pg_sys::Oid) -> bool {
+        true
+    }
+
+    /// Prepare to scan the index, before its keys are known
+    fn begin_scan(scan: &mut IndexScanDescRef<'_>) -> Self;
+
+    /// Start, or restart, the scan, with `keys` to match and `orderbys` to order the results by
+    fn rescan(&mut self, scan: &mut IndexScanDescRef<'_>, keys: &[ScanKey], orderbys: &[ScanKey]);
+
+    /// Return the heap tid of the next match of the scan in `direction`, or `None` when there are
+    /// no more
+    ///
+    /// Call [`IndexScanDescRef::set_recheck`] if the keys need to be checked against the heap
+    /// tuple.
+    fn get_tuple(
+        &mut self,
+        scan: &mut IndexScanDescRef<'_>,
+        direction: pg_sys::ScanDirection,
+    ) -> Option<pg_sys::ItemPointerData>;
+
+    /// Add all of the scan's matches to `bitmap`, returning how many there were
+    ///
+    /// The default adds each heap tid returned by [`IndexAmRoutine::get_tuple`].
+    fn get_bitmap(
+        &mut self,
+        scan: &mut IndexScanDescRef<'_>,
+        bitmap: &mut TidBitmapRef<'_>,
+    ) -> i64 {
+        let mut ntids = 0;
+        while let Some(tid) = self.get_tuple(scan, pg_sys::ScanDirection_ForwardScanDirection) {
+            bitmap.add(&[tid], scan.xs_recheck);
+            ntids += 1;
+        }
+        ntids
+    }
+
+    /// Finish the scan
+    fn end_scan(&mut self, _scan: &mut IndexScanDescRef<'_>) {}
+}
+
+/// The properties of an index access method, which are the flags and numbers of its
+/// `IndexAmRoutine`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexAmCapabilities {
+    /// How many operator strategies it has, or 0 if it doesn't have a fixed set
+    pub strategies: u16,
+    /// How many support functions it has
+    pub support: u16,
+    /// Whether it returns matches in the order of the indexed values
+    pub can_order: bool,
+    /// Whether it returns matches in the order of an operator's result
+    pub can_order_by_op: bool,
+    /// Whether scans can go backwards
+    pub can_backward: bool,
+    /// Whether it supports unique indexes
+    pub can_unique: bool,
+    /// Whether it supports indexes of more than one column
+    pub can_multicol: bool,
+    /// Whether scans can have no key for the first column
+    pub optional_key: bool,
+    /// Whether it handles `column = ANY(array)` keys itself
+    pub search_array: bool,
+    /// Whether it handles `IS NULL` and `IS NOT NULL` keys
+    pub search_nulls: bool,
+    /// Whether the index's values can be of a different type than the indexed columns
+    pub storage: bool,
+    /// Whether tables can be `CLUSTER`ed on its indexes
+    pub clusterable: bool,
+    /// Whether it takes its own predicate locks
+    pub predlocks: bool,
+    /// Whether it supports `INCLUDE` columns, which Postgres 10 doesn't have
+    pub can_include: bool,
+    /// The type of the index's values, if they're always the same type
+    pub key_type: pg_sys::Oid,
+    /// Whether it supports index scans, with [`IndexAmRoutine::get_tuple`]
+    pub index_scans: bool,
+    /// Whether it supports bitmap index scans, with [`IndexAmRoutine::get_bitmap`]
+    pub bitmap_scans: bool,
+}
+
+impl IndexAmCapabilities {
+    /// An access method of single column indexes that supports index and bitmap scans, and
+    /// nothing else
+    pub const DEFAULT: IndexAmCapabilities = IndexAmCapabilities {
+        strategies: 0,
+        support: 1,
+        can_order: false,
+        can_order_by_op: false,
+        can_backward: false,
+        can_unique: false,
+        can_multicol: false,
+        optional_key: false,
+        search_array: false,
+        search_nulls: false,
+        storage: false,
+        clusterable: false,
+        predlocks: false,
+        can_include: false,
+        key_type: pg_sys::InvalidOid,
+        index_scans: true,
+        bitmap_scans: true,
+    };
+}
+
+impl Default for IndexAmCapabilities {
+    fn default() -> Self {
+        IndexAmCapabilities::DEFAULT
+    }
+}
+
+/// The estimated costs of an index scan
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct IndexCosts {
+    pub startup_cost: pg_sys::Cost,
+    pub total_cost: pg_sys::Cost,
+    /// The fraction of the table's rows that match the scan's keys
+    pub selectivity: pg_sys::Selectivity,
+    /// The correlation between the order of the index and the order of the table, from -1 to 1
+    pub correlation: f64,
+    /// How many pages of the index the scan reads
+    pub pages: f64,
+}
+
+/// Postgres' cost estimate for index scans, which estimates a page read for each matching index
+/// tuple and the cost of evaluating the scan's keys on them
+pub fn generic_cost_estimate(
+    root: &PlannerInfoRef<'_>,
+    path: &mut pg_sys::IndexPath,
+    loop_count: f64,
+) -> IndexCosts {
+    let mut costs = pg_sys::GenericCosts::default();
+    unsafe {
+        #[cfg(any(feature = "pg10", feature = "pg11"))]
+        {
+            let qinfos = pg_sys::deconstruct_indexquals(path);
+            pg_sys::genericcostestimate(root.as_ptr(), path, loop_count, qinfos, &mut costs);
+        }
+        #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14"))]
+        pg_sys::genericcostestimate(root.as_ptr(), path, loop_count, &mut costs);
+    }
+    IndexCosts {
+        startup_cost: costs.indexStartupCost,
+        total_cost: costs.indexTotalCost,
+        selectivity: costs.indexSelectivity,
+        correlation: costs.indexCorrelation,
+        pages: costs.numIndexPages,
+    }
+}
+
+/// Call `callback` with the heap tid, the values of the indexed columns, and whether the tuple is
+/// alive, of each heap tuple an index of `heap` being built should have an entry for, returning
+/// how many heap tuples were scanned
+pub fn index_build_heap_scan<F>(
+    heap: &PgRelation,
+    index: &PgRelation,
+    index_info: &mut pg_sys::IndexInfo,
+    callback: F,
+) -> f64
+where
+    F: FnMut(pg_sys::ItemPointerData, &[Option<pg_sys::Datum>], bool),
+{
+    let mut state = BuildState { index_info: index_info as *mut pg_sys::IndexInfo, callback };
+    unsafe {
+        pg_sys::pgx_index_build_heap_scan(
+            heap.as_ptr(),
+            index.as_ptr(),
+            state.index_info,
+            Some(build_callback::<F>),
+            &mut state as *mut BuildState<F> as *mut c_void,
+        )
+    }
+}
+
+#[cfg(any(feature = "pg10", feature = "pg11", feature = "pg12"))]
+unsafe extern "C" fn build_callback<F>(
+    _index: pg_sys::Relation,
+    htup: pg_sys::HeapTuple,
+    values: *mut pg_sys::Datum,
+    isnull: *mut bool,
+    tuple_is_alive: bool,
+    state: *mut c_void,
+) where
+    F: FnMut(pg_sys::ItemPointerData, &[Option<pg_sys::Datum>], bool),
+{
+    call_build_callback::<F>((*htup).t_self, values, isnull, tuple_is_alive, state)
+}
+
+#[cfg(any(feature = "pg13", feature = "pg14"))]
+unsafe extern "C" fn build_callback<F>(
+    _index: pg_sys::Relation,
+    tid: pg_sys::ItemPointer,
+    values: *mut pg_sys::Datum,
+    isnull: *mut bool,
+    tuple_is_alive: bool,
+    state: *mut c_void,
+) where
+    F: FnMut(pg_sys::ItemPointerData, &[Option<pg_sys::Datum>], bool),
+{
+    call_build_callback::<F>(*tid, values, isnull, tuple_is_alive, state)
+}
+
+unsafe fn call_build_callback<F>(
+    tid: pg_sys::ItemPointerData,
+    values: *mut pg_sys::Datum,
+    isnull: *mut bool,
+    tuple_is_alive: bool,
+    state: *mut c_void,
+) where
+    F: FnMut(pg_sys::ItemPointerData, &[Option<pg_sys::Datum>], bool),
+{
+    crate::guard::guard(|| unsafe {
+        let state = &mut *(state as *mut BuildState<F>);
+        // the values are of the index's key columns, followed by its INCLUDE columns
+        let values = datums(values, isnull, (*state.index_info).ii_NumIndexAttrs);
+        (state.callback)(tid, &values, tuple_is_alive)
+    })
+}
+
+/// Asks whether a heap tuple is dead, for [`IndexAmRoutine::bulk_delete`]
+pub struct DeadTuples {
+    callback: pg_sys::IndexBulkDeleteCallback,
+    state: *mut c_void,
+}
+
+impl DeadTuples {
+    /// Whether the heap tuple `tid` is dead, so its entries should be removed from the index
+    pub fn is_dead(&self, mut tid: pg_sys::ItemPointerData) -> bool {
+        match self.callback {
+            Some(callback) => unsafe { callback(&mut tid, self.state) },
+            None => false,
+        }
+    }
+}
+
+/// A key of an index scan, such as `column = 42`, whose argument is compared to the indexed
+/// values with the operator of its strategy
+///
+/// This dereferences to the underlying `pg_sys::ScanKeyData` for anything that doesn't have a
+/// method of its own.
+#[repr(transparent)]
+pub struct ScanKey(pg_sys::ScanKeyData);
+
+impl ScanKey {
+    pub fn as_ptr(&self) -> *const pg_sys::ScanKeyData {
+        &self.0
+    }
+
+    /// The index column it's a key of, counting from 1
+    pub fn attno(&self) -> pg_sys::AttrNumber {
+        self.0.sk_attno
+    }
+
+    /// The strategy number of its operator in the column's operator class
+    pub fn strategy(&self) -> pg_sys::StrategyNumber {
+        self.0.sk_strategy
+    }
+
+    /// The type of its argument, or `InvalidOid` if it's the column's type
+    pub fn subtype(&self) -> pg_sys::Oid {
+        self.0.sk_subtype
+    }
+
+    /// The collation to compare with
+    pub fn collation(&self) -> pg_sys::Oid {
+        self.0.sk_collation
+    }
+
+    /// Its `SK_*` flags
+    pub fn flags(&self) -> i32 {
+        self.0.sk_flags
+    }
+
+    /// Whether its argument is NULL, including for `IS NULL` and `IS NOT NULL` keys
+    pub fn is_null(&self) -> bool {
+        self.0.sk_flags & pg_sys::SK_ISNULL as i32 != 0
+    }
+
+    /// Its argument, unless that's NULL
+    pub fn argument(&self) -> Option<pg_sys::Datum> {
+        if self.is_null() {
+            None
+        } else {
+            Some(self.0.sk_argument)
+        }
+    }
+
+    /// Its argument, converted from a `Datum`, unless that's NULL
+    ///
+    /// ## Errors
+    /// - return [`TryFromDatumError::IncompatibleTypes`] if its [`ScanKey::subtype`] is not
+    /// compatible with `T`.  An `InvalidOid` subtype is the indexed column's type, which is
+    /// assumed to be `T`.
+    pub fn argument_as<T: FromDatum + IntoDatum + 'static>(
+        &self,
+    ) -> Result<Option<T>, TryFromDatumError> {
+        unsafe {
+            match self.0.sk_subtype {
+                pg_sys::InvalidOid => Ok(T::from_datum(self.0.sk_argument, self.is_null())),
+                subtype => T::try_from_datum(self.0.sk_argument, self.is_null(), subtype),
+            }
+        }
+    }
+}
+
+impl Deref for ScanKey {
+    type Target = pg_sys::ScanKeyData;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// The state of a scan of an index
+///
+/// This dereferences to the underlying `pg_sys::IndexScanDescData` for anything that doesn't have
+/// a method of its own.
+pub struct IndexScanDescRef<'a> {
+    ptr: pg_sys::IndexScanDesc,
+    _marker: PhantomData<&'a mut pg_sys::IndexScanDescData>,
+}
+
+impl<'a> IndexScanDescRef<'a> {
+    /// ## Safety
+    ///
+    /// `ptr` must be a valid pointer that outlives the returned `IndexScanDescRef`
+    pub unsafe fn from_pg(ptr: pg_sys::IndexScanDesc) -> Self {
+        assert!(!ptr.is_null(), "IndexScanDesc is NULL");
+        IndexScanDescRef { ptr, _marker: PhantomData }
+    }
+
+    pub fn as_ptr(&self) -> pg_sys::IndexScanDesc {
+        self.ptr
+    }
+
+    /// The index being scanned
+    pub fn index_relation(&self) -> PgRelation {
+        unsafe { PgRelation::from_pg((*self.ptr).indexRelation) }
+    }
+
+    /// The table of the index, which isn't known to bitmap scans
+    pub fn heap_relation(&self) -> Option<PgRelation> {
+        unsafe {
+            let heap = (*self.ptr).heapRelation;
+            if heap.is_null() {
+                None
+            } else {
+                Some(PgRelation::from_pg(heap))
+            }
+        }
+    }
+
+    /// The snapshot the scan's matches must be visible to
+    pub fn snapshot(&self) -> pg_sys::Snapshot {
+        unsafe { (*self.ptr).xs_snapshot }
+    }
+
+    /// The keys given to the latest rescan
+    pub fn keys(&self) -> &'a [ScanKey] {
+        unsafe { scan_keys((*self.ptr).keyData, (*self.ptr).numberOfKeys) }
+    }
+
+    /// The `ORDER BY` keys given to the latest rescan
+    pub fn orderbys(&self) -> &'a [ScanKey] {
+        unsafe { scan_keys((*self.ptr).orderByData, (*self.ptr).numberOfOrderBys) }
+    }
+
+    /// Say whether the executor must check the scan's keys against the heap tuple of the match
+    /// [`IndexAmRoutine::get_tuple`] returns
+    pub fn set_recheck(&mut self, recheck: bool) {
+        unsafe { (*self.ptr).xs_recheck = recheck }
+    }
+
+    fn set_heap_tid(&mut self, tid: pg_sys::ItemPointerData) {
+        #[cfg(any(feature = "pg10", feature = "pg11"))]
+        unsafe {
+            (*self.ptr).xs_ctup.t_self = tid
+        }
+        #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14"))]
+        unsafe {
+            (*self.ptr).xs_heaptid = tid
+        }
+    }
+}
+
+impl Deref for IndexScanDescRef<'_> {
+    type Target = pg_sys::IndexScanDescData;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.ptr }
+    }
+}
+
+impl DerefMut for IndexScanDescRef<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.ptr }
+    }
+}
+
+/// The bitmap of heap tids that a bitmap index scan adds its matches to
+pub struct TidBitmapRef<'a> {
+    ptr: *mut pg_sys::TIDBitmap,
+    _marker: PhantomData<&'a mut pg_sys::TIDBitmap>,
+}
+
+impl<'a> TidBitmapRef<'a> {
+    /// ## Safety
+    ///
+    /// `ptr` must be a valid pointer that outlives the returned `TidBitmapRef`
+    pub unsafe fn from_pg(ptr: *mut pg_sys::TIDBitmap) -> Self {
+        assert!(!ptr.is_null(), "TIDBitmap is NULL");
+        TidBitmapRef { ptr, _marker: PhantomData }
+    }
+
+    pub fn as_ptr(&self) -> *mut pg_sys::TIDBitmap {
+        self.ptr
+    }
+
+    /// Add heap tids, which need the scan's keys checked against their tuples if `recheck`
+    pub fn add(&mut self, tids: &[pg_sys::ItemPointerData], recheck: bool) {
+        unsafe {
+            pg_sys::tbm_add_tuples(
+                self.ptr,
+                tids.as_ptr() as *mut pg_sys::ItemPointerData,
+                tids.len() as c_int,
+                recheck,
+            )
+        }
+    }
+}
+
+/// The handler function of the access method `W`, which `#[pg_index_am]` makes, returns this
+pub fn index_am_handler<W: IndexAmRoutine>() -> pg_sys::Datum {
+    let capabilities = W::CAPABILITIES;
+    let mut routine = PgBox::<pg_sys::IndexAmRoutine>::alloc_node(pg_sys::NodeTag_T_IndexAmRoutine);
+    routine.amstrategies = capabilities.strategies;
+    routine.amsupport = capabilities.support;
+    routine.amcanorder = capabilities.can_order;
+    routine.amcanorderbyop = capabilities.can_order_by_op;
+    routine.amcanbackward = capabilities.can_backward;
+    routine.amcanunique = capabilities.can_unique;
+    routine.amcanmulticol = capabilities.can_multicol;
+    routine.amoptionalkey = capabilities.optional_key;
+    routine.amsearcharray = capabilities.search_array;
+    routine.amsearchnulls = capabilities.search_nulls;
+    routine.amstorage = capabilities.storage;
+    routine.amclusterable = capabilities.clusterable;
+    routine.ampredlocks = capabilities.predlocks;
+    routine.amcanparallel = false;
+    #[cfg(not(feature = "pg10"))]
+    {
+        routine.amcaninclude = capabilities.can_include;
+    }
+    routine.amkeytype = capabilities.key_type;
+
+    routine.ambuild = Some(ambuild::<W>);
+    routine.ambuildempty = Some(ambuildempty::<W>);
+    routine.aminsert = Some(aminsert::<W>);
+    routine.ambulkdelete = Some(ambulkdelete::<W>);
+    routine.amvacuumcleanup = Some(amvacuumcleanup::<W>);
+    routine.amcostestimate = Some(amcostestimate::<W>);
+    routine.amoptions = Some(amoptions::<W>);
+    routine.amvalidate = Some(amvalidate::<W>);
+    routine.ambeginscan = Some(ambeginscan::<W>);
+    routine.amrescan = Some(amrescan::<W>);
+    if capabilities.index_scans {
+        routine.amgettuple = Some(amgettuple::<W>);
+    }
+    if capabilities.bitmap_scans {
+        routine.amgetbitmap = Some(amgetbitmap::<W>);
+    }
+    routine.amendscan = Some(amendscan::<W>);
+    routine.into_pg().into()
+}
+
+/// What [`index_build_heap_scan`] passes to its callback through the scan
+struct BuildState<F> {
+    index_info: *mut pg_sys::IndexInfo,
+    callback: F,
+}
+
+unsafe fn datums(
+    values: *mut pg_sys::Datum,
+    isnull: *mut bool,
+    natts: c_int,
+) -> Vec<Option<pg_sys::Datum>> {
+    (0..natts as usize).map(|i| if *isnull.add(i) { None } else { Some(*values.add(i)) }).collect()
+}
+
+unsafe fn scan_keys<'a>(keys: *mut pg_sys::ScanKeyData, nkeys: c_int) -> &'a [ScanKey] {
+    if keys.is_null() || nkeys <= 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(keys as *const ScanKey, nkeys as usize)
+    }
+}
+
+/// The state [`ambeginscan`] left in `scan`, which is `None` once [`amendscan`] has dropped it
+unsafe fn scan_state<'a, W>(scan: pg_sys::IndexScanDesc) -> &'a mut W {
+    (*((*scan).opaque as *mut Option<W>)).as_mut().expect("index scan has already ended")
+}
+
+unsafe extern "C" fn ambuild<W: IndexAmRoutine>(
+    heap: pg_sys::Relation,
+    index: pg_sys::Relation,
+    index_info: *mut pg_sys::IndexInfo,
+) -> *mut pg_sys::IndexBuildResult {
+    crate::guard::guard(|| unsafe {
+        let result =
+            W::build(&PgRelation::from_pg(heap), &PgRelation::from_pg(index), &mut *index_info);
+        let mut boxed = PgBox::<pg_sys::IndexBuildResult>::alloc0();
+        *boxed = result;
+        boxed.into_pg()
+    })
+}
+
+unsafe extern "C" fn ambuildempty<W: IndexAmRoutine>(index: pg_sys::Relation) {
+    crate::guard::guard(|| unsafe { W::build_empty(&PgRelation::from_pg(index)) })
+}
+
+#[cfg(not(feature = "pg14"))]
+unsafe extern "C" fn aminsert<W: IndexAmRoutine>(
+    index: pg_sys::Relation,
+    values: *mut pg_sys::Datum,
+    isnull: *mut bool,
+    heap_tid: pg_sys::ItemPointer,
+    heap: pg_sys::Relation,
+    check_unique: pg_sys::IndexUniqueCheck,
+    index_info: *mut pg_sys::IndexInfo,
+) -> bool {
+    insert::<W>(index, values, isnull, heap_tid, heap, check_unique, index_info)
+}
+
+#[cfg(feature = "pg14")]
+unsafe extern "C" fn aminsert<W: IndexAmRoutine>(
+    index: pg_sys::Relation,
+    values: *mut pg_sys::Datum,
+    isnull: *mut bool,
+    heap_tid: pg_sys::ItemPointer,
+    heap: pg_sys::Relation,
+    check_unique: pg_sys::IndexUniqueCheck,
+    _index_unchanged: bool,
+    index_info: *mut pg_sys::IndexInfo,
+) -> bool {
+    insert::<W>(index, values, isnull, heap_tid, heap, check_unique, index_info)
+}
+
+unsafe fn insert<W: IndexAmRoutine>(
+    index: pg_sys::Relation,
+    values: *mut pg_sys::Datum,
+    isnull: *mut bool,
+    heap_tid: pg_sys::ItemPointer,
+    heap: pg_sys::Relation,
+    check_unique: pg_sys::IndexUniqueCheck,
+    index_info: *mut pg_sys::IndexInfo,
+) -> bool {
+    crate::guard::guard(|| unsafe {
+        let values = datums(values, isnull, (*index_info).ii_NumIndexAttrs);
+        W::insert(
+            &PgRelation::from_pg(index),
+            &values,
+            *heap_tid,
+            &PgRelation::from_pg(heap),
+            check_unique,
+            &mut *index_info,
+        )
+    })
+}
+
+unsafe extern "C" fn ambulkdelete<W: IndexAmRoutine>(
+    info: *mut pg_sys::IndexVacuumInfo,
+    stats: *mut pg_sys::IndexBulkDeleteResult,
+    callback: pg_sys::IndexBulkDeleteCallback,
+    callback_state: *mut c_void,
+) -> *mut pg_sys::IndexBulkDeleteResult {
+    crate::guard::guard(|| unsafe {
+        // the statistics are null on the first call of a `VACUUM`
+        let stats = if stats.is_null() {
+            PgBox::<pg_sys::IndexBulkDeleteResult>::alloc0().into_pg()
+        } else {
+            stats
+        };
+        W::bulk_delete(&*info, &mut *stats, &DeadTuples { callback, state: callback_state });
+        stats
+    })
+}
+
+unsafe extern "C" fn amvacuumcleanup<W: IndexAmRoutine>(
+    info: *mut pg_sys::IndexVacuumInfo,
+    stats: *mut pg_sys::IndexBulkDeleteResult,
+) -> *mut pg_sys::IndexBulkDeleteResult {
+    crate::guard::guard(|| unsafe {
+        let stats = if stats.is_null() { None } else { Some(PgBox::from_pg(stats)) };
+        match W::vacuum_cleanup(&*info, stats) {
+            Some(stats) => stats.into_pg(),
+            None => std::ptr::null_mut(),
+        }
+    })
+}
+
+unsafe extern "C" fn amcostestimate<W: IndexAmRoutine>(
+    root: *mut pg_sys::PlannerInfo,
+    path: *mut pg_sys::IndexPath,
+    loop_count: f64,
+    index_startup_cost: *mut pg_sys::Cost,
+    index_total_cost: *mut pg_sys::Cost,
+    index_selectivity: *mut pg_sys::Selectivity,
+    index_correlation: *mut f64,
+    index_pages: *mut f64,
+) {
+    crate::guard::guard(|| unsafe {
+        let costs = W::cost_estimate(&PlannerInfoRef::from_pg(root), &mut *path, loop_count);
+        *index_startup_cost = costs.startup_cost;
+        *index_total_cost = costs.total_cost;
+        *index_selectivity = costs.selectivity;
+        *index_correlation = costs.correlation;
+        *index_pages = costs.pages;
+    })
+}
+
+unsafe extern "C" fn amoptions<W: IndexAmRoutine>(
+    reloptions: pg_sys::Datum,
+    validate: bool,
+) -> *mut pg_sys::bytea {
+    crate::guard::guard(|| W::options(reloptions, validate))
+}
+
+unsafe extern "C" fn amvalidate<W: IndexAmRoutine>(opclassoid: pg_sys::Oid) -> bool {
+    crate::guard::guard(|| W::validate(opclassoid))
+}
+
+unsafe extern "C" fn ambeginscan<W: IndexAmRoutine>(
+    index: pg_sys::Relation,
+    nkeys: c_int,
+    norderbys: c_int,
+) -> pg_sys::IndexScanDesc {
+    crate::guard::guard(|| unsafe {
+        let scan = pg_sys::RelationGetIndexScan(index, nkeys, norderbys);
+        let state = W::begin_scan(&mut IndexScanDescRef::from_pg(scan));
+        // amendscan drops the state, but an error aborts the scan without calling it, so the
+        // memory context drops whatever is left
+        (*scan).opaque = PgMemoryContexts::CurrentMemoryContext.leak_and_drop_on_delete(Some(state))
+            as *mut c_void;
+        scan
+    })
+}
+
+unsafe extern "C" fn amrescan<W: IndexAmRoutine>(
+    scan: pg_sys::IndexScanDesc,
+    keys: pg_sys::ScanKey,
+    nkeys: c_int,
+    orderbys: pg_sys::ScanKey,
+    norderbys: c_int,
+) {
+    crate::guard::guard(|| unsafe {
+        // the scan keeps its own copies of the keys, which RelationGetIndexScan allocated
+        if !keys.is_null() && nkeys > 0 {
+            std::ptr::copy(keys, (*scan).keyData, nkeys as usize);
+        }
+        if !orderbys.is_null() && norderbys > 0 {
+            std::ptr::copy(orderbys, (*scan).orderByData, norderbys as usize);
+        }
+        let mut desc = IndexScanDescRef::from_pg(scan);
+        let (keys, orderbys) = (desc.keys(), desc.orderbys());
+        scan_state::<W>(scan).rescan(&mut desc, keys, orderbys)
+    })
+}
+
+unsafe extern "C" fn amgettuple<W: IndexAmRoutine>(
+    scan: pg_sys::IndexScanDesc,
+    direction: pg_sys::ScanDirection,
+) -> bool {
+    crate::guard::guard(|| unsafe {
+        let mut desc = IndexScanDescRef::from_pg(scan);
+        match scan_state::<W>(scan).get_tuple(&mut desc, direction) {
+            Some(tid) => {
+                desc.set_heap_tid(tid);
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+unsafe extern "C" fn amgetbitmap<W: IndexAmRoutine>(
+    scan: pg_sys::IndexScanDesc,
+    tbm: *mut pg_sys::TIDBitmap,
+) -> i64 {
+    crate::guard::guard(|| unsafe {
+        scan_state::<W>(scan)
+            .get_bitmap(&mut IndexScanDescRef::from_pg(scan), &mut TidBitmapRef::from_pg(tbm))
+    })
+}
+
+unsafe extern "C" fn amendscan<W: IndexAmRoutine>(scan: pg_sys::IndexScanDesc) {
+    crate::guard::guard(|| unsafe {
+        let state = &mut *((*scan).opaque as *mut Option<W>);
+        if let Some(mut state) = state.take() {
+            state.end_scan(&mut IndexScanDescRef::from_pg(scan));
+        }
+        (*scan).opaque = std::ptr::null_mut();
+    })
+}
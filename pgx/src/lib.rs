@@ -35,18 +35,24 @@ pub mod prelude;
 
 pub mod aggregate;
 pub mod callbacks;
+pub mod copy;
+pub mod custom_scan;
 pub mod datum;
 pub mod enum_helper;
+pub mod explain;
 pub mod fcinfo;
+pub mod fdw;
 pub mod guc;
 pub mod hooks;
 pub mod htup;
+pub mod index_am;
 pub mod inoutfuncs;
 pub mod itemptr;
 pub mod iter;
 pub mod list;
 #[macro_use]
 pub mod log;
+pub mod logical;
 pub mod array;
 pub mod atomics;
 pub mod bgworkers;
@@ -57,12 +63,18 @@ pub mod misc;
 pub mod namespace;
 pub mod nodes;
 pub mod pgbox;
+pub mod planner;
+pub mod port;
 pub mod rel;
 pub mod shmem;
 pub mod spi;
 pub mod stringinfo;
+pub mod subxact;
+#[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14"))]
+pub mod table_am;
 pub mod trigger_support;
 pub mod tupdesc;
+pub mod tuptable;
 pub mod varlena;
 pub mod wrappers;
 pub mod xid;
@@ -75,27 +87,38 @@ mod layout;
 pub use aggregate::*;
 pub use atomics::*;
 pub use callbacks::*;
+pub use custom_scan::*;
 pub use datum::*;
 pub use enum_helper::*;
+pub use explain::*;
 pub use fcinfo::*;
+pub use fdw::*;
 pub use guc::*;
 pub use hooks::*;
 pub use htup::*;
+pub use index_am::*;
 pub use inoutfuncs::*;
 pub use itemptr::*;
 pub use list::*;
 pub use log::*;
+pub use logical::*;
 pub use lwlock::*;
 pub use memcxt::*;
 pub use namespace::*;
 pub use nodes::*;
 pub use pgbox::*;
+pub use planner::*;
+pub use port::*;
 pub use rel::*;
 pub use shmem::*;
 pub use spi::*;
 pub use stringinfo::*;
+pub use subxact::*;
+#[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14"))]
+pub use table_am::*;
 pub use trigger_support::*;
 pub use tupdesc::*;
+pub use tuptable::*;
 pub use varlena::*;
 pub use wrappers::*;
 pub use xid::*;
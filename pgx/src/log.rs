@@ -59,7 +59,7 @@ pub enum PgLogLevel {
 
 /// This list of SQL Error Codes is taken directly from Postgres 12's generated "utils/errcodes.h"
 #[allow(non_camel_case_types)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum PgSqlErrorCode {
     /// Class 00 - Successful Completion
     ERRCODE_SUCCESSFUL_COMPLETION = MAKE_SQLSTATE('0', '0', '0', '0', '0') as isize,
@@ -433,6 +433,276 @@ pub enum PgSqlErrorCode {
     ERRCODE_INDEX_CORRUPTED = MAKE_SQLSTATE('X', 'X', '0', '0', '2') as isize,
 }
 
+impl PgSqlErrorCode {
+    /// The [`PgSqlErrorCode`] for an encoded SQLSTATE, as created by `MAKE_SQLSTATE()`, if it's
+    /// one that's known about
+    pub fn from_sqlerrcode(sqlerrcode: i32) -> Option<Self> {
+        use PgSqlErrorCode::*;
+
+        [
+            ERRCODE_SUCCESSFUL_COMPLETION,
+            ERRCODE_WARNING,
+            ERRCODE_WARNING_DYNAMIC_RESULT_SETS_RETURNED,
+            ERRCODE_WARNING_IMPLICIT_ZERO_BIT_PADDING,
+            ERRCODE_WARNING_NULL_VALUE_ELIMINATED_IN_SET_FUNCTION,
+            ERRCODE_WARNING_PRIVILEGE_NOT_GRANTED,
+            ERRCODE_WARNING_PRIVILEGE_NOT_REVOKED,
+            ERRCODE_WARNING_STRING_DATA_RIGHT_TRUNCATION,
+            ERRCODE_WARNING_DEPRECATED_FEATURE,
+            ERRCODE_NO_DATA,
+            ERRCODE_NO_ADDITIONAL_DYNAMIC_RESULT_SETS_RETURNED,
+            ERRCODE_SQL_STATEMENT_NOT_YET_COMPLETE,
+            ERRCODE_CONNECTION_EXCEPTION,
+            ERRCODE_CONNECTION_DOES_NOT_EXIST,
+            ERRCODE_CONNECTION_FAILURE,
+            ERRCODE_SQLCLIENT_UNABLE_TO_ESTABLISH_SQLCONNECTION,
+            ERRCODE_SQLSERVER_REJECTED_ESTABLISHMENT_OF_SQLCONNECTION,
+            ERRCODE_TRANSACTION_RESOLUTION_UNKNOWN,
+            ERRCODE_PROTOCOL_VIOLATION,
+            ERRCODE_TRIGGERED_ACTION_EXCEPTION,
+            ERRCODE_FEATURE_NOT_SUPPORTED,
+            ERRCODE_INVALID_TRANSACTION_INITIATION,
+            ERRCODE_LOCATOR_EXCEPTION,
+            ERRCODE_L_E_INVALID_SPECIFICATION,
+            ERRCODE_INVALID_GRANTOR,
+            ERRCODE_INVALID_GRANT_OPERATION,
+            ERRCODE_INVALID_ROLE_SPECIFICATION,
+            ERRCODE_DIAGNOSTICS_EXCEPTION,
+            ERRCODE_STACKED_DIAGNOSTICS_ACCESSED_WITHOUT_ACTIVE_HANDLER,
+            ERRCODE_CASE_NOT_FOUND,
+            ERRCODE_CARDINALITY_VIOLATION,
+            ERRCODE_DATA_EXCEPTION,
+            ERRCODE_ARRAY_ELEMENT_ERROR,
+            ERRCODE_CHARACTER_NOT_IN_REPERTOIRE,
+            ERRCODE_DATETIME_FIELD_OVERFLOW,
+            ERRCODE_DIVISION_BY_ZERO,
+            ERRCODE_ERROR_IN_ASSIGNMENT,
+            ERRCODE_ESCAPE_CHARACTER_CONFLICT,
+            ERRCODE_INDICATOR_OVERFLOW,
+            ERRCODE_INTERVAL_FIELD_OVERFLOW,
+            ERRCODE_INVALID_ARGUMENT_FOR_LOG,
+            ERRCODE_INVALID_ARGUMENT_FOR_NTILE,
+            ERRCODE_INVALID_ARGUMENT_FOR_NTH_VALUE,
+            ERRCODE_INVALID_ARGUMENT_FOR_POWER_FUNCTION,
+            ERRCODE_INVALID_ARGUMENT_FOR_WIDTH_BUCKET_FUNCTION,
+            ERRCODE_INVALID_CHARACTER_VALUE_FOR_CAST,
+            ERRCODE_INVALID_DATETIME_FORMAT,
+            ERRCODE_INVALID_ESCAPE_CHARACTER,
+            ERRCODE_INVALID_ESCAPE_OCTET,
+            ERRCODE_INVALID_ESCAPE_SEQUENCE,
+            ERRCODE_NONSTANDARD_USE_OF_ESCAPE_CHARACTER,
+            ERRCODE_INVALID_INDICATOR_PARAMETER_VALUE,
+            ERRCODE_INVALID_PARAMETER_VALUE,
+            ERRCODE_INVALID_PRECEDING_OR_FOLLOWING_SIZE,
+            ERRCODE_INVALID_REGULAR_EXPRESSION,
+            ERRCODE_INVALID_ROW_COUNT_IN_LIMIT_CLAUSE,
+            ERRCODE_INVALID_ROW_COUNT_IN_RESULT_OFFSET_CLAUSE,
+            ERRCODE_INVALID_TABLESAMPLE_ARGUMENT,
+            ERRCODE_INVALID_TABLESAMPLE_REPEAT,
+            ERRCODE_INVALID_TIME_ZONE_DISPLACEMENT_VALUE,
+            ERRCODE_INVALID_USE_OF_ESCAPE_CHARACTER,
+            ERRCODE_MOST_SPECIFIC_TYPE_MISMATCH,
+            ERRCODE_NULL_VALUE_NOT_ALLOWED,
+            ERRCODE_NULL_VALUE_NO_INDICATOR_PARAMETER,
+            ERRCODE_NUMERIC_VALUE_OUT_OF_RANGE,
+            ERRCODE_SEQUENCE_GENERATOR_LIMIT_EXCEEDED,
+            ERRCODE_STRING_DATA_LENGTH_MISMATCH,
+            ERRCODE_STRING_DATA_RIGHT_TRUNCATION,
+            ERRCODE_SUBSTRING_ERROR,
+            ERRCODE_TRIM_ERROR,
+            ERRCODE_UNTERMINATED_C_STRING,
+            ERRCODE_ZERO_LENGTH_CHARACTER_STRING,
+            ERRCODE_FLOATING_POINT_EXCEPTION,
+            ERRCODE_INVALID_TEXT_REPRESENTATION,
+            ERRCODE_INVALID_BINARY_REPRESENTATION,
+            ERRCODE_BAD_COPY_FILE_FORMAT,
+            ERRCODE_UNTRANSLATABLE_CHARACTER,
+            ERRCODE_NOT_AN_XML_DOCUMENT,
+            ERRCODE_INVALID_XML_DOCUMENT,
+            ERRCODE_INVALID_XML_CONTENT,
+            ERRCODE_INVALID_XML_COMMENT,
+            ERRCODE_INVALID_XML_PROCESSING_INSTRUCTION,
+            ERRCODE_DUPLICATE_JSON_OBJECT_KEY_VALUE,
+            ERRCODE_INVALID_JSON_TEXT,
+            ERRCODE_INVALID_SQL_JSON_SUBSCRIPT,
+            ERRCODE_MORE_THAN_ONE_SQL_JSON_ITEM,
+            ERRCODE_NO_SQL_JSON_ITEM,
+            ERRCODE_NON_NUMERIC_SQL_JSON_ITEM,
+            ERRCODE_NON_UNIQUE_KEYS_IN_A_JSON_OBJECT,
+            ERRCODE_SINGLETON_SQL_JSON_ITEM_REQUIRED,
+            ERRCODE_SQL_JSON_ARRAY_NOT_FOUND,
+            ERRCODE_SQL_JSON_MEMBER_NOT_FOUND,
+            ERRCODE_SQL_JSON_NUMBER_NOT_FOUND,
+            ERRCODE_SQL_JSON_OBJECT_NOT_FOUND,
+            ERRCODE_TOO_MANY_JSON_ARRAY_ELEMENTS,
+            ERRCODE_TOO_MANY_JSON_OBJECT_MEMBERS,
+            ERRCODE_SQL_JSON_SCALAR_REQUIRED,
+            ERRCODE_INTEGRITY_CONSTRAINT_VIOLATION,
+            ERRCODE_RESTRICT_VIOLATION,
+            ERRCODE_NOT_NULL_VIOLATION,
+            ERRCODE_FOREIGN_KEY_VIOLATION,
+            ERRCODE_UNIQUE_VIOLATION,
+            ERRCODE_CHECK_VIOLATION,
+            ERRCODE_EXCLUSION_VIOLATION,
+            ERRCODE_INVALID_CURSOR_STATE,
+            ERRCODE_INVALID_TRANSACTION_STATE,
+            ERRCODE_ACTIVE_SQL_TRANSACTION,
+            ERRCODE_BRANCH_TRANSACTION_ALREADY_ACTIVE,
+            ERRCODE_HELD_CURSOR_REQUIRES_SAME_ISOLATION_LEVEL,
+            ERRCODE_INAPPROPRIATE_ACCESS_MODE_FOR_BRANCH_TRANSACTION,
+            ERRCODE_INAPPROPRIATE_ISOLATION_LEVEL_FOR_BRANCH_TRANSACTION,
+            ERRCODE_NO_ACTIVE_SQL_TRANSACTION_FOR_BRANCH_TRANSACTION,
+            ERRCODE_READ_ONLY_SQL_TRANSACTION,
+            ERRCODE_SCHEMA_AND_DATA_STATEMENT_MIXING_NOT_SUPPORTED,
+            ERRCODE_NO_ACTIVE_SQL_TRANSACTION,
+            ERRCODE_IN_FAILED_SQL_TRANSACTION,
+            ERRCODE_IDLE_IN_TRANSACTION_SESSION_TIMEOUT,
+            ERRCODE_INVALID_SQL_STATEMENT_NAME,
+            ERRCODE_TRIGGERED_DATA_CHANGE_VIOLATION,
+            ERRCODE_INVALID_AUTHORIZATION_SPECIFICATION,
+            ERRCODE_INVALID_PASSWORD,
+            ERRCODE_DEPENDENT_PRIVILEGE_DESCRIPTORS_STILL_EXIST,
+            ERRCODE_DEPENDENT_OBJECTS_STILL_EXIST,
+            ERRCODE_INVALID_TRANSACTION_TERMINATION,
+            ERRCODE_SQL_ROUTINE_EXCEPTION,
+            ERRCODE_S_R_E_FUNCTION_EXECUTED_NO_RETURN_STATEMENT,
+            ERRCODE_S_R_E_MODIFYING_SQL_DATA_NOT_PERMITTED,
+            ERRCODE_S_R_E_PROHIBITED_SQL_STATEMENT_ATTEMPTED,
+            ERRCODE_S_R_E_READING_SQL_DATA_NOT_PERMITTED,
+            ERRCODE_INVALID_CURSOR_NAME,
+            ERRCODE_EXTERNAL_ROUTINE_EXCEPTION,
+            ERRCODE_E_R_E_CONTAINING_SQL_NOT_PERMITTED,
+            ERRCODE_E_R_E_MODIFYING_SQL_DATA_NOT_PERMITTED,
+            ERRCODE_E_R_E_PROHIBITED_SQL_STATEMENT_ATTEMPTED,
+            ERRCODE_E_R_E_READING_SQL_DATA_NOT_PERMITTED,
+            ERRCODE_EXTERNAL_ROUTINE_INVOCATION_EXCEPTION,
+            ERRCODE_E_R_I_E_INVALID_SQLSTATE_RETURNED,
+            ERRCODE_E_R_I_E_NULL_VALUE_NOT_ALLOWED,
+            ERRCODE_E_R_I_E_TRIGGER_PROTOCOL_VIOLATED,
+            ERRCODE_E_R_I_E_SRF_PROTOCOL_VIOLATED,
+            ERRCODE_E_R_I_E_EVENT_TRIGGER_PROTOCOL_VIOLATED,
+            ERRCODE_SAVEPOINT_EXCEPTION,
+            ERRCODE_S_E_INVALID_SPECIFICATION,
+            ERRCODE_INVALID_CATALOG_NAME,
+            ERRCODE_INVALID_SCHEMA_NAME,
+            ERRCODE_TRANSACTION_ROLLBACK,
+            ERRCODE_T_R_INTEGRITY_CONSTRAINT_VIOLATION,
+            ERRCODE_T_R_SERIALIZATION_FAILURE,
+            ERRCODE_T_R_STATEMENT_COMPLETION_UNKNOWN,
+            ERRCODE_T_R_DEADLOCK_DETECTED,
+            ERRCODE_SYNTAX_ERROR_OR_ACCESS_RULE_VIOLATION,
+            ERRCODE_SYNTAX_ERROR,
+            ERRCODE_INSUFFICIENT_PRIVILEGE,
+            ERRCODE_CANNOT_COERCE,
+            ERRCODE_GROUPING_ERROR,
+            ERRCODE_WINDOWING_ERROR,
+            ERRCODE_INVALID_RECURSION,
+            ERRCODE_INVALID_FOREIGN_KEY,
+            ERRCODE_INVALID_NAME,
+            ERRCODE_NAME_TOO_LONG,
+            ERRCODE_RESERVED_NAME,
+            ERRCODE_DATATYPE_MISMATCH,
+            ERRCODE_INDETERMINATE_DATATYPE,
+            ERRCODE_COLLATION_MISMATCH,
+            ERRCODE_INDETERMINATE_COLLATION,
+            ERRCODE_WRONG_OBJECT_TYPE,
+            ERRCODE_GENERATED_ALWAYS,
+            ERRCODE_UNDEFINED_COLUMN,
+            ERRCODE_UNDEFINED_FUNCTION,
+            ERRCODE_UNDEFINED_TABLE,
+            ERRCODE_UNDEFINED_PARAMETER,
+            ERRCODE_UNDEFINED_OBJECT,
+            ERRCODE_DUPLICATE_COLUMN,
+            ERRCODE_DUPLICATE_CURSOR,
+            ERRCODE_DUPLICATE_DATABASE,
+            ERRCODE_DUPLICATE_FUNCTION,
+            ERRCODE_DUPLICATE_PSTATEMENT,
+            ERRCODE_DUPLICATE_SCHEMA,
+            ERRCODE_DUPLICATE_TABLE,
+            ERRCODE_DUPLICATE_ALIAS,
+            ERRCODE_DUPLICATE_OBJECT,
+            ERRCODE_AMBIGUOUS_COLUMN,
+            ERRCODE_AMBIGUOUS_FUNCTION,
+            ERRCODE_AMBIGUOUS_PARAMETER,
+            ERRCODE_AMBIGUOUS_ALIAS,
+            ERRCODE_INVALID_COLUMN_REFERENCE,
+            ERRCODE_INVALID_COLUMN_DEFINITION,
+            ERRCODE_INVALID_CURSOR_DEFINITION,
+            ERRCODE_INVALID_DATABASE_DEFINITION,
+            ERRCODE_INVALID_FUNCTION_DEFINITION,
+            ERRCODE_INVALID_PSTATEMENT_DEFINITION,
+            ERRCODE_INVALID_SCHEMA_DEFINITION,
+            ERRCODE_INVALID_TABLE_DEFINITION,
+            ERRCODE_INVALID_OBJECT_DEFINITION,
+            ERRCODE_WITH_CHECK_OPTION_VIOLATION,
+            ERRCODE_INSUFFICIENT_RESOURCES,
+            ERRCODE_DISK_FULL,
+            ERRCODE_OUT_OF_MEMORY,
+            ERRCODE_TOO_MANY_CONNECTIONS,
+            ERRCODE_CONFIGURATION_LIMIT_EXCEEDED,
+            ERRCODE_PROGRAM_LIMIT_EXCEEDED,
+            ERRCODE_STATEMENT_TOO_COMPLEX,
+            ERRCODE_TOO_MANY_COLUMNS,
+            ERRCODE_TOO_MANY_ARGUMENTS,
+            ERRCODE_OBJECT_NOT_IN_PREREQUISITE_STATE,
+            ERRCODE_OBJECT_IN_USE,
+            ERRCODE_CANT_CHANGE_RUNTIME_PARAM,
+            ERRCODE_LOCK_NOT_AVAILABLE,
+            ERRCODE_UNSAFE_NEW_ENUM_VALUE_USAGE,
+            ERRCODE_OPERATOR_INTERVENTION,
+            ERRCODE_QUERY_CANCELED,
+            ERRCODE_ADMIN_SHUTDOWN,
+            ERRCODE_CRASH_SHUTDOWN,
+            ERRCODE_CANNOT_CONNECT_NOW,
+            ERRCODE_DATABASE_DROPPED,
+            ERRCODE_SYSTEM_ERROR,
+            ERRCODE_IO_ERROR,
+            ERRCODE_UNDEFINED_FILE,
+            ERRCODE_DUPLICATE_FILE,
+            ERRCODE_SNAPSHOT_TOO_OLD,
+            ERRCODE_CONFIG_FILE_ERROR,
+            ERRCODE_LOCK_FILE_EXISTS,
+            ERRCODE_FDW_ERROR,
+            ERRCODE_FDW_COLUMN_NAME_NOT_FOUND,
+            ERRCODE_FDW_DYNAMIC_PARAMETER_VALUE_NEEDED,
+            ERRCODE_FDW_FUNCTION_SEQUENCE_ERROR,
+            ERRCODE_FDW_INCONSISTENT_DESCRIPTOR_INFORMATION,
+            ERRCODE_FDW_INVALID_ATTRIBUTE_VALUE,
+            ERRCODE_FDW_INVALID_COLUMN_NAME,
+            ERRCODE_FDW_INVALID_COLUMN_NUMBER,
+            ERRCODE_FDW_INVALID_DATA_TYPE,
+            ERRCODE_FDW_INVALID_DATA_TYPE_DESCRIPTORS,
+            ERRCODE_FDW_INVALID_DESCRIPTOR_FIELD_IDENTIFIER,
+            ERRCODE_FDW_INVALID_HANDLE,
+            ERRCODE_FDW_INVALID_OPTION_INDEX,
+            ERRCODE_FDW_INVALID_OPTION_NAME,
+            ERRCODE_FDW_INVALID_STRING_LENGTH_OR_BUFFER_LENGTH,
+            ERRCODE_FDW_INVALID_STRING_FORMAT,
+            ERRCODE_FDW_INVALID_USE_OF_NULL_POINTER,
+            ERRCODE_FDW_TOO_MANY_HANDLES,
+            ERRCODE_FDW_OUT_OF_MEMORY,
+            ERRCODE_FDW_NO_SCHEMAS,
+            ERRCODE_FDW_OPTION_NAME_NOT_FOUND,
+            ERRCODE_FDW_REPLY_HANDLE,
+            ERRCODE_FDW_SCHEMA_NOT_FOUND,
+            ERRCODE_FDW_TABLE_NOT_FOUND,
+            ERRCODE_FDW_UNABLE_TO_CREATE_EXECUTION,
+            ERRCODE_FDW_UNABLE_TO_CREATE_REPLY,
+            ERRCODE_FDW_UNABLE_TO_ESTABLISH_CONNECTION,
+            ERRCODE_PLPGSQL_ERROR,
+            ERRCODE_RAISE_EXCEPTION,
+            ERRCODE_NO_DATA_FOUND,
+            ERRCODE_TOO_MANY_ROWS,
+            ERRCODE_ASSERT_FAILURE,
+            ERRCODE_INTERNAL_ERROR,
+            ERRCODE_DATA_CORRUPTED,
+            ERRCODE_INDEX_CORRUPTED,
+        ]
+        .into_iter()
+        .find(|code| *code as i32 == sqlerrcode)
+    }
+}
+
 #[allow(non_snake_case)]
 #[inline]
 const fn PGSIXBIT(ch: i32) -> i32 {
@@ -449,6 +719,125 @@ const fn MAKE_SQLSTATE(ch1: char, ch2: char, ch3: char, ch4: char, ch5: char) ->
         + (PGSIXBIT(ch5 as i32) << 24)) as i32
 }
 
+/// A view of a message that is being logged, as given to
+/// [`PgHooks::emit_log`][crate::hooks::PgHooks::emit_log]
+pub struct ErrorDataRef<'a> {
+    error_data: &'a mut crate::pg_sys::ErrorData,
+}
+
+impl<'a> ErrorDataRef<'a> {
+    /// ## Safety
+    ///
+    /// `error_data` must be a valid pointer that outlives the returned `ErrorDataRef`
+    pub unsafe fn from_pg(error_data: *mut crate::pg_sys::ErrorData) -> Self {
+        ErrorDataRef { error_data: error_data.as_mut().expect("ErrorData is NULL") }
+    }
+
+    pub fn as_ptr(&self) -> *mut crate::pg_sys::ErrorData {
+        self.error_data as *const _ as *mut _
+    }
+
+    /// The message's level, such as `pg_sys::WARNING` or `pg_sys::ERROR`
+    pub fn elevel(&self) -> i32 {
+        self.error_data.elevel
+    }
+
+    /// The message's level, if it's one that [`PgLogLevel`] knows about
+    pub fn log_level(&self) -> Option<PgLogLevel> {
+        use PgLogLevel::*;
+
+        [
+            DEBUG5,
+            DEBUG4,
+            DEBUG3,
+            DEBUG2,
+            DEBUG1,
+            LOG,
+            LOG_SERVER_ONLY,
+            INFO,
+            NOTICE,
+            WARNING,
+            ERROR,
+            FATAL,
+            PANIC,
+        ]
+        .into_iter()
+        .find(|level| *level as i32 == self.error_data.elevel)
+    }
+
+    /// The message's encoded SQLSTATE, as created by `MAKE_SQLSTATE()`
+    pub fn sqlerrcode(&self) -> i32 {
+        self.error_data.sqlerrcode
+    }
+
+    /// The message's SQLSTATE, if it's one that [`PgSqlErrorCode`] knows about
+    ///
+    /// Any other code can be turned into its five-character form, such as `"42P01"`, with
+    /// [`unpack_sql_state`][crate::unpack_sql_state]`(self.sqlerrcode())`.
+    pub fn sqlstate(&self) -> Option<PgSqlErrorCode> {
+        PgSqlErrorCode::from_sqlerrcode(self.error_data.sqlerrcode)
+    }
+
+    /// The primary message, in the server's encoding (replacing anything that isn't valid UTF8)
+    pub fn message(&self) -> Option<std::borrow::Cow<'_, str>> {
+        unsafe { Self::string(self.error_data.message) }
+    }
+
+    /// The `DETAIL` line, if any
+    pub fn detail(&self) -> Option<std::borrow::Cow<'_, str>> {
+        unsafe { Self::string(self.error_data.detail) }
+    }
+
+    /// The `HINT` line, if any
+    pub fn hint(&self) -> Option<std::borrow::Cow<'_, str>> {
+        unsafe { Self::string(self.error_data.hint) }
+    }
+
+    /// The `CONTEXT` lines, if any
+    pub fn context(&self) -> Option<std::borrow::Cow<'_, str>> {
+        unsafe { Self::string(self.error_data.context) }
+    }
+
+    /// The source file the message was raised from
+    pub fn filename(&self) -> Option<std::borrow::Cow<'_, str>> {
+        unsafe { Self::string(self.error_data.filename) }
+    }
+
+    /// The line of [`filename`](Self::filename) the message was raised from
+    pub fn lineno(&self) -> i32 {
+        self.error_data.lineno
+    }
+
+    /// The function the message was raised from
+    pub fn funcname(&self) -> Option<std::borrow::Cow<'_, str>> {
+        unsafe { Self::string(self.error_data.funcname) }
+    }
+
+    /// Will the message be written to the server log?
+    pub fn output_to_server(&self) -> bool {
+        self.error_data.output_to_server
+    }
+
+    /// Change whether the message is written to the server log.  This has no effect on whether
+    /// it's sent to the client.
+    pub fn set_output_to_server(&mut self, output_to_server: bool) {
+        self.error_data.output_to_server = output_to_server;
+    }
+
+    /// Will the message be sent to the client?
+    pub fn output_to_client(&self) -> bool {
+        self.error_data.output_to_client
+    }
+
+    unsafe fn string<'s>(string: *const std::os::raw::c_char) -> Option<std::borrow::Cow<'s, str>> {
+        if string.is_null() {
+            None
+        } else {
+            Some(std::ffi::CStr::from_ptr(string).to_string_lossy())
+        }
+    }
+}
+
 /// Emit a Postgres log message.
 ///
 /// Log messages of level `pg_sys::ERROR` will cause the current transaction to abort
@@ -556,6 +945,14 @@ pub fn ereport(
     }
 }
 
+/// Raise an `ERROR` with `code` and `message`, reported from the caller's location
+#[track_caller]
+pub(crate) fn raise(code: PgSqlErrorCode, message: &str) -> ! {
+    let location = std::panic::Location::caller();
+    ereport(PgLogLevel::ERROR, code, message, location.file(), location.line(), location.column());
+    unreachable!("ERROR ereport() returned")
+}
+
 /// Log to Postgres' `debug5` log level.
 ///
 /// This macro accepts arguments like the [`println`](std::println) and [`format`](std::format) macros.
@@ -0,0 +1,603 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+
+//! Logical decoding output plugins, which turn the changes Postgres decodes from its WAL into
+//! whatever format the consumers of a replication slot expect
+//!
+//! A plugin implements [`OutputPlugin`] in an `impl` block marked `#[pg_output_plugin]`, which
+//! adds the `_PG_output_plugin_init` function Postgres looks for when a slot names the
+//! extension's library as its plugin.  An extension has at most one output plugin.
+//!
+//! An instance of the plugin is made by [`OutputPlugin::startup`] each time a slot is created or
+//! read from, and is then told about each decoded transaction, in commit order, through its
+//! methods.  Each writes its output with [`LogicalDecodingContextRef::prepare_write`] and
+//! [`LogicalDecodingContextRef::write`], or [`LogicalDecodingContextRef::write_bytes`] when it has
+//! all of it at hand.
+//!
+//! Extensions can also write messages of their own to the WAL with [`emit_message`], which
+//! plugins see through [`OutputPlugin::message`].
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//! use pgx::logical::*;
+//!
+//! /// Writes each change as a line of `action table`
+//! struct Changes;
+//!
+//! #[pg_output_plugin]
+//! impl OutputPlugin for Changes {
+//!     fn startup(
+//!         _ctx: &mut LogicalDecodingContextRef<'_>,
+//!         _options: &mut StartupOptions<'_>,
+//!         _is_init: bool,
+//!     ) -> Self {
+//!         Changes
+//!     }
+//!
+//!     fn change(
+//!         &mut self,
+//!         ctx: &mut LogicalDecodingContextRef<'_>,
+//!         _txn: &ReorderBufferTxnRef<'_>,
+//!         change: &ReorderBufferChange<'_>,
+//!     ) {
+//!         let line = format!("{:?} {}", change.action(), change.relation().name());
+//!         ctx.write_bytes(line.as_bytes());
+//!     }
+//! }
+//! ```
+use crate::log::raise;
+use crate::{
+    heap_tuple::PgHeapTuple, pg_sys, AllocatedByPostgres, PgList, PgLsn, PgMemoryContexts,
+    PgRelation, PgSqlErrorCode, PgTupleDesc, StringInfo,
+};
+use std::ffi::{CStr, CString};
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+/// A logical decoding output plugin
+///
+/// Only [`OutputPlugin::startup`] and [`OutputPlugin::change`] are required.  The rest write
+/// nothing by default.
+pub trait OutputPlugin: Sized + 'static {
+    /// Make the instance of the plugin that decodes for a slot
+    ///
+    /// `is_init` is `true` when the slot is being created, rather than read from.  The options
+    /// the slot's consumer passed are available from [`LogicalDecodingContextRef::options`].
+    fn startup(
+        ctx: &mut LogicalDecodingContextRef<'_>,
+        options: &mut StartupOptions<'_>,
+        is_init: bool,
+    ) -> Self;
+
+    /// A transaction's changes follow
+    fn begin(&mut self, _ctx: &mut LogicalDecodingContextRef<'_>, _txn: &ReorderBufferTxnRef<'_>) {}
+
+    /// A row of a table was inserted, updated, or deleted
+    fn change(
+        &mut self,
+        ctx: &mut LogicalDecodingContextRef<'_>,
+        txn: &ReorderBufferTxnRef<'_>,
+        change: &ReorderBufferChange<'_>,
+    );
+
+    /// `relations` were truncated, as of Postgres 11
+    fn truncate(
+        &mut self,
+        _ctx: &mut LogicalDecodingContextRef<'_>,
+        _txn: &ReorderBufferTxnRef<'_>,
+        _relations: &[PgRelation],
+        _cascade: bool,
+        _restart_seqs: bool,
+    ) {
+    }
+
+    /// A message was written with `pg_logical_emit_message()`
+    ///
+    /// `txn` is `None` for messages that aren't transactional, which are decoded as soon as
+    /// they're written, even if their transaction later aborts.
+    fn message(
+        &mut self,
+        _ctx: &mut LogicalDecodingContextRef<'_>,
+        _txn: Option<&ReorderBufferTxnRef<'_>>,
+        _message: &LogicalMessage<'_>,
+    ) {
+    }
+
+    /// The transaction, whose changes have all been decoded, committed at `commit_lsn`
+    fn commit(
+        &mut self,
+        _ctx: &mut LogicalDecodingContextRef<'_>,
+        _txn: &ReorderBufferTxnRef<'_>,
+        _commit_lsn: pg_sys::XLogRecPtr,
+    ) {
+    }
+
+    /// Whether to leave out the changes replayed from the replication origin `origin_id`
+    fn filter_by_origin(
+        &mut self,
+        _ctx: &mut LogicalDecodingContextRef<'_>,
+        _origin_id: pg_sys::RepOriginId,
+    ) -> bool {
+        false
+    }
+
+    /// Decoding is done, and the instance is dropped after this returns
+    fn shutdown(&mut self, _ctx: &mut LogicalDecodingContextRef<'_>) {}
+}
+
+/// The format of a plugin's output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputType {
+    /// Text in the database's encoding, which `pg_logical_slot_get_changes()` can return
+    Textual,
+    /// Anything, which only `pg_logical_slot_get_binary_changes()` and the replication
+    /// protocol can return
+    Binary,
+}
+
+/// What a plugin tells Postgres about itself when it starts up
+pub struct StartupOptions<'a> {
+    ptr: *mut pg_sys::OutputPluginOptions,
+    _marker: PhantomData<&'a mut pg_sys::OutputPluginOptions>,
+}
+
+impl<'a> StartupOptions<'a> {
+    /// ## Safety
+    ///
+    /// `ptr` must be a valid pointer that outlives the returned `StartupOptions`
+    pub unsafe fn from_pg(ptr: *mut pg_sys::OutputPluginOptions) -> Self {
+        assert!(!ptr.is_null(), "OutputPluginOptions is NULL");
+        StartupOptions { ptr, _marker: PhantomData }
+    }
+
+    pub fn as_ptr(&self) -> *mut pg_sys::OutputPluginOptions {
+        self.ptr
+    }
+
+    /// The format of the plugin's output, which is [`OutputType::Textual`] unless set otherwise
+    pub fn output_type(&self) -> OutputType {
+        match unsafe { (*self.ptr).output_type } {
+            pg_sys::OutputPluginOutputType_OUTPUT_PLUGIN_BINARY_OUTPUT => OutputType::Binary,
+            _ => OutputType::Textual,
+        }
+    }
+
+    pub fn set_output_type(&mut self, output_type: OutputType) {
+        unsafe {
+            (*self.ptr).output_type = match output_type {
+                OutputType::Textual => pg_sys::OutputPluginOutputType_OUTPUT_PLUGIN_TEXTUAL_OUTPUT,
+                OutputType::Binary => pg_sys::OutputPluginOutputType_OUTPUT_PLUGIN_BINARY_OUTPUT,
+            }
+        }
+    }
+}
+
+/// The state of decoding for a replication slot, which plugins write their output to
+pub struct LogicalDecodingContextRef<'a> {
+    ptr: *mut pg_sys::LogicalDecodingContext,
+    _marker: PhantomData<&'a mut pg_sys::LogicalDecodingContext>,
+}
+
+impl<'a> LogicalDecodingContextRef<'a> {
+    /// ## Safety
+    ///
+    /// `ptr` must be a valid pointer that outlives the returned `LogicalDecodingContextRef`
+    pub unsafe fn from_pg(ptr: *mut pg_sys::LogicalDecodingContext) -> Self {
+        assert!(!ptr.is_null(), "LogicalDecodingContext is NULL");
+        LogicalDecodingContextRef { ptr, _marker: PhantomData }
+    }
+
+    pub fn as_ptr(&self) -> *mut pg_sys::LogicalDecodingContext {
+        self.ptr
+    }
+
+    /// The `(name, value)` pairs of options the slot's consumer passed, such as those after the
+    /// slot's name and LSNs in `pg_logical_slot_get_changes()`
+    pub fn options(&self) -> Vec<(String, Option<String>)> {
+        unsafe {
+            PgList::<pg_sys::DefElem>::from_pg(
+                pg_sys::pgx_LogicalDecodingContext_output_plugin_options(self.ptr),
+            )
+            .iter_ptr()
+            .map(|def| {
+                let name = CStr::from_ptr((*def).defname).to_string_lossy().into_owned();
+                let value = if (*def).arg.is_null() {
+                    None
+                } else {
+                    Some(CStr::from_ptr(pg_sys::defGetString(def)).to_string_lossy().into_owned())
+                };
+                (name, value)
+            })
+            .collect()
+        }
+    }
+
+    /// Start writing an output message, whose contents are then appended to the returned
+    /// `StringInfo` before calling [`LogicalDecodingContextRef::write`]
+    ///
+    /// `last_write` is whether this is the last message of the callback.
+    pub fn prepare_write(&mut self, last_write: bool) -> StringInfo {
+        unsafe {
+            pg_sys::OutputPluginPrepareWrite(self.ptr, last_write);
+        }
+        self.out()
+    }
+
+    /// Finish writing the output message started by [`LogicalDecodingContextRef::prepare_write`]
+    pub fn write(&mut self, last_write: bool) {
+        unsafe { pg_sys::OutputPluginWrite(self.ptr, last_write) }
+    }
+
+    /// Write `bytes` as a single output message
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.prepare_write(true).push_bytes(bytes);
+        self.write(true);
+    }
+
+    /// The output message being written, which belongs to Postgres
+    pub fn out(&mut self) -> StringInfo {
+        unsafe { StringInfo::from_pg(pg_sys::pgx_LogicalDecodingContext_out(self.ptr)) }
+            .expect("LogicalDecodingContext has no output buffer")
+    }
+
+    /// Tell the slot's consumer how far decoding has come, for plugins that skip a lot of
+    /// changes without writing anything
+    pub fn update_progress(&mut self) {
+        unsafe { pg_sys::OutputPluginUpdateProgress(self.ptr) }
+    }
+}
+
+/// A decoded transaction
+///
+/// This dereferences to the underlying `pg_sys::ReorderBufferTXN` for anything that doesn't have
+/// a method of its own.
+pub struct ReorderBufferTxnRef<'a> {
+    ptr: *mut pg_sys::ReorderBufferTXN,
+    _marker: PhantomData<&'a pg_sys::ReorderBufferTXN>,
+}
+
+impl<'a> ReorderBufferTxnRef<'a> {
+    /// ## Safety
+    ///
+    /// `ptr` must be a valid pointer that outlives the returned `ReorderBufferTxnRef`
+    pub unsafe fn from_pg(ptr: *mut pg_sys::ReorderBufferTXN) -> Self {
+        assert!(!ptr.is_null(), "ReorderBufferTXN is NULL");
+        ReorderBufferTxnRef { ptr, _marker: PhantomData }
+    }
+
+    pub fn as_ptr(&self) -> *mut pg_sys::ReorderBufferTXN {
+        self.ptr
+    }
+
+    pub fn xid(&self) -> pg_sys::TransactionId {
+        self.xid
+    }
+
+    /// The LSN of the transaction's commit record
+    pub fn final_lsn(&self) -> pg_sys::XLogRecPtr {
+        self.final_lsn
+    }
+
+    /// The LSN just past the transaction's commit record
+    pub fn end_lsn(&self) -> pg_sys::XLogRecPtr {
+        self.end_lsn
+    }
+
+    pub fn commit_time(&self) -> pg_sys::TimestampTz {
+        self.commit_time
+    }
+
+    /// The replication origin the transaction was replayed from, or `InvalidRepOriginId`
+    pub fn origin_id(&self) -> pg_sys::RepOriginId {
+        self.origin_id
+    }
+
+    /// The LSN of the transaction's commit at its replication origin
+    pub fn origin_lsn(&self) -> pg_sys::XLogRecPtr {
+        self.origin_lsn
+    }
+}
+
+impl<'a> Deref for ReorderBufferTxnRef<'a> {
+    type Target = pg_sys::ReorderBufferTXN;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.ptr }
+    }
+}
+
+/// What a [`ReorderBufferChange`] did to its row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeAction {
+    Insert,
+    Update,
+    Delete,
+}
+
+/// A decoded change to a row of a table
+///
+/// The old row of an update or delete, when Postgres logged it, only has the columns of the
+/// table's replica identity unless the identity is `FULL`.  Values stored in TOAST tables that an
+/// update didn't change can't be read from its new row.
+pub struct ReorderBufferChange<'a> {
+    ptr: *mut pg_sys::ReorderBufferChange,
+    action: ChangeAction,
+    relation: PgRelation,
+    _marker: PhantomData<&'a pg_sys::ReorderBufferChange>,
+}
+
+impl<'a> ReorderBufferChange<'a> {
+    /// ## Safety
+    ///
+    /// `ptr` must be a valid pointer to an insert, update, or delete of `relation`, and both
+    /// must outlive the returned `ReorderBufferChange`
+    pub unsafe fn from_pg(
+        ptr: *mut pg_sys::ReorderBufferChange,
+        relation: pg_sys::Relation,
+    ) -> Self {
+        assert!(!ptr.is_null(), "ReorderBufferChange is NULL");
+        let action = match (*ptr).action {
+            pg_sys::ReorderBufferChangeType_REORDER_BUFFER_CHANGE_INSERT => ChangeAction::Insert,
+            pg_sys::ReorderBufferChangeType_REORDER_BUFFER_CHANGE_UPDATE => ChangeAction::Update,
+            pg_sys::ReorderBufferChangeType_REORDER_BUFFER_CHANGE_DELETE => ChangeAction::Delete,
+            other => raise(
+                PgSqlErrorCode::ERRCODE_INTERNAL_ERROR,
+                &format!("unexpected ReorderBufferChange action {}", other),
+            ),
+        };
+        ReorderBufferChange {
+            ptr,
+            action,
+            relation: PgRelation::from_pg(relation),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn as_ptr(&self) -> *mut pg_sys::ReorderBufferChange {
+        self.ptr
+    }
+
+    pub fn action(&self) -> ChangeAction {
+        self.action
+    }
+
+    /// The LSN of the change's WAL record
+    pub fn lsn(&self) -> pg_sys::XLogRecPtr {
+        unsafe { (*self.ptr).lsn }
+    }
+
+    /// The replication origin the change was replayed from, or `InvalidRepOriginId`
+    pub fn origin_id(&self) -> pg_sys::RepOriginId {
+        unsafe { (*self.ptr).origin_id }
+    }
+
+    /// The changed table, as of the change
+    pub fn relation(&self) -> &PgRelation {
+        &self.relation
+    }
+
+    /// The shape of the changed table's rows, as of the change
+    pub fn tuple_desc(&self) -> PgTupleDesc<'a> {
+        unsafe { PgTupleDesc::from_pg_unchecked(self.relation.rd_att) }
+    }
+
+    /// The row before an update or delete, if Postgres logged it
+    pub fn old_tuple(&self) -> Option<PgHeapTuple<'a, AllocatedByPostgres>> {
+        unsafe { self.tuple((*self.ptr).data.tp.oldtuple) }
+    }
+
+    /// The row after an insert or update
+    pub fn new_tuple(&self) -> Option<PgHeapTuple<'a, AllocatedByPostgres>> {
+        unsafe { self.tuple((*self.ptr).data.tp.newtuple) }
+    }
+
+    unsafe fn tuple(
+        &self,
+        buf: *mut pg_sys::ReorderBufferTupleBuf,
+    ) -> Option<PgHeapTuple<'a, AllocatedByPostgres>> {
+        if buf.is_null() {
+            None
+        } else {
+            Some(PgHeapTuple::from_heap_tuple(self.tuple_desc(), &mut (*buf).tuple))
+        }
+    }
+}
+
+/// A message written with `pg_logical_emit_message()`
+#[derive(Debug, Clone, Copy)]
+pub struct LogicalMessage<'a> {
+    /// The LSN of the message's WAL record
+    pub lsn: pg_sys::XLogRecPtr,
+    /// Whether the message was decoded with its transaction, rather than as soon as it was written
+    pub transactional: bool,
+    /// The prefix that tells plugins whose message it is, in the database's encoding
+    pub prefix: &'a CStr,
+    pub content: &'a [u8],
+}
+
+/// Write a message to the WAL for output plugins, returning the LSN just past its record, as
+/// `pg_logical_emit_message()` does
+///
+/// Transactional messages are decoded with the rest of the current transaction when it commits,
+/// and not at all if it aborts.  The rest are decoded as soon as they're written.
+///
+/// Raises an `ERROR` if `prefix` contains a NUL byte, which a prefix can't hold.
+pub fn emit_message(prefix: &str, payload: &[u8], transactional: bool) -> PgLsn {
+    let prefix = CString::new(prefix).unwrap_or_else(|_| {
+        raise(
+            PgSqlErrorCode::ERRCODE_INVALID_PARAMETER_VALUE,
+            "logical decoding message prefix must not contain a NUL byte",
+        )
+    });
+    unsafe {
+        pg_sys::LogLogicalMessage(
+            prefix.as_ptr(),
+            payload.as_ptr() as *const std::os::raw::c_char,
+            payload.len(),
+            transactional,
+        )
+    }
+    .into()
+}
+
+/// Fill in the callbacks of the output plugin `P`, from its `_PG_output_plugin_init` function
+///
+/// ## Safety
+///
+/// `cb` must be a valid pointer to the callbacks Postgres gives `_PG_output_plugin_init`
+pub unsafe fn output_plugin_init<P: OutputPlugin>(cb: *mut pg_sys::OutputPluginCallbacks) {
+    let cb = &mut *cb;
+    cb.startup_cb = Some(startup::<P>);
+    cb.begin_cb = Some(begin::<P>);
+    cb.change_cb = Some(change::<P>);
+    #[cfg(any(feature = "pg11", feature = "pg12", feature = "pg13", feature = "pg14"))]
+    {
+        cb.truncate_cb = Some(truncate::<P>);
+    }
+    cb.commit_cb = Some(commit::<P>);
+    cb.message_cb = Some(message::<P>);
+    cb.filter_by_origin_cb = Some(filter_by_origin::<P>);
+    cb.shutdown_cb = Some(shutdown::<P>);
+}
+
+/// The plugin's instance, which lives in the decoding context's memory context
+unsafe fn plugin<'a, P: OutputPlugin>(ctx: *mut pg_sys::LogicalDecodingContext) -> &'a mut P {
+    let private = *pg_sys::pgx_LogicalDecodingContext_output_plugin_private(ctx) as *mut P;
+    assert!(!private.is_null(), "output plugin has not started up");
+    &mut *private
+}
+
+unsafe extern "C" fn startup<P: OutputPlugin>(
+    ctx: *mut pg_sys::LogicalDecodingContext,
+    options: *mut pg_sys::OutputPluginOptions,
+    is_init: bool,
+) {
+    crate::guard::guard(|| unsafe {
+        let mut options = StartupOptions::from_pg(options);
+        options.set_output_type(OutputType::Textual);
+        let plugin =
+            P::startup(&mut LogicalDecodingContextRef::from_pg(ctx), &mut options, is_init);
+        // Postgres calls this in the decoding context's memory context, which is deleted once
+        // decoding is done
+        *pg_sys::pgx_LogicalDecodingContext_output_plugin_private(ctx) =
+            PgMemoryContexts::CurrentMemoryContext.leak_and_drop_on_delete(plugin) as *mut _;
+    })
+}
+
+unsafe extern "C" fn begin<P: OutputPlugin>(
+    ctx: *mut pg_sys::LogicalDecodingContext,
+    txn: *mut pg_sys::ReorderBufferTXN,
+) {
+    crate::guard::guard(|| unsafe {
+        plugin::<P>(ctx)
+            .begin(&mut LogicalDecodingContextRef::from_pg(ctx), &ReorderBufferTxnRef::from_pg(txn))
+    })
+}
+
+unsafe extern "C" fn change<P: OutputPlugin>(
+    ctx: *mut pg_sys::LogicalDecodingContext,
+    txn: *mut pg_sys::ReorderBufferTXN,
+    relation: pg_sys::Relation,
+    change: *mut pg_sys::ReorderBufferChange,
+) {
+    crate::guard::guard(|| unsafe {
+        plugin::<P>(ctx).change(
+            &mut LogicalDecodingContextRef::from_pg(ctx),
+            &ReorderBufferTxnRef::from_pg(txn),
+            &ReorderBufferChange::from_pg(change, relation),
+        )
+    })
+}
+
+#[cfg(any(feature = "pg11", feature = "pg12", feature = "pg13", feature = "pg14"))]
+unsafe extern "C" fn truncate<P: OutputPlugin>(
+    ctx: *mut pg_sys::LogicalDecodingContext,
+    txn: *mut pg_sys::ReorderBufferTXN,
+    nrelations: std::os::raw::c_int,
+    relations: *mut pg_sys::Relation,
+    change: *mut pg_sys::ReorderBufferChange,
+) {
+    crate::guard::guard(|| unsafe {
+        let relations = std::slice::from_raw_parts(relations, nrelations as usize)
+            .iter()
+            .map(|relation| PgRelation::from_pg(*relation))
+            .collect::<Vec<_>>();
+        let truncate = (*change).data.truncate;
+        plugin::<P>(ctx).truncate(
+            &mut LogicalDecodingContextRef::from_pg(ctx),
+            &ReorderBufferTxnRef::from_pg(txn),
+            &relations,
+            truncate.cascade,
+            truncate.restart_seqs,
+        )
+    })
+}
+
+unsafe extern "C" fn commit<P: OutputPlugin>(
+    ctx: *mut pg_sys::LogicalDecodingContext,
+    txn: *mut pg_sys::ReorderBufferTXN,
+    commit_lsn: pg_sys::XLogRecPtr,
+) {
+    crate::guard::guard(|| unsafe {
+        plugin::<P>(ctx).commit(
+            &mut LogicalDecodingContextRef::from_pg(ctx),
+            &ReorderBufferTxnRef::from_pg(txn),
+            commit_lsn,
+        )
+    })
+}
+
+unsafe extern "C" fn message<P: OutputPlugin>(
+    ctx: *mut pg_sys::LogicalDecodingContext,
+    txn: *mut pg_sys::ReorderBufferTXN,
+    message_lsn: pg_sys::XLogRecPtr,
+    transactional: bool,
+    prefix: *const std::os::raw::c_char,
+    message_size: pg_sys::Size,
+    message: *const std::os::raw::c_char,
+) {
+    crate::guard::guard(|| unsafe {
+        let txn = if txn.is_null() { None } else { Some(ReorderBufferTxnRef::from_pg(txn)) };
+        let content = if message_size == 0 {
+            &[][..]
+        } else {
+            std::slice::from_raw_parts(message as *const u8, message_size)
+        };
+        let message = LogicalMessage {
+            lsn: message_lsn,
+            transactional,
+            prefix: CStr::from_ptr(prefix),
+            content,
+        };
+        plugin::<P>(ctx).message(
+            &mut LogicalDecodingContextRef::from_pg(ctx),
+            txn.as_ref(),
+            &message,
+        )
+    })
+}
+
+unsafe extern "C" fn filter_by_origin<P: OutputPlugin>(
+    ctx: *mut pg_sys::LogicalDecodingContext,
+    origin_id: pg_sys::RepOriginId,
+) -> bool {
+    crate::guard::guard(|| unsafe {
+        plugin::<P>(ctx).filter_by_origin(&mut LogicalDecodingContextRef::from_pg(ctx), origin_id)
+    })
+}
+
+unsafe extern "C" fn shutdown<P: OutputPlugin>(ctx: *mut pg_sys::LogicalDecodingContext) {
+    crate::guard::guard(|| unsafe {
+        // there's no instance when startup raised an ERROR
+        let private = *pg_sys::pgx_LogicalDecodingContext_output_plugin_private(ctx) as *mut P;
+        if let Some(plugin) = private.as_mut() {
+            plugin.shutdown(&mut LogicalDecodingContextRef::from_pg(ctx))
+        }
+    })
+}
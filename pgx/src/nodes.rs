@@ -9,7 +9,9 @@ Use of this source code is governed by the MIT license that can be found in the
 
 //! Helper functions and such for Postgres' various query tree `Node`s
 
+use crate::misc::pgx_seahash;
 use crate::pg_sys;
+use std::iter::Peekable;
 
 /// #define IsA(nodeptr,_type_)            (nodeTag(nodeptr) == T_##_type_)
 #[inline]
@@ -38,3 +40,110 @@ pub unsafe fn node_to_string<'a>(nodeptr: *mut pg_sys::Node) -> Option<&'a str>
         }
     }
 }
+
+/// Compute a fingerprint of an analyzed query: a stable 64-bit id that is the same for all queries
+/// that only differ in the values of their constants.
+///
+/// Like Postgres' own query ids, this ignores the values of constants (but not their types), where
+/// things are in the query text, and the names given to aliases and output columns.  Tables,
+/// functions, operators, and so on, are identified by oid, so the same query text can have
+/// different fingerprints in different databases.  The fingerprint is never zero, as Postgres
+/// uses that to mean a query has no id.
+///
+/// ### Safety
+///
+/// We cannot guarantee the provided `query` is a valid pointer
+pub unsafe fn query_fingerprint(query: *mut pg_sys::Query) -> u64 {
+    let string = pg_sys::nodeToString(query as crate::void_ptr);
+    let normalized = normalize_node_string(std::ffi::CStr::from_ptr(string).to_bytes());
+    pg_sys::pfree(string as crate::void_mut_ptr);
+
+    match pgx_seahash(&normalized) {
+        0 => 1,
+        id => id,
+    }
+}
+
+/// The fields of a query tree's nodes that [`query_fingerprint`] ignores
+const IGNORED_QUERY_FIELDS: &[&[u8]] = &[
+    b":location",
+    b":stmt_location",
+    b":stmt_len",
+    b":queryId",
+    b":constvalue",
+    b":constisnull",
+    b":alias",
+    b":eref",
+    b":resname",
+];
+
+/// Remove the [`IGNORED_QUERY_FIELDS`] from the output of `nodeToString()`
+fn normalize_node_string(string: &[u8]) -> Vec<u8> {
+    let mut tokens = node_string_tokens(string).peekable();
+    let mut normalized = Vec::with_capacity(string.len());
+    while let Some(token) = tokens.next() {
+        if IGNORED_QUERY_FIELDS.contains(&token) {
+            skip_node_value(&mut tokens);
+        } else {
+            normalized.extend_from_slice(token);
+            normalized.push(b' ');
+        }
+    }
+    normalized
+}
+
+/// Split the output of `nodeToString()` into tokens, the same way Postgres' `pg_strtok()` does
+fn node_string_tokens(string: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let is_delimiter = |byte: u8| matches!(byte, b'(' | b')' | b'{' | b'}');
+    let mut position = 0;
+    std::iter::from_fn(move || {
+        while position < string.len() && string[position].is_ascii_whitespace() {
+            position += 1;
+        }
+        if position == string.len() {
+            return None;
+        }
+
+        let start = position;
+        if is_delimiter(string[position]) {
+            position += 1;
+        } else {
+            while position < string.len()
+                && !string[position].is_ascii_whitespace()
+                && !is_delimiter(string[position])
+            {
+                // a backslash escapes the character after it
+                position += if string[position] == b'\\' { 2 } else { 1 };
+            }
+            position = position.min(string.len());
+        }
+        Some(&string[start..position])
+    })
+}
+
+/// Skip over a field's value, which is a single token, a node or list with values of its own, or
+/// a datum
+fn skip_node_value<'a>(tokens: &mut Peekable<impl Iterator<Item = &'a [u8]>>) {
+    match tokens.next() {
+        Some(b"{") | Some(b"(") => {
+            let mut depth = 1;
+            while depth > 0 {
+                match tokens.next() {
+                    Some(b"{") | Some(b"(") => depth += 1,
+                    Some(b"}") | Some(b")") => depth -= 1,
+                    Some(_) => {}
+                    None => break,
+                }
+            }
+        }
+        // a datum is its length followed by its bytes, in brackets
+        Some(_) if tokens.peek() == Some(&&b"["[..]) => {
+            for token in tokens.by_ref() {
+                if token == b"]" {
+                    break;
+                }
+            }
+        }
+        _ => {}
+    }
+}
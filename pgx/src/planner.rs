@@ -0,0 +1,256 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+
+//! Views of the planner's state, as given to the path-generation hooks of
+//! [`PgHooks`][crate::hooks::PgHooks]
+use crate::{pg_sys, PgBox, PgList, PgRelation};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+/// The planner's state for the query (or subquery) being planned
+///
+/// This dereferences to the underlying `pg_sys::PlannerInfo` for anything that doesn't have a
+/// method of its own.
+pub struct PlannerInfoRef<'a> {
+    ptr: *mut pg_sys::PlannerInfo,
+    _marker: PhantomData<&'a mut pg_sys::PlannerInfo>,
+}
+
+/// A relation (a base relation, a join, or some other kind of relation) that the planner is
+/// considering paths for
+///
+/// This dereferences to the underlying `pg_sys::RelOptInfo` for anything that doesn't have a
+/// method of its own.
+pub struct RelOptInfoRef<'a> {
+    ptr: *mut pg_sys::RelOptInfo,
+    _marker: PhantomData<&'a mut pg_sys::RelOptInfo>,
+}
+
+/// An entry in a query's range table, which is anything that can appear in its `FROM` clause
+///
+/// This dereferences to the underlying `pg_sys::RangeTblEntry` for anything that doesn't have a
+/// method of its own.
+pub struct RangeTblEntryRef<'a> {
+    ptr: *mut pg_sys::RangeTblEntry,
+    _marker: PhantomData<&'a mut pg_sys::RangeTblEntry>,
+}
+
+impl<'a> PlannerInfoRef<'a> {
+    /// ## Safety
+    ///
+    /// `ptr` must be a valid pointer that outlives the returned `PlannerInfoRef`
+    pub unsafe fn from_pg(ptr: *mut pg_sys::PlannerInfo) -> Self {
+        assert!(!ptr.is_null(), "PlannerInfo is NULL");
+        PlannerInfoRef { ptr, _marker: PhantomData }
+    }
+
+    pub fn as_ptr(&self) -> *mut pg_sys::PlannerInfo {
+        self.ptr
+    }
+
+    /// The query being planned
+    pub fn parse(&self) -> PgBox<pg_sys::Query> {
+        unsafe { PgBox::from_pg((*self.ptr).parse) }
+    }
+
+    /// How deeply nested this query is, where the outermost query is `1`
+    pub fn query_level(&self) -> u32 {
+        unsafe { (*self.ptr).query_level }
+    }
+
+    /// The range table entry with index `rti`, if there is one
+    pub fn rt_fetch(&self, rti: pg_sys::Index) -> Option<RangeTblEntryRef<'a>> {
+        unsafe {
+            let rtable = PgList::<pg_sys::RangeTblEntry>::from_pg((*(*self.ptr).parse).rtable);
+            if rti == 0 || rti as usize > rtable.len() {
+                return None;
+            }
+            Some(RangeTblEntryRef::from_pg(pg_sys::planner_rt_fetch(rti, self.ptr)))
+        }
+    }
+
+    /// The base relation with range table index `rti`, if the planner has built one
+    pub fn simple_rel(&self, rti: pg_sys::Index) -> Option<RelOptInfoRef<'a>> {
+        unsafe {
+            let root = &*self.ptr;
+            if root.simple_rel_array.is_null() || rti as i32 >= root.simple_rel_array_size {
+                return None;
+            }
+            let rel = *root.simple_rel_array.add(rti as usize);
+            if rel.is_null() {
+                None
+            } else {
+                Some(RelOptInfoRef::from_pg(rel))
+            }
+        }
+    }
+}
+
+impl<'a> RelOptInfoRef<'a> {
+    /// ## Safety
+    ///
+    /// `ptr` must be a valid pointer that outlives the returned `RelOptInfoRef`
+    pub unsafe fn from_pg(ptr: *mut pg_sys::RelOptInfo) -> Self {
+        assert!(!ptr.is_null(), "RelOptInfo is NULL");
+        RelOptInfoRef { ptr, _marker: PhantomData }
+    }
+
+    pub fn as_ptr(&self) -> *mut pg_sys::RelOptInfo {
+        self.ptr
+    }
+
+    /// What kind of relation this is, such as `pg_sys::RelOptKind_RELOPT_BASEREL`
+    pub fn kind(&self) -> pg_sys::RelOptKind {
+        unsafe { (*self.ptr).reloptkind }
+    }
+
+    /// The range table index of a base relation, or zero for a join
+    pub fn relid(&self) -> pg_sys::Index {
+        unsafe { (*self.ptr).relid }
+    }
+
+    /// The estimated number of rows the relation will produce
+    pub fn rows(&self) -> f64 {
+        unsafe { (*self.ptr).rows }
+    }
+
+    pub fn set_rows(&mut self, rows: f64) {
+        unsafe { (*self.ptr).rows = rows }
+    }
+
+    /// The number of pages in a base relation, as found by the planner
+    pub fn pages(&self) -> pg_sys::BlockNumber {
+        unsafe { (*self.ptr).pages }
+    }
+
+    pub fn set_pages(&mut self, pages: pg_sys::BlockNumber) {
+        unsafe { (*self.ptr).pages = pages }
+    }
+
+    /// The number of tuples in a base relation, as found by the planner
+    pub fn tuples(&self) -> f64 {
+        unsafe { (*self.ptr).tuples }
+    }
+
+    pub fn set_tuples(&mut self, tuples: f64) {
+        unsafe { (*self.ptr).tuples = tuples }
+    }
+
+    /// The paths considered so far for producing this relation
+    pub fn pathlist(&self) -> PgList<pg_sys::Path> {
+        unsafe { PgList::from_pg((*self.ptr).pathlist) }
+    }
+
+    /// The partial paths (for parallel query) considered so far for producing this relation
+    pub fn partial_pathlist(&self) -> PgList<pg_sys::Path> {
+        unsafe { PgList::from_pg((*self.ptr).partial_pathlist) }
+    }
+
+    /// The restriction clauses (from `WHERE`, for example) on a base relation
+    pub fn baserestrictinfo(&self) -> PgList<pg_sys::RestrictInfo> {
+        unsafe { PgList::from_pg((*self.ptr).baserestrictinfo) }
+    }
+
+    /// The indexes of a base relation
+    pub fn indexlist(&self) -> PgList<pg_sys::IndexOptInfo> {
+        unsafe { PgList::from_pg((*self.ptr).indexlist) }
+    }
+
+    /// Consider `path` as a way of producing this relation, using `add_path()`.  It will be kept
+    /// if it's not dominated by another path, and freed otherwise.
+    ///
+    /// ## Safety
+    ///
+    /// `path` must be a valid, `palloc`'d path for this relation
+    pub unsafe fn add_path(&mut self, path: *mut pg_sys::Path) {
+        pg_sys::add_path(self.ptr, path)
+    }
+
+    /// Consider `path` as a partial path (for parallel query) for this relation, using
+    /// `add_partial_path()`
+    ///
+    /// ## Safety
+    ///
+    /// `path` must be a valid, `palloc`'d, parallel-safe path for this relation
+    pub unsafe fn add_partial_path(&mut self, path: *mut pg_sys::Path) {
+        pg_sys::add_partial_path(self.ptr, path)
+    }
+}
+
+impl<'a> RangeTblEntryRef<'a> {
+    /// ## Safety
+    ///
+    /// `ptr` must be a valid pointer that outlives the returned `RangeTblEntryRef`
+    pub unsafe fn from_pg(ptr: *mut pg_sys::RangeTblEntry) -> Self {
+        assert!(!ptr.is_null(), "RangeTblEntry is NULL");
+        RangeTblEntryRef { ptr, _marker: PhantomData }
+    }
+
+    pub fn as_ptr(&self) -> *mut pg_sys::RangeTblEntry {
+        self.ptr
+    }
+
+    /// What kind of entry this is, such as `pg_sys::RTEKind_RTE_RELATION`
+    pub fn kind(&self) -> pg_sys::RTEKind {
+        unsafe { (*self.ptr).rtekind }
+    }
+
+    /// The oid of the relation, if this entry is for one
+    pub fn relid(&self) -> Option<pg_sys::Oid> {
+        if self.kind() == pg_sys::RTEKind_RTE_RELATION {
+            Some(unsafe { (*self.ptr).relid })
+        } else {
+            None
+        }
+    }
+
+    /// Open the relation, if this entry is for one.  The planner already holds a lock on it.
+    pub fn relation(&self) -> Option<PgRelation> {
+        self.relid().map(|relid| unsafe { PgRelation::open(relid) })
+    }
+
+    /// The name the entry is known by in the query, which is its alias if it has one
+    pub fn name(&self) -> Option<&str> {
+        unsafe {
+            let eref = (*self.ptr).eref;
+            if eref.is_null() || (*eref).aliasname.is_null() {
+                None
+            } else {
+                std::ffi::CStr::from_ptr((*eref).aliasname).to_str().ok()
+            }
+        }
+    }
+
+    /// Should inheritance children (or partitions) also be scanned?
+    pub fn inh(&self) -> bool {
+        unsafe { (*self.ptr).inh }
+    }
+}
+
+macro_rules! impl_deref {
+    ($wrapper:ident, $target:ty) => {
+        impl Deref for $wrapper<'_> {
+            type Target = $target;
+
+            fn deref(&self) -> &Self::Target {
+                unsafe { &*self.ptr }
+            }
+        }
+
+        impl DerefMut for $wrapper<'_> {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                unsafe { &mut *self.ptr }
+            }
+        }
+    };
+}
+
+impl_deref!(PlannerInfoRef, pg_sys::PlannerInfo);
+impl_deref!(RelOptInfoRef, pg_sys::RelOptInfo);
+impl_deref!(RangeTblEntryRef, pg_sys::RangeTblEntry);
@@ -0,0 +1,106 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+
+//! A view of a client connection's `Port`, as given to
+//! [`PgHooks::client_authentication`][crate::hooks::PgHooks::client_authentication]
+use crate::{ereport, pg_sys, PgLogLevel, PgSqlErrorCode};
+use std::borrow::Cow;
+use std::ffi::CStr;
+use std::os::raw::c_char;
+
+/// A view of the `Port` that a client is connecting (or has connected) through
+pub struct PortRef<'a> {
+    port: &'a mut pg_sys::Port,
+}
+
+impl<'a> PortRef<'a> {
+    /// ## Safety
+    ///
+    /// `port` must be a valid pointer that outlives the returned `PortRef`
+    pub unsafe fn from_pg(port: *mut pg_sys::Port) -> Self {
+        PortRef { port: port.as_mut().expect("Port is NULL") }
+    }
+
+    /// The `Port` of the current backend's client, if it has one (background workers don't)
+    pub fn my_port() -> Option<PortRef<'static>> {
+        unsafe {
+            if pg_sys::MyProcPort.is_null() {
+                None
+            } else {
+                Some(PortRef::from_pg(pg_sys::MyProcPort))
+            }
+        }
+    }
+
+    pub fn as_ptr(&self) -> *mut pg_sys::Port {
+        self.port as *const _ as *mut _
+    }
+
+    /// The database the client asked to connect to
+    pub fn database_name(&self) -> Option<Cow<'_, str>> {
+        unsafe { Self::string(pg_sys::pgx_Port_database_name(self.as_ptr())) }
+    }
+
+    /// The role the client asked to connect as
+    pub fn user_name(&self) -> Option<Cow<'_, str>> {
+        unsafe { Self::string(pg_sys::pgx_Port_user_name(self.as_ptr())) }
+    }
+
+    /// The client's address, such as `"192.168.1.10"`, or `"[local]"` for a Unix-domain socket
+    pub fn remote_host(&self) -> Option<Cow<'_, str>> {
+        unsafe { Self::string(pg_sys::pgx_Port_remote_host(self.as_ptr())) }
+    }
+
+    /// The client's host name, if it has been looked up (see the `log_hostname` setting)
+    pub fn remote_hostname(&self) -> Option<Cow<'_, str>> {
+        unsafe { Self::string(pg_sys::pgx_Port_remote_hostname(self.as_ptr())) }
+    }
+
+    /// The client's port number, which is empty for a Unix-domain socket
+    pub fn remote_port(&self) -> Option<Cow<'_, str>> {
+        unsafe { Self::string(pg_sys::pgx_Port_remote_port(self.as_ptr())) }
+    }
+
+    /// The authentication method chosen for the client by `pg_hba.conf`, by the name it's given
+    /// there, such as `"scram-sha-256"` or `"trust"`
+    pub fn auth_method(&self) -> Option<&'static str> {
+        unsafe {
+            let method = pg_sys::pgx_Port_auth_method(self.as_ptr());
+            if method.is_null() {
+                None
+            } else {
+                CStr::from_ptr(method).to_str().ok()
+            }
+        }
+    }
+
+    /// Refuse the client's connection by raising a FATAL error with `message`, which is sent to
+    /// the client with the SQLSTATE `28000` (`invalid_authorization_specification`)
+    #[track_caller]
+    pub fn reject(&self, message: &str) -> ! {
+        let location = std::panic::Location::caller();
+        ereport(
+            PgLogLevel::FATAL,
+            PgSqlErrorCode::ERRCODE_INVALID_AUTHORIZATION_SPECIFICATION,
+            message,
+            location.file(),
+            location.line(),
+            location.column(),
+        );
+        unreachable!("FATAL ereport() returned")
+    }
+
+    unsafe fn string<'s>(string: *const c_char) -> Option<Cow<'s, str>> {
+        if string.is_null() {
+            None
+        } else {
+            Some(CStr::from_ptr(string).to_string_lossy())
+        }
+    }
+}
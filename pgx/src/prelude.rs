@@ -1,7 +1,8 @@
 // From "external" crates:
 pub use ::pgx_macros::{
-    extension_sql, extension_sql_file, pg_aggregate, pg_extern, pg_guard, pg_schema, pg_test,
-    pg_trigger, search_path, PostgresEnum, PostgresType,
+    extension_sql, extension_sql_file, pg_aggregate, pg_event_trigger, pg_extern, pg_fdw, pg_guard,
+    pg_index_am, pg_output_plugin, pg_procedure, pg_schema, pg_table_am, pg_test, pg_trigger,
+    search_path, PostgresEnum, PostgresType,
 };
 pub use ::pgx_pg_sys as pg_sys;
 
@@ -9,11 +9,16 @@ Use of this source code is governed by the MIT license that can be found in the
 
 //! Safe access to Postgres' *Server Programming Interface* (SPI).
 
-use crate::{pg_sys, FromDatum, IntoDatum, Json, PgMemoryContexts, PgOid};
+use crate::{
+    pg_sys, subtransaction, Explain, ExplainOptions, FromDatum, IntoDatum, Json, PgError,
+    PgMemoryContexts, PgOid,
+};
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::marker::PhantomData;
 use std::mem;
 use std::ops::{Index, IndexMut};
+use std::ptr::NonNull;
 
 /// These match the Postgres `#define`d constants prefixed `SPI_OK_*` that you can find in `pg_sys`.
 #[derive(Debug, PartialEq)]
@@ -44,23 +49,57 @@ pub enum SpiOk {
 /// These match the Postgres `#define`d constants prefixed `SPI_ERROR_*` that you can find in `pg_sys`.
 /// It is hypothetically possible for a Postgres-defined status code to be `0`, AKA `NULL`, however,
 /// this should not usually occur in Rust code paths. If it does happen, please report such bugs to the pgx repo.
-#[derive(Debug, PartialEq)]
-#[repr(i32)]
+///
+/// Additionally, [`SpiError::Postgres`] carries a Postgres ERROR that was caught while running a
+/// statement through [`SpiClient::try_select`], [`SpiClient::try_update`] or
+/// [`SpiClient::prepare`], and the `PreparedStatement*` variants describe arguments that don't
+/// match what a [`PreparedStatement`] expects.
+#[derive(thiserror::Error, Debug, PartialEq)]
 pub enum SpiError {
-    Connect = -1,
-    Copy = -2,
-    OpUnknown = -3,
-    Unconnected = -4,
+    #[error("SPI_ERROR_CONNECT")]
+    Connect,
+    #[error("SPI_ERROR_COPY")]
+    Copy,
+    #[error("SPI_ERROR_OPUNKNOWN")]
+    OpUnknown,
+    #[error("SPI_ERROR_UNCONNECTED")]
+    Unconnected,
     #[allow(dead_code)]
-    Cursor = -5, /* not used anymore */
-    Argument = -6,
-    Param = -7,
-    Transaction = -8,
-    NoAttribute = -9,
-    NoOutFunc = -10,
-    TypUnknown = -11,
-    RelDuplicate = -12,
-    RelNotFound = -13,
+    #[error("SPI_ERROR_CURSOR")]
+    Cursor, /* not used anymore */
+    #[error("SPI_ERROR_ARGUMENT")]
+    Argument,
+    #[error("SPI_ERROR_PARAM")]
+    Param,
+    #[error("SPI_ERROR_TRANSACTION")]
+    Transaction,
+    #[error("SPI_ERROR_NOATTRIBUTE")]
+    NoAttribute,
+    #[error("SPI_ERROR_NOOUTFUNC")]
+    NoOutFunc,
+    #[error("SPI_ERROR_TYPUNKNOWN")]
+    TypUnknown,
+    #[error("SPI_ERROR_REL_DUPLICATE")]
+    RelDuplicate,
+    #[error("SPI_ERROR_REL_NOT_FOUND")]
+    RelNotFound,
+    #[error("{message}")]
+    Postgres {
+        /// The five-character SQLSTATE code, such as `23505` for a unique violation
+        sqlstate: String,
+        message: String,
+        detail: Option<String>,
+        hint: Option<String>,
+    },
+    #[error("prepared statement expects {expected} arguments, but {got} were provided")]
+    PreparedStatementArgumentMismatch { expected: usize, got: usize },
+    #[error("prepared statement argument ${ordinal} has type oid {expected}, but a value of type oid {got} was provided")]
+    PreparedStatementArgumentTypeMismatch {
+        /// The 1-based position of the argument
+        ordinal: usize,
+        expected: pg_sys::Oid,
+        got: pg_sys::Oid,
+    },
 }
 
 #[derive(Debug)]
@@ -74,10 +113,19 @@ impl TryFrom<libc::c_int> for SpiOk {
         // Cast to assure that we're obeying repr rules even on platforms where c_ints are not 4 bytes wide,
         // as we don't support any but we may wish to in the future.
         match code as i32 {
-            err @ -13..=-1 => Err(Ok(
-                // SAFETY: These values are described in SpiError, thus they are inbounds for transmute
-                unsafe { mem::transmute::<i32, SpiError>(err) },
-            )),
+            pg_sys::SPI_ERROR_CONNECT => Err(Ok(SpiError::Connect)),
+            pg_sys::SPI_ERROR_COPY => Err(Ok(SpiError::Copy)),
+            pg_sys::SPI_ERROR_OPUNKNOWN => Err(Ok(SpiError::OpUnknown)),
+            pg_sys::SPI_ERROR_UNCONNECTED => Err(Ok(SpiError::Unconnected)),
+            pg_sys::SPI_ERROR_CURSOR => Err(Ok(SpiError::Cursor)),
+            pg_sys::SPI_ERROR_ARGUMENT => Err(Ok(SpiError::Argument)),
+            pg_sys::SPI_ERROR_PARAM => Err(Ok(SpiError::Param)),
+            pg_sys::SPI_ERROR_TRANSACTION => Err(Ok(SpiError::Transaction)),
+            pg_sys::SPI_ERROR_NOATTRIBUTE => Err(Ok(SpiError::NoAttribute)),
+            pg_sys::SPI_ERROR_NOOUTFUNC => Err(Ok(SpiError::NoOutFunc)),
+            pg_sys::SPI_ERROR_TYPUNKNOWN => Err(Ok(SpiError::TypUnknown)),
+            pg_sys::SPI_ERROR_REL_DUPLICATE => Err(Ok(SpiError::RelDuplicate)),
+            pg_sys::SPI_ERROR_REL_NOT_FOUND => Err(Ok(SpiError::RelNotFound)),
             ok @ 1..=18 => Ok(
                 //SAFETY: These values are described in SpiOk, thus they are inbounds for transmute
                 unsafe { mem::transmute::<i32, SpiOk>(ok) },
@@ -102,16 +150,163 @@ impl TryFrom<libc::c_int> for SpiError {
 
 pub struct Spi;
 
-pub struct SpiClient;
+/// A connection to SPI, handed to the closures given to [`Spi::connect`] and friends.
+///
+/// The `'conn` lifetime ends when the connection is finished, so neither the client nor the
+/// cursors borrowed from it can escape the closure.
+pub struct SpiClient<'conn> {
+    __marker: PhantomData<&'conn SpiConnection>,
+}
 
+/// A parsed and planned query, created with [`SpiClient::prepare`].
+///
+/// The underlying plan is saved with `SPI_keepplan()`, so a `PreparedStatement` can outlive the
+/// SPI connection that created it and be executed again from later calls in the same backend,
+/// for example by storing it in a `thread_local!` [`once_cell::unsync::OnceCell`].  The plan is
+/// freed when the `PreparedStatement` is dropped.
+///
+/// A `PreparedStatement` is neither `Send` nor `Sync`:  the plan belongs to the backend's main
+/// thread and must not be executed or freed from any other thread.
 #[derive(Debug)]
-pub struct SpiTupleTable {
+pub struct PreparedStatement {
+    plan: NonNull<pg_sys::_SPI_plan>,
+    argtypes: Vec<pg_sys::Oid>,
+}
+
+/// The result of a SPI statement.
+///
+/// A `SpiTupleTable` borrows the [`SpiClient`] that ran the statement, as do the rows read from
+/// it, so none of them can outlive the SPI connection or be held across [`SpiClient::commit`] or
+/// [`SpiClient::rollback`], which free them.
+#[derive(Debug)]
+pub struct SpiTupleTable<'client> {
     #[allow(dead_code)]
     status_code: SpiOk,
     table: *mut pg_sys::SPITupleTable,
     size: usize,
     tupdesc: Option<pg_sys::TupleDesc>,
     current: isize,
+    __marker: PhantomData<&'client SpiClient<'client>>,
+}
+
+/// An open SPI cursor (a Postgres "portal"), created with [`SpiClient::open_cursor`].
+///
+/// Rather than materializing an entire result set, a cursor hands out rows in batches with
+/// [`SpiCursor::fetch`] or [`SpiCursor::iter`].  The cursor is closed when it is dropped, unless
+/// it was detached with [`SpiCursor::detach_into_name`], in which case it remains open until the
+/// end of the current transaction and can be found again with [`SpiClient::find_cursor`].
+///
+/// A cursor borrows the [`SpiClient`] it was opened with, so it can't outlive the SPI connection
+/// or be held across [`SpiClient::commit`] or [`SpiClient::rollback`], both of which close it.
+#[derive(Debug)]
+pub struct SpiCursor<'client> {
+    portal: NonNull<pg_sys::PortalData>,
+    __marker: PhantomData<&'client SpiClient<'client>>,
+}
+
+/// Iterates over the rows of a [`SpiCursor`], fetching them in fixed-size batches.
+///
+/// Only one batch is held in memory at a time:  it is freed before the next one is fetched, and
+/// when the `SpiCursorIter` is dropped.  Because of that, [`SpiCursorIter::next`] lends out each
+/// row only until it is called again, rather than implementing [`Iterator`].
+pub struct SpiCursorIter<'a, 'client> {
+    cursor: &'a mut SpiCursor<'client>,
+    batch_size: i64,
+    batch: Option<SpiTupleTable<'client>>,
+    row: Option<SpiHeapTupleData<'client>>,
+    exhausted: bool,
+}
+
+/// Arguments for a SPI query, as `(type oid, value)` pairs.
+///
+/// This is implemented for tuples (up to 16 elements) and slices of [`IntoDatum`] values, which
+/// derive each argument's type from [`IntoDatum::type_oid`], as well as for explicit
+/// `Vec<(PgOid, Option<pg_sys::Datum>)>` lists.  `None` and `()` mean "no arguments".
+///
+/// ## Examples
+///
+/// ```rust,no_run
+/// use pgx::*;
+///
+/// Spi::execute(|client| {
+///     let name = client
+///         .select("SELECT name FROM dogs WHERE id = $1 AND age > $2", None, (42, 3))
+///         .first()
+///         .get_one::<String>();
+/// });
+/// ```
+pub trait IntoSpiArgs {
+    /// Convert into argument pairs.  `None` means the query has no arguments.
+    fn into_spi_args(self) -> Option<Vec<(PgOid, Option<pg_sys::Datum>)>>;
+}
+
+impl IntoSpiArgs for Option<Vec<(PgOid, Option<pg_sys::Datum>)>> {
+    fn into_spi_args(self) -> Option<Vec<(PgOid, Option<pg_sys::Datum>)>> {
+        self
+    }
+}
+
+impl IntoSpiArgs for Vec<(PgOid, Option<pg_sys::Datum>)> {
+    fn into_spi_args(self) -> Option<Vec<(PgOid, Option<pg_sys::Datum>)>> {
+        Some(self)
+    }
+}
+
+impl IntoSpiArgs for () {
+    fn into_spi_args(self) -> Option<Vec<(PgOid, Option<pg_sys::Datum>)>> {
+        None
+    }
+}
+
+impl<T: IntoDatum + Clone> IntoSpiArgs for &[T] {
+    fn into_spi_args(self) -> Option<Vec<(PgOid, Option<pg_sys::Datum>)>> {
+        Some(
+            self.iter().map(|arg| (PgOid::from(T::type_oid()), arg.clone().into_datum())).collect(),
+        )
+    }
+}
+
+seq_macro::seq!(I in 0..16 {
+    #(
+        seq_macro::seq!(N in 0..=I {
+            impl<#(Arg~N: IntoDatum,)*> IntoSpiArgs for (#(Arg~N,)*) {
+                fn into_spi_args(self) -> Option<Vec<(PgOid, Option<pg_sys::Datum>)>> {
+                    Some(vec![
+                        #(
+                            (PgOid::from(Arg~N::type_oid()), self.N.into_datum()),
+                        )*
+                    ])
+                }
+            }
+        });
+    )*
+});
+
+/// A type that can be built from a single row of a [`SpiTupleTable`].
+///
+/// This is normally implemented with `#[derive(FromSpiRow)]`, which maps each struct field to
+/// the result column of the same name.  See [`SpiTupleTable::rows`].
+pub trait FromSpiRow: Sized {
+    fn from_spi_row(row: &SpiHeapTupleData) -> std::result::Result<Self, FromSpiRowError>;
+}
+
+/// If building a [`FromSpiRow`] type from a row fails, this is the set of possible reasons why.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum FromSpiRowError {
+    #[error("The column `{0}` is not present in the result set")]
+    NoSuchColumn(String),
+
+    #[error("The column `{column}` has type oid {type_oid}, which is not compatible with the Rust type `{rust_type}`")]
+    IncompatibleTypes { column: String, type_oid: pg_sys::Oid, rust_type: &'static str },
+
+    #[error("The column `{0}` is NULL, but its Rust type is not an `Option`")]
+    UnexpectedNull(String),
+}
+
+/// An iterator over the rows of a [`SpiTupleTable`], each converted into a [`FromSpiRow`] type.
+pub struct SpiRows<'client, T: FromSpiRow> {
+    table: SpiTupleTable<'client>,
+    __marker: std::marker::PhantomData<T>,
 }
 
 /// Represents a single `pg_sys::Datum` inside a `SpiHeapTupleData`
@@ -121,9 +316,10 @@ pub struct SpiHeapTupleDataEntry {
 }
 
 /// Represents the set of `pg_sys::Datum`s in a `pg_sys::HeapTuple`
-pub struct SpiHeapTupleData {
+pub struct SpiHeapTupleData<'client> {
     tupdesc: pg_sys::TupleDesc,
     entries: HashMap<usize, SpiHeapTupleDataEntry>,
+    __marker: PhantomData<&'client SpiClient<'client>>,
 }
 
 impl Spi {
@@ -160,17 +356,17 @@ impl Spi {
 
     pub fn get_one_with_args<A: FromDatum + IntoDatum>(
         query: &str,
-        args: Vec<(PgOid, Option<pg_sys::Datum>)>,
+        args: impl IntoSpiArgs,
     ) -> Option<A> {
-        Spi::connect(|client| Ok(client.select(query, Some(1), Some(args)).first().get_one()))
+        Spi::connect(|client| Ok(client.select(query, Some(1), args).first().get_one()))
     }
 
     pub fn get_two_with_args<A: FromDatum + IntoDatum, B: FromDatum + IntoDatum>(
         query: &str,
-        args: Vec<(PgOid, Option<pg_sys::Datum>)>,
+        args: impl IntoSpiArgs,
     ) -> (Option<A>, Option<B>) {
         Spi::connect(|client| {
-            let (a, b) = client.select(query, Some(1), Some(args)).first().get_two::<A, B>();
+            let (a, b) = client.select(query, Some(1), args).first().get_two::<A, B>();
             Ok(Some((a, b)))
         })
         .unwrap()
@@ -182,11 +378,10 @@ impl Spi {
         C: FromDatum + IntoDatum,
     >(
         query: &str,
-        args: Vec<(PgOid, Option<pg_sys::Datum>)>,
+        args: impl IntoSpiArgs,
     ) -> (Option<A>, Option<B>, Option<C>) {
         Spi::connect(|client| {
-            let (a, b, c) =
-                client.select(query, Some(1), Some(args)).first().get_three::<A, B, C>();
+            let (a, b, c) = client.select(query, Some(1), args).first().get_three::<A, B, C>();
             Ok(Some((a, b, c)))
         })
         .unwrap()
@@ -213,8 +408,37 @@ impl Spi {
         .unwrap()
     }
 
+    /// explain a query with the specified [`ExplainOptions`], returning the typed plan tree
+    ///
+    /// ## Examples
+    ///
+    /// ```rust,no_run
+    /// use pgx::*;
+    ///
+    /// let explain = Spi::explain_with(
+    ///     "SELECT * FROM dogs WHERE id = 42",
+    ///     ExplainOptions { analyze: true, ..Default::default() },
+    /// );
+    /// assert_eq!(explain.plan.node_type, "Index Scan");
+    /// assert_eq!(explain.plan.actual_rows, Some(1.0));
+    /// ```
+    pub fn explain_with(query: &str, options: ExplainOptions) -> Explain {
+        let json = Spi::connect(|mut client| {
+            let table = client
+                .update(&format!("EXPLAIN ({}) {}", options.to_sql(), query), None, None)
+                .first();
+            Ok(Some(table.get_one::<Json>().expect("failed to get json EXPLAIN result")))
+        })
+        .unwrap();
+
+        // the json output is an array with a single element
+        let mut explain = serde_json::from_value::<Vec<Explain>>(json.0)
+            .unwrap_or_else(|e| panic!("failed to deserialize EXPLAIN result: {}", e));
+        explain.pop().expect("EXPLAIN returned no plan")
+    }
+
     /// execute SPI commands via the provided `SpiClient`
-    pub fn execute<F: FnOnce(SpiClient) + std::panic::UnwindSafe>(f: F) {
+    pub fn execute<F: FnOnce(SpiClient<'_>) + std::panic::UnwindSafe>(f: F) {
         Spi::connect(|client| {
             f(client);
             Ok(Some(()))
@@ -225,40 +449,21 @@ impl Spi {
     /// automatically copied into the `CurrentMemoryContext` at the time of this function call
     pub fn connect<
         R: FromDatum + IntoDatum,
-        F: FnOnce(SpiClient) -> std::result::Result<Option<R>, SpiError>,
+        F: FnOnce(SpiClient<'_>) -> std::result::Result<Option<R>, SpiError>,
     >(
         f: F,
     ) -> Option<R> {
         let outer_memory_context =
             PgMemoryContexts::For(PgMemoryContexts::CurrentMemoryContext.value());
 
-        /// a struct to manage our SPI connection lifetime
-        struct SpiConnection;
-        impl SpiConnection {
-            /// Connect to Postgres' SPI system
-            fn connect() -> Self {
-                // connect to SPI
-                Spi::check_status(unsafe { pg_sys::SPI_connect() });
-                SpiConnection
-            }
-        }
-
-        impl Drop for SpiConnection {
-            /// when SpiConnection is dropped, we make sure to disconnect from SPI
-            fn drop(&mut self) {
-                // disconnect from SPI
-                Spi::check_status(unsafe { pg_sys::SPI_finish() });
-            }
-        }
-
         // connect to SPI
-        let _connection = SpiConnection::connect();
+        let connection = SpiConnection::connect();
 
         // run the provided closure within the memory context that SPI_connect()
         // just put us un.  We'll disconnect from SPI when the closure is finished.
         // If there's a panic or elog(ERROR), we don't care about also disconnecting from
         // SPI b/c Postgres will do that for us automatically
-        match f(SpiClient) {
+        match f(connection.client()) {
             // copy the result to the outer memory context we saved above
             Ok(result) => {
                 // we need to copy the resulting Datum into the outer memory context
@@ -292,23 +497,140 @@ impl Spi {
         }
     }
 
+    /// execute SPI commands via the provided `SpiClient` and return any owned Rust value
+    ///
+    /// Unlike [`Spi::connect`], the result isn't copied through a `Datum`, so it can be a `Vec`,
+    /// a `HashMap`, a tuple of `String`s, or a struct built with [`FromSpiRow`].
+    ///
+    /// The result must be `'static` so it can't borrow from SPI memory, and `Send` so it can't
+    /// hold onto the raw Postgres pointers inside types such as [`SpiTupleTable`] or
+    /// [`PgBox`][crate::PgBox], all of which are freed when SPI is disconnected.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust,no_run
+    /// use pgx::*;
+    ///
+    /// let names: Vec<String> = Spi::connect_owned(|client| {
+    ///     Ok(client
+    ///         .select("SELECT relname::text FROM pg_class", None, None)
+    ///         .filter_map(|row| row[1].value::<String>())
+    ///         .collect())
+    /// });
+    /// ```
+    pub fn connect_owned<
+        R: Send + 'static,
+        F: FnOnce(SpiClient<'_>) -> std::result::Result<R, SpiError>,
+    >(
+        f: F,
+    ) -> R {
+        // connect to SPI
+        let connection = SpiConnection::connect();
+
+        // the result lives in Rust's heap, not in SPI's memory context, so there's nothing to
+        // copy out before we disconnect
+        match f(connection.client()) {
+            Ok(result) => result,
+
+            // closure returned an error
+            Err(e) => panic!("{:?}", e),
+        }
+    }
+
+    /// execute SPI commands via the provided `SpiClient`, allowing the closure to end the current
+    /// transaction with [`SpiClient::commit`] or [`SpiClient::rollback`]
+    ///
+    /// This is meant to be used from a [`#[pg_procedure]`](crate::pg_procedure), which must take
+    /// a `pg_sys::FunctionCallInfo` argument and pass it along here.  Transaction control is only
+    /// possible when the procedure was `CALL`ed outside of an explicit transaction block (and not
+    /// from within a function or another atomic context).  Otherwise the connection is atomic and
+    /// committing or rolling back raises an "invalid transaction termination" ERROR, just as it
+    /// does in PL/pgSQL.
+    #[cfg(not(feature = "pg10"))]
+    pub fn execute_nonatomic<F: FnOnce(SpiClient<'_>)>(fcinfo: pg_sys::FunctionCallInfo, f: F) {
+        let connection = if Spi::is_nonatomic_call(fcinfo) {
+            SpiConnection::connect_nonatomic()
+        } else {
+            SpiConnection::connect()
+        };
+
+        f(connection.client());
+    }
+
+    /// Was this function invoked by a `CALL` that allows transaction control?
+    #[cfg(not(feature = "pg10"))]
+    fn is_nonatomic_call(fcinfo: pg_sys::FunctionCallInfo) -> bool {
+        unsafe {
+            if fcinfo.is_null() || (*fcinfo).context.is_null() {
+                return false;
+            }
+
+            let context = (*fcinfo).context;
+            (*context).type_ == pg_sys::NodeTag_T_CallContext
+                && !(*(context as *mut pg_sys::CallContext)).atomic
+        }
+    }
+
     pub fn check_status(status_code: i32) -> SpiOk {
-        match SpiOk::try_from(status_code) {
+        match Spi::try_check_status(status_code) {
             Ok(ok) => ok,
+            Err(code) => panic!("{code:?}"),
+        }
+    }
+
+    /// Like [`Spi::check_status`], but returns SPI error codes as an `Err` rather than panicking
+    ///
+    /// Unrecognized status codes still panic.
+    pub fn try_check_status(status_code: i32) -> std::result::Result<SpiOk, SpiError> {
+        match SpiOk::try_from(status_code) {
+            Ok(ok) => Ok(ok),
             Err(Err(UnknownVariant)) => panic!("unrecognized SPI status code: {status_code}"),
-            Err(Ok(code)) => panic!("{code:?}"),
+            Err(Ok(code)) => Err(code),
         }
     }
 }
 
-impl SpiClient {
+/// a struct to manage our SPI connection lifetime
+struct SpiConnection;
+impl SpiConnection {
+    /// Connect to Postgres' SPI system
+    fn connect() -> Self {
+        // connect to SPI
+        Spi::check_status(unsafe { pg_sys::SPI_connect() });
+        SpiConnection
+    }
+
+    /// Connect to Postgres' SPI system in a mode that allows transaction control
+    #[cfg(not(feature = "pg10"))]
+    fn connect_nonatomic() -> Self {
+        Spi::check_status(unsafe {
+            pg_sys::SPI_connect_ext(pg_sys::SPI_OPT_NONATOMIC as std::os::raw::c_int)
+        });
+        SpiConnection
+    }
+
+    /// a client whose lifetime is bound to this connection
+    fn client(&self) -> SpiClient<'_> {
+        SpiClient { __marker: PhantomData }
+    }
+}
+
+impl Drop for SpiConnection {
+    /// when SpiConnection is dropped, we make sure to disconnect from SPI
+    fn drop(&mut self) {
+        // disconnect from SPI
+        Spi::check_status(unsafe { pg_sys::SPI_finish() });
+    }
+}
+
+impl<'conn> SpiClient<'conn> {
     /// perform a SELECT statement
     pub fn select(
         &self,
         query: &str,
         limit: Option<i64>,
-        args: Option<Vec<(PgOid, Option<pg_sys::Datum>)>>,
-    ) -> SpiTupleTable {
+        args: impl IntoSpiArgs,
+    ) -> SpiTupleTable<'_> {
         // Postgres docs say:
         //
         //    It is generally unwise to mix read-only and read-write commands within a single function
@@ -320,7 +642,7 @@ impl SpiClient {
         // TODO:  can we detect if the command counter (or something?) has incremented and if yes
         //        then we set read_only=false, else we can set it to true?
         //        Is this even a good idea?
-        SpiClient::execute(query, false, limit, args)
+        SpiClient::execute(query, false, limit, args.into_spi_args())
     }
 
     /// perform any query (including utility statements) that modify the database in some way
@@ -328,17 +650,194 @@ impl SpiClient {
         &mut self,
         query: &str,
         limit: Option<i64>,
-        args: Option<Vec<(PgOid, Option<pg_sys::Datum>)>>,
-    ) -> SpiTupleTable {
-        SpiClient::execute(query, false, limit, args)
+        args: impl IntoSpiArgs,
+    ) -> SpiTupleTable<'_> {
+        SpiClient::execute(query, false, limit, args.into_spi_args())
+    }
+
+    /// perform a SELECT statement, returning any Postgres ERROR it raises as an `Err`
+    ///
+    /// The statement is run in its own subtransaction, which is rolled back if it fails.  This
+    /// allows the caller to inspect the error (via [`SpiError::Postgres`]) and carry on with
+    /// the current transaction.
+    pub fn try_select(
+        &self,
+        query: &str,
+        limit: Option<i64>,
+        args: impl IntoSpiArgs,
+    ) -> std::result::Result<SpiTupleTable<'_>, SpiError> {
+        SpiClient::try_execute(query, false, limit, args.into_spi_args())
+    }
+
+    /// perform any query (including utility statements) that modifies the database in some way,
+    /// returning any Postgres ERROR it raises as an `Err`
+    ///
+    /// As with [`SpiClient::try_select`], the statement is run in its own subtransaction.
+    pub fn try_update(
+        &mut self,
+        query: &str,
+        limit: Option<i64>,
+        args: impl IntoSpiArgs,
+    ) -> std::result::Result<SpiTupleTable<'_>, SpiError> {
+        SpiClient::try_execute(query, false, limit, args.into_spi_args())
+    }
+
+    /// prepare a statement for repeated execution, returning any Postgres ERROR raised while
+    /// parsing or planning it as an `Err`
+    ///
+    /// The query is parsed and planned once.  The returned [`PreparedStatement`] is kept via
+    /// `SPI_keepplan()` and so remains valid after this SPI connection is finished.  It can be
+    /// executed with [`SpiClient::select_prepared`] and [`SpiClient::update_prepared`].
+    pub fn prepare(
+        &self,
+        query: &str,
+        args: &[PgOid],
+    ) -> std::result::Result<PreparedStatement, SpiError> {
+        let src = std::ffi::CString::new(query).expect("query contained a null byte");
+        let mut argtypes = args.iter().map(|oid| oid.value()).collect::<Vec<_>>();
+
+        // like `SpiClient::try_execute()`, parse and plan in a subtransaction so that an ERROR
+        // (such as a syntax error) comes back to us rather than aborting the transaction
+        let plan = subtransaction(|| unsafe {
+            pg_sys::SPI_prepare(src.as_ptr(), argtypes.len() as i32, argtypes.as_mut_ptr())
+        })?;
+        let plan = match NonNull::new(plan) {
+            Some(plan) => plan,
+            None => {
+                // SPI_prepare() returns NULL on failure and sets SPI_result
+                Spi::try_check_status(unsafe { pg_sys::SPI_result })?;
+                panic!("SPI_prepare() returned a NULL plan")
+            }
+        };
+
+        // move the plan out of the SPI procedure memory context so it survives `SPI_finish()`
+        Spi::try_check_status(unsafe { pg_sys::SPI_keepplan(plan.as_ptr()) })?;
+
+        Ok(PreparedStatement { plan, argtypes })
+    }
+
+    /// perform a SELECT statement using a [`PreparedStatement`]
+    ///
+    /// If the number or the types of `args` don't match the statement's argument types, an
+    /// `Err` is returned and the statement is not run.  As with [`SpiClient::try_select`], the
+    /// statement is run in its own subtransaction, so any Postgres ERROR it raises is returned as
+    /// [`SpiError::Postgres`] and the current transaction carries on.
+    pub fn select_prepared(
+        &self,
+        statement: &PreparedStatement,
+        limit: Option<i64>,
+        args: impl IntoSpiArgs,
+    ) -> std::result::Result<SpiTupleTable<'_>, SpiError> {
+        // see the comment in `SpiClient::select()` as to why this isn't read-only
+        statement.execute(false, limit, args.into_spi_args())
+    }
+
+    /// perform any [`PreparedStatement`] that modifies the database in some way
+    ///
+    /// As with [`SpiClient::select_prepared`], mismatched `args` and any Postgres ERROR the
+    /// statement raises are returned as an `Err`.
+    pub fn update_prepared(
+        &mut self,
+        statement: &PreparedStatement,
+        limit: Option<i64>,
+        args: impl IntoSpiArgs,
+    ) -> std::result::Result<SpiTupleTable<'_>, SpiError> {
+        statement.execute(false, limit, args.into_spi_args())
+    }
+
+    /// commit the current transaction and start a new one
+    ///
+    /// Only possible within [`Spi::execute_nonatomic`].  Any [`SpiTupleTable`]s, rows and
+    /// cursors borrow the client, so they must be dropped before committing.
+    #[cfg(not(feature = "pg10"))]
+    pub fn commit(&mut self) {
+        unsafe {
+            pg_sys::SPI_commit();
+            pg_sys::SPI_start_transaction();
+        }
     }
 
-    fn execute(
+    /// roll back the current transaction and start a new one
+    ///
+    /// Only possible within [`Spi::execute_nonatomic`].  As with [`SpiClient::commit`], any
+    /// [`SpiTupleTable`]s, rows and cursors must be dropped first.
+    #[cfg(not(feature = "pg10"))]
+    pub fn rollback(&mut self) {
+        unsafe {
+            pg_sys::SPI_rollback();
+            pg_sys::SPI_start_transaction();
+        }
+    }
+
+    /// open a cursor for the specified query
+    ///
+    /// Rows are not read until they are fetched through the returned [`SpiCursor`].
+    pub fn open_cursor(&self, query: &str, args: impl IntoSpiArgs) -> SpiCursor<'_> {
+        let src = std::ffi::CString::new(query).expect("query contained a null byte");
+        let args = args.into_spi_args().unwrap_or_default();
+        let nargs = args.len();
+        let (mut argtypes, args): (Vec<_>, Vec<_>) =
+            args.into_iter().map(|(argtype, datum)| (argtype.value(), datum)).unzip();
+        let (mut datums, nulls) = SpiClient::prepare_datums(args);
+
+        let portal = unsafe {
+            pg_sys::SPI_cursor_open_with_args(
+                std::ptr::null_mut(), // let Postgres assign a name
+                src.as_ptr(),
+                nargs as i32,
+                argtypes.as_mut_ptr(),
+                datums.as_mut_ptr(),
+                nulls.as_ptr(),
+                false,
+                0,
+            )
+        };
+
+        SpiCursor::new(portal)
+    }
+
+    /// open a cursor for the specified [`PreparedStatement`]
+    ///
+    /// As with [`SpiClient::select_prepared`], mismatched `args` and any Postgres ERROR the
+    /// statement raises are returned as an `Err`.
+    pub fn open_cursor_prepared(
+        &self,
+        statement: &PreparedStatement,
+        args: impl IntoSpiArgs,
+    ) -> std::result::Result<SpiCursor<'_>, SpiError> {
+        let args = statement.check_args(args.into_spi_args())?;
+        let (mut datums, nulls) = SpiClient::prepare_datums(args);
+
+        let portal = unsafe {
+            pg_sys::SPI_cursor_open(
+                std::ptr::null_mut(), // let Postgres assign a name
+                statement.plan.as_ptr(),
+                datums.as_mut_ptr(),
+                nulls.as_ptr(),
+                false,
+            )
+        };
+
+        Ok(SpiCursor::new(portal))
+    }
+
+    /// find an already open cursor by its name
+    ///
+    /// This is typically a cursor that was previously detached with
+    /// [`SpiCursor::detach_into_name`], possibly from an earlier function call in the same
+    /// transaction.  Returns `None` if no cursor with that name exists.
+    pub fn find_cursor(&self, name: &str) -> Option<SpiCursor<'_>> {
+        let name = std::ffi::CString::new(name).expect("cursor name contained a null byte");
+        let portal = unsafe { pg_sys::SPI_cursor_find(name.as_ptr()) };
+        NonNull::new(portal).map(|portal| SpiCursor { portal, __marker: PhantomData })
+    }
+
+    fn execute<'a>(
         query: &str,
         read_only: bool,
         limit: Option<i64>,
         args: Option<Vec<(PgOid, Option<pg_sys::Datum>)>>,
-    ) -> SpiTupleTable {
+    ) -> SpiTupleTable<'a> {
         unsafe {
             pg_sys::SPI_tuptable = std::ptr::null_mut();
         }
@@ -347,27 +846,9 @@ impl SpiClient {
         let status_code = match args {
             Some(args) => {
                 let nargs = args.len();
-                let mut argtypes = vec![];
-                let mut datums = vec![];
-                let mut nulls = vec![];
-
-                for (argtype, datum) in args {
-                    argtypes.push(argtype.value());
-
-                    match datum {
-                        Some(datum) => {
-                            // ' ' here means that the datum is not null
-                            datums.push(datum);
-                            nulls.push(' ' as std::os::raw::c_char);
-                        }
-
-                        None => {
-                            // 'n' here means that the datum is null
-                            datums.push(pg_sys::Datum::from(0usize));
-                            nulls.push('n' as std::os::raw::c_char);
-                        }
-                    }
-                }
+                let (mut argtypes, args): (Vec<_>, Vec<_>) =
+                    args.into_iter().map(|(argtype, datum)| (argtype.value(), datum)).unzip();
+                let (mut datums, nulls) = SpiClient::prepare_datums(args);
 
                 unsafe {
                     pg_sys::SPI_execute_with_args(
@@ -384,6 +865,73 @@ impl SpiClient {
             None => unsafe { pg_sys::SPI_execute(src.as_ptr(), read_only, limit.unwrap_or(0)) },
         };
 
+        SpiClient::prepare_tuple_table(status_code)
+    }
+
+    fn try_execute<'a>(
+        query: &str,
+        read_only: bool,
+        limit: Option<i64>,
+        args: Option<Vec<(PgOid, Option<pg_sys::Datum>)>>,
+    ) -> std::result::Result<SpiTupleTable<'a>, SpiError> {
+        let status_code = subtransaction(move || {
+            let src = std::ffi::CString::new(query).expect("query contained a null byte");
+            let args = args.map(|args| {
+                let (argtypes, args): (Vec<_>, Vec<_>) =
+                    args.into_iter().map(|(argtype, datum)| (argtype.value(), datum)).unzip();
+                let (datums, nulls) = SpiClient::prepare_datums(args);
+                (argtypes, datums, nulls)
+            });
+
+            unsafe {
+                pg_sys::SPI_tuptable = std::ptr::null_mut();
+
+                match args {
+                    Some((mut argtypes, mut datums, nulls)) => pg_sys::SPI_execute_with_args(
+                        src.as_ptr(),
+                        argtypes.len() as i32,
+                        argtypes.as_mut_ptr(),
+                        datums.as_mut_ptr(),
+                        nulls.as_ptr(),
+                        read_only,
+                        limit.unwrap_or(0),
+                    ),
+                    None => pg_sys::SPI_execute(src.as_ptr(), read_only, limit.unwrap_or(0)),
+                }
+            }
+        })?;
+        Spi::try_check_status(status_code)?;
+        Ok(SpiClient::prepare_tuple_table(status_code))
+    }
+
+    /// split argument values into the parallel `Datum` and null-flag arrays that SPI wants
+    fn prepare_datums(
+        args: Vec<Option<pg_sys::Datum>>,
+    ) -> (Vec<pg_sys::Datum>, Vec<std::os::raw::c_char>) {
+        let mut datums = Vec::with_capacity(args.len());
+        let mut nulls = Vec::with_capacity(args.len());
+
+        for datum in args {
+            match datum {
+                Some(datum) => {
+                    // ' ' here means that the datum is not null
+                    datums.push(datum);
+                    nulls.push(' ' as std::os::raw::c_char);
+                }
+
+                None => {
+                    // 'n' here means that the datum is null
+                    datums.push(pg_sys::Datum::from(0usize));
+                    nulls.push('n' as std::os::raw::c_char);
+                }
+            }
+        }
+
+        (datums, nulls)
+    }
+
+    /// wrap the results of the SPI command that just ran in a [`SpiTupleTable`]
+    fn prepare_tuple_table<'a>(status_code: i32) -> SpiTupleTable<'a> {
         SpiTupleTable {
             status_code: Spi::check_status(status_code),
             table: unsafe { pg_sys::SPI_tuptable },
@@ -394,11 +942,209 @@ impl SpiClient {
                 Some(unsafe { (*pg_sys::SPI_tuptable).tupdesc })
             },
             current: -1,
+            __marker: PhantomData,
+        }
+    }
+}
+
+impl From<PgError> for SpiError {
+    fn from(error: PgError) -> Self {
+        let PgError { sqlstate, message, detail, hint, .. } = error;
+        SpiError::Postgres { sqlstate, message, detail, hint }
+    }
+}
+
+impl PreparedStatement {
+    /// How many arguments does this statement expect?
+    pub fn nargs(&self) -> usize {
+        self.argtypes.len()
+    }
+
+    /// The type oids of the arguments this statement expects, in order
+    pub fn argtypes(&self) -> &[pg_sys::Oid] {
+        &self.argtypes
+    }
+
+    fn execute<'a>(
+        &self,
+        read_only: bool,
+        limit: Option<i64>,
+        args: Option<Vec<(PgOid, Option<pg_sys::Datum>)>>,
+    ) -> std::result::Result<SpiTupleTable<'a>, SpiError> {
+        let args = self.check_args(args)?;
+        let (mut datums, nulls) = SpiClient::prepare_datums(args);
+
+        // like `SpiClient::try_execute()`, run the plan in a subtransaction so that an ERROR
+        // (such as a constraint violation) comes back to us rather than aborting the transaction
+        let status_code = subtransaction(|| unsafe {
+            pg_sys::SPI_tuptable = std::ptr::null_mut();
+
+            pg_sys::SPI_execute_plan(
+                self.plan.as_ptr(),
+                datums.as_mut_ptr(),
+                nulls.as_ptr(),
+                read_only,
+                limit.unwrap_or(0),
+            )
+        })?;
+        Spi::try_check_status(status_code)?;
+        Ok(SpiClient::prepare_tuple_table(status_code))
+    }
+
+    /// make sure `args` match this statement's argument types, returning just their values
+    fn check_args(
+        &self,
+        args: Option<Vec<(PgOid, Option<pg_sys::Datum>)>>,
+    ) -> std::result::Result<Vec<Option<pg_sys::Datum>>, SpiError> {
+        let args = args.unwrap_or_default();
+        if args.len() != self.argtypes.len() {
+            return Err(SpiError::PreparedStatementArgumentMismatch {
+                expected: self.argtypes.len(),
+                got: args.len(),
+            });
         }
+
+        args.into_iter()
+            .zip(self.argtypes.iter())
+            .enumerate()
+            .map(|(i, ((argtype, datum), expected))| {
+                if argtype.value() != *expected {
+                    Err(SpiError::PreparedStatementArgumentTypeMismatch {
+                        ordinal: i + 1,
+                        expected: *expected,
+                        got: argtype.value(),
+                    })
+                } else {
+                    Ok(datum)
+                }
+            })
+            .collect()
+    }
+}
+
+impl Drop for PreparedStatement {
+    /// when a PreparedStatement is dropped we release its saved plan
+    fn drop(&mut self) {
+        unsafe {
+            pg_sys::SPI_freeplan(self.plan.as_ptr());
+        }
+    }
+}
+
+impl<'client> SpiCursor<'client> {
+    fn new(portal: pg_sys::Portal) -> Self {
+        match NonNull::new(portal) {
+            Some(portal) => SpiCursor { portal, __marker: PhantomData },
+            // SPI reports failures to open a cursor by raising an ERROR, so this shouldn't happen
+            None => panic!("SPI failed to open a cursor"),
+        }
+    }
+
+    /// The name of the underlying portal
+    pub fn name(&self) -> &str {
+        unsafe {
+            std::ffi::CStr::from_ptr(self.portal.as_ref().name)
+                .to_str()
+                .expect("cursor name is not valid UTF8")
+        }
+    }
+
+    /// fetch up to `count` rows from the cursor, moving forward
+    pub fn fetch(&mut self, count: i64) -> SpiTupleTable<'client> {
+        unsafe {
+            pg_sys::SPI_tuptable = std::ptr::null_mut();
+            pg_sys::SPI_cursor_fetch(self.portal.as_ptr(), true, count);
+        }
+        SpiClient::prepare_tuple_table(SpiOk::Fetch as i32)
+    }
+
+    /// skip over up to `count` rows without fetching them
+    pub fn move_forward(&mut self, count: i64) {
+        unsafe { pg_sys::SPI_cursor_move(self.portal.as_ptr(), true, count) }
+    }
+
+    /// move backward by up to `count` rows
+    ///
+    /// Not every query plan supports backward scans, in which case Postgres will raise an ERROR.
+    pub fn move_backward(&mut self, count: i64) {
+        unsafe { pg_sys::SPI_cursor_move(self.portal.as_ptr(), false, count) }
+    }
+
+    /// iterate over the remaining rows of the cursor, fetching `batch_size` rows at a time
+    pub fn iter(&mut self, batch_size: i64) -> SpiCursorIter<'_, 'client> {
+        assert!(batch_size > 0, "cursor batch size must be positive");
+        SpiCursorIter { cursor: self, batch_size, batch: None, row: None, exhausted: false }
+    }
+
+    /// leave the cursor open and return its name
+    ///
+    /// The cursor will stay open until the end of the current transaction, and can be found
+    /// again with [`SpiClient::find_cursor`], even from a different SPI connection.
+    pub fn detach_into_name(self) -> String {
+        let name = self.name().to_string();
+        // don't run our Drop impl, which would close the cursor
+        mem::forget(self);
+        name
     }
 }
 
-impl SpiTupleTable {
+impl Drop for SpiCursor<'_> {
+    /// when a SpiCursor is dropped we close its portal
+    fn drop(&mut self) {
+        unsafe {
+            pg_sys::SPI_cursor_close(self.portal.as_ptr());
+        }
+    }
+}
+
+impl<'client> SpiCursorIter<'_, 'client> {
+    /// advance to the next row of the cursor, fetching another batch when the current one runs out
+    ///
+    /// The returned row borrows from the current batch, which is freed once the iterator moves
+    /// past it, so the row must be used (or its values copied out) before calling `next()` again.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<&SpiHeapTupleData<'client>> {
+        self.row = None;
+
+        loop {
+            if let Some(row) = self.batch.as_mut().and_then(|batch| batch.next()) {
+                self.row = Some(row);
+                return self.row.as_ref();
+            }
+
+            // nothing borrows from the finished batch anymore, so release it before fetching
+            // the next one
+            self.free_batch();
+
+            if self.exhausted {
+                return None;
+            }
+
+            let batch = self.cursor.fetch(self.batch_size);
+            if (batch.len() as i64) < self.batch_size {
+                // the cursor has no more rows after this batch
+                self.exhausted = true;
+            }
+            self.batch = Some(batch);
+        }
+    }
+
+    fn free_batch(&mut self) {
+        if let Some(batch) = self.batch.take() {
+            unsafe { pg_sys::SPI_freetuptable(batch.table) }
+        }
+    }
+}
+
+impl Drop for SpiCursorIter<'_, '_> {
+    /// when a SpiCursorIter is dropped we free the batch it was reading from
+    fn drop(&mut self) {
+        self.row = None;
+        self.free_batch();
+    }
+}
+
+impl<'client> SpiTupleTable<'client> {
     /// `SpiTupleTable`s are positioned before the start, for iteration purposes.
     ///
     /// This method moves the position to the first row.  If there are no rows, this
@@ -417,6 +1163,33 @@ impl SpiTupleTable {
         self.len() == 0
     }
 
+    /// Iterate over the rows of this table, converting each one into a `T`.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust,no_run
+    /// use pgx::*;
+    ///
+    /// #[derive(FromSpiRow)]
+    /// struct Dog {
+    ///     name: String,
+    ///     #[pgx(rename = "age_in_years")]
+    ///     age: Option<i32>,
+    /// }
+    ///
+    /// let dogs = Spi::connect(|client| {
+    ///     let dogs = client
+    ///         .select("SELECT name, age_in_years FROM dogs", None, None)
+    ///         .rows::<Dog>()
+    ///         .collect::<Result<Vec<_>, _>>()
+    ///         .expect("failed to read dogs");
+    ///     Ok(Some(dogs.len() as i64))
+    /// });
+    /// ```
+    pub fn rows<T: FromSpiRow>(self) -> SpiRows<'client, T> {
+        SpiRows { table: self, __marker: std::marker::PhantomData }
+    }
+
     pub fn get_one<A: FromDatum>(&self) -> Option<A> {
         self.get_datum(1)
     }
@@ -436,11 +1209,11 @@ impl SpiTupleTable {
         (a, b, c)
     }
 
-    pub fn get_heap_tuple(&self) -> Option<SpiHeapTupleData> {
+    pub fn get_heap_tuple(&self) -> Option<SpiHeapTupleData<'client>> {
         if self.current < 0 {
             panic!("SpiTupleTable positioned before start")
         }
-        if self.current as u64 >= unsafe { pg_sys::SPI_processed } {
+        if self.current as usize >= self.size {
             None
         } else {
             match self.tupdesc {
@@ -460,7 +1233,7 @@ impl SpiTupleTable {
         if self.current < 0 {
             panic!("SpiTupleTable positioned before start")
         }
-        if self.current as u64 >= unsafe { pg_sys::SPI_processed } {
+        if self.current as usize >= self.size {
             None
         } else {
             match self.tupdesc {
@@ -489,10 +1262,11 @@ impl SpiTupleTable {
     }
 }
 
-impl SpiHeapTupleData {
+impl SpiHeapTupleData<'_> {
     /// Create a new `SpiHeapTupleData` from its constituent parts
     pub unsafe fn new(tupdesc: pg_sys::TupleDesc, htup: *mut pg_sys::HeapTupleData) -> Self {
-        let mut data = SpiHeapTupleData { tupdesc, entries: HashMap::default() };
+        let mut data =
+            SpiHeapTupleData { tupdesc, entries: HashMap::default(), __marker: PhantomData };
 
         for i in 1..=tupdesc.as_ref().unwrap().natts {
             let mut is_null = false;
@@ -548,6 +1322,26 @@ impl SpiHeapTupleData {
         }
     }
 
+    /// Get a typed value from this HeapTuple by its column name, checking that the column's type
+    /// is compatible with `T`.
+    ///
+    /// This is primarily used by `#[derive(FromSpiRow)]`.
+    pub fn get_column<T: FromDatum + IntoDatum + 'static>(
+        &self,
+        name: &str,
+    ) -> std::result::Result<Option<T>, FromSpiRowError> {
+        let entry =
+            self.by_name(name).map_err(|_| FromSpiRowError::NoSuchColumn(name.to_string()))?;
+        if !T::is_compatible_with(entry.type_oid) {
+            return Err(FromSpiRowError::IncompatibleTypes {
+                column: name.to_string(),
+                type_oid: entry.type_oid,
+                rust_type: std::any::type_name::<T>(),
+            });
+        }
+        Ok(entry.value())
+    }
+
     /// Get a mutable typed Datum value from this HeapTuple by its ordinal position.  
     ///
     /// The ordinal position is 1-based.
@@ -640,7 +1434,7 @@ impl SpiHeapTupleDataEntry {
 /// Provide ordinal indexing into a `SpiHeapTupleData`.
 ///
 /// If the index is out of bounds, it will panic
-impl Index<usize> for SpiHeapTupleData {
+impl Index<usize> for SpiHeapTupleData<'_> {
     type Output = SpiHeapTupleDataEntry;
 
     fn index(&self, index: usize) -> &Self::Output {
@@ -651,7 +1445,7 @@ impl Index<usize> for SpiHeapTupleData {
 /// Provide named indexing into a `SpiHeapTupleData`.  
 ///
 /// If the field name doesn't exist, it will panic
-impl Index<&str> for SpiHeapTupleData {
+impl Index<&str> for SpiHeapTupleData<'_> {
     type Output = SpiHeapTupleDataEntry;
 
     fn index(&self, index: &str) -> &Self::Output {
@@ -662,7 +1456,7 @@ impl Index<&str> for SpiHeapTupleData {
 /// Provide mutable ordinal indexing into a `SpiHeapTupleData`.  
 ///
 /// If the index is out of bounds, it will panic
-impl IndexMut<usize> for SpiHeapTupleData {
+impl IndexMut<usize> for SpiHeapTupleData<'_> {
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
         self.by_ordinal_mut(index).expect("invalid ordinal value")
     }
@@ -671,14 +1465,28 @@ impl IndexMut<usize> for SpiHeapTupleData {
 /// Provide mutable named indexing into a `SpiHeapTupleData`.  
 ///
 /// If the field name doesn't exist, it will panic
-impl IndexMut<&str> for SpiHeapTupleData {
+impl IndexMut<&str> for SpiHeapTupleData<'_> {
     fn index_mut(&mut self, index: &str) -> &mut Self::Output {
         self.by_name_mut(index).expect("invalid field name")
     }
 }
 
-impl Iterator for SpiTupleTable {
-    type Item = SpiHeapTupleData;
+impl<T: FromSpiRow> Iterator for SpiRows<'_, T> {
+    type Item = std::result::Result<T, FromSpiRowError>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.table.next().map(|row| T::from_spi_row(&row))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.table.size_hint()
+    }
+}
+
+impl<'client> Iterator for SpiTupleTable<'client> {
+    type Item = SpiHeapTupleData<'client>;
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
@@ -0,0 +1,144 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+
+//! Safe wrappers around Postgres' internal subtransactions, which behave like SQL `SAVEPOINT`s
+
+use crate::pg_sys;
+
+/// A Postgres ERROR that was caught and rolled back by [`subtransaction`].
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("{message}")]
+pub struct PgError {
+    /// The five-character SQLSTATE code, such as `23505` for a unique violation
+    pub sqlstate: String,
+    pub message: String,
+    pub detail: Option<String>,
+    pub hint: Option<String>,
+    /// The source file that raised the error, if known
+    pub filename: Option<String>,
+    /// The line of `filename` that raised the error
+    pub lineno: i32,
+}
+
+impl PgError {
+    /// Build a [`PgError`] from a Postgres `ErrorData`
+    ///
+    /// ## Safety
+    ///
+    /// The string pointers in `edata` must be valid or NULL
+    pub unsafe fn from_error_data(edata: &pg_sys::ErrorData) -> Self {
+        unsafe fn to_string(s: *const std::os::raw::c_char) -> Option<String> {
+            if s.is_null() {
+                None
+            } else {
+                Some(std::ffi::CStr::from_ptr(s).to_string_lossy().into_owned())
+            }
+        }
+
+        PgError {
+            sqlstate: unpack_sql_state(edata.sqlerrcode),
+            message: to_string(edata.message).unwrap_or_default(),
+            detail: to_string(edata.detail),
+            hint: to_string(edata.hint),
+            filename: to_string(edata.filename),
+            lineno: edata.lineno,
+        }
+    }
+}
+
+/// Convert a packed SQLSTATE into its five-character form, in the same manner as Postgres'
+/// `unpack_sql_state()`
+pub fn unpack_sql_state(mut sql_state: i32) -> String {
+    let mut unpacked = String::with_capacity(5);
+    for _ in 0..5 {
+        unpacked.push((((sql_state & 0x3F) + '0' as i32) as u8) as char);
+        sql_state >>= 6;
+    }
+    unpacked
+}
+
+/// Run `f` inside an internal subtransaction, like a SQL `SAVEPOINT`.
+///
+/// If `f` completes, the subtransaction is committed and its result is returned.  If `f` raises
+/// a Postgres ERROR, the subtransaction is rolled back, the error is cleared, and it is returned
+/// as an `Err(PgError)` so the outer transaction can carry on.  If `f` panics, the subtransaction
+/// is rolled back and the panic continues on its way.
+///
+/// In all cases the `CurrentMemoryContext` and `CurrentResourceOwner` are restored to what they
+/// were when this function was called.
+///
+/// `subtransaction` can be used from within [`Spi::connect`][crate::Spi::connect], and can itself
+/// open SPI connections.
+///
+/// ## Examples
+///
+/// ```rust,no_run
+/// use pgx::*;
+///
+/// #[pg_extern]
+/// fn import(ids: Vec<i32>) -> i64 {
+///     let mut imported = 0;
+///     for id in ids {
+///         let result = subtransaction(|| {
+///             Spi::run(&format!("INSERT INTO imports VALUES ({})", id));
+///         });
+///         match result {
+///             Ok(()) => imported += 1,
+///             Err(e) => warning!("skipping {}: {}", id, e),
+///         }
+///     }
+///     imported
+/// }
+/// ```
+pub fn subtransaction<R, F: FnOnce() -> R>(f: F) -> Result<R, PgError> {
+    unsafe {
+        let outer_memory_context = pg_sys::CurrentMemoryContext;
+        let outer_resource_owner = pg_sys::CurrentResourceOwner;
+
+        pg_sys::BeginInternalSubTransaction(std::ptr::null_mut());
+        // run the closure in the caller's memory context, not the subtransaction's
+        pg_sys::CurrentMemoryContext = outer_memory_context;
+
+        // we only ever swallow Postgres ERRORs, which are cleaned up by the rollback below, so
+        // there's no broken state for the caller to observe
+        let result = pg_sys::pg_try(std::panic::AssertUnwindSafe(f)).into_result();
+        match result {
+            Ok(result) => {
+                pg_sys::ReleaseCurrentSubTransaction();
+                pg_sys::CurrentMemoryContext = outer_memory_context;
+                pg_sys::CurrentResourceOwner = outer_resource_owner;
+                Ok(result)
+            }
+
+            // a Postgres ERROR, which we turn into an Err
+            Err(e) if e.is::<pg_sys::JumpContext>() => {
+                // `CopyErrorData()` can't be called from the ErrorContext
+                pg_sys::CurrentMemoryContext = outer_memory_context;
+                let edata = pg_sys::CopyErrorData();
+                pg_sys::FlushErrorState();
+
+                pg_sys::RollbackAndReleaseCurrentSubTransaction();
+                pg_sys::CurrentMemoryContext = outer_memory_context;
+                pg_sys::CurrentResourceOwner = outer_resource_owner;
+
+                let error = PgError::from_error_data(edata.as_ref().unwrap());
+                pg_sys::FreeErrorData(edata);
+                Err(error)
+            }
+
+            // a Rust panic, which we let continue on its way
+            Err(e) => {
+                pg_sys::RollbackAndReleaseCurrentSubTransaction();
+                pg_sys::CurrentMemoryContext = outer_memory_context;
+                pg_sys::CurrentResourceOwner = outer_resource_owner;
+                std::panic::resume_unwind(e)
+            }
+        }
+    }
+}
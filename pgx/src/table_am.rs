@@ -0,0 +1,1104 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+
+//! Table access methods, which store the rows of tables in something other than Postgres' heap
+//!
+//! An access method implements [`TableAmRoutine`] in an `impl` block marked `#[pg_table_am]`,
+//! which adds its handler function and the `CREATE ACCESS METHOD ... TYPE TABLE` statement that
+//! uses it to the extension's schema.  The associated functions store, change, and size the rows
+//! of tables of the access method, and the methods scan one of them, on an instance of the access
+//! method made by [`TableAmRoutine::begin_scan`] for each scan.
+//!
+//! Each row has a tid, which the access method chooses when it stores the row and which indexes
+//! of the table point at.  Rows are passed around in `TupleTableSlot`s, which scans fill with
+//! [`TupleTableSlotRef::store_datums`] and [`TupleTableSlotRef::set_tid`], and the rest of
+//! Postgres sees as heap tuples.
+//!
+//! Table access methods exist as of Postgres 12.
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//! use std::sync::Mutex;
+//!
+//! /// Every row, as `(table, value)`, with its position in the log as its tid
+//! static LOG: Mutex<Vec<(pg_sys::Oid, Option<i64>)>> = Mutex::new(Vec::new());
+//!
+//! fn tid_of(position: usize) -> pg_sys::ItemPointerData {
+//!     let mut tid = pg_sys::ItemPointerData::default();
+//!     u64_to_item_pointer(position as u64 + 1, &mut tid);
+//!     tid
+//! }
+//!
+//! /// Appends the rows of its tables, which have a single `int8` column, to a log in memory
+//! struct AppendLog {
+//!     position: usize,
+//! }
+//!
+//! #[pg_table_am]
+//! impl TableAmRoutine for AppendLog {
+//!     const NAME: &'static str = "append_log";
+//!
+//!     fn begin_scan(_scan: &mut TableScanDescRef<'_>) -> Self {
+//!         AppendLog { position: 0 }
+//!     }
+//!
+//!     fn rescan(&mut self, _scan: &mut TableScanDescRef<'_>) {
+//!         self.position = 0;
+//!     }
+//!
+//!     fn scan_next(
+//!         &mut self,
+//!         scan: &mut TableScanDescRef<'_>,
+//!         _direction: pg_sys::ScanDirection,
+//!         slot: &mut TupleTableSlotRef<'_>,
+//!     ) -> bool {
+//!         let table = scan.relation().oid();
+//!         let log = LOG.lock().unwrap();
+//!         while let Some((row_table, value)) = log.get(self.position) {
+//!             self.position += 1;
+//!             if *row_table == table {
+//!                 slot.store_datums([value.into_datum()]);
+//!                 slot.set_tid(tid_of(self.position - 1));
+//!                 return true;
+//!             }
+//!         }
+//!         false
+//!     }
+//!
+//!     fn tuple_insert(rel: &PgRelation, slot: &mut TupleTableSlotRef<'_>, _cid: pg_sys::CommandId) {
+//!         let mut log = LOG.lock().unwrap();
+//!         log.push((rel.oid(), slot.get_by_index(std::num::NonZeroUsize::new(1).unwrap()).unwrap()));
+//!         slot.set_tid(tid_of(log.len() - 1));
+//!     }
+//!
+//!     fn tuple_fetch_row_version(
+//!         rel: &PgRelation,
+//!         tid: pg_sys::ItemPointerData,
+//!         _snapshot: pg_sys::Snapshot,
+//!         slot: &mut TupleTableSlotRef<'_>,
+//!     ) -> bool {
+//!         let log = LOG.lock().unwrap();
+//!         match (item_pointer_to_u64(tid) as usize).checked_sub(1).and_then(|p| log.get(p)) {
+//!             Some((table, value)) if *table == rel.oid() => {
+//!                 slot.store_datums([value.into_datum()]);
+//!                 true
+//!             }
+//!             _ => false,
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! With the extension installed, the access method is used like any other:
+//!
+//! ```sql
+//! CREATE TABLE events (id int8) USING append_log;
+//! ```
+use crate::index_am::ScanKey;
+use crate::log::raise;
+use crate::TupleTableSlotRef;
+use crate::{pg_sys, PgBox, PgMemoryContexts, PgRelation, PgSqlErrorCode};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::os::raw::{c_char, c_int, c_void};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A table access method
+///
+/// The associated functions store, change, and size the rows of tables of the access method, and
+/// the methods are called on an instance of it made by [`TableAmRoutine::begin_scan`] for each
+/// scan of one of them.
+///
+/// The access method doesn't support `INSERT ... ON CONFLICT`, `TABLESAMPLE`, `CLUSTER`,
+/// `VACUUM FULL`, moving tables to another tablespace, partial indexes, or building indexes
+/// `CONCURRENTLY`.
+pub trait TableAmRoutine: Sized + 'static {
+    /// The access method's name, as given to `CREATE ACCESS METHOD`
+    const NAME: &'static str;
+
+    /// Prepare to scan the table
+    ///
+    /// This is also called for the scans of `ANALYZE`, which don't return any rows.
+    fn begin_scan(scan: &mut TableScanDescRef<'_>) -> Self;
+
+    /// Restart the scan from the beginning, with its keys, if it has any, possibly changed
+    fn rescan(&mut self, scan: &mut TableScanDescRef<'_>);
+
+    /// Store the next row of the scan in `direction` into `slot`, with its tid, returning whether
+    /// there was one
+    ///
+    /// The row must be visible to the scan's snapshot.
+    fn scan_next(
+        &mut self,
+        scan: &mut TableScanDescRef<'_>,
+        direction: pg_sys::ScanDirection,
+        slot: &mut TupleTableSlotRef<'_>,
+    ) -> bool;
+
+    /// Finish the scan
+    fn end_scan(&mut self, _scan: &mut TableScanDescRef<'_>) {}
+
+    /// Store the row in `slot` into the table, and set the slot's tid to the tid it was given
+    fn tuple_insert(rel: &PgRelation, slot: &mut TupleTableSlotRef<'_>, cid: pg_sys::CommandId);
+
+    /// Store the rows in `slots`, such as those of a `COPY`, into the table, and set each slot's
+    /// tid to the tid its row was given
+    ///
+    /// The default stores each with [`TableAmRoutine::tuple_insert`].
+    fn multi_insert(rel: &PgRelation, slots: &mut [TupleTableSlotRef<'_>], cid: pg_sys::CommandId) {
+        for slot in slots {
+            Self::tuple_insert(rel, slot, cid)
+        }
+    }
+
+    /// Delete the row `tid` from the table, returning whether it was still there
+    ///
+    /// The default raises an error, for append-only access methods.
+    fn tuple_delete(
+        _rel: &PgRelation,
+        _tid: pg_sys::ItemPointerData,
+        _cid: pg_sys::CommandId,
+        _snapshot: pg_sys::Snapshot,
+    ) -> bool {
+        not_supported::<Self>("DELETE")
+    }
+
+    /// Replace the row `otid` of the table with the row in `slot`, and set the slot's tid to the
+    /// tid it was given, returning whether the old row was still there
+    ///
+    /// The indexes of the table get entries for the new row.  The default raises an error, for
+    /// append-only access methods.
+    fn tuple_update(
+        _rel: &PgRelation,
+        _otid: pg_sys::ItemPointerData,
+        _slot: &mut TupleTableSlotRef<'_>,
+        _cid: pg_sys::CommandId,
+        _snapshot: pg_sys::Snapshot,
+    ) -> bool {
+        not_supported::<Self>("UPDATE")
+    }
+
+    /// Store the row `tid` of the table into `slot`, returning whether there is one that's visible
+    /// to `snapshot`
+    ///
+    /// This is how index scans, `UPDATE`, and `DELETE` get rows, and how Postgres checks whether
+    /// a row it already has is visible, or whether a tid it was given is one of the table's, so
+    /// `tid` can be any tid at all.
+    fn tuple_fetch_row_version(
+        rel: &PgRelation,
+        tid: pg_sys::ItemPointerData,
+        snapshot: pg_sys::Snapshot,
+        slot: &mut TupleTableSlotRef<'_>,
+    ) -> bool;
+
+    /// Create the new storage `newrnode` of the table, which `CREATE TABLE` and `TRUNCATE` give
+    /// it, returning the oldest transaction id and multixact id it holds (or the invalid ones, if
+    /// it doesn't keep them)
+    ///
+    /// The default creates the storage's files, as the heap does, for access methods that keep
+    /// their rows in them, or that don't use them.
+    fn relation_set_new_filenode(
+        _rel: &PgRelation,
+        newrnode: &pg_sys::RelFileNode,
+        persistence: c_char,
+    ) -> (pg_sys::TransactionId, pg_sys::MultiXactId) {
+        unsafe { pg_sys::pgx_table_storage_create(newrnode, persistence) }
+        (pg_sys::InvalidTransactionId, pg_sys::InvalidMultiXactId)
+    }
+
+    /// Remove all of the table's rows, for a `TRUNCATE` of a table created in the same
+    /// transaction
+    ///
+    /// The default truncates the storage's files.
+    fn relation_nontransactional_truncate(rel: &PgRelation) {
+        unsafe { pg_sys::pgx_table_storage_truncate(rel.as_ptr()) }
+    }
+
+    /// The size, in bytes, of the fork `fork_number` of the table, or of all of its forks if
+    /// that's `InvalidForkNumber`
+    ///
+    /// The default is the size of the storage's files.
+    fn relation_size(rel: &PgRelation, fork_number: pg_sys::ForkNumber) -> u64 {
+        unsafe { pg_sys::pgx_table_storage_size(rel.as_ptr(), fork_number) }
+    }
+
+    /// Whether the table needs a TOAST table for values that are too wide for it
+    ///
+    /// The TOAST table is a heap table, so its values are fetched as the heap's are.
+    fn relation_needs_toast_table(_rel: &PgRelation) -> bool {
+        false
+    }
+
+    /// Estimate the size of the table, for the planner
+    ///
+    /// The default is the pages of the main fork's [`TableAmRoutine::relation_size`] and the
+    /// number of rows the last `VACUUM` or `ANALYZE` counted.
+    fn relation_estimate_size(rel: &PgRelation) -> TableSize {
+        let bytes = Self::relation_size(rel, pg_sys::ForkNumber_MAIN_FORKNUM);
+        TableSize {
+            pages: ((bytes + pg_sys::BLCKSZ as u64 - 1) / pg_sys::BLCKSZ as u64)
+                as pg_sys::BlockNumber,
+            tuples: rel.reltuples().unwrap_or(0.0).max(0.0) as f64,
+            all_visible_fraction: 0.0,
+        }
+    }
+
+    /// Clean up the table for a `VACUUM`, removing its dead rows
+    fn relation_vacuum(_rel: &PgRelation, _params: &pg_sys::VacuumParams) {}
+}
+
+/// The estimated size of a table
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TableSize {
+    pub pages: pg_sys::BlockNumber,
+    pub tuples: f64,
+    /// The fraction of its pages that are all visible, for index only scans
+    pub all_visible_fraction: f64,
+}
+
+/// The state of a scan of a table
+///
+/// This dereferences to the underlying `pg_sys::TableScanDescData` for anything that doesn't have
+/// a method of its own.
+pub struct TableScanDescRef<'a> {
+    ptr: pg_sys::TableScanDesc,
+    _marker: PhantomData<&'a mut pg_sys::TableScanDescData>,
+}
+
+impl<'a> TableScanDescRef<'a> {
+    /// ## Safety
+    ///
+    /// `ptr` must be a valid pointer that outlives the returned `TableScanDescRef`
+    pub unsafe fn from_pg(ptr: pg_sys::TableScanDesc) -> Self {
+        assert!(!ptr.is_null(), "TableScanDesc is NULL");
+        TableScanDescRef { ptr, _marker: PhantomData }
+    }
+
+    pub fn as_ptr(&self) -> pg_sys::TableScanDesc {
+        self.ptr
+    }
+
+    /// The table being scanned
+    pub fn relation(&self) -> PgRelation {
+        unsafe { PgRelation::from_pg((*self.ptr).rs_rd) }
+    }
+
+    /// The snapshot the scan's rows must be visible to
+    pub fn snapshot(&self) -> pg_sys::Snapshot {
+        unsafe { (*self.ptr).rs_snapshot }
+    }
+
+    /// The keys the scan's rows must match, which only scans of system catalogs have
+    pub fn keys(&self) -> &'a [ScanKey] {
+        unsafe {
+            let (keys, nkeys) = ((*self.ptr).rs_key, (*self.ptr).rs_nkeys);
+            if keys.is_null() || nkeys <= 0 {
+                &[]
+            } else {
+                std::slice::from_raw_parts(keys as *const ScanKey, nkeys as usize)
+            }
+        }
+    }
+
+    /// Its `SO_*` flags, which say what kind of scan it is
+    pub fn flags(&self) -> u32 {
+        unsafe { (*self.ptr).rs_flags }
+    }
+
+    /// Whether it's part of a parallel scan, where only one of the participants returns rows
+    pub fn is_parallel(&self) -> bool {
+        unsafe { !(*self.ptr).rs_parallel.is_null() }
+    }
+}
+
+impl Deref for TableScanDescRef<'_> {
+    type Target = pg_sys::TableScanDescData;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.ptr }
+    }
+}
+
+impl DerefMut for TableScanDescRef<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.ptr }
+    }
+}
+
+#[track_caller]
+fn not_supported<W: TableAmRoutine>(what: &str) -> ! {
+    raise(
+        PgSqlErrorCode::ERRCODE_FEATURE_NOT_SUPPORTED,
+        &format!("access method \"{}\" does not support {}", W::NAME, what),
+    )
+}
+
+/// The handler function of the access method `W`, which `#[pg_table_am]` makes, returns this
+pub fn table_am_handler<W: TableAmRoutine>() -> pg_sys::Datum {
+    // relcache entries keep a pointer to the routine, so it must never be freed
+    let routine: &'static pg_sys::TableAmRoutine = &Routine::<W>::ROUTINE;
+    (routine as *const pg_sys::TableAmRoutine as usize).into()
+}
+
+struct Routine<W>(PhantomData<W>);
+
+impl<W: TableAmRoutine> Routine<W> {
+    const ROUTINE: pg_sys::TableAmRoutine = pg_sys::TableAmRoutine {
+        type_: pg_sys::NodeTag_T_TableAmRoutine,
+        slot_callbacks: Some(slot_callbacks),
+        scan_begin: Some(scan_begin::<W>),
+        scan_end: Some(scan_end::<W>),
+        scan_rescan: Some(scan_rescan::<W>),
+        scan_getnextslot: Some(scan_getnextslot::<W>),
+        #[cfg(feature = "pg14")]
+        scan_set_tidrange: None,
+        #[cfg(feature = "pg14")]
+        scan_getnextslot_tidrange: None,
+        parallelscan_estimate: Some(parallelscan_estimate),
+        parallelscan_initialize: Some(parallelscan_initialize),
+        parallelscan_reinitialize: Some(parallelscan_reinitialize),
+        index_fetch_begin: Some(index_fetch_begin),
+        index_fetch_reset: Some(index_fetch_reset),
+        index_fetch_end: Some(index_fetch_end),
+        index_fetch_tuple: Some(index_fetch_tuple::<W>),
+        tuple_fetch_row_version: Some(tuple_fetch_row_version::<W>),
+        tuple_tid_valid: Some(tuple_tid_valid::<W>),
+        tuple_get_latest_tid: Some(tuple_get_latest_tid),
+        tuple_satisfies_snapshot: Some(tuple_satisfies_snapshot::<W>),
+        #[cfg(any(feature = "pg12", feature = "pg13"))]
+        compute_xid_horizon_for_tuples: Some(compute_xid_horizon_for_tuples),
+        #[cfg(feature = "pg14")]
+        index_delete_tuples: Some(index_delete_tuples),
+        tuple_insert: Some(tuple_insert::<W>),
+        tuple_insert_speculative: Some(tuple_insert_speculative::<W>),
+        tuple_complete_speculative: Some(tuple_complete_speculative::<W>),
+        multi_insert: Some(multi_insert::<W>),
+        tuple_delete: Some(tuple_delete::<W>),
+        tuple_update: Some(tuple_update::<W>),
+        tuple_lock: Some(tuple_lock::<W>),
+        finish_bulk_insert: None,
+        relation_set_new_filenode: Some(relation_set_new_filenode::<W>),
+        relation_nontransactional_truncate: Some(relation_nontransactional_truncate::<W>),
+        relation_copy_data: Some(relation_copy_data::<W>),
+        relation_copy_for_cluster: Some(relation_copy_for_cluster::<W>),
+        relation_vacuum: Some(relation_vacuum::<W>),
+        scan_analyze_next_block: Some(scan_analyze_next_block),
+        scan_analyze_next_tuple: Some(scan_analyze_next_tuple),
+        index_build_range_scan: Some(index_build_range_scan::<W>),
+        index_validate_scan: Some(index_validate_scan::<W>),
+        relation_size: Some(relation_size::<W>),
+        relation_needs_toast_table: Some(relation_needs_toast_table::<W>),
+        #[cfg(any(feature = "pg13", feature = "pg14"))]
+        relation_toast_am: Some(relation_toast_am),
+        #[cfg(any(feature = "pg13", feature = "pg14"))]
+        relation_fetch_toast_slice: Some(relation_fetch_toast_slice),
+        relation_estimate_size: Some(relation_estimate_size::<W>),
+        // without these, the planner doesn't make bitmap scans of the table
+        scan_bitmap_next_block: None,
+        scan_bitmap_next_tuple: None,
+        scan_sample_next_block: Some(scan_sample_next_block::<W>),
+        scan_sample_next_tuple: Some(scan_sample_next_tuple::<W>),
+    };
+}
+
+/// A `TableScanDescData` followed by the state of the scan
+#[repr(C)]
+struct TableScan<W> {
+    base: pg_sys::TableScanDescData,
+    /// `None` once [`end_scan`] has dropped it
+    state: *mut Option<W>,
+    /// Whether this participant of a parallel scan returns its rows, once it has asked
+    claimed: Option<bool>,
+}
+
+/// The state [`begin_scan`] left in `scan`
+unsafe fn scan_state<'a, W>(scan: pg_sys::TableScanDesc) -> &'a mut W {
+    (*(*(scan as *mut TableScan<W>)).state).as_mut().expect("table scan has already ended")
+}
+
+/// Store the row a callback stored into `slot`, which is a heap tuple slot, as a heap tuple, so
+/// its tid and table can be read as the `ctid` and `tableoid` columns
+unsafe fn store_heap_tuple(rel: pg_sys::Relation, slot: *mut pg_sys::TupleTableSlot) {
+    let tid = (*slot).tts_tid;
+    let tuple = pg_sys::heap_form_tuple(
+        (*slot).tts_tupleDescriptor,
+        (*slot).tts_values,
+        (*slot).tts_isnull,
+    );
+    (*tuple).t_self = tid;
+    (*tuple).t_tableOid = (*rel).rd_id;
+    pg_sys::ExecStoreHeapTuple(tuple, slot, true);
+}
+
+unsafe extern "C" fn slot_callbacks(_rel: pg_sys::Relation) -> *const pg_sys::TupleTableSlotOps {
+    &pg_sys::TTSOpsHeapTuple
+}
+
+unsafe fn begin_scan<W: TableAmRoutine>(
+    rel: pg_sys::Relation,
+    snapshot: pg_sys::Snapshot,
+    nkeys: c_int,
+    key: *mut pg_sys::ScanKeyData,
+    pscan: pg_sys::ParallelTableScanDesc,
+    flags: u32,
+) -> pg_sys::TableScanDesc {
+    let scan = pg_sys::palloc(std::mem::size_of::<TableScan<W>>()) as *mut TableScan<W>;
+    scan.write(TableScan {
+        base: pg_sys::TableScanDescData {
+            rs_rd: rel,
+            rs_snapshot: snapshot,
+            rs_nkeys: nkeys,
+            rs_flags: flags,
+            rs_parallel: pscan,
+            ..Default::default()
+        },
+        state: std::ptr::null_mut(),
+        claimed: None,
+    });
+    if nkeys > 0 {
+        // the scan keeps its own copies of the keys
+        let keys = pg_sys::palloc(std::mem::size_of::<pg_sys::ScanKeyData>() * nkeys as usize)
+            as *mut pg_sys::ScanKeyData;
+        if !key.is_null() {
+            std::ptr::copy(key, keys, nkeys as usize);
+        }
+        (*scan).base.rs_key = keys;
+    }
+
+    let desc = scan as pg_sys::TableScanDesc;
+    let state = W::begin_scan(&mut TableScanDescRef::from_pg(desc));
+    // end_scan drops the state, but an error aborts the scan without calling it, so the memory
+    // context drops whatever is left
+    (*scan).state = PgMemoryContexts::CurrentMemoryContext.leak_and_drop_on_delete(Some(state));
+    desc
+}
+
+/// Store the scan's next row into `slot`, in the form the rest of Postgres expects
+unsafe fn scan_next<W: TableAmRoutine>(
+    desc: pg_sys::TableScanDesc,
+    direction: pg_sys::ScanDirection,
+    slot: *mut pg_sys::TupleTableSlot,
+) -> bool {
+    let scan = &mut *(desc as *mut TableScan<W>);
+    let pscan = scan.base.rs_parallel;
+    // the first participant of a parallel scan to ask returns all of the rows, and the others
+    // return none, since the access method's rows aren't divided into blocks
+    let claimed = *scan.claimed.get_or_insert_with(|| {
+        pscan.is_null() || {
+            let nallocated = &(*(pscan as pg_sys::ParallelBlockTableScanDesc)).phs_nallocated;
+            let nallocated = &*(&nallocated.value as *const u64 as *const AtomicU64);
+            nallocated.fetch_add(1, Ordering::SeqCst) == 0
+        }
+    });
+
+    let mut slot_ref = TupleTableSlotRef::from_pg(slot);
+    if claimed
+        && scan_state::<W>(desc).scan_next(
+            &mut TableScanDescRef::from_pg(desc),
+            direction,
+            &mut slot_ref,
+        )
+    {
+        store_heap_tuple(scan.base.rs_rd, slot);
+        true
+    } else {
+        slot_ref.clear();
+        false
+    }
+}
+
+unsafe fn end_scan<W: TableAmRoutine>(desc: pg_sys::TableScanDesc) {
+    let state = &mut *(*(desc as *mut TableScan<W>)).state;
+    if let Some(mut state) = state.take() {
+        state.end_scan(&mut TableScanDescRef::from_pg(desc));
+    }
+    if (*desc).rs_flags & pg_sys::ScanOptions_SO_TEMP_SNAPSHOT != 0 {
+        pg_sys::UnregisterSnapshot((*desc).rs_snapshot);
+    }
+    if !(*desc).rs_key.is_null() {
+        pg_sys::pfree((*desc).rs_key as *mut c_void);
+    }
+    pg_sys::pfree(desc as *mut c_void);
+}
+
+unsafe extern "C" fn scan_begin<W: TableAmRoutine>(
+    rel: pg_sys::Relation,
+    snapshot: pg_sys::Snapshot,
+    nkeys: c_int,
+    key: *mut pg_sys::ScanKeyData,
+    pscan: pg_sys::ParallelTableScanDesc,
+    flags: u32,
+) -> pg_sys::TableScanDesc {
+    crate::guard::guard(|| unsafe { begin_scan::<W>(rel, snapshot, nkeys, key, pscan, flags) })
+}
+
+unsafe extern "C" fn scan_end<W: TableAmRoutine>(scan: pg_sys::TableScanDesc) {
+    crate::guard::guard(|| unsafe { end_scan::<W>(scan) })
+}
+
+unsafe extern "C" fn scan_rescan<W: TableAmRoutine>(
+    scan: pg_sys::TableScanDesc,
+    key: *mut pg_sys::ScanKeyData,
+    _set_params: bool,
+    _allow_strat: bool,
+    _allow_sync: bool,
+    _allow_pagemode: bool,
+) {
+    crate::guard::guard(|| unsafe {
+        if !key.is_null() && (*scan).rs_nkeys > 0 {
+            std::ptr::copy(key, (*scan).rs_key, (*scan).rs_nkeys as usize);
+        }
+        (*(scan as *mut TableScan<W>)).claimed = None;
+        scan_state::<W>(scan).rescan(&mut TableScanDescRef::from_pg(scan))
+    })
+}
+
+unsafe extern "C" fn scan_getnextslot<W: TableAmRoutine>(
+    scan: pg_sys::TableScanDesc,
+    direction: pg_sys::ScanDirection,
+    slot: *mut pg_sys::TupleTableSlot,
+) -> bool {
+    crate::guard::guard(|| unsafe { scan_next::<W>(scan, direction, slot) })
+}
+
+unsafe extern "C" fn parallelscan_estimate(rel: pg_sys::Relation) -> pg_sys::Size {
+    crate::guard::guard(|| unsafe { pg_sys::table_block_parallelscan_estimate(rel) })
+}
+
+unsafe extern "C" fn parallelscan_initialize(
+    rel: pg_sys::Relation,
+    pscan: pg_sys::ParallelTableScanDesc,
+) -> pg_sys::Size {
+    crate::guard::guard(|| unsafe { pg_sys::table_block_parallelscan_initialize(rel, pscan) })
+}
+
+unsafe extern "C" fn parallelscan_reinitialize(
+    rel: pg_sys::Relation,
+    pscan: pg_sys::ParallelTableScanDesc,
+) {
+    crate::guard::guard(|| unsafe { pg_sys::table_block_parallelscan_reinitialize(rel, pscan) })
+}
+
+unsafe extern "C" fn index_fetch_begin(rel: pg_sys::Relation) -> *mut pg_sys::IndexFetchTableData {
+    crate::guard::guard(|| {
+        let mut fetch = PgBox::<pg_sys::IndexFetchTableData>::alloc0();
+        fetch.rel = rel;
+        fetch.into_pg()
+    })
+}
+
+unsafe extern "C" fn index_fetch_reset(_fetch: *mut pg_sys::IndexFetchTableData) {}
+
+unsafe extern "C" fn index_fetch_end(fetch: *mut pg_sys::IndexFetchTableData) {
+    crate::guard::guard(|| unsafe { pg_sys::pfree(fetch as *mut c_void) })
+}
+
+/// Fetch the row `tid` into `slot`, in the form the rest of Postgres expects
+unsafe fn fetch_row_version<W: TableAmRoutine>(
+    rel: pg_sys::Relation,
+    tid: pg_sys::ItemPointer,
+    snapshot: pg_sys::Snapshot,
+    slot: *mut pg_sys::TupleTableSlot,
+) -> bool {
+    let mut slot_ref = TupleTableSlotRef::from_pg(slot);
+    if W::tuple_fetch_row_version(&PgRelation::from_pg(rel), *tid, snapshot, &mut slot_ref) {
+        slot_ref.set_tid(*tid);
+        store_heap_tuple(rel, slot);
+        true
+    } else {
+        slot_ref.clear();
+        false
+    }
+}
+
+unsafe extern "C" fn index_fetch_tuple<W: TableAmRoutine>(
+    fetch: *mut pg_sys::IndexFetchTableData,
+    tid: pg_sys::ItemPointer,
+    snapshot: pg_sys::Snapshot,
+    slot: *mut pg_sys::TupleTableSlot,
+    call_again: *mut bool,
+    all_dead: *mut bool,
+) -> bool {
+    crate::guard::guard(|| unsafe {
+        // each tid is a single row, rather than a chain of versions of it
+        *call_again = false;
+        if !all_dead.is_null() {
+            *all_dead = false;
+        }
+        fetch_row_version::<W>((*fetch).rel, tid, snapshot, slot)
+    })
+}
+
+unsafe extern "C" fn tuple_fetch_row_version<W: TableAmRoutine>(
+    rel: pg_sys::Relation,
+    tid: pg_sys::ItemPointer,
+    snapshot: pg_sys::Snapshot,
+    slot: *mut pg_sys::TupleTableSlot,
+) -> bool {
+    crate::guard::guard(|| unsafe { fetch_row_version::<W>(rel, tid, snapshot, slot) })
+}
+
+/// Whether the row `tid` of the table is visible to `snapshot`, fetched into a slot of its own
+unsafe fn row_is_visible<W: TableAmRoutine>(
+    rel: pg_sys::Relation,
+    tid: pg_sys::ItemPointerData,
+    snapshot: pg_sys::Snapshot,
+) -> bool {
+    let slot = pg_sys::MakeSingleTupleTableSlot((*rel).rd_att, slot_callbacks(rel));
+    let visible = W::tuple_fetch_row_version(
+        &PgRelation::from_pg(rel),
+        tid,
+        snapshot,
+        &mut TupleTableSlotRef::from_pg(slot),
+    );
+    pg_sys::ExecDropSingleTupleTableSlot(slot);
+    visible
+}
+
+unsafe extern "C" fn tuple_tid_valid<W: TableAmRoutine>(
+    scan: pg_sys::TableScanDesc,
+    tid: pg_sys::ItemPointer,
+) -> bool {
+    crate::guard::guard(|| unsafe { row_is_visible::<W>((*scan).rs_rd, *tid, (*scan).rs_snapshot) })
+}
+
+unsafe extern "C" fn tuple_get_latest_tid(_scan: pg_sys::TableScanDesc, _tid: pg_sys::ItemPointer) {
+}
+
+unsafe extern "C" fn tuple_satisfies_snapshot<W: TableAmRoutine>(
+    rel: pg_sys::Relation,
+    slot: *mut pg_sys::TupleTableSlot,
+    snapshot: pg_sys::Snapshot,
+) -> bool {
+    crate::guard::guard(|| unsafe { row_is_visible::<W>(rel, (*slot).tts_tid, snapshot) })
+}
+
+#[cfg(any(feature = "pg12", feature = "pg13"))]
+unsafe extern "C" fn compute_xid_horizon_for_tuples(
+    _rel: pg_sys::Relation,
+    _items: *mut pg_sys::ItemPointerData,
+    _nitems: c_int,
+) -> pg_sys::TransactionId {
+    pg_sys::InvalidTransactionId
+}
+
+#[cfg(feature = "pg14")]
+unsafe extern "C" fn index_delete_tuples(
+    _rel: pg_sys::Relation,
+    delstate: *mut pg_sys::TM_IndexDeleteOp,
+) -> pg_sys::TransactionId {
+    // none of the index entries can be deleted
+    (*delstate).ndeltids = 0;
+    pg_sys::InvalidTransactionId
+}
+
+unsafe extern "C" fn tuple_insert<W: TableAmRoutine>(
+    rel: pg_sys::Relation,
+    slot: *mut pg_sys::TupleTableSlot,
+    cid: pg_sys::CommandId,
+    _options: c_int,
+    _bistate: *mut pg_sys::BulkInsertStateData,
+) {
+    crate::guard::guard(|| unsafe {
+        W::tuple_insert(&PgRelation::from_pg(rel), &mut TupleTableSlotRef::from_pg(slot), cid);
+        (*slot).tts_tableOid = (*rel).rd_id;
+    })
+}
+
+unsafe extern "C" fn tuple_insert_speculative<W: TableAmRoutine>(
+    _rel: pg_sys::Relation,
+    _slot: *mut pg_sys::TupleTableSlot,
+    _cid: pg_sys::CommandId,
+    _options: c_int,
+    _bistate: *mut pg_sys::BulkInsertStateData,
+    _spec_token: u32,
+) {
+    crate::guard::guard(|| not_supported::<W>("INSERT ... ON CONFLICT"))
+}
+
+unsafe extern "C" fn tuple_complete_speculative<W: TableAmRoutine>(
+    _rel: pg_sys::Relation,
+    _slot: *mut pg_sys::TupleTableSlot,
+    _spec_token: u32,
+    _succeeded: bool,
+) {
+    crate::guard::guard(|| not_supported::<W>("INSERT ... ON CONFLICT"))
+}
+
+unsafe extern "C" fn multi_insert<W: TableAmRoutine>(
+    rel: pg_sys::Relation,
+    slots: *mut *mut pg_sys::TupleTableSlot,
+    nslots: c_int,
+    cid: pg_sys::CommandId,
+    _options: c_int,
+    _bistate: *mut pg_sys::BulkInsertStateData,
+) {
+    crate::guard::guard(|| unsafe {
+        let slots = std::slice::from_raw_parts(slots, nslots as usize);
+        let mut slot_refs =
+            slots.iter().map(|slot| TupleTableSlotRef::from_pg(*slot)).collect::<Vec<_>>();
+        W::multi_insert(&PgRelation::from_pg(rel), &mut slot_refs, cid);
+        for slot in slots {
+            (**slot).tts_tableOid = (*rel).rd_id;
+        }
+    })
+}
+
+/// Report that the row `tid` was already deleted
+unsafe fn deleted(
+    tid: pg_sys::ItemPointer,
+    tmfd: *mut pg_sys::TM_FailureData,
+) -> pg_sys::TM_Result {
+    (*tmfd).ctid = *tid;
+    (*tmfd).xmax = pg_sys::InvalidTransactionId;
+    (*tmfd).cmax = pg_sys::InvalidCommandId;
+    (*tmfd).traversed = false;
+    pg_sys::TM_Result_TM_Deleted
+}
+
+unsafe extern "C" fn tuple_delete<W: TableAmRoutine>(
+    rel: pg_sys::Relation,
+    tid: pg_sys::ItemPointer,
+    cid: pg_sys::CommandId,
+    snapshot: pg_sys::Snapshot,
+    _crosscheck: pg_sys::Snapshot,
+    _wait: bool,
+    tmfd: *mut pg_sys::TM_FailureData,
+    _changing_part: bool,
+) -> pg_sys::TM_Result {
+    crate::guard::guard(|| unsafe {
+        if W::tuple_delete(&PgRelation::from_pg(rel), *tid, cid, snapshot) {
+            pg_sys::TM_Result_TM_Ok
+        } else {
+            deleted(tid, tmfd)
+        }
+    })
+}
+
+unsafe extern "C" fn tuple_update<W: TableAmRoutine>(
+    rel: pg_sys::Relation,
+    otid: pg_sys::ItemPointer,
+    slot: *mut pg_sys::TupleTableSlot,
+    cid: pg_sys::CommandId,
+    snapshot: pg_sys::Snapshot,
+    _crosscheck: pg_sys::Snapshot,
+    _wait: bool,
+    tmfd: *mut pg_sys::TM_FailureData,
+    lockmode: *mut pg_sys::LockTupleMode,
+    update_indexes: *mut bool,
+) -> pg_sys::TM_Result {
+    crate::guard::guard(|| unsafe {
+        let mut slot_ref = TupleTableSlotRef::from_pg(slot);
+        if W::tuple_update(&PgRelation::from_pg(rel), *otid, &mut slot_ref, cid, snapshot) {
+            (*slot).tts_tableOid = (*rel).rd_id;
+            *lockmode = pg_sys::LockTupleMode_LockTupleExclusive;
+            *update_indexes = true;
+            pg_sys::TM_Result_TM_Ok
+        } else {
+            deleted(otid, tmfd)
+        }
+    })
+}
+
+unsafe extern "C" fn tuple_lock<W: TableAmRoutine>(
+    rel: pg_sys::Relation,
+    tid: pg_sys::ItemPointer,
+    snapshot: pg_sys::Snapshot,
+    slot: *mut pg_sys::TupleTableSlot,
+    _cid: pg_sys::CommandId,
+    _mode: pg_sys::LockTupleMode,
+    _wait_policy: pg_sys::LockWaitPolicy,
+    _flags: u8,
+    tmfd: *mut pg_sys::TM_FailureData,
+) -> pg_sys::TM_Result {
+    // rows aren't locked individually, so locking one only fetches it
+    crate::guard::guard(|| unsafe {
+        if fetch_row_version::<W>(rel, tid, snapshot, slot) {
+            pg_sys::TM_Result_TM_Ok
+        } else {
+            deleted(tid, tmfd)
+        }
+    })
+}
+
+unsafe extern "C" fn relation_set_new_filenode<W: TableAmRoutine>(
+    rel: pg_sys::Relation,
+    newrnode: *const pg_sys::RelFileNode,
+    persistence: c_char,
+    freeze_xid: *mut pg_sys::TransactionId,
+    minmulti: *mut pg_sys::MultiXactId,
+) {
+    crate::guard::guard(|| unsafe {
+        let (xid, multi) =
+            W::relation_set_new_filenode(&PgRelation::from_pg(rel), &*newrnode, persistence);
+        *freeze_xid = xid;
+        *minmulti = multi;
+    })
+}
+
+unsafe extern "C" fn relation_nontransactional_truncate<W: TableAmRoutine>(rel: pg_sys::Relation) {
+    crate::guard::guard(|| unsafe {
+        W::relation_nontransactional_truncate(&PgRelation::from_pg(rel))
+    })
+}
+
+unsafe extern "C" fn relation_copy_data<W: TableAmRoutine>(
+    _rel: pg_sys::Relation,
+    _newrnode: *const pg_sys::RelFileNode,
+) {
+    crate::guard::guard(|| not_supported::<W>("moving tables to another tablespace"))
+}
+
+unsafe extern "C" fn relation_copy_for_cluster<W: TableAmRoutine>(
+    _new_table: pg_sys::Relation,
+    _old_table: pg_sys::Relation,
+    _old_index: pg_sys::Relation,
+    _use_sort: bool,
+    _oldest_xmin: pg_sys::TransactionId,
+    _xid_cutoff: *mut pg_sys::TransactionId,
+    _multi_cutoff: *mut pg_sys::MultiXactId,
+    _num_tuples: *mut f64,
+    _tups_vacuumed: *mut f64,
+    _tups_recently_dead: *mut f64,
+) {
+    crate::guard::guard(|| not_supported::<W>("CLUSTER or VACUUM FULL"))
+}
+
+unsafe extern "C" fn relation_vacuum<W: TableAmRoutine>(
+    rel: pg_sys::Relation,
+    params: *mut pg_sys::VacuumParams,
+    _bstrategy: pg_sys::BufferAccessStrategy,
+) {
+    crate::guard::guard(|| unsafe { W::relation_vacuum(&PgRelation::from_pg(rel), &*params) })
+}
+
+// ANALYZE samples the rows of tables by block, which the access method doesn't have, so it
+// doesn't gather statistics of the table's columns
+unsafe extern "C" fn scan_analyze_next_block(
+    _scan: pg_sys::TableScanDesc,
+    _blockno: pg_sys::BlockNumber,
+    _bstrategy: pg_sys::BufferAccessStrategy,
+) -> bool {
+    false
+}
+
+unsafe extern "C" fn scan_analyze_next_tuple(
+    _scan: pg_sys::TableScanDesc,
+    _oldest_xmin: pg_sys::TransactionId,
+    _liverows: *mut f64,
+    _deadrows: *mut f64,
+    _slot: *mut pg_sys::TupleTableSlot,
+) -> bool {
+    false
+}
+
+unsafe extern "C" fn index_build_range_scan<W: TableAmRoutine>(
+    table_rel: pg_sys::Relation,
+    index_rel: pg_sys::Relation,
+    index_info: *mut pg_sys::IndexInfo,
+    _allow_sync: bool,
+    _anyvisible: bool,
+    _progress: bool,
+    _start_blockno: pg_sys::BlockNumber,
+    _numblocks: pg_sys::BlockNumber,
+    callback: pg_sys::IndexBuildCallback,
+    callback_state: *mut c_void,
+    scan: pg_sys::TableScanDesc,
+) -> f64 {
+    crate::guard::guard(|| unsafe {
+        if !(*index_info).ii_Predicate.is_null() {
+            not_supported::<W>("partial indexes")
+        }
+        let callback = callback.expect("index build callback is NULL");
+
+        // expression indexes evaluate their expressions on the row in the scan tuple
+        let estate = pg_sys::CreateExecutorState();
+        let econtext = pg_sys::MakePerTupleExprContext(estate);
+        let slot = pg_sys::MakeSingleTupleTableSlot((*table_rel).rd_att, &pg_sys::TTSOpsHeapTuple);
+        (*econtext).ecxt_scantuple = slot;
+
+        let (scan, snapshot) = if scan.is_null() {
+            // under `REPEATABLE READ` the transaction's snapshot misses rows committed since it
+            // started, which the index needs.  The heap scans with `SnapshotAny` and decides which
+            // rows are live itself, but only the access method knows that, so a plain build scans
+            // with the latest snapshot, and the lock it holds keeps the rows from changing under
+            // it.  `CREATE INDEX CONCURRENTLY` builds in a transaction of its own, whose snapshot
+            // is the one it goes on to validate the index with.
+            let snapshot = if (*index_info).ii_Concurrent {
+                pg_sys::GetTransactionSnapshot()
+            } else {
+                pg_sys::GetLatestSnapshot()
+            };
+            let snapshot = pg_sys::RegisterSnapshot(snapshot);
+            let flags = pg_sys::ScanOptions_SO_TYPE_SEQSCAN | pg_sys::ScanOptions_SO_ALLOW_STRAT;
+            let scan = begin_scan::<W>(
+                table_rel,
+                snapshot,
+                0,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                flags,
+            );
+            (scan, Some(snapshot))
+        } else {
+            (scan, None)
+        };
+
+        let natts = (*(*index_rel).rd_att).natts as usize;
+        let mut values = vec![pg_sys::Datum::from(0); natts];
+        let mut isnull = vec![false; natts];
+        let mut reltuples = 0.0;
+        while scan_next::<W>(scan, pg_sys::ScanDirection_ForwardScanDirection, slot) {
+            reltuples += 1.0;
+            pg_sys::MemoryContextReset((*econtext).ecxt_per_tuple_memory);
+            pg_sys::FormIndexDatum(
+                index_info,
+                slot,
+                estate,
+                values.as_mut_ptr(),
+                isnull.as_mut_ptr(),
+            );
+            call_index_build_callback(
+                callback,
+                index_rel,
+                slot,
+                values.as_mut_ptr(),
+                isnull.as_mut_ptr(),
+                callback_state,
+            );
+        }
+
+        end_scan::<W>(scan);
+        if let Some(snapshot) = snapshot {
+            pg_sys::UnregisterSnapshot(snapshot);
+        }
+        pg_sys::ExecDropSingleTupleTableSlot(slot);
+        pg_sys::FreeExecutorState(estate);
+        reltuples
+    })
+}
+
+#[cfg(feature = "pg12")]
+unsafe fn call_index_build_callback(
+    callback: unsafe extern "C" fn(
+        pg_sys::Relation,
+        pg_sys::HeapTuple,
+        *mut pg_sys::Datum,
+        *mut bool,
+        bool,
+        *mut c_void,
+    ),
+    index_rel: pg_sys::Relation,
+    slot: *mut pg_sys::TupleTableSlot,
+    values: *mut pg_sys::Datum,
+    isnull: *mut bool,
+    callback_state: *mut c_void,
+) {
+    // the slot holds a heap tuple, whose t_self is the row's tid
+    let mut should_free = false;
+    let tuple = pg_sys::ExecFetchSlotHeapTuple(slot, false, &mut should_free);
+    callback(index_rel, tuple, values, isnull, true, callback_state)
+}
+
+#[cfg(any(feature = "pg13", feature = "pg14"))]
+unsafe fn call_index_build_callback(
+    callback: unsafe extern "C" fn(
+        pg_sys::Relation,
+        pg_sys::ItemPointer,
+        *mut pg_sys::Datum,
+        *mut bool,
+        bool,
+        *mut c_void,
+    ),
+    index_rel: pg_sys::Relation,
+    slot: *mut pg_sys::TupleTableSlot,
+    values: *mut pg_sys::Datum,
+    isnull: *mut bool,
+    callback_state: *mut c_void,
+) {
+    callback(index_rel, &mut (*slot).tts_tid, values, isnull, true, callback_state)
+}
+
+unsafe extern "C" fn index_validate_scan<W: TableAmRoutine>(
+    _table_rel: pg_sys::Relation,
+    _index_rel: pg_sys::Relation,
+    _index_info: *mut pg_sys::IndexInfo,
+    _snapshot: pg_sys::Snapshot,
+    _state: *mut pg_sys::ValidateIndexState,
+) {
+    crate::guard::guard(|| not_supported::<W>("CREATE INDEX CONCURRENTLY"))
+}
+
+unsafe extern "C" fn relation_size<W: TableAmRoutine>(
+    rel: pg_sys::Relation,
+    fork_number: pg_sys::ForkNumber,
+) -> u64 {
+    crate::guard::guard(|| unsafe { W::relation_size(&PgRelation::from_pg(rel), fork_number) })
+}
+
+unsafe extern "C" fn relation_needs_toast_table<W: TableAmRoutine>(rel: pg_sys::Relation) -> bool {
+    crate::guard::guard(|| unsafe { W::relation_needs_toast_table(&PgRelation::from_pg(rel)) })
+}
+
+/// TOAST tables are heap tables, whatever the access method of the table they're for
+#[cfg(any(feature = "pg13", feature = "pg14"))]
+unsafe extern "C" fn relation_toast_am(_rel: pg_sys::Relation) -> pg_sys::Oid {
+    pg_sys::HEAP_TABLE_AM_OID
+}
+
+#[cfg(any(feature = "pg13", feature = "pg14"))]
+unsafe extern "C" fn relation_fetch_toast_slice(
+    toastrel: pg_sys::Relation,
+    valueid: pg_sys::Oid,
+    attrsize: i32,
+    sliceoffset: i32,
+    slicelength: i32,
+    result: *mut pg_sys::varlena,
+) {
+    crate::guard::guard(|| unsafe {
+        pg_sys::heap_fetch_toast_slice(
+            toastrel,
+            valueid,
+            attrsize,
+            sliceoffset,
+            slicelength,
+            result,
+        )
+    })
+}
+
+unsafe extern "C" fn relation_estimate_size<W: TableAmRoutine>(
+    rel: pg_sys::Relation,
+    _attr_widths: *mut i32,
+    pages: *mut pg_sys::BlockNumber,
+    tuples: *mut f64,
+    allvisfrac: *mut f64,
+) {
+    crate::guard::guard(|| unsafe {
+        let size = W::relation_estimate_size(&PgRelation::from_pg(rel));
+        *pages = size.pages;
+        *tuples = size.tuples;
+        *allvisfrac = size.all_visible_fraction;
+    })
+}
+
+unsafe extern "C" fn scan_sample_next_block<W: TableAmRoutine>(
+    _scan: pg_sys::TableScanDesc,
+    _scanstate: *mut pg_sys::SampleScanState,
+) -> bool {
+    crate::guard::guard(|| not_supported::<W>("TABLESAMPLE"))
+}
+
+unsafe extern "C" fn scan_sample_next_tuple<W: TableAmRoutine>(
+    _scan: pg_sys::TableScanDesc,
+    _scanstate: *mut pg_sys::SampleScanState,
+    _slot: *mut pg_sys::TupleTableSlot,
+) -> bool {
+    crate::guard::guard(|| not_supported::<W>("TABLESAMPLE"))
+}
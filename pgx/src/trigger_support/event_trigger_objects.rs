@@ -0,0 +1,99 @@
+use crate::pg_sys;
+use crate::spi::{FromSpiRow, FromSpiRowError, SpiHeapTupleData};
+use crate::{FromDatum, IntoDatum};
+
+/// A command run by the statement that fired a `ddl_command_end` event trigger
+///
+/// One row of `pg_event_trigger_ddl_commands()`, from [`PgEventTrigger::ddl_commands`][crate::PgEventTrigger::ddl_commands].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DdlCommand {
+    /// OID of the catalog the object belongs in
+    pub classid: pg_sys::Oid,
+    /// OID of the object itself
+    pub objid: pg_sys::Oid,
+    /// Sub-object ID, such as the attribute number of a column
+    pub objsubid: i32,
+    /// Command tag, such as `CREATE TABLE`
+    pub command_tag: String,
+    /// Type of the object, such as `table`
+    pub object_type: String,
+    /// Name of the schema the object belongs in, if any
+    pub schema_name: Option<String>,
+    /// Text rendering of the object identity, schema-qualified
+    pub object_identity: String,
+    /// Whether the command is part of an extension script
+    pub in_extension: bool,
+}
+
+impl FromSpiRow for DdlCommand {
+    fn from_spi_row(row: &SpiHeapTupleData) -> Result<Self, FromSpiRowError> {
+        Ok(DdlCommand {
+            classid: required(row, "classid")?,
+            objid: required(row, "objid")?,
+            objsubid: required(row, "objsubid")?,
+            command_tag: required(row, "command_tag")?,
+            object_type: required(row, "object_type")?,
+            schema_name: row.get_column("schema_name")?,
+            object_identity: required(row, "object_identity")?,
+            in_extension: required(row, "in_extension")?,
+        })
+    }
+}
+
+/// An object dropped by the statement that fired a `sql_drop` event trigger
+///
+/// One row of `pg_event_trigger_dropped_objects()`, from [`PgEventTrigger::dropped_objects`][crate::PgEventTrigger::dropped_objects].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DroppedObject {
+    /// OID of the catalog the object belonged in
+    pub classid: pg_sys::Oid,
+    /// OID of the object itself
+    pub objid: pg_sys::Oid,
+    /// Sub-object ID, such as the attribute number of a column
+    pub objsubid: i32,
+    /// Whether the object was one the statement was told to drop, rather than a dependent one
+    pub original: bool,
+    /// Whether the object was dropped through a normal dependency, rather than an internal one
+    pub normal: bool,
+    /// Whether the object was temporary
+    pub is_temporary: bool,
+    /// Type of the object, such as `table`
+    pub object_type: String,
+    /// Name of the schema the object belonged in, if any
+    pub schema_name: Option<String>,
+    /// Name of the object, if its schema and name identify it uniquely
+    pub object_name: Option<String>,
+    /// Text rendering of the object identity, schema-qualified
+    pub object_identity: String,
+    /// The names that, with `object_type` and `address_args`, `pg_get_object_address()` takes
+    pub address_names: Vec<String>,
+    /// The arguments that, with `object_type` and `address_names`, `pg_get_object_address()` takes
+    pub address_args: Vec<String>,
+}
+
+impl FromSpiRow for DroppedObject {
+    fn from_spi_row(row: &SpiHeapTupleData) -> Result<Self, FromSpiRowError> {
+        Ok(DroppedObject {
+            classid: required(row, "classid")?,
+            objid: required(row, "objid")?,
+            objsubid: required(row, "objsubid")?,
+            original: required(row, "original")?,
+            normal: required(row, "normal")?,
+            is_temporary: required(row, "is_temporary")?,
+            object_type: required(row, "object_type")?,
+            schema_name: row.get_column("schema_name")?,
+            object_name: row.get_column("object_name")?,
+            object_identity: required(row, "object_identity")?,
+            address_names: required(row, "address_names")?,
+            address_args: required(row, "address_args")?,
+        })
+    }
+}
+
+/// Get a column the event trigger functions never return `NULL` for
+fn required<T: FromDatum + IntoDatum + 'static>(
+    row: &SpiHeapTupleData,
+    name: &str,
+) -> Result<T, FromSpiRowError> {
+    row.get_column(name)?.ok_or_else(|| FromSpiRowError::UnexpectedNull(name.to_string()))
+}
@@ -89,6 +89,57 @@ INSERT INTO test (title, description, payload) VALUES ('Fox', 'a description', '
 );
 ```
 
+# Creating the trigger
+
+Given a `table`, the `CREATE TRIGGER` statement is generated alongside the function. Its `events` are
+separated by `|`, and the `requires` of it are positioned like those of [`extension_sql`][crate::extension_sql]:
+
+```rust,no_run
+# use pgx::{pg_trigger, pg_sys, heap_tuple::{PgHeapTuple, PgHeapTupleError}, WhoAllocated, PgTrigger};
+#
+pgx::extension_sql!(
+    "CREATE TABLE orders (id serial8 NOT NULL PRIMARY KEY, status text, x int);",
+    name = "create_orders",
+);
+
+#[pg_trigger(
+    table = "orders",
+    events = "insert|update of status",
+    timing = "before",
+    level = "row",
+    when = "NEW.x > 0",
+    requires = [ "create_orders" ]
+)]
+fn orders_trigger(trigger: &PgTrigger) -> Result<
+    PgHeapTuple<'_, impl WhoAllocated<pg_sys::HeapTupleData>>,
+    PgHeapTupleError,
+> {
+    Ok(unsafe { trigger.current() }.expect("No current HeapTuple"))
+}
+```
+
+Which would generate something like the following SQL, after that of `create_orders`:
+
+```sql
+CREATE FUNCTION "orders_trigger"()
+    RETURNS TRIGGER
+    LANGUAGE c
+    AS 'MODULE_PATHNAME', 'orders_trigger_wrapper';
+
+CREATE TRIGGER "orders_trigger"
+    BEFORE INSERT OR UPDATE OF status ON orders
+    FOR EACH ROW
+    WHEN (NEW.x > 0)
+    EXECUTE PROCEDURE "orders_trigger"();
+```
+
+The trigger is named after the function unless given a `name`. A `level` of `"statement"` is the default,
+as it is in PostgreSQL.
+
+Constraint triggers are created with `constraint = true`, and can be `deferrable = true` and
+`initially = "deferred"` (which implies `deferrable`), generating `CREATE CONSTRAINT TRIGGER ... DEFERRABLE INITIALLY DEFERRED`.
+PostgreSQL requires them to have a `timing` of `"after"` and a `level` of `"row"`.
+
 # Working with [`WhoAllocated`][crate::WhoAllocated]
 
 Trigger functions can return [`PgHeapTuple`][crate::PgHeapTuple]s which are [`AllocatedByRust`][crate::AllocatedByRust]
@@ -199,22 +250,106 @@ fn trigger_safe(trigger: &PgTrigger) -> Result<
 }
 ```
 
+# Transition tables
+
+Statement-level `AFTER` triggers created with `REFERENCING OLD TABLE AS ... NEW TABLE AS ...` can iterate
+those transition tables directly with [`PgTrigger::old_transition_table`] and [`PgTrigger::new_transition_table`],
+as [`PgHeapTuple`][crate::PgHeapTuple]s or as typed rows from [`TransitionTable::rows`]:
+
+```rust,no_run
+use pgx::{pg_trigger, pg_sys, heap_tuple::{PgHeapTuple, PgHeapTupleError}, AllocatedByRust, FromSpiRow, FromSpiRowError, PgTrigger, PgTupleDesc};
+
+#[derive(FromSpiRow)]
+struct Order {
+    id: i64,
+    status: Option<String>,
+}
+
+#[derive(thiserror::Error, Debug)]
+enum AuditError {
+    #[error("pgx::PgHeapTupleError: {0}")]
+    PgHeapTuple(#[from] PgHeapTupleError),
+    #[error("pgx::FromSpiRowError: {0}")]
+    FromSpiRow(#[from] FromSpiRowError),
+}
+
+#[pg_trigger]
+fn audit_orders(trigger: &PgTrigger) -> Result<
+    PgHeapTuple<'_, AllocatedByRust>,
+    AuditError,
+> {
+    if let Some(new) = trigger.new_transition_table() {
+        for order in new.rows::<Order>() {
+            let order = order?;
+            pgx::info!("order {} is now {:?}", order.id, order.status);
+        }
+    }
+    // the row an `AFTER ... FOR EACH STATEMENT` trigger returns is ignored
+    let tupdesc = unsafe { PgTupleDesc::from_pg_copy(trigger.relation_data().rd_att) };
+    let natts = tupdesc.len();
+    Ok(PgHeapTuple::from_datums(tupdesc, vec![None; natts])?)
+}
+```
+
+# Event triggers
+
+[Event trigger](https://www.postgresql.org/docs/current/event-triggers.html) functions are written with
+[`#[pg_event_trigger]`][crate::pg_event_trigger], accept a [`PgEventTrigger`], and return a
+[`Result`][std::result::Result] of `()` or any error that implements [`impl std::error::Error`][std::error::Error].
+
+Given an `event`, and optionally the command `tags` to limit it to, the `CREATE EVENT TRIGGER`
+statement is generated alongside the function:
+
+```rust,no_run
+use pgx::{info, pg_event_trigger, PgEventTrigger, PgTriggerError};
+
+#[pg_event_trigger(event = "ddl_command_end", tags = "CREATE TABLE, ALTER TABLE")]
+fn event_trigger_example(trigger: &PgEventTrigger) -> Result<(), PgTriggerError> {
+    for command in trigger.ddl_commands()? {
+        info!("{} {}", trigger.tag()?, command.object_identity);
+    }
+    Ok(())
+}
+```
+
+Which would generate something like the following SQL:
+
+```sql
+CREATE FUNCTION "event_trigger_example"()
+    RETURNS event_trigger
+    LANGUAGE c
+    AS 'MODULE_PATHNAME', 'event_trigger_example_wrapper';
+
+CREATE EVENT TRIGGER "event_trigger_example"
+    ON ddl_command_end
+    WHEN TAG IN ('CREATE TABLE', 'ALTER TABLE')
+    EXECUTE PROCEDURE "event_trigger_example"();
+```
+
  */
 
+mod event_trigger_objects;
+mod pg_event_trigger;
+mod pg_event_trigger_event;
 mod pg_trigger;
 mod pg_trigger_error;
 mod pg_trigger_level;
 mod pg_trigger_option;
 mod pg_trigger_safe;
 mod pg_trigger_when;
+mod transition_table;
 mod trigger_tuple;
 
+pub use event_trigger_objects::{DdlCommand, DroppedObject};
+pub use pg_event_trigger::PgEventTrigger;
+pub use pg_event_trigger_event::PgEventTriggerEvent;
 pub use pg_trigger::PgTrigger;
 pub use pg_trigger_error::PgTriggerError;
 pub use pg_trigger_level::PgTriggerLevel;
 pub use pg_trigger_option::PgTriggerOperation;
 pub use pg_trigger_safe::PgTriggerSafe;
 pub use pg_trigger_when::PgTriggerWhen;
+pub use transition_table::TransitionTable;
 pub use trigger_tuple::TriggerTuple;
 
 use crate::{is_a, pg_sys};
@@ -229,6 +364,12 @@ pub unsafe fn called_as_trigger(fcinfo: pg_sys::FunctionCallInfo) -> bool {
     !fcinfo.context.is_null() && is_a(fcinfo.context, pg_sys::NodeTag_T_TriggerData)
 }
 
+#[inline]
+pub unsafe fn called_as_event_trigger(fcinfo: pg_sys::FunctionCallInfo) -> bool {
+    let fcinfo = fcinfo.as_ref().expect("fcinfo was null");
+    !fcinfo.context.is_null() && is_a(fcinfo.context, pg_sys::NodeTag_T_EventTriggerData)
+}
+
 #[inline]
 pub fn trigger_fired_by_insert(event: u32) -> bool {
     event & pg_sys::TRIGGER_EVENT_OPMASK == pg_sys::TRIGGER_EVENT_INSERT
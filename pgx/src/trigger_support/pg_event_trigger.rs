@@ -0,0 +1,141 @@
+use crate::is_a;
+use crate::pg_sys;
+use crate::pgbox::PgBox;
+use crate::spi::{FromSpiRow, Spi};
+use crate::trigger_support::{
+    called_as_event_trigger, DdlCommand, DroppedObject, PgEventTriggerEvent, PgTriggerError,
+};
+use std::borrow::Borrow;
+
+/**
+The datatype accepted by an event trigger
+
+A safe structure providing an API similar to the `TG_EVENT` and `TG_TAG` variables and the event
+trigger functions available to a PL/pgSQL event trigger.
+
+Usage examples exist in the module level docs.
+*/
+pub struct PgEventTrigger {
+    event_trigger_data: PgBox<pg_sys::EventTriggerData>,
+    #[allow(dead_code)]
+    fcinfo: pg_sys::FunctionCallInfo,
+}
+
+impl PgEventTrigger {
+    /// Construct a new [`PgEventTrigger`] from a [`FunctionCallInfo`][pg_sys::FunctionCallInfo]
+    ///
+    /// Generally this would be automatically done for the user in a [`#[pg_event_trigger]`][crate::pg_event_trigger].
+    ///
+    /// # Safety
+    ///
+    /// This constructor attempts to do some checks for validity, but it is ultimately unsafe
+    /// because it must dereference several raw pointers.
+    ///
+    /// Users should ensure the provided `fcinfo` is:
+    ///
+    /// * one provided by PostgreSQL during an event trigger invocation,
+    /// * unharmed (the user has not mutated it since PostgreSQL provided it),
+    ///
+    /// If any of these conditions are untrue, this or any other function on this type is
+    /// undefined behavior, hopefully panicking.
+    pub unsafe fn from_fcinfo(fcinfo: pg_sys::FunctionCallInfo) -> Result<Self, PgTriggerError> {
+        if fcinfo.is_null() {
+            return Err(PgTriggerError::NullFunctionCallInfo);
+        }
+        if !called_as_event_trigger(fcinfo) {
+            return Err(PgTriggerError::NotEventTrigger);
+        }
+        let fcinfo_data = &*fcinfo;
+
+        if fcinfo_data.context.is_null() {
+            return Err(PgTriggerError::NullEventTriggerData);
+        }
+        let event_trigger_data: PgBox<pg_sys::EventTriggerData> =
+            PgBox::from_pg(fcinfo_data.context as *mut pg_sys::EventTriggerData);
+
+        Ok(Self { event_trigger_data, fcinfo })
+    }
+
+    /// The event the trigger was fired for (`ddl_command_start`, `ddl_command_end`, `sql_drop`, `table_rewrite`)
+    pub fn event(&self) -> Result<PgEventTriggerEvent, PgTriggerError> {
+        // Safety: Given that we have a known good `FunctionCallInfo`, which PostgreSQL has checked is indeed an
+        // event trigger, containing a known good `EventTriggerData`... and the user aggreed to our `unsafe`
+        // constructor safety rules, we choose to trust this is indeed a valid pointer offered to us by PostgreSQL.
+        let event_cstr = unsafe { cstr_core::CStr::from_ptr(self.event_trigger_data.event) };
+        PgEventTriggerEvent::try_from(event_cstr.to_str()?)
+    }
+
+    /// The command tag of the statement the trigger was fired for, such as `CREATE TABLE`
+    pub fn tag(&self) -> Result<&str, PgTriggerError> {
+        #[cfg(any(feature = "pg10", feature = "pg11", feature = "pg12"))]
+        let tag_ptr = self.event_trigger_data.tag;
+        #[cfg(any(feature = "pg13", feature = "pg14"))]
+        let tag_ptr = unsafe { pg_sys::GetCommandTagName(self.event_trigger_data.tag) };
+        // Safety: Command tag names are static strings inside PostgreSQL
+        let tag_cstr = unsafe { cstr_core::CStr::from_ptr(tag_ptr) };
+        Ok(tag_cstr.to_str()?)
+    }
+
+    /// The parse tree of the statement the trigger was fired for, if there is one
+    ///
+    /// Only statements that aren't plain DML have one, so its [`NodeTag`][pg_sys::NodeTag] is
+    /// that of a utility statement, such as [`pg_sys::NodeTag_T_CreateStmt`].
+    pub fn parse_tree(&self) -> Option<PgBox<pg_sys::Node>> {
+        let parse_tree = self.event_trigger_data.parsetree;
+        if parse_tree.is_null() {
+            None
+        } else {
+            Some(unsafe { PgBox::from_pg(parse_tree) })
+        }
+    }
+
+    /// The parse tree of the statement the trigger was fired for, if there is one and it is the
+    /// node `T` that `tag` is the [`NodeTag`][pg_sys::NodeTag] of
+    ///
+    /// # Safety
+    ///
+    /// `T` must be the node type `tag` is for, such as [`pg_sys::CreateStmt`] for
+    /// [`pg_sys::NodeTag_T_CreateStmt`].
+    pub unsafe fn parse_tree_as<T>(&self, tag: pg_sys::NodeTag) -> Option<PgBox<T>> {
+        let parse_tree = self.event_trigger_data.parsetree;
+        if is_a(parse_tree, tag) {
+            Some(PgBox::from_pg(parse_tree as *mut T))
+        } else {
+            None
+        }
+    }
+
+    /// The commands run by the statement the trigger was fired for
+    ///
+    /// Built from `pg_event_trigger_ddl_commands()`, which PostgreSQL only allows from
+    /// `ddl_command_end` event triggers.
+    pub fn ddl_commands(&self) -> Result<impl Iterator<Item = DdlCommand>, PgTriggerError> {
+        Self::select::<DdlCommand>("SELECT * FROM pg_catalog.pg_event_trigger_ddl_commands()")
+    }
+
+    /// The objects dropped by the statement the trigger was fired for
+    ///
+    /// Built from `pg_event_trigger_dropped_objects()`, which PostgreSQL only allows from
+    /// `sql_drop` event triggers.
+    pub fn dropped_objects(&self) -> Result<impl Iterator<Item = DroppedObject>, PgTriggerError> {
+        Self::select::<DroppedObject>("SELECT * FROM pg_catalog.pg_event_trigger_dropped_objects()")
+    }
+
+    fn select<T: FromSpiRow + Send + 'static>(
+        query: &str,
+    ) -> Result<std::vec::IntoIter<T>, PgTriggerError> {
+        let rows: Result<Vec<T>, _> =
+            Spi::connect_owned(|client| Ok(client.select(query, None, None).rows::<T>().collect()));
+        Ok(rows?.into_iter())
+    }
+
+    /// A reference to the underlaying [`EventTriggerData`][pgx_pg_sys::EventTriggerData]
+    pub fn event_trigger_data(&self) -> &pgx_pg_sys::EventTriggerData {
+        self.event_trigger_data.borrow()
+    }
+
+    /// A reference to the underlaying fcinfo
+    pub fn fcinfo(&self) -> &pg_sys::FunctionCallInfo {
+        self.fcinfo.borrow()
+    }
+}
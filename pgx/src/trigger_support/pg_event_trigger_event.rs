@@ -0,0 +1,41 @@
+use crate::trigger_support::PgTriggerError;
+
+/// The event that fired an event trigger
+///
+/// Maps from a `TEXT` of `ddl_command_start`, `ddl_command_end`, `sql_drop`, or `table_rewrite`.
+// Postgres docs: https://www.postgresql.org/docs/current/event-trigger-definition.html
+pub enum PgEventTriggerEvent {
+    /// `ddl_command_start`
+    DdlCommandStart,
+    /// `ddl_command_end`
+    DdlCommandEnd,
+    /// `sql_drop`
+    SqlDrop,
+    /// `table_rewrite`
+    TableRewrite,
+}
+
+impl TryFrom<&str> for PgEventTriggerEvent {
+    type Error = PgTriggerError;
+    fn try_from(event: &str) -> Result<Self, Self::Error> {
+        match event {
+            "ddl_command_start" => Ok(Self::DdlCommandStart),
+            "ddl_command_end" => Ok(Self::DdlCommandEnd),
+            "sql_drop" => Ok(Self::SqlDrop),
+            "table_rewrite" => Ok(Self::TableRewrite),
+            _ => Err(PgTriggerError::InvalidPgEventTriggerEvent),
+        }
+    }
+}
+
+impl ToString for PgEventTriggerEvent {
+    fn to_string(&self) -> String {
+        match self {
+            PgEventTriggerEvent::DdlCommandStart => "ddl_command_start",
+            PgEventTriggerEvent::DdlCommandEnd => "ddl_command_end",
+            PgEventTriggerEvent::SqlDrop => "sql_drop",
+            PgEventTriggerEvent::TableRewrite => "table_rewrite",
+        }
+        .to_string()
+    }
+}
@@ -4,10 +4,11 @@ use crate::pgbox::{AllocatedByPostgres, PgBox};
 use crate::rel::PgRelation;
 use crate::trigger_support::{
     called_as_trigger, PgTriggerError, PgTriggerLevel, PgTriggerOperation, PgTriggerSafe,
-    PgTriggerWhen, TriggerEvent, TriggerTuple,
+    PgTriggerWhen, TransitionTable, TriggerEvent, TriggerTuple,
 };
 use cstr_core::c_char;
 use std::borrow::Borrow;
+use std::cell::RefCell;
 
 /**
 The datatype accepted by a trigger
@@ -22,6 +23,9 @@ pub struct PgTrigger {
     relation_data: pg_sys::RelationData,
     #[allow(dead_code)]
     fcinfo: pg_sys::FunctionCallInfo,
+    /// The read pointers of the old and new transition tables no [`TransitionTable`] is using
+    old_read_pointers: RefCell<Vec<i32>>,
+    new_read_pointers: RefCell<Vec<i32>>,
 }
 
 impl PgTrigger {
@@ -68,7 +72,14 @@ impl PgTrigger {
         }
         let relation_data = *relation_data_ptr;
 
-        Ok(Self { relation_data, trigger, trigger_data, fcinfo })
+        Ok(Self {
+            relation_data,
+            trigger,
+            trigger_data,
+            fcinfo,
+            old_read_pointers: RefCell::default(),
+            new_read_pointers: RefCell::default(),
+        })
     }
 
     /// The new HeapTuple
@@ -159,6 +170,37 @@ impl PgTrigger {
             Ok(None)
         }
     }
+    /// The rows of the old transition table of this trigger invocation
+    ///
+    /// Only statement-level `AFTER` triggers `REFERENCING OLD TABLE AS ...` have one, when fired by
+    /// an `UPDATE` or `DELETE`. Each call reads the table from its first row.
+    // Derived from `pgx_pg_sys::TriggerData.tg_oldtable`
+    pub fn old_transition_table(&self) -> Option<TransitionTable<'_>> {
+        self.transition_table(self.trigger_data.tg_oldtable, &self.old_read_pointers)
+    }
+    /// The rows of the new transition table of this trigger invocation
+    ///
+    /// Only statement-level `AFTER` triggers `REFERENCING NEW TABLE AS ...` have one, when fired by
+    /// an `INSERT` or `UPDATE`. Each call reads the table from its first row.
+    // Derived from `pgx_pg_sys::TriggerData.tg_newtable`
+    pub fn new_transition_table(&self) -> Option<TransitionTable<'_>> {
+        self.transition_table(self.trigger_data.tg_newtable, &self.new_read_pointers)
+    }
+    fn transition_table<'a>(
+        &'a self,
+        tuplestore: *mut pg_sys::Tuplestorestate,
+        spare_read_pointers: &'a RefCell<Vec<i32>>,
+    ) -> Option<TransitionTable<'a>> {
+        if tuplestore.is_null() {
+            return None;
+        }
+        // Safety: Given that we have a known good `FunctionCallInfo`, which PostgreSQL has checked is indeed a trigger,
+        // containing a known good `TriggerData`... and the user aggreed to our `unsafe` constructor safety rules, we
+        // choose to trust this is indeed a transition table offered to us by PostgreSQL, in the shape of its relation.
+        Some(unsafe {
+            TransitionTable::new(tuplestore, self.relation_data.rd_att, spare_read_pointers)
+        })
+    }
     /// The `PgRelation` corresponding to the trigger.
     ///
     /// # Panics
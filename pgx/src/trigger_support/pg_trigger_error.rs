@@ -1,4 +1,4 @@
-#[derive(thiserror::Error, Debug, Clone, Copy)]
+#[derive(thiserror::Error, Debug, Clone)]
 pub enum PgTriggerError {
     #[error("`PgTrigger`s can only be built from `FunctionCallInfo` instances which `pgx::pg_sys::called_as_trigger(fcinfo)` returns `true`")]
     NotTrigger,
@@ -22,4 +22,12 @@ pub enum PgTriggerError {
     NullTriggerData,
     #[error("The `pgx::pg_sys::TriggerData`'s `tg_relation` field was a NULL pointer")]
     NullRelation,
+    #[error("`PgEventTrigger`s can only be built from `FunctionCallInfo` instances which `pgx::called_as_event_trigger(fcinfo)` returns `true`")]
+    NotEventTrigger,
+    #[error("The `pgx::pg_sys::FunctionCallInfo`'s `context` field was a NULL pointer")]
+    NullEventTriggerData,
+    #[error("`PgEventTriggerEvent` cannot be built from an event other than `ddl_command_start`, `ddl_command_end`, `sql_drop`, or `table_rewrite`")]
+    InvalidPgEventTriggerEvent,
+    #[error("pgx::FromSpiRowError: {0}")]
+    FromSpiRow(#[from] crate::spi::FromSpiRowError),
 }
@@ -0,0 +1,127 @@
+use crate::heap_tuple::PgHeapTuple;
+use crate::pg_sys;
+use crate::pgbox::AllocatedByRust;
+use crate::spi::{FromSpiRow, FromSpiRowError, SpiHeapTupleData};
+use crate::tupdesc::PgTupleDesc;
+use std::cell::RefCell;
+
+/**
+The rows of a statement-level trigger's `REFERENCING OLD TABLE` or `NEW TABLE` transition table
+
+Reads the transition tuplestore directly, rather than through SPI queries against its ephemeral
+named relation, yielding a [`PgHeapTuple`] for each row in the shape of the trigger's table.
+Typed rows can be built from those with [`TransitionTable::rows`].
+
+Built from [`PgTrigger::old_transition_table`][crate::PgTrigger::old_transition_table] and
+[`PgTrigger::new_transition_table`][crate::PgTrigger::new_transition_table].
+*/
+pub struct TransitionTable<'a> {
+    tuplestore: *mut pg_sys::Tuplestorestate,
+    tupdesc: pg_sys::TupleDesc,
+    slot: *mut pg_sys::TupleTableSlot,
+    read_pointer: i32,
+    spare_read_pointers: &'a RefCell<Vec<i32>>,
+}
+
+impl<'a> TransitionTable<'a> {
+    /// Start reading `tuplestore`, whose rows `tupdesc` describes, from its first row
+    ///
+    /// Reads with one of `spare_read_pointers`, the read pointers of `tuplestore` no other
+    /// [`TransitionTable`] is using, or a new one if there are none, and gives it back when dropped.
+    ///
+    /// # Safety
+    ///
+    /// `tuplestore` must be a transition table PostgreSQL provided to the currently running trigger,
+    /// `tupdesc` the descriptor of the trigger's table, and `spare_read_pointers` only ever hold
+    /// read pointers of `tuplestore`.
+    pub(crate) unsafe fn new(
+        tuplestore: *mut pg_sys::Tuplestorestate,
+        tupdesc: pg_sys::TupleDesc,
+        spare_read_pointers: &'a RefCell<Vec<i32>>,
+    ) -> Self {
+        // other triggers may be reading the same tuplestore, so read it with a pointer of our own,
+        // like a scan of its ephemeral named relation would.  tuplestores can't free read pointers,
+        // so reuse those of tables read before
+        let read_pointer = spare_read_pointers.borrow_mut().pop().unwrap_or_else(|| {
+            pg_sys::tuplestore_alloc_read_pointer(tuplestore, pg_sys::EXEC_FLAG_REWIND as i32)
+        });
+        pg_sys::tuplestore_select_read_pointer(tuplestore, read_pointer);
+        pg_sys::tuplestore_rescan(tuplestore);
+
+        #[cfg(any(feature = "pg10", feature = "pg11"))]
+        let slot = pg_sys::MakeSingleTupleTableSlot(tupdesc);
+        #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14"))]
+        let slot = pg_sys::MakeSingleTupleTableSlot(tupdesc, &pg_sys::TTSOpsMinimalTuple);
+
+        Self { tuplestore, tupdesc, slot, read_pointer, spare_read_pointers }
+    }
+
+    /// The descriptor of the rows, that of the trigger's table
+    pub fn tuple_desc(&self) -> PgTupleDesc<'a> {
+        unsafe { PgTupleDesc::from_pg_unchecked(self.tupdesc) }
+    }
+
+    /// The rows as `T`, built the way [`SpiTupleTable::rows`][crate::SpiTupleTable::rows] builds
+    /// them, so `T` can `#[derive(FromSpiRow)]`
+    pub fn rows<T: FromSpiRow>(mut self) -> impl Iterator<Item = Result<T, FromSpiRowError>> + 'a {
+        std::iter::from_fn(move || {
+            let heap_tuple = self.next_heap_tuple()?;
+            unsafe {
+                let row = SpiHeapTupleData::new(self.tupdesc, heap_tuple);
+                let result = T::from_spi_row(&row);
+                pg_sys::heap_freetuple(heap_tuple);
+                Some(result)
+            }
+        })
+    }
+
+    /// A copy of the next row, `palloc()`'d in the `CurrentMemoryContext`
+    fn next_heap_tuple(&mut self) -> Option<pg_sys::HeapTuple> {
+        unsafe {
+            pg_sys::tuplestore_select_read_pointer(self.tuplestore, self.read_pointer);
+            if !pg_sys::tuplestore_gettupleslot(self.tuplestore, true, false, self.slot) {
+                return None;
+            }
+
+            #[cfg(any(feature = "pg10", feature = "pg11"))]
+            let heap_tuple = pg_sys::ExecCopySlotTuple(self.slot);
+            #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14"))]
+            let heap_tuple = {
+                let mut should_free = false;
+                let heap_tuple = pg_sys::ExecFetchSlotHeapTuple(self.slot, false, &mut should_free);
+                if should_free {
+                    heap_tuple
+                } else {
+                    pg_sys::heap_copytuple(heap_tuple)
+                }
+            };
+            Some(heap_tuple)
+        }
+    }
+}
+
+impl<'a> Iterator for TransitionTable<'a> {
+    type Item = PgHeapTuple<'a, AllocatedByRust>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let heap_tuple = self.next_heap_tuple()?;
+        // Safety: `next_heap_tuple` returns copies in the shape of the trigger's table
+        unsafe {
+            Some(PgHeapTuple::from_owned_heap_tuple(
+                PgTupleDesc::from_pg_unchecked(self.tupdesc),
+                heap_tuple,
+            ))
+        }
+    }
+}
+
+impl<'a> Drop for TransitionTable<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            pg_sys::ExecDropSingleTupleTableSlot(self.slot);
+            // leave the tuplestore with the read pointer its owner uses selected again
+            pg_sys::tuplestore_select_read_pointer(self.tuplestore, 0);
+        }
+        self.spare_read_pointers.borrow_mut().push(self.read_pointer);
+    }
+}
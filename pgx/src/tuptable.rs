@@ -0,0 +1,169 @@
+/*
+Portions Copyright 2019-2021 ZomboDB, LLC.
+Portions Copyright 2021-2022 Technology Concepts & Design, Inc. <support@tcdi.com>
+
+All rights reserved.
+
+Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+*/
+
+//! A view of a `TupleTableSlot`, which is how the executor passes rows between plan nodes
+use crate::{pg_sys, FromDatum, IntoDatum, PgTupleDesc, TryFromDatumError};
+use std::marker::PhantomData;
+use std::num::NonZeroUsize;
+
+/// A slot that holds one row at a time, such as the scan slot of a
+/// [`CustomScan`][crate::custom_scan::CustomScan] that it fills with each row it produces
+///
+/// This dereferences to the underlying `pg_sys::TupleTableSlot` for anything that doesn't have a
+/// method of its own.
+pub struct TupleTableSlotRef<'a> {
+    ptr: *mut pg_sys::TupleTableSlot,
+    _marker: PhantomData<&'a mut pg_sys::TupleTableSlot>,
+}
+
+impl<'a> TupleTableSlotRef<'a> {
+    /// ## Safety
+    ///
+    /// `ptr` must be a valid pointer that outlives the returned `TupleTableSlotRef`
+    pub unsafe fn from_pg(ptr: *mut pg_sys::TupleTableSlot) -> Self {
+        assert!(!ptr.is_null(), "TupleTableSlot is NULL");
+        TupleTableSlotRef { ptr, _marker: PhantomData }
+    }
+
+    pub fn as_ptr(&self) -> *mut pg_sys::TupleTableSlot {
+        self.ptr
+    }
+
+    /// The descriptor of the rows this slot holds
+    pub fn tuple_desc(&self) -> PgTupleDesc<'a> {
+        unsafe { PgTupleDesc::from_pg_unchecked((*self.ptr).tts_tupleDescriptor) }
+    }
+
+    /// The number of columns in the rows this slot holds, including any dropped columns
+    pub fn natts(&self) -> usize {
+        unsafe { (*(*self.ptr).tts_tupleDescriptor).natts as usize }
+    }
+
+    /// Retrieve the value of the specified attribute of the row the slot holds, by name.
+    ///
+    /// ## Errors
+    /// - return [`TryFromDatumError::NoSuchAttributeName`] if the attribute does not exist
+    /// - return [`TryFromDatumError::IncompatibleTypes`] if the Rust type of the `value` is not
+    /// compatible with the attribute's Postgres type
+    pub fn get_by_name<T: FromDatum + IntoDatum + 'static>(
+        &self,
+        attname: &str,
+    ) -> Result<Option<T>, TryFromDatumError> {
+        for att in self.tuple_desc().iter() {
+            if !att.is_dropped() && att.name() == attname {
+                return self.get_by_index(NonZeroUsize::new(att.attnum as usize).unwrap());
+            }
+        }
+        Err(TryFromDatumError::NoSuchAttributeName(attname.to_owned()))
+    }
+
+    /// Retrieve the value of the specified attribute of the row the slot holds, by index.
+    ///
+    /// Attribute numbers start at 1, not 0.
+    ///
+    /// ## Errors
+    /// - return [`TryFromDatumError::NoSuchAttributeNumber`] if the attribute does not exist
+    /// - return [`TryFromDatumError::IncompatibleTypes`] if the Rust type of the `value` is not
+    /// compatible with the attribute's Postgres type
+    pub fn get_by_index<T: FromDatum + IntoDatum + 'static>(
+        &self,
+        attno: NonZeroUsize,
+    ) -> Result<Option<T>, TryFromDatumError> {
+        let tupdesc = self.tuple_desc();
+        match tupdesc.get(attno.get() - 1) {
+            None => Err(TryFromDatumError::NoSuchAttributeNumber(attno)),
+            Some(att) => unsafe {
+                let mut is_null = false;
+                let datum = pg_sys::pgx_slot_getattr(self.ptr, attno.get() as i32, &mut is_null);
+                if is_null {
+                    return Ok(None);
+                }
+                T::try_from_datum(datum, false, att.type_oid().value())
+            },
+        }
+    }
+
+    /// The values (where `None` is NULL) of every column of the row the slot holds, in order
+    pub fn values(&self) -> Vec<Option<pg_sys::Datum>> {
+        (1..=self.natts() as i32)
+            .map(|attno| unsafe {
+                let mut is_null = false;
+                let datum = pg_sys::pgx_slot_getattr(self.ptr, attno, &mut is_null);
+                if is_null {
+                    None
+                } else {
+                    Some(datum)
+                }
+            })
+            .collect()
+    }
+
+    /// The tid of the row the slot holds, in the table it came from
+    #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14"))]
+    pub fn tid(&self) -> pg_sys::ItemPointerData {
+        unsafe { (*self.ptr).tts_tid }
+    }
+
+    /// Set the tid of the row the slot holds, such as to where a table access method stored it
+    #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14"))]
+    pub fn set_tid(&mut self, tid: pg_sys::ItemPointerData) {
+        unsafe { (*self.ptr).tts_tid = tid }
+    }
+
+    /// Empty the slot, releasing whatever row it held
+    pub fn clear(&mut self) {
+        unsafe {
+            pg_sys::pgx_ExecClearTuple(self.ptr);
+        }
+    }
+
+    /// Store a row into the slot, replacing whatever row it held, with a value (where `None` is
+    /// NULL) for each of its columns, in order
+    ///
+    /// The datums aren't copied, so any that are pointers must remain valid for as long as the
+    /// row is in the slot.
+    ///
+    /// ## Panics
+    ///
+    /// If the number of values isn't the slot's [`natts()`][TupleTableSlotRef::natts]
+    pub fn store_datums<I: IntoIterator<Item = Option<pg_sys::Datum>>>(&mut self, values: I) {
+        let natts = self.natts();
+        self.clear();
+
+        let mut count = 0;
+        for value in values {
+            assert!(count < natts, "too many values for a slot with {} columns", natts);
+            unsafe {
+                let slot = &mut *self.ptr;
+                *slot.tts_values.add(count) = value.unwrap_or(pg_sys::Datum::from(0));
+                *slot.tts_isnull.add(count) = value.is_none();
+            }
+            count += 1;
+        }
+        assert_eq!(count, natts, "not enough values for a slot with {} columns", natts);
+
+        unsafe {
+            pg_sys::ExecStoreVirtualTuple(self.ptr);
+        }
+    }
+}
+
+impl std::ops::Deref for TupleTableSlotRef<'_> {
+    type Target = pg_sys::TupleTableSlot;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.ptr }
+    }
+}
+
+impl std::ops::DerefMut for TupleTableSlotRef<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.ptr }
+    }
+}